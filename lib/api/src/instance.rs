@@ -7,7 +7,7 @@ use std::fmt;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use wasmer_engine::Resolver;
-use wasmer_vm::{InstanceHandle, VMContext};
+use wasmer_vm::{InstanceHandle, InstanceSnapshot, SnapshotError, VMContext};
 
 /// A WebAssembly Instance is a stateful, executable
 /// instance of a WebAssembly [`Module`].
@@ -77,6 +77,36 @@ impl From<HostEnvInitError> for InstantiationError {
     }
 }
 
+/// An error while restoring an [`Instance`] from an [`InstanceSnapshot`].
+#[derive(Error, Debug)]
+pub enum RestoreError {
+    /// An error while instantiating the module.
+    #[error(transparent)]
+    Instantiation(InstantiationError),
+
+    /// The snapshot couldn't be restored.
+    #[error(transparent)]
+    Snapshot(SnapshotError),
+}
+
+impl From<InstantiationError> for RestoreError {
+    fn from(other: InstantiationError) -> Self {
+        Self::Instantiation(other)
+    }
+}
+
+impl From<HostEnvInitError> for RestoreError {
+    fn from(other: HostEnvInitError) -> Self {
+        Self::Instantiation(other.into())
+    }
+}
+
+impl From<SnapshotError> for RestoreError {
+    fn from(other: SnapshotError) -> Self {
+        Self::Snapshot(other)
+    }
+}
+
 impl Instance {
     /// Creates a new `Instance` from a WebAssembly [`Module`] and a
     /// set of imports resolved by the [`Resolver`].
@@ -112,8 +142,52 @@ impl Instance {
     ///  * Link errors that happen when plugging the imports into the instance
     ///  * Runtime errors that happen when running the module `start` function.
     pub fn new(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
-        let store = module.store();
         let handle = module.instantiate(resolver)?;
+        Ok(Self::from_handle(module, handle)?)
+    }
+
+    /// Creates a new `Instance` from a WebAssembly [`Module`] in the state
+    /// captured by [`Instance::snapshot`], with a set of imports resolved
+    /// by the [`Resolver`].
+    ///
+    /// The snapshot must have been taken from an instance of the same
+    /// module, possibly in another process. The start function of the
+    /// module isn't called again.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance, Value};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(&store, r#"
+    ///     (module
+    ///       (global $count (mut i32) (i32.const 0))
+    ///       (func (export "count") (result i32)
+    ///         (global.set $count (i32.add (global.get $count) (i32.const 1)))
+    ///         (global.get $count)))
+    /// "#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// instance.exports.get_function("count")?.call(&[])?;
+    /// let snapshot = instance.snapshot()?;
+    ///
+    /// let restored = Instance::restore(&module, &snapshot, &imports! {})?;
+    /// let count = restored.exports.get_function("count")?.call(&[])?;
+    /// assert_eq!(count[0], Value::I32(2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore(
+        module: &Module,
+        snapshot: &InstanceSnapshot,
+        resolver: &dyn Resolver,
+    ) -> Result<Self, RestoreError> {
+        let handle = module.instantiate_from_snapshot(resolver, snapshot)?;
+        Ok(Self::from_handle(module, handle)?)
+    }
+
+    /// Wraps a just created `InstanceHandle` and sets up the host
+    /// environments of its imports.
+    fn from_handle(module: &Module, handle: InstanceHandle) -> Result<Self, HostEnvInitError> {
+        let store = module.store();
         let exports = module
             .exports()
             .map(|export| {
@@ -149,6 +223,15 @@ impl Instance {
         Ok(instance)
     }
 
+    /// Takes a snapshot of the memories, mutable globals and tables the
+    /// instance defines, to restore it later with [`Instance::restore`].
+    ///
+    /// The snapshot must be taken while no function of the instance is
+    /// running: the stack of a running function isn't part of it.
+    pub fn snapshot(&self) -> Result<InstanceSnapshot, SnapshotError> {
+        self.handle.lock().unwrap().snapshot()
+    }
+
     /// Gets the [`Module`] associated with this instance.
     pub fn module(&self) -> &Module {
         &self.module
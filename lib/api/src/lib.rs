@@ -261,6 +261,11 @@ mod instance;
 mod module;
 mod native;
 mod ptr;
+pub mod record;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "compiler")]
+pub mod snapshot_init;
 mod store;
 mod tunables;
 mod types;
@@ -289,10 +294,12 @@ pub use crate::externals::{
     Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
 };
 pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
-pub use crate::instance::{Instance, InstantiationError};
+pub use crate::instance::{Instance, InstantiationError, RestoreError};
 pub use crate::module::Module;
 pub use crate::native::NativeFunc;
 pub use crate::ptr::{Array, Item, WasmPtr};
+#[cfg(feature = "compiler")]
+pub use crate::snapshot_init::SnapshotInitError;
 pub use crate::store::{Store, StoreObject};
 pub use crate::tunables::BaseTunables;
 pub use crate::types::{
@@ -320,7 +327,7 @@ pub use wasmer_types::{
 };
 
 // TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError, VMExport};
+pub use wasmer_vm::{raise_user_trap, InstanceSnapshot, MemoryError, SnapshotError, VMExport};
 pub mod vm {
     //! The vm module re-exports wasmer-vm types.
 
@@ -1,6 +1,6 @@
 use crate::store::Store;
 use crate::types::{ExportType, ImportType};
-use crate::InstantiationError;
+use crate::{InstantiationError, RestoreError};
 use std::fmt;
 use std::io;
 use std::path::Path;
@@ -10,7 +10,7 @@ use wasmer_compiler::CompileError;
 #[cfg(feature = "wat")]
 use wasmer_compiler::WasmError;
 use wasmer_engine::{Artifact, DeserializeError, Resolver, SerializeError};
-use wasmer_vm::{ExportsIterator, ImportsIterator, InstanceHandle, ModuleInfo};
+use wasmer_vm::{ExportsIterator, ImportsIterator, InstanceHandle, InstanceSnapshot, ModuleInfo};
 
 #[derive(Error, Debug)]
 pub enum IoCompileError {
@@ -251,6 +251,79 @@ impl Module {
         Ok(Self::from_artifact(store, artifact))
     }
 
+    /// Serializes a module and signs the result with the provided
+    /// ed25519 `keypair`.
+    ///
+    /// The signed binary can only be loaded back via
+    /// [`Module::deserialize_verified`] using the matching public key.
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let module = Module::from_file(&store, "path/to/foo.wasm")?;
+    /// # let keypair = signing::Keypair::from_bytes(&key_bytes)?;
+    /// let signed = module.serialize_signed(&keypair)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "signing")]
+    pub fn serialize_signed(
+        &self,
+        keypair: &crate::signing::Keypair,
+    ) -> Result<Vec<u8>, SerializeError> {
+        let serialized = self.serialize()?;
+        Ok(crate::signing::sign(&serialized, keypair))
+    }
+
+    /// Deserializes a module previously serialized with
+    /// [`Module::serialize_signed`].
+    ///
+    /// The signature is verified against `public_key` before any of the
+    /// contents are handed to the `Engine`, so unsigned or tampered
+    /// binaries are rejected with [`DeserializeError::InvalidSignature`]
+    /// and no code is mapped into memory.
+    ///
+    /// # Security
+    ///
+    /// Opposed to [`Module::deserialize`], this function is safe as long
+    /// as the holder of the secret key only signs trusted artifacts.
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::deserialize_verified(&store, signed_data, &public_key)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "signing")]
+    pub fn deserialize_verified(
+        store: &Store,
+        bytes: &[u8],
+        public_key: &crate::signing::PublicKey,
+    ) -> Result<Self, DeserializeError> {
+        let (_metadata, payload) = crate::signing::verify(bytes, public_key)?;
+        unsafe { Self::deserialize(store, payload) }
+    }
+
+    /// Deserializes a signed Module located in a `Path` into a `Module`.
+    ///
+    /// Please check [`Module::deserialize_verified`].
+    #[cfg(feature = "signing")]
+    pub fn deserialize_verified_from_file(
+        store: &Store,
+        path: impl AsRef<Path>,
+        public_key: &crate::signing::PublicKey,
+    ) -> Result<Self, DeserializeError> {
+        let bytes = std::fs::read(path.as_ref())?;
+        Self::deserialize_verified(store, &bytes, public_key)
+    }
+
     fn from_artifact(store: &Store, artifact: Arc<dyn Artifact>) -> Self {
         Self {
             store: store.clone(),
@@ -278,6 +351,26 @@ impl Module {
         }
     }
 
+    pub(crate) fn instantiate_from_snapshot(
+        &self,
+        resolver: &dyn Resolver,
+        snapshot: &InstanceSnapshot,
+    ) -> Result<InstanceHandle, RestoreError> {
+        unsafe {
+            let instance_handle = self
+                .artifact
+                .instantiate(self.store.tunables(), resolver, Box::new(()))
+                .map_err(InstantiationError::from)?;
+
+            // The snapshot replaces what the data and element segments
+            // initialize, and the start function already ran in the instance
+            // the snapshot was taken from, so `finish_instantiation` is skipped.
+            instance_handle.restore(snapshot)?;
+
+            Ok(instance_handle)
+        }
+    }
+
     /// Returns the name of the current module.
     ///
     /// This name is normally set in the WebAssembly bytecode by some
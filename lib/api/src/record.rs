@@ -0,0 +1,831 @@
+//! Recording the calls a module makes to its imported functions, to replay
+//! them later.
+//!
+//! A [`RecordingResolver`] wraps the resolver of the imports of a module,
+//! e.g. an [`ImportObject`], and writes every call to an imported function
+//! to a trace: its arguments, its results or the error it raised, and what
+//! it wrote to the memories the module exports. A [`ReplayResolver`] reads
+//! the trace back and answers the calls with it instead of calling the host
+//! functions, so an execution depending on the environment, like the time
+//! or the files read through WASI, can be reproduced exactly.
+//!
+//! The calls which raised an error, like WASI's `proc_exit`, are made again
+//! when replaying, so the host raises the same error.
+//!
+//! Both resolvers are meant for a single instance. The environments of the
+//! host functions are initialized with an instance exporting the memories
+//! of the module only, and host functions calling back into the module
+//! can't be replayed.
+//!
+//! To find what a call wrote, the memories are copied before the call and
+//! compared with the copies afterwards.
+//!
+//! The trace is made of a header followed by the calls, in the order they
+//! returned:
+//!
+//! ```text
+//! magic (16 bytes) | call | call | ...
+//! ```
+//!
+//! [`ImportObject`]: crate::ImportObject
+
+use crate::exports::Exportable;
+use crate::externals::{Function, Memory};
+use crate::instance::Instance;
+use crate::module::Module;
+use crate::store::Store;
+use crate::types::{FunctionType, Val, ValType};
+use crate::{HostEnvInitError, WasmerEnv};
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use wasmer_engine::{Export, Resolver, RuntimeError};
+use wasmer_types::Pages;
+
+/// The magic header of a trace.
+const MAGIC_HEADER: &[u8; 16] = b"\0wasmer-trace\0\0\0";
+
+/// The size of the chunks of memory compared to find what a call wrote.
+const CHUNK_SIZE: usize = 4096;
+
+/// Unchanged runs of bytes shorter than this are kept inside writes rather
+/// than splitting them.
+const MIN_UNCHANGED_BETWEEN_WRITES: usize = 16;
+
+/// The prefix of the names under which the host module exports the
+/// imported functions.
+const FUNCTION_EXPORT_PREFIX: &str = "__wasmer_import_";
+
+/// An error while reading or writing a trace.
+#[derive(Error, Debug)]
+pub enum TraceError {
+    /// The trace couldn't be read or written.
+    #[error("failed to read or write the trace: {0}")]
+    Io(io::Error),
+
+    /// The trace is malformed.
+    #[error("invalid trace: {0}")]
+    Invalid(String),
+}
+
+impl From<io::Error> for TraceError {
+    fn from(other: io::Error) -> Self {
+        Self::Io(other)
+    }
+}
+
+/// A call made by a module to one of its imported functions.
+#[derive(Debug, Clone)]
+pub struct TraceCall {
+    /// The module of the imported function
+    pub module: String,
+    /// The name of the imported function
+    pub field: String,
+    /// The arguments of the call
+    pub args: Vec<Val>,
+    /// The results of the call, or the message of the error it raised
+    pub outcome: Result<Vec<Val>, String>,
+    /// The new sizes of the memories the call grew, by export name
+    pub memory_sizes: Vec<(String, Pages)>,
+    /// What the call wrote to the memories, by export name
+    pub memory_writes: Vec<MemoryWrite>,
+}
+
+/// Bytes written to a memory by a host function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryWrite {
+    /// The export name of the memory
+    pub memory: String,
+    /// Where the bytes were written
+    pub offset: u64,
+    /// The bytes written
+    pub data: Vec<u8>,
+}
+
+/// Read all the calls of the trace in `input`.
+pub fn read_trace(mut input: impl Read) -> Result<Vec<TraceCall>, TraceError> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    split_calls(&bytes)?
+        .iter()
+        .map(|call| TraceReader { bytes: call }.read_call())
+        .collect()
+}
+
+/// Split the trace `bytes` into the encoded calls it holds.
+fn split_calls(bytes: &[u8]) -> Result<Vec<Vec<u8>>, TraceError> {
+    if !bytes.starts_with(MAGIC_HEADER) {
+        return Err(TraceError::Invalid(
+            "the magic header is missing".to_string(),
+        ));
+    }
+    let mut reader = TraceReader {
+        bytes: &bytes[MAGIC_HEADER.len()..],
+    };
+    let mut calls = Vec::new();
+    while !reader.bytes.is_empty() {
+        let call = reader.bytes;
+        reader.read_call()?;
+        calls.push(call[..call.len() - reader.bytes.len()].to_vec());
+    }
+    Ok(calls)
+}
+
+/// A resolver recording the calls to the imported functions it resolves,
+/// see the [module documentation](self).
+///
+/// ```no_run
+/// # use wasmer::{imports, Instance, Module, Store};
+/// # use wasmer::record::RecordingResolver;
+/// # fn main() -> anyhow::Result<()> {
+/// # let store = Store::default();
+/// # let module = Module::new(&store, "(module)")?;
+/// let trace = std::fs::File::create("trace.bin")?;
+/// let resolver = RecordingResolver::new(module.store(), imports! {}, trace)?;
+/// let instance = Instance::new(&module, &resolver)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingResolver<R: Resolver> {
+    resolver: R,
+    interceptor: Arc<Interceptor>,
+}
+
+impl<R: Resolver> RecordingResolver<R> {
+    /// Record the calls to the functions `resolver` resolves to `output`.
+    pub fn new(
+        store: &Store,
+        resolver: R,
+        mut output: impl Write + Send + 'static,
+    ) -> Result<Self, TraceError> {
+        output.write_all(MAGIC_HEADER)?;
+        Ok(Self {
+            resolver,
+            interceptor: Arc::new(Interceptor::new(store, Mode::Record(Box::new(output)))),
+        })
+    }
+}
+
+impl<R: Resolver> Resolver for RecordingResolver<R> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        let export = self.resolver.resolve(index, module, field)?;
+        Some(Interceptor::intercept(
+            &self.interceptor,
+            module,
+            field,
+            export,
+        ))
+    }
+}
+
+/// A resolver answering the calls to the imported functions it resolves
+/// with a trace, see the [module documentation](self).
+pub struct ReplayResolver<R: Resolver> {
+    resolver: R,
+    interceptor: Arc<Interceptor>,
+}
+
+impl<R: Resolver> ReplayResolver<R> {
+    /// Answer the calls to the functions `resolver` resolves with the trace
+    /// in `input`.
+    ///
+    /// The host functions are only called for the calls which raised an
+    /// error.
+    pub fn new(store: &Store, resolver: R, mut input: impl Read) -> Result<Self, TraceError> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let calls = split_calls(&bytes)?;
+        Ok(Self {
+            resolver,
+            interceptor: Arc::new(Interceptor::new(store, Mode::Replay(calls.into()))),
+        })
+    }
+}
+
+impl<R: Resolver> Resolver for ReplayResolver<R> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        let export = self.resolver.resolve(index, module, field)?;
+        Some(Interceptor::intercept(
+            &self.interceptor,
+            module,
+            field,
+            export,
+        ))
+    }
+}
+
+enum Mode {
+    Record(Box<dyn Write + Send>),
+    /// The calls left, encoded as values can't be sent between threads
+    Replay(VecDeque<Vec<u8>>),
+}
+
+/// An imported function, as resolved by the wrapped resolver.
+struct Import {
+    module: String,
+    field: String,
+    ty: FunctionType,
+    export: Export,
+}
+
+/// The state shared by the functions wrapping the imported functions.
+struct Interceptor {
+    store: Store,
+    mode: Mutex<Mode>,
+    imports: Mutex<Vec<Import>>,
+    /// The memories exported by the module, once it's instantiated
+    memories: Mutex<Option<Vec<(String, Memory)>>>,
+    /// The copies of the memories made before a call, kept to be reused by
+    /// the next calls. They are taken during a call, so the nested calls
+    /// make their own.
+    copies: Mutex<Vec<Vec<u8>>>,
+    /// The imported functions, callable through the host module
+    functions: Mutex<Option<Vec<Function>>>,
+}
+
+#[derive(Clone)]
+struct CallEnv {
+    interceptor: Arc<Interceptor>,
+    index: usize,
+}
+
+impl WasmerEnv for CallEnv {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let mut memories = self.interceptor.memories.lock().unwrap();
+        if memories.is_none() {
+            *memories = Some(
+                instance
+                    .exports
+                    .iter()
+                    .memories()
+                    .map(|(name, memory)| (name.clone(), memory.clone()))
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Interceptor {
+    fn new(store: &Store, mode: Mode) -> Self {
+        Self {
+            store: store.clone(),
+            mode: Mutex::new(mode),
+            imports: Mutex::new(Vec::new()),
+            memories: Mutex::new(None),
+            copies: Mutex::new(Vec::new()),
+            functions: Mutex::new(None),
+        }
+    }
+
+    /// Wrap `export` if it's a function, so its calls go through `this`.
+    fn intercept(this: &Arc<Self>, module: &str, field: &str, export: Export) -> Export {
+        let ty = match &export {
+            Export::Function(function) => function.vm_function.signature.clone(),
+            _ => return export,
+        };
+        let mut imports = this.imports.lock().unwrap();
+        let env = CallEnv {
+            interceptor: this.clone(),
+            index: imports.len(),
+        };
+        imports.push(Import {
+            module: module.to_string(),
+            field: field.to_string(),
+            ty: ty.clone(),
+            export,
+        });
+        Function::new_with_env(&this.store, ty, env, |env, args| {
+            env.interceptor.call(env.index, args)
+        })
+        .to_export()
+    }
+
+    fn call(&self, index: usize, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+        let (module, field) = {
+            let imports = self.imports.lock().unwrap();
+            (imports[index].module.clone(), imports[index].field.clone())
+        };
+        let memories = self.memories.lock().unwrap().clone().unwrap_or_default();
+
+        let mut mode = self.mode.lock().unwrap();
+        match &mut *mode {
+            Mode::Record(_) => {
+                // Don't keep the lock while the host function runs, it may
+                // call back into the module.
+                drop(mode);
+                let function = self.function(index)?;
+                let mut copies = mem::take(&mut *self.copies.lock().unwrap());
+                copies.resize_with(memories.len(), Vec::new);
+                for ((_, memory), copy) in memories.iter().zip(copies.iter_mut()) {
+                    copy.clear();
+                    copy.extend_from_slice(unsafe { memory.data_unchecked() });
+                }
+                let result = function.call(args);
+
+                let mut call = TraceCall {
+                    module,
+                    field,
+                    args: args.to_vec(),
+                    outcome: match &result {
+                        Ok(results) => Ok(results.to_vec()),
+                        Err(error) => Err(error.message()),
+                    },
+                    memory_sizes: Vec::new(),
+                    memory_writes: Vec::new(),
+                };
+                for ((name, memory), before) in memories.iter().zip(copies.iter()) {
+                    let after = unsafe { memory.data_unchecked() };
+                    if after.len() != before.len() {
+                        call.memory_sizes.push((name.clone(), memory.size()));
+                    }
+                    for (offset, data) in changes(before, after) {
+                        call.memory_writes.push(MemoryWrite {
+                            memory: name.clone(),
+                            offset: offset as u64,
+                            data: data.to_vec(),
+                        });
+                    }
+                }
+
+                *self.copies.lock().unwrap() = copies;
+
+                let mut bytes = Vec::new();
+                write_call(&mut bytes, &call)?;
+                if let Mode::Record(output) = &mut *self.mode.lock().unwrap() {
+                    output
+                        .write_all(&bytes)
+                        .and_then(|()| output.flush())
+                        .map_err(|e| {
+                            RuntimeError::new(format!("failed to write the trace: {}", e))
+                        })?;
+                }
+                result.map(|results| results.into_vec())
+            }
+            Mode::Replay(calls) => {
+                let call = calls.pop_front().ok_or_else(|| {
+                    RuntimeError::new(format!(
+                        "the trace ended before the call to `{}`.`{}`",
+                        module, field
+                    ))
+                })?;
+                drop(mode);
+                let call = TraceReader { bytes: &call }
+                    .read_call()
+                    .map_err(|e| RuntimeError::new(e.to_string()))?;
+                if call.module != module
+                    || call.field != field
+                    || encode_values(&call.args)? != encode_values(args)?
+                {
+                    return Err(RuntimeError::new(format!(
+                        "the execution diverged from the trace: `{}`.`{}` was called with {:?} \
+                         instead of `{}`.`{}` with {:?}",
+                        module, field, args, call.module, call.field, call.args
+                    )));
+                }
+
+                for (name, size) in call.memory_sizes.iter() {
+                    let memory = find_memory(&memories, name)?;
+                    if memory.size() < *size {
+                        memory
+                            .grow(*size - memory.size())
+                            .map_err(|e| RuntimeError::new(e.to_string()))?;
+                    }
+                }
+                for write in call.memory_writes.iter() {
+                    let memory = find_memory(&memories, &write.memory)?;
+                    let data = unsafe { memory.data_unchecked_mut() };
+                    let start = write.offset as usize;
+                    start
+                        .checked_add(write.data.len())
+                        .and_then(|end| data.get_mut(start..end))
+                        .ok_or_else(|| {
+                            RuntimeError::new("a write of the trace is out of the memory")
+                        })?
+                        .copy_from_slice(&write.data);
+                }
+
+                match call.outcome {
+                    Ok(results) => Ok(results),
+                    // Make the call again, so the host raises its own error,
+                    // like the exit of WASI's `proc_exit`.
+                    Err(message) => match self.function(index)?.call(args) {
+                        Err(error) => Err(error),
+                        Ok(_) => Err(RuntimeError::new(message)),
+                    },
+                }
+            }
+        }
+    }
+
+    /// The imported function `index`, callable through the host module.
+    fn function(&self, index: usize) -> Result<Function, RuntimeError> {
+        let mut functions = self.functions.lock().unwrap();
+        if functions.is_none() {
+            *functions = Some(self.instantiate_host_module()?);
+        }
+        Ok(functions.as_ref().unwrap()[index].clone())
+    }
+
+    /// Host functions can't be called from outside of an instance, so they
+    /// are imported by a module reexporting them, together with the
+    /// memories of the module calling them.
+    fn instantiate_host_module(&self) -> Result<Vec<Function>, RuntimeError> {
+        let imports = self.imports.lock().unwrap();
+        let memories = self.memories.lock().unwrap().clone().unwrap_or_default();
+        let binary = host_module(&imports, &memories)?;
+        let module = Module::from_binary(&self.store, &binary)
+            .map_err(|e| RuntimeError::new(format!("failed to compile the host module: {}", e)))?;
+
+        let exports = imports
+            .iter()
+            .map(|import| import.export.clone())
+            .chain(memories.iter().map(|(_, memory)| memory.to_export()))
+            .collect();
+        let instance = Instance::new(&module, &HostResolver(exports)).map_err(|e| {
+            RuntimeError::new(format!("failed to instantiate the host module: {}", e))
+        })?;
+        (0..imports.len())
+            .map(|index| {
+                let name = format!("{}{}", FUNCTION_EXPORT_PREFIX, index);
+                Ok(instance
+                    .exports
+                    .get_function(&name)
+                    .map_err(|e| RuntimeError::new(e.to_string()))?
+                    .clone())
+            })
+            .collect()
+    }
+}
+
+/// Resolves the imports of the host module, in order.
+struct HostResolver(Vec<Export>);
+
+impl Resolver for HostResolver {
+    fn resolve(&self, index: u32, _module: &str, _field: &str) -> Option<Export> {
+        self.0.get(index as usize).cloned()
+    }
+}
+
+fn find_memory<'a>(
+    memories: &'a [(String, Memory)],
+    name: &str,
+) -> Result<&'a Memory, RuntimeError> {
+    memories
+        .iter()
+        .find(|(memory_name, _)| memory_name == name)
+        .map(|(_, memory)| memory)
+        .ok_or_else(|| {
+            RuntimeError::new(format!("the module doesn't export the memory `{}`", name))
+        })
+}
+
+/// The runs of bytes which differ between `before` and `after`, with their
+/// offset. Memory beyond the end of `before` counts as zeros.
+fn changes<'a>(before: &[u8], after: &'a [u8]) -> Vec<(usize, &'a [u8])> {
+    let mut runs: Vec<(usize, &[u8])> = Vec::new();
+    let zeros = [0; CHUNK_SIZE];
+    for (chunk_index, chunk) in after.chunks(CHUNK_SIZE).enumerate() {
+        let start = chunk_index * CHUNK_SIZE;
+        let old = before.get(start..).unwrap_or(&[]);
+        let old = if old.len() >= chunk.len() {
+            &old[..chunk.len()]
+        } else {
+            &zeros[..0]
+        };
+        if old == chunk || (old.is_empty() && chunk == &zeros[..chunk.len()]) {
+            continue;
+        }
+        for (index, byte) in chunk.iter().enumerate() {
+            let offset = start + index;
+            let old_byte = before.get(offset).copied().unwrap_or(0);
+            if *byte == old_byte {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((run_start, run))
+                    if offset - (*run_start + run.len()) < MIN_UNCHANGED_BETWEEN_WRITES =>
+                {
+                    *run = &after[*run_start..=offset];
+                }
+                _ => runs.push((offset, &after[offset..=offset])),
+            }
+        }
+    }
+    runs
+}
+
+/// The binary of the host module: it imports the functions and the
+/// memories, and exports them.
+fn host_module(imports: &[Import], memories: &[(String, Memory)]) -> Result<Vec<u8>, RuntimeError> {
+    let mut binary = b"\0asm\x01\0\0\0".to_vec();
+
+    let mut types = Vec::new();
+    write_u32(&mut types, imports.len() as u32);
+    for import in imports {
+        types.push(0x60);
+        for values in &[import.ty.params(), import.ty.results()] {
+            write_u32(&mut types, values.len() as u32);
+            for ty in values.iter() {
+                types.push(encode_type(*ty));
+            }
+        }
+    }
+    write_section(&mut binary, 1, &types);
+
+    let mut section = Vec::new();
+    write_u32(&mut section, (imports.len() + memories.len()) as u32);
+    for index in 0..imports.len() {
+        write_name(&mut section, "");
+        write_name(&mut section, &index.to_string());
+        section.push(0x00);
+        write_u32(&mut section, index as u32);
+    }
+    for (name, memory) in memories {
+        let ty = memory.ty();
+        write_name(&mut section, "");
+        write_name(&mut section, name);
+        section.push(0x02);
+        section.push(ty.maximum.is_some() as u8 | (ty.shared as u8) << 1);
+        write_u32(&mut section, ty.minimum.0);
+        if let Some(maximum) = ty.maximum {
+            write_u32(&mut section, maximum.0);
+        }
+    }
+    write_section(&mut binary, 2, &section);
+
+    let mut section = Vec::new();
+    write_u32(&mut section, (imports.len() + memories.len()) as u32);
+    for index in 0..imports.len() {
+        write_name(
+            &mut section,
+            &format!("{}{}", FUNCTION_EXPORT_PREFIX, index),
+        );
+        section.push(0x00);
+        write_u32(&mut section, index as u32);
+    }
+    for (index, (name, _)) in memories.iter().enumerate() {
+        write_name(&mut section, name);
+        section.push(0x02);
+        write_u32(&mut section, index as u32);
+    }
+    write_section(&mut binary, 7, &section);
+
+    Ok(binary)
+}
+
+fn encode_type(ty: ValType) -> u8 {
+    match ty {
+        ValType::I32 => 0x7f,
+        ValType::I64 => 0x7e,
+        ValType::F32 => 0x7d,
+        ValType::F64 => 0x7c,
+        ValType::V128 => 0x7b,
+        ValType::FuncRef => 0x70,
+        ValType::ExternRef => 0x6f,
+    }
+}
+
+fn write_section(output: &mut Vec<u8>, id: u8, body: &[u8]) {
+    output.push(id);
+    write_u32(output, body.len() as u32);
+    output.extend_from_slice(body);
+}
+
+fn write_u32(output: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn write_name(output: &mut Vec<u8>, name: &str) {
+    write_u32(output, name.len() as u32);
+    output.extend_from_slice(name.as_bytes());
+}
+
+fn write_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    output.extend_from_slice(bytes);
+}
+
+fn encode_values(values: &[Val]) -> Result<Vec<u8>, RuntimeError> {
+    let mut output = Vec::new();
+    output.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        match value {
+            Val::I32(value) => {
+                output.push(0);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Val::I64(value) => {
+                output.push(1);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Val::F32(value) => {
+                output.push(2);
+                output.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Val::F64(value) => {
+                output.push(3);
+                output.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Val::V128(value) => {
+                output.push(4);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Val::ExternRef(_) | Val::FuncRef(_) => {
+                return Err(RuntimeError::new("references can't be recorded"))
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Encode `call`, see [`TraceReader::read_call`] for the layout.
+fn write_call(output: &mut Vec<u8>, call: &TraceCall) -> Result<(), RuntimeError> {
+    write_bytes(output, call.module.as_bytes());
+    write_bytes(output, call.field.as_bytes());
+    output.extend_from_slice(&encode_values(&call.args)?);
+    match &call.outcome {
+        Ok(results) => {
+            output.push(0);
+            output.extend_from_slice(&encode_values(results)?);
+        }
+        Err(message) => {
+            output.push(1);
+            write_bytes(output, message.as_bytes());
+        }
+    }
+    output.extend_from_slice(&(call.memory_sizes.len() as u32).to_le_bytes());
+    for (name, size) in call.memory_sizes.iter() {
+        write_bytes(output, name.as_bytes());
+        output.extend_from_slice(&size.0.to_le_bytes());
+    }
+    output.extend_from_slice(&(call.memory_writes.len() as u32).to_le_bytes());
+    for write in call.memory_writes.iter() {
+        write_bytes(output, write.memory.as_bytes());
+        output.extend_from_slice(&write.offset.to_le_bytes());
+        write_bytes(output, &write.data);
+    }
+    Ok(())
+}
+
+struct TraceReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> TraceReader<'a> {
+    /// Read a call:
+    ///
+    /// ```text
+    /// module | field | args | outcome (u8) | results or message | sizes | writes
+    /// ```
+    ///
+    /// Strings and byte arrays are prefixed with their length (u64 LE),
+    /// lists with their length (u32 LE).
+    fn read_call(&mut self) -> Result<TraceCall, TraceError> {
+        let module = self.read_string()?;
+        let field = self.read_string()?;
+        let args = self.read_values()?;
+        let outcome = match self.read_u8()? {
+            0 => Ok(self.read_values()?),
+            1 => Err(self.read_string()?),
+            outcome => return Err(TraceError::Invalid(format!("unknown outcome {}", outcome))),
+        };
+        let mut memory_sizes = Vec::new();
+        for _ in 0..self.read_u32()? {
+            memory_sizes.push((self.read_string()?, Pages(self.read_u32()?)));
+        }
+        let mut memory_writes = Vec::new();
+        for _ in 0..self.read_u32()? {
+            memory_writes.push(MemoryWrite {
+                memory: self.read_string()?,
+                offset: self.read_u64()?,
+                data: self.read_bytes()?.to_vec(),
+            });
+        }
+        Ok(TraceCall {
+            module,
+            field,
+            args,
+            outcome,
+            memory_sizes,
+            memory_writes,
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TraceError> {
+        if self.bytes.len() < len {
+            return Err(TraceError::Invalid("the trace is truncated".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, TraceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, TraceError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, TraceError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], TraceError> {
+        let len = self.read_u64()?;
+        self.take(len as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, TraceError> {
+        String::from_utf8(self.read_bytes()?.to_vec())
+            .map_err(|_| TraceError::Invalid("a string isn't UTF-8".to_string()))
+    }
+
+    fn read_values(&mut self) -> Result<Vec<Val>, TraceError> {
+        (0..self.read_u32()?)
+            .map(|_| {
+                Ok(match self.read_u8()? {
+                    0 => Val::I32(self.read_u32()? as i32),
+                    1 => Val::I64(self.read_u64()? as i64),
+                    2 => Val::F32(f32::from_bits(self.read_u32()?)),
+                    3 => Val::F64(f64::from_bits(self.read_u64()?)),
+                    4 => Val::V128(u128::from_le_bytes(self.take(16)?.try_into().unwrap())),
+                    tag => return Err(TraceError::Invalid(format!("unknown value type {}", tag))),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_coalesced() {
+        let before = vec![0; 2 * CHUNK_SIZE];
+        let mut after = before.clone();
+        after[10] = 1;
+        after[20] = 2;
+        after[CHUNK_SIZE + 100] = 3;
+        after.extend_from_slice(&[0, 0, 4]);
+
+        let changes = changes(&before, &after);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].0, 10);
+        assert_eq!(changes[0].1.len(), 11);
+        assert_eq!(changes[1], (CHUNK_SIZE + 100, &[3][..]));
+        assert_eq!(changes[2], (2 * CHUNK_SIZE + 2, &[4][..]));
+    }
+
+    #[test]
+    fn calls_round_trip() {
+        let call = TraceCall {
+            module: "env".to_string(),
+            field: "now".to_string(),
+            args: vec![Val::I32(-1), Val::F64(0.5)],
+            outcome: Ok(vec![Val::I64(42), Val::V128(7)]),
+            memory_sizes: vec![("memory".to_string(), Pages(3))],
+            memory_writes: vec![MemoryWrite {
+                memory: "memory".to_string(),
+                offset: 1024,
+                data: b"hello".to_vec(),
+            }],
+        };
+        let mut trace = MAGIC_HEADER.to_vec();
+        write_call(&mut trace, &call).unwrap();
+        write_call(
+            &mut trace,
+            &TraceCall {
+                outcome: Err("exited".to_string()),
+                ..call.clone()
+            },
+        )
+        .unwrap();
+
+        let calls = read_trace(&trace[..]).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].field, "now");
+        assert_eq!(calls[0].args, call.args);
+        assert_eq!(calls[0].outcome, call.outcome);
+        assert_eq!(calls[0].memory_sizes, call.memory_sizes);
+        assert_eq!(calls[0].memory_writes, call.memory_writes);
+        assert_eq!(calls[1].outcome, Err("exited".to_string()));
+
+        assert!(read_trace(&trace[..trace.len() - 1]).is_err());
+        assert!(read_trace(&b"not a trace"[..]).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Signing and verification of serialized modules.
+//!
+//! A signed artifact wraps the bytes produced by [`Module::serialize`]
+//! in an envelope that carries some metadata and an ed25519 signature
+//! over the whole envelope. The layout is:
+//!
+//! ```text
+//! magic (16 bytes) | metadata length (u32 LE) | metadata | payload length (u64 LE) | payload | signature (64 bytes)
+//! ```
+//!
+//! The signature covers every byte preceding it, so tampering with
+//! either the metadata or the payload is detected before the payload
+//! is handed to the `Engine`.
+//!
+//! [`Module::serialize`]: crate::Module::serialize
+
+use std::convert::TryInto;
+use wasmer_engine::DeserializeError;
+
+pub use ed25519_dalek::{
+    Keypair, PublicKey, SecretKey, Signature, KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH,
+    SIGNATURE_LENGTH,
+};
+use ed25519_dalek::{Signer, Verifier};
+
+/// The magic header of a signed artifact.
+const MAGIC_HEADER: &[u8; 16] = b"\0wasmer-signed\0\0";
+
+/// The metadata stored (and signed) alongside a serialized module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedArtifactMetadata {
+    /// The Wasmer version that produced the artifact.
+    pub wasmer_version: String,
+}
+
+impl SignedArtifactMetadata {
+    fn current() -> Self {
+        Self {
+            wasmer_version: crate::VERSION.to_string(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let version = self.wasmer_version.as_bytes();
+        let mut bytes = Vec::with_capacity(4 + version.len());
+        bytes.extend_from_slice(&(version.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(version);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let mut reader = Reader::new(bytes);
+        let version_len = reader.read_u32()? as usize;
+        let version = reader.read_bytes(version_len)?;
+        let wasmer_version = String::from_utf8(version.to_vec()).map_err(|e| {
+            DeserializeError::CorruptedBinary(format!("invalid signed metadata: {}", e))
+        })?;
+        Ok(Self { wasmer_version })
+    }
+}
+
+/// Returns true if the provided bytes look like a signed artifact.
+///
+/// This only checks the magic header; the signature is not verified.
+pub fn is_signed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC_HEADER)
+}
+
+/// Wraps a serialized module into a signed envelope.
+pub(crate) fn sign(payload: &[u8], keypair: &Keypair) -> Vec<u8> {
+    let metadata = SignedArtifactMetadata::current().to_bytes();
+    let mut signed = Vec::with_capacity(
+        MAGIC_HEADER.len() + 4 + metadata.len() + 8 + payload.len() + SIGNATURE_LENGTH,
+    );
+    signed.extend_from_slice(MAGIC_HEADER);
+    signed.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    signed.extend_from_slice(&metadata);
+    signed.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    signed.extend_from_slice(payload);
+    let signature = keypair.sign(&signed);
+    signed.extend_from_slice(&signature.to_bytes());
+    signed
+}
+
+/// Verifies a signed envelope against the provided public key and
+/// returns the metadata and the inner serialized module.
+pub fn verify<'a>(
+    bytes: &'a [u8],
+    public_key: &PublicKey,
+) -> Result<(SignedArtifactMetadata, &'a [u8]), DeserializeError> {
+    if !is_signed(bytes) {
+        return Err(DeserializeError::InvalidSignature(
+            "the artifact is not signed".to_string(),
+        ));
+    }
+    if bytes.len() < MAGIC_HEADER.len() + SIGNATURE_LENGTH {
+        return Err(DeserializeError::CorruptedBinary(
+            "the signed artifact is truncated".to_string(),
+        ));
+    }
+    let (signed, signature) = bytes.split_at(bytes.len() - SIGNATURE_LENGTH);
+    let signature = Signature::from_bytes(signature).map_err(|e| {
+        DeserializeError::InvalidSignature(format!("malformed artifact signature: {}", e))
+    })?;
+    public_key.verify(signed, &signature).map_err(|_| {
+        DeserializeError::InvalidSignature(
+            "the artifact signature does not match the provided public key".to_string(),
+        )
+    })?;
+
+    // From this point on, the contents are trusted by the key holder.
+    parse(signed)
+}
+
+/// Returns the inner serialized module of a signed envelope, without
+/// verifying the signature.
+///
+/// This is only meant to inspect the artifact, e.g. to find out which
+/// `Engine` can load it; the contents must not be trusted.
+pub fn unverified_payload(bytes: &[u8]) -> Result<&[u8], DeserializeError> {
+    if !is_signed(bytes) || bytes.len() < MAGIC_HEADER.len() + SIGNATURE_LENGTH {
+        return Err(DeserializeError::CorruptedBinary(
+            "the artifact is not signed".to_string(),
+        ));
+    }
+    let (signed, _signature) = bytes.split_at(bytes.len() - SIGNATURE_LENGTH);
+    let (_metadata, payload) = parse(signed)?;
+    Ok(payload)
+}
+
+/// Parses the signed part of an envelope, i.e. everything but the
+/// signature.
+fn parse(signed: &[u8]) -> Result<(SignedArtifactMetadata, &[u8]), DeserializeError> {
+    let mut reader = Reader::new(&signed[MAGIC_HEADER.len()..]);
+    let metadata_len = reader.read_u32()? as usize;
+    let metadata = SignedArtifactMetadata::from_bytes(reader.read_bytes(metadata_len)?)?;
+    let payload_len = reader.read_u64()? as usize;
+    let payload = reader.read_bytes(payload_len)?;
+    if !reader.is_empty() {
+        return Err(DeserializeError::CorruptedBinary(
+            "unexpected trailing data in the signed artifact".to_string(),
+        ));
+    }
+    Ok((metadata, payload))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializeError> {
+        if self.bytes.len() < len {
+            return Err(DeserializeError::CorruptedBinary(
+                "the signed artifact is truncated".to_string(),
+            ));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DeserializeError> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DeserializeError> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
@@ -0,0 +1,202 @@
+//! Pre-initialization of modules, to run their initialization at build
+//! time rather than every time they start.
+//!
+//! [`Instance::snapshot_init`] calls an initialization function exported
+//! by the module, then writes a new module binary whose data segments,
+//! element segments and global initializers hold the state the instance
+//! ended in. Instantiating it is like instantiating the original module and
+//! calling the initialization function, without the cost of the latter.
+
+use crate::exports::ExportError;
+use crate::instance::Instance;
+use std::convert::TryInto;
+use thiserror::Error;
+use wasmer_compiler::{rewrite_module, ModuleInitialization, WasmError};
+use wasmer_engine::RuntimeError;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{
+    DataIndex, DataInitializer, DataInitializerLocation, ElemIndex, FunctionIndex, GlobalInit,
+    LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, Pages, TableInitializer, Type, V128,
+    WASM_PAGE_SIZE,
+};
+use wasmer_vm::SnapshotError;
+
+/// Runs of zeros shorter than this are kept inside data segments rather
+/// than splitting them, as a segment costs a few bytes too.
+const MIN_ZEROS_BETWEEN_SEGMENTS: usize = 16;
+
+/// An error while pre-initializing a module.
+#[derive(Error, Debug)]
+pub enum SnapshotInitError {
+    /// The initialization function isn't exported.
+    #[error(transparent)]
+    Export(ExportError),
+
+    /// The initialization function failed.
+    #[error(transparent)]
+    Runtime(RuntimeError),
+
+    /// The state of the instance couldn't be captured.
+    #[error(transparent)]
+    Snapshot(SnapshotError),
+
+    /// The module couldn't be rewritten.
+    #[error(transparent)]
+    Wasm(WasmError),
+}
+
+impl Instance {
+    /// Call the initialization function `init_func` exported by the
+    /// instance, then write a copy of the module binary `wasm` starting in
+    /// the state the instance ended in, see the
+    /// [module documentation](crate::snapshot_init).
+    ///
+    /// `wasm` must be the binary the module of the instance was compiled
+    /// from. The initialization function is removed from the exports of
+    /// the new module, and so is its start function, which already ran.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance, Value};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasm = wasmer::wat2wasm(br#"
+    ///     (module
+    ///       (global $answer (mut i32) (i32.const 0))
+    ///       (func (export "init")
+    ///         (global.set $answer (i32.const 42)))
+    ///       (func (export "answer") (result i32)
+    ///         (global.get $answer)))
+    /// "#)?;
+    /// let module = Module::new(&store, &wasm)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// let initialized = instance.snapshot_init(&wasm, "init")?;
+    ///
+    /// let module = Module::new(&store, &initialized)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// let answer = instance.exports.get_function("answer")?.call(&[])?;
+    /// assert_eq!(answer[0], Value::I32(42));
+    /// assert!(instance.exports.get_function("init").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn snapshot_init(
+        &self,
+        wasm: &[u8],
+        init_func: &str,
+    ) -> Result<Vec<u8>, SnapshotInitError> {
+        self.exports
+            .get_function(init_func)
+            .map_err(SnapshotInitError::Export)?
+            .call(&[])
+            .map_err(SnapshotInitError::Runtime)?;
+        let snapshot = self.snapshot().map_err(SnapshotInitError::Snapshot)?;
+        let info = self.module().info();
+
+        let mut initialization = ModuleInitialization {
+            removed_exports: vec![init_func.to_string()],
+            ..Default::default()
+        };
+
+        for (index, contents) in snapshot.memories.iter().enumerate() {
+            initialization
+                .memory_sizes
+                .push(Pages((contents.len() / WASM_PAGE_SIZE) as u32));
+            let memory_index = info.memory_index(LocalMemoryIndex::new(index));
+            for (offset, data) in non_zero_runs(contents) {
+                initialization.data_initializers.push(DataInitializer {
+                    location: DataInitializerLocation {
+                        memory_index,
+                        base: None,
+                        offset,
+                    },
+                    data,
+                });
+            }
+        }
+
+        for (local_index, bytes) in snapshot.globals.iter() {
+            let index = info.global_index(LocalGlobalIndex::from_u32(*local_index));
+            let init = match info.globals[index].ty {
+                Type::I32 => {
+                    GlobalInit::I32Const(i32::from_ne_bytes(bytes[..4].try_into().unwrap()))
+                }
+                Type::I64 => {
+                    GlobalInit::I64Const(i64::from_ne_bytes(bytes[..8].try_into().unwrap()))
+                }
+                Type::F32 => {
+                    GlobalInit::F32Const(f32::from_ne_bytes(bytes[..4].try_into().unwrap()))
+                }
+                Type::F64 => {
+                    GlobalInit::F64Const(f64::from_ne_bytes(bytes[..8].try_into().unwrap()))
+                }
+                Type::V128 => GlobalInit::V128Const(V128::from(*bytes)),
+                // `snapshot` refuses globals holding references
+                Type::ExternRef | Type::FuncRef => unreachable!(),
+            };
+            initialization.globals.push((index, init));
+        }
+
+        for (index, elements) in snapshot.tables.iter().enumerate() {
+            initialization.table_sizes.push(elements.len() as u32);
+            let table_index = info.table_index(LocalTableIndex::new(index));
+            let mut start = 0;
+            while start < elements.len() {
+                if elements[start].is_none() {
+                    start += 1;
+                    continue;
+                }
+                let functions = elements[start..]
+                    .iter()
+                    .take_while(|element| element.is_some())
+                    .map(|element| FunctionIndex::from_u32(element.unwrap()))
+                    .collect::<Vec<_>>();
+                let len = functions.len();
+                initialization.table_initializers.push(TableInitializer {
+                    table_index,
+                    base: None,
+                    offset: start,
+                    elements: functions.into_boxed_slice(),
+                });
+                start += len;
+            }
+        }
+
+        initialization.dropped_elements = snapshot
+            .dropped_elements
+            .iter()
+            .map(|index| ElemIndex::from_u32(*index))
+            .collect();
+        initialization.dropped_data = snapshot
+            .dropped_data
+            .iter()
+            .map(|index| DataIndex::from_u32(*index))
+            .collect();
+
+        rewrite_module(wasm, &initialization).map_err(SnapshotInitError::Wasm)
+    }
+}
+
+/// Split `contents` into the runs of bytes which aren't zero, with their
+/// offset.
+fn non_zero_runs(contents: &[u8]) -> Vec<(usize, &[u8])> {
+    let mut runs: Vec<(usize, &[u8])> = Vec::new();
+    let mut offset = 0;
+    while offset < contents.len() {
+        if contents[offset] == 0 {
+            offset += 1;
+            continue;
+        }
+        let len = contents[offset..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(contents.len() - offset);
+        match runs.last_mut() {
+            Some((start, run)) if offset - (*start + run.len()) < MIN_ZEROS_BETWEEN_SEGMENTS => {
+                *run = &contents[*start..offset + len];
+            }
+            _ => runs.push((offset, &contents[offset..offset + len])),
+        }
+        offset += len;
+    }
+    runs
+}
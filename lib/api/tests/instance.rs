@@ -37,3 +37,298 @@ fn exports_work_after_multiple_instances_have_been_freed() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn restore_from_snapshot() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (memory (export "memory") 1 4)
+      (global $count (mut i32) (i32.const 0))
+      (table 2 funcref)
+      (type $get_t (func (result i32)))
+      (func $one (type $get_t) (i32.const 1))
+      (func $two (type $get_t) (i32.const 2))
+      (elem (i32.const 0) $one)
+      (elem $functions func $two)
+      (data $greeting "hello")
+      (func $start
+        (global.set $count (i32.add (global.get $count) (i32.const 100))))
+      (start $start)
+      (func (export "step") (result i32)
+        (global.set $count (i32.add (global.get $count) (i32.const 1)))
+        (drop (memory.grow (i32.const 1)))
+        (memory.init $greeting (i32.const 65536) (i32.const 0) (i32.const 5))
+        (data.drop $greeting)
+        (table.init $functions (i32.const 1) (i32.const 0) (i32.const 1))
+        (elem.drop $functions)
+        (global.get $count))
+      (func (export "call") (param i32) (result i32)
+        (call_indirect (type $get_t) (local.get 0)))
+      (func (export "count") (result i32)
+        (global.get $count)))
+"#,
+    )?;
+
+    let import_object = ImportObject::new();
+    let instance = Instance::new(&module, &import_object)?;
+    assert_eq!(
+        instance.exports.get_function("step")?.call(&[])?.into_vec(),
+        vec![Value::I32(101)],
+    );
+    let snapshot = instance.snapshot()?;
+    assert_eq!(snapshot.tables, vec![vec![Some(0), Some(1)]]);
+    assert_eq!(snapshot.dropped_elements, vec![1]);
+    assert_eq!(snapshot.dropped_data, vec![0]);
+
+    let restored = Instance::restore(&module, &snapshot, &import_object)?;
+    // The start function isn't called again
+    assert_eq!(
+        restored
+            .exports
+            .get_function("count")?
+            .call(&[])?
+            .into_vec(),
+        vec![Value::I32(101)],
+    );
+    let memory = restored.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(2));
+    let bytes = unsafe { &memory.data_unchecked()[65536..65541] };
+    assert_eq!(bytes, b"hello");
+    let call = restored.exports.get_function("call")?;
+    assert_eq!(call.call(&[Value::I32(1)])?.into_vec(), vec![Value::I32(2)]);
+    // The data segment stays dropped
+    assert!(restored.exports.get_function("step")?.call(&[]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_init() -> Result<()> {
+    let store = Store::default();
+    let wasm = wat2wasm(
+        br#"
+    (module
+      (memory (export "memory") 1)
+      (global $calls (mut i32) (i32.const 0))
+      (global $scale (mut i64) (i64.const 1))
+      (table 2 funcref)
+      (type $get_t (func (result i32)))
+      (func $one (type $get_t) (i32.const 1))
+      (func $two (type $get_t) (i32.const 2))
+      (elem $functions func $two)
+      (data $greeting "hello")
+      (data $unused "unused")
+      (func $start
+        (global.set $calls (i32.add (global.get $calls) (i32.const 1))))
+      (start $start)
+      (func (export "init")
+        (global.set $scale (i64.const -5))
+        (drop (memory.grow (i32.const 1)))
+        (memory.init $greeting (i32.const 65536) (i32.const 0) (i32.const 5))
+        (data.drop $greeting)
+        (i32.store (i32.const 100) (i32.const 7))
+        (table.init $functions (i32.const 1) (i32.const 0) (i32.const 1)))
+      (func (export "calls") (result i32)
+        (global.get $calls))
+      (func (export "scale") (result i64)
+        (global.get $scale))
+      (func (export "call") (param i32) (result i32)
+        (call_indirect (type $get_t) (local.get 0)))
+      (func (export "init_greeting")
+        (memory.init $greeting (i32.const 0) (i32.const 0) (i32.const 5)))
+      (func (export "init_unused")
+        (memory.init $unused (i32.const 0) (i32.const 0) (i32.const 6))))
+"#,
+    )?;
+    let module = Module::new(&store, &wasm)?;
+    let import_object = ImportObject::new();
+    let instance = Instance::new(&module, &import_object)?;
+    let initialized = instance.snapshot_init(&wasm, "init")?;
+
+    let module = Module::new(&store, &initialized)?;
+    let instance = Instance::new(&module, &import_object)?;
+    assert!(instance.exports.get_function("init").is_err());
+    // The start function isn't called again
+    let calls = instance.exports.get_function("calls")?;
+    assert_eq!(calls.call(&[])?.into_vec(), vec![Value::I32(1)]);
+    let scale = instance.exports.get_function("scale")?;
+    assert_eq!(scale.call(&[])?.into_vec(), vec![Value::I64(-5)]);
+
+    let memory = instance.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(2));
+    let bytes = unsafe { memory.data_unchecked() };
+    assert_eq!(&bytes[100..104], &7u32.to_le_bytes());
+    assert_eq!(&bytes[65536..65541], b"hello");
+
+    let call = instance.exports.get_function("call")?;
+    assert_eq!(call.call(&[Value::I32(1)])?.into_vec(), vec![Value::I32(2)]);
+    assert!(call.call(&[Value::I32(0)]).is_err());
+
+    // The passive segments keep their indices, dropped or not
+    assert!(instance
+        .exports
+        .get_function("init_greeting")?
+        .call(&[])
+        .is_err());
+    instance.exports.get_function("init_unused")?.call(&[])?;
+    assert_eq!(unsafe { &memory.data_unchecked()[..6] }, b"unused");
+
+    Ok(())
+}
+
+#[test]
+fn record_and_replay_imports() -> Result<()> {
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::Arc;
+    use wasmer::record::{read_trace, RecordingResolver, ReplayResolver};
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        counter: Arc<AtomicI32>,
+        #[wasmer(export)]
+        memory: LazyInit<Memory>,
+    }
+
+    fn next(env: &Env, ptr: WasmPtr<i32>) -> i32 {
+        let value = env.counter.fetch_add(1, Ordering::SeqCst);
+        let memory = env.memory_ref().unwrap();
+        ptr.deref(memory).unwrap().set(value * 2);
+        value
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "next" (func $next (param i32) (result i32)))
+      (memory (export "memory") 1)
+      (func (export "run") (result i32)
+        (i32.add
+          (call $next (i32.const 16))
+          (i32.load (i32.const 16)))))
+"#,
+    )?;
+    let run = |resolver: &dyn Resolver| -> Result<Vec<Value>> {
+        let instance = Instance::new(&module, resolver)?;
+        let run = instance.exports.get_function("run")?;
+        Ok(vec![run.call(&[])?[0].clone(), run.call(&[])?[0].clone()])
+    };
+    let import_object = |first: i32| {
+        let env = Env {
+            counter: Arc::new(AtomicI32::new(first)),
+            memory: LazyInit::new(),
+        };
+        imports! {
+            "env" => {
+                "next" => Function::new_native_with_env(&store, env, next),
+            },
+        }
+    };
+
+    let path = std::env::temp_dir().join("wasmer-record-and-replay-imports.trace");
+    let resolver =
+        RecordingResolver::new(&store, import_object(10), std::fs::File::create(&path)?)?;
+    assert_eq!(run(&resolver)?, vec![Value::I32(30), Value::I32(33)]);
+
+    let calls = read_trace(std::fs::File::open(&path)?)?;
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1].field, "next");
+    assert_eq!(calls[1].args, vec![Value::I32(16)]);
+    assert_eq!(calls[1].outcome, Ok(vec![Value::I32(11)]));
+    // Only the byte which changed from the first call is recorded.
+    assert_eq!(calls[1].memory_writes[0].offset, 16);
+    assert_eq!(calls[1].memory_writes[0].data, vec![22]);
+
+    // The host function isn't called, so the counter doesn't matter.
+    let resolver = ReplayResolver::new(&store, import_object(100), std::fs::File::open(&path)?)?;
+    assert_eq!(run(&resolver)?, vec![Value::I32(30), Value::I32(33)]);
+
+    // The trace holds two calls only.
+    let resolver = ReplayResolver::new(&store, import_object(100), std::fs::File::open(&path)?)?;
+    let instance = Instance::new(&module, &resolver)?;
+    let run = instance.exports.get_function("run")?;
+    run.call(&[])?;
+    run.call(&[])?;
+    assert!(run.call(&[]).is_err());
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn record_writes_across_pages_and_growth() -> Result<()> {
+    use wasmer::record::{read_trace, RecordingResolver, ReplayResolver};
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        #[wasmer(export)]
+        memory: LazyInit<Memory>,
+    }
+
+    fn write(env: &Env) {
+        let memory = env.memory_ref().unwrap();
+        memory.grow(1).unwrap();
+        let view = memory.view::<u8>();
+        view[4095].set(1);
+        view[4096].set(2);
+        view[65536 + 5].set(3);
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "write" (func $write))
+      (memory (export "memory") 1)
+      (func (export "run") (result i32)
+        (i32.store8 (i32.const 100) (i32.const 9))
+        (call $write)
+        (i32.add
+          (i32.load8_u (i32.const 100))
+          (i32.add
+            (i32.load16_u (i32.const 4095))
+            (i32.load8_u (i32.const 65541))))))
+"#,
+    )?;
+    let run = |resolver: &dyn Resolver| -> Result<Value> {
+        let instance = Instance::new(&module, resolver)?;
+        Ok(instance.exports.get_function("run")?.call(&[])?[0].clone())
+    };
+    let import_object = || {
+        let env = Env {
+            memory: LazyInit::new(),
+        };
+        imports! {
+            "env" => {
+                "write" => Function::new_native_with_env(&store, env, write),
+            },
+        }
+    };
+
+    let path = std::env::temp_dir().join("wasmer-record-writes-across-pages.trace");
+    let resolver = RecordingResolver::new(&store, import_object(), std::fs::File::create(&path)?)?;
+    assert_eq!(run(&resolver)?, Value::I32(9 + 0x0201 + 3));
+
+    let calls = read_trace(std::fs::File::open(&path)?)?;
+    assert_eq!(calls.len(), 1);
+    assert_eq!(
+        calls[0].memory_sizes,
+        vec![("memory".to_string(), Pages(2))]
+    );
+    // The write of the module itself isn't recorded.
+    let writes = &calls[0].memory_writes;
+    assert_eq!(writes.len(), 2);
+    assert_eq!((writes[0].offset, &writes[0].data[..]), (4095, &[1, 2][..]));
+    assert_eq!((writes[1].offset, &writes[1].data[..]), (65541, &[3][..]));
+
+    let resolver = ReplayResolver::new(&store, import_object(), std::fs::File::open(&path)?)?;
+    assert_eq!(run(&resolver)?, Value::I32(9 + 0x0201 + 3));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
@@ -246,3 +246,58 @@ fn calling_host_functions_with_negative_values_works() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "signing")]
+#[test]
+fn serialize_signed_roundtrip() -> Result<()> {
+    use wasmer::signing::{Keypair, SecretKey};
+
+    let store = Store::default();
+    let wat = r#"(module (func (export "answer") (result i32) (i32.const 42)))"#;
+    let module = Module::new(&store, wat)?;
+
+    let secret = SecretKey::from_bytes(&[7; 32])?;
+    let keypair = Keypair {
+        public: (&secret).into(),
+        secret,
+    };
+    let signed = module.serialize_signed(&keypair)?;
+    assert!(signing::is_signed(&signed));
+
+    let module = Module::deserialize_verified(&store, &signed, &keypair.public)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let answer = instance
+        .exports
+        .get_native_function::<(), i32>("answer")?
+        .call()?;
+    assert_eq!(answer, 42);
+
+    // Tampering with any of the signed bytes is detected.
+    let mut tampered = signed.clone();
+    let middle = tampered.len() / 2;
+    tampered[middle] ^= 0xff;
+    assert!(matches!(
+        Module::deserialize_verified(&store, &tampered, &keypair.public),
+        Err(DeserializeError::InvalidSignature(_))
+    ));
+
+    // A different key is rejected.
+    let other_secret = SecretKey::from_bytes(&[8; 32])?;
+    let other_public: signing::PublicKey = (&other_secret).into();
+    assert!(matches!(
+        Module::deserialize_verified(&store, &signed, &other_public),
+        Err(DeserializeError::InvalidSignature(_))
+    ));
+
+    // The payload can be inspected without the key.
+    let unsigned = module.serialize()?;
+    assert_eq!(signing::unverified_payload(&signed)?, &unsigned[..]);
+
+    // Unsigned artifacts are rejected.
+    assert!(matches!(
+        Module::deserialize_verified(&store, &unsigned, &keypair.public),
+        Err(DeserializeError::InvalidSignature(_))
+    ));
+
+    Ok(())
+}
@@ -36,6 +36,9 @@ const COMPILER_FEATURE_AS_C_DEFINE: &'static str = "WASMER_COMPILER_ENABLED";
 #[allow(unused)]
 const WASI_FEATURE_AS_C_DEFINE: &'static str = "WASMER_WASI_ENABLED";
 
+#[allow(unused)]
+const MIDDLEWARES_FEATURE_AS_C_DEFINE: &'static str = "WASMER_MIDDLEWARES_ENABLED";
+
 #[allow(unused)]
 const EMSCRIPTEN_FEATURE_AS_C_DEFINE: &'static str = "WASMER_EMSCRIPTEN_ENABLED";
 
@@ -86,6 +89,7 @@ fn build_wasm_c_api_headers(crate_dir: &str, out_dir: &str) {
     map_feature_as_c_define!("jit", JIT_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("compiler", COMPILER_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("wasi", WASI_FEATURE_AS_C_DEFINE, pre_header);
+    map_feature_as_c_define!("middlewares", MIDDLEWARES_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE, pre_header);
 
     add_wasmer_version(&mut pre_header);
@@ -236,6 +240,7 @@ fn new_builder(language: Language, crate_dir: &str, include_guard: &str, header:
         .with_define("feature", "jit", JIT_FEATURE_AS_C_DEFINE)
         .with_define("feature", "compiler", COMPILER_FEATURE_AS_C_DEFINE)
         .with_define("feature", "wasi", WASI_FEATURE_AS_C_DEFINE)
+        .with_define("feature", "middlewares", MIDDLEWARES_FEATURE_AS_C_DEFINE)
         .with_define("feature", "emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE)
 }
 
@@ -384,10 +389,18 @@ fn exclude_items_from_deprecated(builder: Builder) -> Builder {
 /// ones from the WASI API.
 fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
     builder
+        .exclude_item("wasi_clock_res_get_callback_t")
+        .exclude_item("wasi_clock_time_get_callback_t")
         .exclude_item("wasi_config_arg")
+        .exclude_item("wasi_config_capture_stderr_callback")
+        .exclude_item("wasi_config_capture_stdout_callback")
+        .exclude_item("wasi_config_clock")
         .exclude_item("wasi_config_env")
         .exclude_item("wasi_config_mapdir")
+        .exclude_item("wasi_config_memory_fs")
+        .exclude_item("wasi_config_memory_fs_write_file")
         .exclude_item("wasi_config_preopen_dir")
+        .exclude_item("wasi_config_random")
         .exclude_item("wasi_config_inherit_stderr")
         .exclude_item("wasi_config_inherit_stdin")
         .exclude_item("wasi_config_inherit_stdout")
@@ -404,13 +417,51 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasi_get_imports_inner")
         .exclude_item("wasi_get_start_function")
         .exclude_item("wasi_get_wasi_version")
+        .exclude_item("wasi_output_callback_t")
+        .exclude_item("wasi_random_get_callback_t")
         .exclude_item("wasi_version_t")
+        .exclude_item("wasm_config_push_middleware")
         .exclude_item("wasm_config_set_compiler")
         .exclude_item("wasm_config_set_engine")
+        .exclude_item("wasm_config_set_features")
+        .exclude_item("wasm_config_set_target")
         .exclude_item("wasm_module_name")
         .exclude_item("wasm_module_set_name")
         .exclude_item("wasmer_compiler_t")
+        .exclude_item("wasmer_cpu_features_add")
+        .exclude_item("wasmer_cpu_features_delete")
+        .exclude_item("wasmer_cpu_features_new")
+        .exclude_item("wasmer_cpu_features_t")
         .exclude_item("wasmer_engine_t")
+        .exclude_item("wasmer_features_bulk_memory")
+        .exclude_item("wasmer_features_delete")
+        .exclude_item("wasmer_features_memory64")
+        .exclude_item("wasmer_features_module_linking")
+        .exclude_item("wasmer_features_multi_memory")
+        .exclude_item("wasmer_features_multi_value")
+        .exclude_item("wasmer_features_new")
+        .exclude_item("wasmer_features_reference_types")
+        .exclude_item("wasmer_features_simd")
+        .exclude_item("wasmer_features_t")
+        .exclude_item("wasmer_features_tail_call")
+        .exclude_item("wasmer_features_threads")
+        .exclude_item("wasmer_metering_as_middleware")
+        .exclude_item("wasmer_metering_cost_function_t")
+        .exclude_item("wasmer_metering_delete")
+        .exclude_item("wasmer_metering_get_remaining_points")
+        .exclude_item("wasmer_metering_new")
+        .exclude_item("wasmer_metering_points_are_exhausted")
+        .exclude_item("wasmer_metering_set_remaining_points")
+        .exclude_item("wasmer_metering_t")
+        .exclude_item("wasmer_middleware_t")
+        .exclude_item("wasmer_parser_operator_t")
+        .exclude_item("wasmer_target_delete")
+        .exclude_item("wasmer_target_new")
+        .exclude_item("wasmer_target_t")
+        .exclude_item("wasmer_triple_delete")
+        .exclude_item("wasmer_triple_new")
+        .exclude_item("wasmer_triple_new_from_host")
+        .exclude_item("wasmer_triple_t")
         .exclude_item("wat2wasm")
 }
 
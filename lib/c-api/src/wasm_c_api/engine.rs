@@ -1,3 +1,9 @@
+#[cfg(feature = "compiler")]
+use super::features::wasmer_features_t;
+#[cfg(feature = "middlewares")]
+use super::middlewares::wasmer_middleware_t;
+#[cfg(feature = "compiler")]
+use super::target::wasmer_target_t;
 use crate::error::{update_last_error, CApiError};
 use cfg_if::cfg_if;
 use std::sync::Arc;
@@ -84,7 +90,8 @@ impl Default for wasmer_engine_t {
     }
 }
 
-/// A configuration holds the compiler and the engine used by the store.
+/// A configuration holds the compiler, its middlewares, the target,
+/// the features and the engine used by the store.
 ///
 /// cbindgen:ignore
 #[derive(Debug, Default)]
@@ -93,6 +100,12 @@ pub struct wasm_config_t {
     engine: wasmer_engine_t,
     #[cfg(feature = "compiler")]
     compiler: wasmer_compiler_t,
+    #[cfg(feature = "middlewares")]
+    pub(super) middlewares: Vec<wasmer_middleware_t>,
+    #[cfg(feature = "compiler")]
+    target: Option<wasmer_target_t>,
+    #[cfg(feature = "compiler")]
+    features: Option<wasmer_features_t>,
 }
 
 /// Create a new default Wasmer configuration.
@@ -174,6 +187,48 @@ pub extern "C" fn wasm_config_set_compiler(
     config.compiler = compiler;
 }
 
+/// Updates the configuration to specify a particular target to
+/// compile for. The host is the target by default.
+///
+/// This function takes ownership of `target`.
+///
+/// The object-file engine doesn't support a target:
+/// [`wasm_engine_new_with_config`] fails for it when one is set.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the [`target`][super::target] module.
+#[cfg(feature = "compiler")]
+#[no_mangle]
+pub extern "C" fn wasm_config_set_target(config: &mut wasm_config_t, target: Box<wasmer_target_t>) {
+    config.target = Some(*target);
+}
+
+/// Updates the configuration to specify the WebAssembly features to
+/// enable. By default, the compiler enables the features it supports
+/// for the target.
+///
+/// This function takes ownership of `features`.
+///
+/// The object-file engine doesn't support features:
+/// [`wasm_engine_new_with_config`] fails for it when they are set.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the [`features`][super::features] module.
+#[cfg(feature = "compiler")]
+#[no_mangle]
+pub extern "C" fn wasm_config_set_features(
+    config: &mut wasm_config_t,
+    features: Box<wasmer_features_t>,
+) {
+    config.features = Some(*features);
+}
+
 /// Updates the configuration to specify a particular engine to use.
 ///
 /// This is a Wasmer-specific function.
@@ -413,11 +468,29 @@ pub extern "C" fn wasm_engine_new_with_config(
                 },
             };
 
+            #[cfg(feature = "middlewares")]
+            for middleware in config.middlewares {
+                compiler_config.push_middleware(middleware.inner);
+            }
+
+            let target = config.target.map(|target| target.inner);
+            let features = config.features.map(|features| features.inner);
+
             let inner: Arc<dyn Engine + Send + Sync> = match config.engine {
                 wasmer_engine_t::JIT => {
                     cfg_if! {
                         if #[cfg(feature = "jit")] {
-                            Arc::new(JIT::new(compiler_config).engine())
+                            let mut builder = JIT::new(compiler_config);
+
+                            if let Some(target) = target {
+                                builder = builder.target(target);
+                            }
+
+                            if let Some(features) = features {
+                                builder = builder.features(features);
+                            }
+
+                            Arc::new(builder.engine())
                         } else {
                             return return_with_error("Wasmer has not been compiled with the `jit` feature.");
                         }
@@ -426,7 +499,17 @@ pub extern "C" fn wasm_engine_new_with_config(
                 wasmer_engine_t::NATIVE => {
                     cfg_if! {
                         if #[cfg(feature = "native")] {
-                            Arc::new(Native::new(compiler_config).engine())
+                            let mut builder = Native::new(compiler_config);
+
+                            if let Some(target) = target {
+                                builder = builder.target(target);
+                            }
+
+                            if let Some(features) = features {
+                                builder = builder.features(features);
+                            }
+
+                            Arc::new(builder.engine())
                         } else {
                             return return_with_error("Wasmer has not been compiled with the `native` feature.");
                         }
@@ -437,6 +520,10 @@ pub extern "C" fn wasm_engine_new_with_config(
                         // There are currently no uses of the object-file engine + compiler from the C API.
                         // So we run in headless mode.
                         if #[cfg(feature = "object-file")] {
+                            if target.is_some() || features.is_some() {
+                                return return_with_error("The `object-file` engine doesn't compile, so it doesn't support a target or features.");
+                            }
+
                             Arc::new(ObjectFile::headless().engine())
                         } else {
                             return return_with_error("Wasmer has not been compiled with the `object-file` feature.");
@@ -500,4 +587,25 @@ mod tests {
         })
         .success();
     }
+
+    #[cfg(all(feature = "compiler", feature = "object-file"))]
+    #[test]
+    fn test_object_file_engine_rejects_features() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+
+            int main() {
+                wasm_config_t* config = wasm_config_new();
+                wasm_config_set_engine(config, OBJECT_FILE);
+                wasm_config_set_features(config, wasmer_features_new());
+
+                wasm_engine_t* engine = wasm_engine_new_with_config(config);
+                assert(!engine);
+                assert(wasmer_last_error_length() > 0);
+
+                return 0;
+            }
+        })
+        .success();
+    }
 }
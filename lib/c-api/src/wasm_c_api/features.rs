@@ -0,0 +1,306 @@
+//! Unofficial API for the WebAssembly features, which are the
+//! [WebAssembly proposals] a module compiled by the engine can use.
+//!
+//! The features are set on a configuration with
+//! [`wasm_config_set_features`][super::engine::wasm_config_set_features].
+//! Without them, the compiler enables the features it supports for
+//! the target.
+//!
+//! [WebAssembly proposals]: https://github.com/WebAssembly/proposals
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Declare the features.
+//!     wasmer_features_t* features = wasmer_features_new();
+//!
+//!     assert(wasmer_features_threads(features, true));
+//!     assert(wasmer_features_reference_types(features, true));
+//!     assert(wasmer_features_simd(features, true));
+//!
+//!     // Create the configuration, and set the features.
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_set_features(config, features);
+//!
+//!     // Create the engine.
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!
+//!     // Check we have an engine!
+//!     assert(engine);
+//!
+//!     // Free everything.
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use wasmer_compiler::Features;
+
+/// Wasmer-specific type wrapping the WebAssembly features.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct wasmer_features_t {
+    pub(crate) inner: Features,
+}
+
+/// Creates the default features, where only the bulk memory and the
+/// multi-value proposals are enabled.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_new() -> Box<wasmer_features_t> {
+    Box::new(wasmer_features_t {
+        inner: Features::new(),
+    })
+}
+
+/// Deletes features.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_delete(_features: Option<Box<wasmer_features_t>>) {}
+
+/// Configures whether the [threads proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [threads proposal]: https://github.com/webassembly/threads
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_threads(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.threads(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [reference types proposal] is enabled.
+/// Enabling it also enables the bulk memory proposal.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [reference types proposal]: https://github.com/webassembly/reference-types
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_reference_types(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.reference_types(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [SIMD proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [SIMD proposal]: https://github.com/webassembly/simd
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_simd(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.simd(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [bulk memory proposal] is enabled.
+/// Disabling it also disables the reference types proposal.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [bulk memory proposal]: https://github.com/webassembly/bulk-memory-operations
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_bulk_memory(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.bulk_memory(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [multi-value proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [multi-value proposal]: https://github.com/webassembly/multi-value
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_multi_value(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.multi_value(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [tail call proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [tail call proposal]: https://github.com/webassembly/tail-call
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_tail_call(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.tail_call(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [module linking proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [module linking proposal]: https://github.com/webassembly/module-linking
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_module_linking(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.module_linking(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [multi-memory proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [multi-memory proposal]: https://github.com/webassembly/multi-memory
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_multi_memory(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.multi_memory(enable);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Configures whether the [64-bit memory proposal] is enabled.
+///
+/// The function returns `false` if `features` is `NULL`, `true`
+/// otherwise.
+///
+/// [64-bit memory proposal]: https://github.com/webassembly/memory64
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_features_memory64(
+    features: Option<&mut wasmer_features_t>,
+    enable: bool,
+) -> bool {
+    match features {
+        Some(features) => {
+            features.inner.memory64(enable);
+
+            true
+        }
+        None => false,
+    }
+}
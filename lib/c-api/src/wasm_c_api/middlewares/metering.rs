@@ -0,0 +1,320 @@
+//! Unofficial API for the metering middleware, which limits the number
+//! of WebAssembly operators an instance can execute.
+//!
+//! Each operator costs a number of points, given by a cost function.
+//! The points are withdrawn from the remaining points of the instance
+//! as the operators are executed, and the instance traps when there
+//! aren't enough points left.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! // Define our “cost function”.
+//! uint64_t cost_function(wasmer_parser_operator_t wasm_operator) {
+//!     switch(wasm_operator) {
+//!         case LocalGet:
+//!         case I32Const:
+//!             return 1;
+//!
+//!         case I32Add:
+//!             return 2;
+//!
+//!         default:
+//!             return 0;
+//!     }
+//! }
+//!
+//! int main() {
+//!     // Create a new metering middleware, with our cost function.
+//!     wasmer_metering_t* metering = wasmer_metering_new(10, cost_function);
+//!
+//!     // Consume `metering` to produce a generic `wasmer_middleware_t` value.
+//!     wasmer_middleware_t* middleware = wasmer_metering_as_middleware(metering);
+//!
+//!     // Create a new configuration, and push the middleware in it.
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_push_middleware(config, middleware);
+//!
+//!     // Create the engine and the store based on the configuration.
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!     wasm_store_t* store = wasm_store_new(engine);
+//!
+//!     // Create the new WebAssembly module.
+//!     wasm_byte_vec_t wat;
+//!     wasmer_byte_vec_new_from_string(
+//!         &wat,
+//!         "(module\n"
+//!         "  (type $add_t (func (param i32) (result i32)))\n"
+//!         "  (func $add_two_f (type $add_t) (param $value i32) (result i32)\n"
+//!         "    local.get $value\n"
+//!         "    i32.const 1\n"
+//!         "    i32.add\n"
+//!         "    i32.const 1\n"
+//!         "    i32.add)\n"
+//!         "  (export \"add_two\" (func $add_two_f)))"
+//!     );
+//!     wasm_byte_vec_t wasm;
+//!     wat2wasm(&wat, &wasm);
+//!
+//!     wasm_module_t* module = wasm_module_new(store, &wasm);
+//!     assert(module);
+//!
+//!     // Instantiate the module.
+//!     wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+//!     wasm_trap_t* traps = NULL;
+//!     wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+//!     assert(instance);
+//!
+//!     // Here we go. At this step, we will get the `add_two` exported function, and
+//!     // call it.
+//!     wasm_extern_vec_t exports;
+//!     wasm_instance_exports(instance, &exports);
+//!     assert(exports.size >= 1);
+//!     assert(wasm_extern_kind(exports.data[0]) == WASM_EXTERN_FUNC);
+//!
+//!     const wasm_func_t* add_two = wasm_extern_as_func(exports.data[0]);
+//!     assert(add_two);
+//!
+//!     wasm_val_t arguments[1] = { WASM_I32_VAL(40) };
+//!     wasm_val_t results[1] = { WASM_INIT_VAL };
+//!
+//!     wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+//!     wasm_val_vec_t results_as_array = WASM_ARRAY_VEC(results);
+//!
+//!     // Let's call `add_two` for the first time!
+//!     {
+//!         wasm_trap_t* trap = wasm_func_call(add_two, &arguments_as_array, &results_as_array);
+//!         assert(trap == NULL);
+//!         assert(results[0].of.i32 == 42);
+//!
+//!         // `add_two` costs 7 points, so there are 3 points left!
+//!         uint64_t remaining_points;
+//!         assert(wasmer_metering_get_remaining_points(instance, &remaining_points));
+//!         assert(remaining_points == 3);
+//!         assert(wasmer_metering_points_are_exhausted(instance) == false);
+//!     }
+//!
+//!     // Let's call `add_two` for the second time!
+//!     {
+//!         wasm_trap_t* trap = wasm_func_call(add_two, &arguments_as_array, &results_as_array);
+//!         assert(trap != NULL);
+//!         wasm_trap_delete(trap);
+//!
+//!         // There are not enough points left!
+//!         uint64_t remaining_points;
+//!         assert(wasmer_metering_get_remaining_points(instance, &remaining_points));
+//!         assert(remaining_points == 0);
+//!         assert(wasmer_metering_points_are_exhausted(instance) == true);
+//!     }
+//!
+//!     // Give it some more points, and call `add_two` for the third time!
+//!     assert(wasmer_metering_set_remaining_points(instance, 8));
+//!     {
+//!         wasm_trap_t* trap = wasm_func_call(add_two, &arguments_as_array, &results_as_array);
+//!         assert(trap == NULL);
+//!         assert(results[0].of.i32 == 42);
+//!
+//!         uint64_t remaining_points;
+//!         assert(wasmer_metering_get_remaining_points(instance, &remaining_points));
+//!         assert(remaining_points == 1);
+//!         assert(wasmer_metering_points_are_exhausted(instance) == false);
+//!     }
+//!
+//!     // Free everything.
+//!     wasm_extern_vec_delete(&exports);
+//!     wasm_instance_delete(instance);
+//!     wasm_module_delete(module);
+//!     wasm_byte_vec_delete(&wasm);
+//!     wasm_byte_vec_delete(&wat);
+//!     wasm_store_delete(store);
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::super::instance::wasm_instance_t;
+use super::{wasmer_middleware_t, wasmer_parser_operator_t};
+use crate::error::{update_last_error, CApiError};
+use std::sync::Arc;
+use wasmer::wasmparser::Operator;
+use wasmer::{GlobalType, ModuleMiddleware, Mutability, Type};
+use wasmer_middlewares::{
+    metering::{get_remaining_points, set_remaining_points, MeteringPoints},
+    Metering,
+};
+
+/// Opaque type representing a metering middleware.
+///
+/// To transform this specific middleware into a generic one, please
+/// see [`wasmer_metering_as_middleware`].
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+pub struct wasmer_metering_t {
+    pub(crate) inner: Arc<dyn ModuleMiddleware>,
+}
+
+/// Function type to represent a user-defined cost function
+/// implemented in C, which gives the number of points an operator
+/// costs.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+pub type wasmer_metering_cost_function_t =
+    extern "C" fn(wasm_operator: wasmer_parser_operator_t) -> u64;
+
+/// Creates a new metering middleware with an initial limit of points
+/// and a cost function.
+///
+/// The middleware must be used by a single module: the points are
+/// tracked in the instances of the first module compiled with it,
+/// and the other modules fail to compile. An engine with it can thus
+/// only compile a single module.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_new(
+    initial_limit: u64,
+    cost_function: wasmer_metering_cost_function_t,
+) -> Box<wasmer_metering_t> {
+    let cost_function = move |operator: &Operator| -> u64 { cost_function(operator.into()) };
+
+    Box::new(wasmer_metering_t {
+        inner: Arc::new(Metering::new(initial_limit, cost_function)),
+    })
+}
+
+/// Deletes a [`wasmer_metering_t`].
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_delete(_metering: Option<Box<wasmer_metering_t>>) {}
+
+/// Transforms a [`wasmer_metering_t`] into a generic
+/// [`wasmer_middleware_t`], to then be pushed in the configuration with
+/// [`wasm_config_push_middleware`][super::wasm_config_push_middleware].
+///
+/// This function takes ownership of `metering`.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_as_middleware(
+    metering: Option<Box<wasmer_metering_t>>,
+) -> Option<Box<wasmer_middleware_t>> {
+    let metering = metering?;
+
+    Some(Box::new(wasmer_middleware_t {
+        inner: metering.inner,
+    }))
+}
+
+/// Checks that the module of `instance` was compiled with the metering
+/// middleware, by looking for the globals it adds, and otherwise sets
+/// the last error.
+fn is_metered(instance: &wasm_instance_t) -> bool {
+    let exports = &instance.inner.exports;
+    let global_type = |name: &str| exports.get_global(name).map(|global| *global.ty());
+    match (
+        global_type("wasmer_metering_remaining_points"),
+        global_type("wasmer_metering_points_exhausted"),
+    ) {
+        (
+            Ok(GlobalType {
+                ty: Type::I64,
+                mutability: Mutability::Var,
+            }),
+            Ok(GlobalType {
+                ty: Type::I32,
+                mutability: Mutability::Var,
+            }),
+        ) => true,
+        _ => {
+            update_last_error(CApiError {
+                msg: "the module of the instance wasn't compiled with the metering middleware"
+                    .to_string(),
+            });
+            false
+        }
+    }
+}
+
+/// Writes the remaining metering points in `remaining_points`. `0`
+/// means there are no points left.
+///
+/// Returns false, and sets the last error, if the module of the
+/// instance wasn't compiled with the metering middleware.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_get_remaining_points(
+    instance: &wasm_instance_t,
+    remaining_points: &mut u64,
+) -> bool {
+    if !is_metered(instance) {
+        return false;
+    }
+    *remaining_points = match get_remaining_points(&instance.inner) {
+        MeteringPoints::Remaining(value) => value,
+        MeteringPoints::Exhausted => 0,
+    };
+    true
+}
+
+/// Returns true if the remaining points are exhausted, false otherwise.
+///
+/// Returns false too, and sets the last error, if the module of the
+/// instance wasn't compiled with the metering middleware.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_points_are_exhausted(instance: &wasm_instance_t) -> bool {
+    is_metered(instance)
+        && matches!(
+            get_remaining_points(&instance.inner),
+            MeteringPoints::Exhausted,
+        )
+}
+
+/// Sets the remaining metering points, which also makes them not
+/// exhausted anymore.
+///
+/// Returns false, and sets the last error, if the module of the
+/// instance wasn't compiled with the metering middleware.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_set_remaining_points(
+    instance: &wasm_instance_t,
+    new_limit: u64,
+) -> bool {
+    if !is_metered(instance) {
+        return false;
+    }
+    set_remaining_points(&instance.inner, new_limit);
+    true
+}
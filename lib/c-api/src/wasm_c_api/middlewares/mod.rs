@@ -0,0 +1,41 @@
+//! Unofficial API for the middlewares, which transform the WebAssembly
+//! operators of a module while it is compiled.
+//!
+//! A middleware is pushed on a configuration with
+//! [`wasm_config_push_middleware`], and is then used by the compiler of
+//! the engine created with that configuration. See the
+//! [`metering`] middleware for an example.
+
+pub mod metering;
+pub mod operator;
+
+pub use self::operator::wasmer_parser_operator_t;
+
+use super::engine::wasm_config_t;
+use std::sync::Arc;
+use wasmer::ModuleMiddleware;
+
+/// Opaque type representing a middleware.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct wasmer_middleware_t {
+    pub(crate) inner: Arc<dyn ModuleMiddleware>,
+}
+
+/// Updates the configuration to add a middleware to the chain of
+/// middlewares of the compiler.
+///
+/// This function takes ownership of `middleware`.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the [`metering`] module.
+#[no_mangle]
+pub extern "C" fn wasm_config_push_middleware(
+    config: &mut wasm_config_t,
+    middleware: Box<wasmer_middleware_t>,
+) {
+    config.middlewares.push(*middleware);
+}
@@ -64,6 +64,9 @@ pub mod engine;
 /// cbindgen:ignore
 pub mod externals;
 
+#[cfg(feature = "compiler")]
+pub mod features;
+
 /// A WebAssembly instance is a stateful, executable instance of a
 /// WebAssembly module.
 ///
@@ -128,6 +131,9 @@ pub mod externals;
 /// cbindgen:ignore
 pub mod instance;
 
+#[cfg(feature = "middlewares")]
+pub mod middlewares;
+
 /// A WebAssembly module contains stateless WebAssembly code that has
 /// already been compiled and can be instantiated multiple times.
 ///
@@ -224,6 +230,9 @@ pub mod module;
 /// cbindgen:ignore
 pub mod store;
 
+#[cfg(feature = "compiler")]
+pub mod target;
+
 /// cbindgen:ignore
 pub mod trap;
 
@@ -0,0 +1,275 @@
+//! Unofficial API for the target the engine compiles for: a triple,
+//! like `x86_64-apple-darwin`, and a set of CPU features, like `sse4.2`.
+//!
+//! A target is set on a configuration with
+//! [`wasm_config_set_target`][super::engine::wasm_config_set_target].
+//! Compiling for a target which isn't the host is useful with the
+//! Native engine, to produce artifacts for another platform.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Declare the target triple.
+//!     wasmer_triple_t* triple;
+//!
+//!     {
+//!         wasm_name_t triple_name;
+//!         wasmer_byte_vec_new_from_string(&triple_name, "x86_64-apple-darwin");
+//!
+//!         triple = wasmer_triple_new(&triple_name);
+//!
+//!         wasm_name_delete(&triple_name);
+//!     }
+//!
+//!     assert(triple);
+//!
+//!     // Declare the target CPU features.
+//!     wasmer_cpu_features_t* cpu_features = wasmer_cpu_features_new();
+//!
+//!     {
+//!         wasm_name_t cpu_feature_name;
+//!         wasmer_byte_vec_new_from_string(&cpu_feature_name, "sse2");
+//!
+//!         assert(wasmer_cpu_features_add(cpu_features, &cpu_feature_name));
+//!
+//!         wasm_name_delete(&cpu_feature_name);
+//!     }
+//!
+//!     // Unknown CPU features are refused.
+//!     {
+//!         wasm_name_t cpu_feature_name;
+//!         wasmer_byte_vec_new_from_string(&cpu_feature_name, "foo");
+//!
+//!         assert(!wasmer_cpu_features_add(cpu_features, &cpu_feature_name));
+//!         assert(wasmer_last_error_length() > 0);
+//!
+//!         wasm_name_delete(&cpu_feature_name);
+//!     }
+//!
+//!     // Create the target.
+//!     wasmer_target_t* target = wasmer_target_new(triple, cpu_features);
+//!     assert(target);
+//!
+//!     // Create the configuration, and set the target.
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_set_target(config, target);
+//!
+//!     // Create the engine.
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!
+//!     // Check we have an engine!
+//!     assert(engine);
+//!
+//!     // Free everything.
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::types::wasm_name_t;
+use crate::error::{update_last_error, CApiError};
+use std::str::{self, FromStr};
+use wasmer_compiler::{CpuFeature, Target, Triple};
+use wasmer_enumset::EnumSet;
+
+/// Wasmer-specific type wrapping a target triple, which
+/// describes the architecture, the vendor, the operating system and the
+/// environment to compile for.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct wasmer_triple_t {
+    inner: Triple,
+}
+
+/// Creates a new triple by parsing `triple`, like
+/// `aarch64-unknown-linux-gnu`. The function returns `NULL` if the
+/// triple isn't valid, and the error can be read with
+/// [`wasmer_last_error_message`][crate::error::wasmer_last_error_message].
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_triple_new(
+    triple: Option<&wasm_name_t>,
+) -> Option<Box<wasmer_triple_t>> {
+    let triple = triple?;
+    let triple = c_try!(str::from_utf8(triple.into_slice()?));
+
+    Some(Box::new(wasmer_triple_t {
+        inner: c_try!(Triple::from_str(triple).map_err(|error| CApiError {
+            msg: error.to_string(),
+        })),
+    }))
+}
+
+/// Creates the triple of the host.
+///
+/// # Example
+///
+/// ```rust
+/// # use inline_c::assert_c;
+/// # fn main() {
+/// #    (assert_c! {
+/// # #include "tests/wasmer_wasm.h"
+/// #
+/// int main() {
+///     wasmer_triple_t* triple = wasmer_triple_new_from_host();
+///     assert(triple);
+///
+///     wasmer_triple_delete(triple);
+///
+///     return 0;
+/// }
+/// #    })
+/// #    .success();
+/// # }
+/// ```
+#[no_mangle]
+pub extern "C" fn wasmer_triple_new_from_host() -> Box<wasmer_triple_t> {
+    Box::new(wasmer_triple_t {
+        inner: Triple::host(),
+    })
+}
+
+/// Deletes a triple.
+///
+/// # Example
+///
+/// See [`wasmer_triple_new_from_host`].
+#[no_mangle]
+pub extern "C" fn wasmer_triple_delete(_triple: Option<Box<wasmer_triple_t>>) {}
+
+/// Wasmer-specific type wrapping a set of CPU features, which
+/// the compiled code is allowed to use.
+///
+/// The known CPU features are `sse2`, `sse3`, `ssse3`, `sse4.1`,
+/// `sse4.2`, `popcnt`, `avx`, `bmi`, `bmi2`, `avx2`, `avx512dq`,
+/// `avx512vl` and `lzcnt`.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct wasmer_cpu_features_t {
+    inner: EnumSet<CpuFeature>,
+}
+
+/// Creates a new empty set of CPU features.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_cpu_features_new() -> Box<wasmer_cpu_features_t> {
+    Box::new(wasmer_cpu_features_t {
+        inner: CpuFeature::set(),
+    })
+}
+
+/// Adds the CPU feature `feature`, like `sse4.2`, to the set. The
+/// function returns `false` if the feature isn't known, and the error
+/// can be read with
+/// [`wasmer_last_error_message`][crate::error::wasmer_last_error_message].
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_cpu_features_add(
+    cpu_features: Option<&mut wasmer_cpu_features_t>,
+    feature: Option<&wasm_name_t>,
+) -> bool {
+    let cpu_features = match cpu_features {
+        Some(cpu_features) => cpu_features,
+        None => return false,
+    };
+    let feature = match feature.and_then(|feature| feature.into_slice()) {
+        Some(feature) => feature,
+        None => return false,
+    };
+    let feature = match str::from_utf8(feature) {
+        Ok(feature) => feature,
+        Err(error) => {
+            update_last_error(error);
+
+            return false;
+        }
+    };
+
+    match CpuFeature::from_str(feature) {
+        Ok(feature) => {
+            cpu_features.inner.insert(feature);
+
+            true
+        }
+        Err(error) => {
+            update_last_error(error);
+
+            false
+        }
+    }
+}
+
+/// Deletes a set of CPU features.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_cpu_features_delete(_cpu_features: Option<Box<wasmer_cpu_features_t>>) {}
+
+/// Wasmer-specific type wrapping a target, made of a triple and
+/// a set of CPU features.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct wasmer_target_t {
+    pub(crate) inner: Target,
+}
+
+/// Creates a new target from a triple and a set of CPU features.
+///
+/// This function takes ownership of `triple` and `cpu_features`.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_target_new(
+    triple: Option<Box<wasmer_triple_t>>,
+    cpu_features: Option<Box<wasmer_cpu_features_t>>,
+) -> Option<Box<wasmer_target_t>> {
+    let triple = triple?;
+    let cpu_features = cpu_features?;
+
+    Some(Box::new(wasmer_target_t {
+        inner: Target::new(triple.inner, cpu_features.inner),
+    }))
+}
+
+/// Deletes a target.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_target_delete(_target: Option<Box<wasmer_target_t>>) {}
@@ -3,7 +3,40 @@
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::io::{self, Read, Seek, Write};
-use wasmer_wasi::{WasiFile, WasiFsError};
+use std::os::raw::{c_char, c_void};
+use wasmer_wasi::{CallbackOutput, WasiFile, WasiFsError};
+
+/// Receives `data_len` bytes of output at `data`, which are only valid
+/// during the call.
+#[allow(non_camel_case_types)]
+pub type wasi_output_callback_t =
+    unsafe extern "C" fn(env: *mut c_void, data: *const c_char, data_len: usize);
+
+/// A [`wasi_output_callback_t`] with its environment.
+struct OutputCallback {
+    callback: wasi_output_callback_t,
+    env: *mut c_void,
+}
+
+// the callback is documented as having to be thread-safe
+unsafe impl Send for OutputCallback {}
+
+/// Output passed to `callback`, one line at a time if `line_buffered`.
+pub(crate) fn callback_output(
+    callback: wasi_output_callback_t,
+    env: *mut c_void,
+    line_buffered: bool,
+) -> CallbackOutput {
+    let target = OutputCallback { callback, env };
+    let write = move |data: &[u8]| unsafe {
+        (target.callback)(target.env, data.as_ptr() as *const c_char, data.len())
+    };
+    if line_buffered {
+        CallbackOutput::line_buffered(write)
+    } else {
+        CallbackOutput::new(write)
+    }
+}
 
 /// For capturing stdout/stderr. Stores all output in a string.
 #[derive(Debug, Serialize, Deserialize)]
@@ -3,23 +3,31 @@
 //! This API will be superseded by a standard WASI API when/if such a standard is created.
 
 mod capture_files;
+mod providers;
+
+pub use self::capture_files::wasi_output_callback_t;
+pub use self::providers::{
+    wasi_clock_res_get_callback_t, wasi_clock_time_get_callback_t, wasi_random_get_callback_t,
+};
 
 use super::{
     externals::{wasm_extern_t, wasm_extern_vec_t, wasm_func_t, wasm_memory_t},
     instance::wasm_instance_t,
     module::wasm_module_t,
     store::wasm_store_t,
+    types::wasm_byte_vec_t,
 };
 // required due to really weird Rust resolution rules for macros
 // https://github.com/rust-lang/rust/issues/57966
 use crate::error::{update_last_error, CApiError};
 use std::convert::TryFrom;
 use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
 use std::slice;
 use wasmer::{Extern, NamedResolver};
 use wasmer_wasi::{
-    generate_import_object_from_env, get_wasi_version, WasiEnv, WasiFile, WasiState,
+    generate_import_object_from_env, get_wasi_version, MemFileSystem, WasiEnv, WasiFile, WasiState,
     WasiStateBuilder, WasiVersion,
 };
 
@@ -30,6 +38,8 @@ pub struct wasi_config_t {
     inherit_stderr: bool,
     inherit_stdin: bool,
     /// cbindgen:ignore
+    memory_fs: Option<MemFileSystem>,
+    /// cbindgen:ignore
     state_builder: WasiStateBuilder,
 }
 
@@ -90,6 +100,13 @@ pub unsafe extern "C" fn wasi_config_preopen_dir(
         }
     };
 
+    if let Some(memory_fs) = &config.memory_fs {
+        if let Err(e) = memory_fs.create_dir_all(dir_str) {
+            update_last_error(e);
+            return false;
+        }
+    }
+
     if let Err(e) = config.state_builder.preopen_dir(dir_str) {
         update_last_error(e);
         return false;
@@ -124,6 +141,13 @@ pub unsafe extern "C" fn wasi_config_mapdir(
         }
     };
 
+    if let Some(memory_fs) = &config.memory_fs {
+        if let Err(e) = memory_fs.create_dir_all(dir_str) {
+            update_last_error(e);
+            return false;
+        }
+    }
+
     if let Err(e) = config.state_builder.map_dir(alias_str, dir_str) {
         update_last_error(e);
         return false;
@@ -132,6 +156,151 @@ pub unsafe extern "C" fn wasi_config_mapdir(
     true
 }
 
+/// Use an in-memory filesystem instead of the host filesystem.
+///
+/// The directories given to `wasi_config_preopen_dir` and
+/// `wasi_config_mapdir` after this call are created in the in-memory
+/// filesystem; nothing is read from or written to the host.
+#[no_mangle]
+pub extern "C" fn wasi_config_memory_fs(config: &mut wasi_config_t) {
+    let memory_fs = MemFileSystem::new();
+    config.state_builder.fs_backend(Box::new(memory_fs.clone()));
+    config.memory_fs = Some(memory_fs);
+}
+
+/// Create or replace the file at `path` in the in-memory filesystem with
+/// `bytes`, creating its missing parent directories.
+///
+/// `wasi_config_memory_fs` must have been called before. The function
+/// returns `false` on error, and the error can be read with
+/// `wasmer_last_error_message`.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_memory_fs_write_file(
+    config: &mut wasi_config_t,
+    path: *const c_char,
+    bytes: &wasm_byte_vec_t,
+) -> bool {
+    let path_cstr = CStr::from_ptr(path);
+    let path_bytes = path_cstr.to_bytes();
+    let path_str = match std::str::from_utf8(path_bytes) {
+        Ok(path_str) => path_str,
+        Err(e) => {
+            update_last_error(e);
+            return false;
+        }
+    };
+
+    let memory_fs = match &config.memory_fs {
+        Some(memory_fs) => memory_fs,
+        None => {
+            update_last_error(CApiError {
+                msg: "the in-memory filesystem isn't enabled, call `wasi_config_memory_fs` first"
+                    .to_string(),
+            });
+            return false;
+        }
+    };
+
+    let path = Path::new(path_str);
+    if let Some(parent) = path.parent() {
+        if let Err(e) = memory_fs.create_dir_all(parent) {
+            update_last_error(e);
+            return false;
+        }
+    }
+
+    let contents = bytes.into_slice().unwrap_or(&[]);
+    if let Err(e) = memory_fs.write_file(path, contents) {
+        update_last_error(e);
+        return false;
+    }
+
+    true
+}
+
+/// Make the WASI program get the time from `time_get` and `res_get`
+/// instead of the clocks of the host.
+///
+/// `env` is passed to both callbacks as is. The callbacks may be called
+/// from any thread, and return a WASI errno: 0 on success.
+#[no_mangle]
+pub extern "C" fn wasi_config_clock(
+    config: &mut wasi_config_t,
+    time_get: wasi_clock_time_get_callback_t,
+    res_get: wasi_clock_res_get_callback_t,
+    env: *mut c_void,
+) {
+    config
+        .state_builder
+        .clock(Box::new(providers::CallbackClock {
+            time_get,
+            res_get,
+            env,
+        }));
+}
+
+/// Make the WASI program get random numbers from `random_get` instead of
+/// the random number generator of the operating system.
+///
+/// `env` is passed to the callback as is. The callback may be called from
+/// any thread, and returns a WASI errno: 0 on success.
+#[no_mangle]
+pub extern "C" fn wasi_config_random(
+    config: &mut wasi_config_t,
+    random_get: wasi_random_get_callback_t,
+    env: *mut c_void,
+) {
+    config
+        .state_builder
+        .random(Box::new(providers::CallbackRandom { random_get, env }));
+}
+
+/// Pass what the WASI program writes to stdout to `callback` as it runs,
+/// one line at a time if `line_buffered`. An unfinished last line is passed
+/// when the `wasi_env_t` is deleted.
+///
+/// `env` is passed to the callback as is. The callback may be called from
+/// any thread. This replaces `wasi_config_inherit_stdout`.
+#[no_mangle]
+pub extern "C" fn wasi_config_capture_stdout_callback(
+    config: &mut wasi_config_t,
+    callback: wasi_output_callback_t,
+    env: *mut c_void,
+    line_buffered: bool,
+) {
+    config.inherit_stdout = false;
+    config
+        .state_builder
+        .stdout(Box::new(capture_files::callback_output(
+            callback,
+            env,
+            line_buffered,
+        )));
+}
+
+/// Pass what the WASI program writes to stderr to `callback` as it runs,
+/// one line at a time if `line_buffered`. An unfinished last line is passed
+/// when the `wasi_env_t` is deleted.
+///
+/// `env` is passed to the callback as is. The callback may be called from
+/// any thread. This replaces `wasi_config_inherit_stderr`.
+#[no_mangle]
+pub extern "C" fn wasi_config_capture_stderr_callback(
+    config: &mut wasi_config_t,
+    callback: wasi_output_callback_t,
+    env: *mut c_void,
+    line_buffered: bool,
+) {
+    config.inherit_stderr = false;
+    config
+        .state_builder
+        .stderr(Box::new(capture_files::callback_output(
+            callback,
+            env,
+            line_buffered,
+        )));
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stdout(config: &mut wasi_config_t) {
     config.inherit_stdout = true;
@@ -0,0 +1,116 @@
+//! Clocks and random number generators implemented in C.
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::os::raw::c_void;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{WasiClock, WasiRandom};
+
+/// Reads the clock `clock_id` into `time`, in nanoseconds, and returns a
+/// WASI errno (0 on success).
+#[allow(non_camel_case_types)]
+pub type wasi_clock_time_get_callback_t =
+    unsafe extern "C" fn(env: *mut c_void, clock_id: u32, precision: u64, time: *mut u64) -> u16;
+
+/// Writes the resolution of the clock `clock_id` into `resolution`, in
+/// nanoseconds, and returns a WASI errno (0 on success).
+#[allow(non_camel_case_types)]
+pub type wasi_clock_res_get_callback_t =
+    unsafe extern "C" fn(env: *mut c_void, clock_id: u32, resolution: *mut u64) -> u16;
+
+/// Fills the `buf_len` bytes of `buf` with random bytes and returns a WASI
+/// errno (0 on success).
+#[allow(non_camel_case_types)]
+pub type wasi_random_get_callback_t =
+    unsafe extern "C" fn(env: *mut c_void, buf: *mut u8, buf_len: usize) -> u16;
+
+/// A [`WasiClock`] calling back into C.
+pub(crate) struct CallbackClock {
+    pub(crate) time_get: wasi_clock_time_get_callback_t,
+    pub(crate) res_get: wasi_clock_res_get_callback_t,
+    pub(crate) env: *mut c_void,
+}
+
+// the callbacks are documented as having to be thread-safe
+unsafe impl Send for CallbackClock {}
+unsafe impl Sync for CallbackClock {}
+
+impl fmt::Debug for CallbackClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackClock")
+            .field("env", &self.env)
+            .finish()
+    }
+}
+
+#[typetag::serde]
+impl WasiClock for CallbackClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let mut resolution = 0;
+        match unsafe { (self.res_get)(self.env, clock_id, &mut resolution) } {
+            __WASI_ESUCCESS => Ok(resolution),
+            errno => Err(errno),
+        }
+    }
+
+    fn time(
+        &mut self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let mut time = 0;
+        match unsafe { (self.time_get)(self.env, clock_id, precision, &mut time) } {
+            __WASI_ESUCCESS => Ok(time),
+            errno => Err(errno),
+        }
+    }
+}
+
+/// A [`WasiRandom`] calling back into C.
+pub(crate) struct CallbackRandom {
+    pub(crate) random_get: wasi_random_get_callback_t,
+    pub(crate) env: *mut c_void,
+}
+
+// the callback is documented as having to be thread-safe
+unsafe impl Send for CallbackRandom {}
+unsafe impl Sync for CallbackRandom {}
+
+impl fmt::Debug for CallbackRandom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackRandom")
+            .field("env", &self.env)
+            .finish()
+    }
+}
+
+#[typetag::serde]
+impl WasiRandom for CallbackRandom {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        match unsafe { (self.random_get)(self.env, buf.as_mut_ptr(), buf.len()) } {
+            __WASI_ESUCCESS => Ok(()),
+            errno => Err(errno),
+        }
+    }
+}
+
+// callbacks are only meaningful in the process which registered them, so a
+// `WasiState` using them can't be frozen
+macro_rules! impl_not_serializable {
+    ($ty:ty, $what:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Err(ser::Error::custom(concat!($what, " can't be serialized")))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+                Err(de::Error::custom(concat!($what, " can't be deserialized")))
+            }
+        }
+    };
+}
+
+impl_not_serializable!(CallbackClock, "a clock implemented in C");
+impl_not_serializable!(CallbackRandom, "a random number generator implemented in C");
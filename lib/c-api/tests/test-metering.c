@@ -0,0 +1,182 @@
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "wasmer_wasm.h"
+
+#define own
+
+// Use the last_error API to retrieve error messages
+void print_wasmer_error() {
+  int error_len = wasmer_last_error_length();
+  printf("Error len: `%d`\n", error_len);
+  char *error_str = malloc(error_len);
+  wasmer_last_error_message(error_str, error_len);
+  printf("Error str: `%s`\n", error_str);
+}
+
+// Every operator costs 1 point, except the calls and the loops which
+// cost 10.
+uint64_t cost_function(wasmer_parser_operator_t wasm_operator) {
+  switch (wasm_operator) {
+  case Call:
+  case CallIndirect:
+  case Loop:
+    return 10;
+
+  default:
+    return 1;
+  }
+}
+
+// Sums the numbers from 1 to `n`, each iteration of the loop costing
+// 12 points.
+const char *wat_string =
+    "(module\n"
+    "  (func (export \"sum\") (param $n i32) (result i32)\n"
+    "    (local $sum i32)\n"
+    "    (block $done\n"
+    "      (loop $next\n"
+    "        (br_if $done (i32.eqz (local.get $n)))\n"
+    "        (local.set $sum (i32.add (local.get $sum) (local.get $n)))\n"
+    "        (local.set $n (i32.sub (local.get $n) (i32.const 1)))\n"
+    "        (br $next)))\n"
+    "    (local.get $sum)))";
+
+own wasm_trap_t *call_sum(const wasm_func_t *sum, int32_t n, int32_t *result) {
+  wasm_val_t args_val[1] = {WASM_I32_VAL(n)};
+  wasm_val_t results_val[1] = {WASM_INIT_VAL};
+  wasm_val_vec_t args = WASM_ARRAY_VEC(args_val);
+  wasm_val_vec_t results = WASM_ARRAY_VEC(results_val);
+
+  own wasm_trap_t *trap = wasm_func_call(sum, &args, &results);
+  *result = results_val[0].of.i32;
+
+  return trap;
+}
+
+int main(int argc, const char *argv[]) {
+  // Initialize.
+  printf("Initializing...\n");
+  own wasmer_metering_t *metering = wasmer_metering_new(1000, cost_function);
+  own wasmer_middleware_t *middleware = wasmer_metering_as_middleware(metering);
+  own wasm_config_t *config = wasm_config_new();
+  wasm_config_push_middleware(config, middleware);
+  own wasm_engine_t *engine = wasm_engine_new_with_config(config);
+  if (!engine) {
+    printf("> Error creating the engine!\n");
+    print_wasmer_error();
+    return 1;
+  }
+  own wasm_store_t *store = wasm_store_new(engine);
+
+  // Compile.
+  printf("Compiling module...\n");
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new(&wat, strlen(wat_string), wat_string);
+  own wasm_byte_vec_t wasm;
+  wat2wasm(&wat, &wasm);
+  own wasm_module_t *module = wasm_module_new(store, &wasm);
+  if (!module) {
+    printf("> Error compiling module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Instantiate.
+  printf("Instantiating module...\n");
+  wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+  own wasm_instance_t *instance =
+      wasm_instance_new(store, module, &imports, NULL);
+  if (!instance) {
+    printf("> Error instantiating module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Extract export.
+  printf("Extracting export...\n");
+  own wasm_extern_vec_t exports;
+  wasm_instance_exports(instance, &exports);
+  const wasm_func_t *sum = wasm_extern_as_func(exports.data[0]);
+  if (sum == NULL) {
+    printf("> Error accessing export!\n");
+    return 1;
+  }
+
+  printf("Checking the initial points...\n");
+  uint64_t remaining = 0;
+  assert(wasmer_metering_get_remaining_points(instance, &remaining));
+  assert(remaining == 1000);
+  assert(!wasmer_metering_points_are_exhausted(instance));
+
+  printf("Calling with enough points...\n");
+  int32_t result = 0;
+  own wasm_trap_t *trap = call_sum(sum, 10, &result);
+  assert(trap == NULL);
+  assert(result == 55);
+  assert(wasmer_metering_get_remaining_points(instance, &remaining));
+  printf("Remaining points: %" PRIu64 "\n", remaining);
+  assert(remaining < 1000 - 10 * 12);
+  assert(!wasmer_metering_points_are_exhausted(instance));
+
+  printf("Calling with too few points...\n");
+  trap = call_sum(sum, 1000, &result);
+  assert(trap != NULL);
+  wasm_trap_delete(trap);
+  assert(wasmer_metering_get_remaining_points(instance, &remaining));
+  assert(remaining == 0);
+  assert(wasmer_metering_points_are_exhausted(instance));
+
+  printf("Calling once the points are exhausted...\n");
+  trap = call_sum(sum, 0, &result);
+  assert(trap != NULL);
+  wasm_trap_delete(trap);
+
+  printf("Calling after setting the points...\n");
+  assert(wasmer_metering_set_remaining_points(instance, 100000));
+  assert(!wasmer_metering_points_are_exhausted(instance));
+  trap = call_sum(sum, 1000, &result);
+  assert(trap == NULL);
+  assert(result == 500500);
+  assert(wasmer_metering_get_remaining_points(instance, &remaining));
+  printf("Remaining points: %" PRIu64 "\n", remaining);
+  assert(remaining < 100000 - 1000 * 12);
+
+  printf("Compiling another module with the same engine...\n");
+  own wasm_module_t *other_module = wasm_module_new(store, &wasm);
+  assert(other_module == NULL);
+  print_wasmer_error();
+
+  printf("Checking an instance without metering...\n");
+  own wasm_engine_t *other_engine = wasm_engine_new();
+  own wasm_store_t *other_store = wasm_store_new(other_engine);
+  other_module = wasm_module_new(other_store, &wasm);
+  assert(other_module);
+  own wasm_instance_t *other_instance =
+      wasm_instance_new(other_store, other_module, &imports, NULL);
+  assert(other_instance);
+  assert(!wasmer_metering_get_remaining_points(other_instance, &remaining));
+  assert(wasmer_last_error_length() > 0);
+  assert(!wasmer_metering_set_remaining_points(other_instance, 1000));
+  assert(!wasmer_metering_points_are_exhausted(other_instance));
+  wasm_instance_delete(other_instance);
+  wasm_module_delete(other_module);
+  wasm_store_delete(other_store);
+  wasm_engine_delete(other_engine);
+
+  // Shut down.
+  printf("Shutting down...\n");
+  wasm_extern_vec_delete(&exports);
+  wasm_instance_delete(instance);
+  wasm_module_delete(module);
+  wasm_byte_vec_delete(&wasm);
+  wasm_byte_vec_delete(&wat);
+  wasm_store_delete(store);
+  wasm_engine_delete(engine);
+
+  // All done.
+  printf("Done.\n");
+  return 0;
+}
@@ -0,0 +1,194 @@
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "wasmer_wasm.h"
+
+#define own
+
+// Use the last_error API to retrieve error messages
+own char *get_wasmer_error() {
+  int error_len = wasmer_last_error_length();
+  printf("Error len: `%d`\n", error_len);
+  char *error_str = malloc(error_len);
+  wasmer_last_error_message(error_str, error_len);
+  return error_str;
+}
+
+// Uses no proposal.
+const char *add_one_wat_string =
+    "(module\n"
+    "  (func (export \"add_one\") (param i32) (result i32)\n"
+    "    (i32.add (local.get 0) (i32.const 1))))";
+
+// Uses the SIMD proposal.
+const char *simd_wat_string =
+    "(module\n"
+    "  (func (export \"splat\") (param i32) (result i32)\n"
+    "    (i32x4.extract_lane 3 (i32x4.splat (local.get 0)))))";
+
+// Uses the bulk memory proposal.
+const char *bulk_memory_wat_string =
+    "(module\n"
+    "  (memory 1)\n"
+    "  (func (export \"fill\")\n"
+    "    (memory.fill (i32.const 0) (i32.const 42) (i32.const 8))))";
+
+// Uses the threads proposal.
+const char *threads_wat_string =
+    "(module\n"
+    "  (memory 1)\n"
+    "  (func (export \"load\") (result i32)\n"
+    "    (i32.atomic.load (i32.const 0))))";
+
+own wasmer_triple_t *new_triple(const char *name) {
+  wasm_name_t triple_name;
+  wasm_name_new_from_string(&triple_name, name);
+  own wasmer_triple_t *triple = wasmer_triple_new(&triple_name);
+  wasm_name_delete(&triple_name);
+  return triple;
+}
+
+bool add_cpu_feature(wasmer_cpu_features_t *cpu_features, const char *name) {
+  wasm_name_t cpu_feature_name;
+  wasm_name_new_from_string(&cpu_feature_name, name);
+  bool added = wasmer_cpu_features_add(cpu_features, &cpu_feature_name);
+  wasm_name_delete(&cpu_feature_name);
+  return added;
+}
+
+// Creates a target for the host, with explicit CPU features.
+own wasmer_target_t *new_host_target() {
+  own wasmer_triple_t *triple = wasmer_triple_new_from_host();
+  own wasmer_cpu_features_t *cpu_features = wasmer_cpu_features_new();
+#if defined(ARCH_X86_64)
+  assert(add_cpu_feature(cpu_features, "sse2"));
+  assert(add_cpu_feature(cpu_features, "sse3"));
+  assert(add_cpu_feature(cpu_features, "ssse3"));
+  assert(add_cpu_feature(cpu_features, "sse4.1"));
+  assert(add_cpu_feature(cpu_features, "sse4.2"));
+#endif
+  return wasmer_target_new(triple, cpu_features);
+}
+
+// Compiles `wat_string` with the engine created with `config`, and
+// returns whether it succeeded.
+bool compile(own wasm_config_t *config, const char *wat_string) {
+  own wasm_engine_t *engine = wasm_engine_new_with_config(config);
+  assert(engine);
+  own wasm_store_t *store = wasm_store_new(engine);
+
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new(&wat, strlen(wat_string), wat_string);
+  own wasm_byte_vec_t wasm;
+  wat2wasm(&wat, &wasm);
+  own wasm_module_t *module = wasm_module_new(store, &wasm);
+  bool compiled = module != NULL;
+
+  if (module) {
+    wasm_module_delete(module);
+  } else {
+    char *error = get_wasmer_error();
+    printf("Compilation error: %s\n", error);
+    free(error);
+  }
+  wasm_byte_vec_delete(&wasm);
+  wasm_byte_vec_delete(&wat);
+  wasm_store_delete(store);
+  wasm_engine_delete(engine);
+
+  return compiled;
+}
+
+int main(int argc, const char *argv[]) {
+  printf("Parsing triples...\n");
+  {
+    own wasmer_triple_t *triple = new_triple("aarch64-unknown-linux-gnu");
+    assert(triple);
+    wasmer_triple_delete(triple);
+  }
+  {
+    own wasmer_triple_t *triple = new_triple("not-a-triple");
+    assert(triple == NULL);
+    char *error = get_wasmer_error();
+    printf("Found error string: %s\n", error);
+    free(error);
+  }
+
+  printf("Adding CPU features...\n");
+  {
+    own wasmer_cpu_features_t *cpu_features = wasmer_cpu_features_new();
+    assert(add_cpu_feature(cpu_features, "sse4.2"));
+    assert(add_cpu_feature(cpu_features, "avx2"));
+    assert(!add_cpu_feature(cpu_features, "not-a-feature"));
+    char *error = get_wasmer_error();
+    printf("Found error string: %s\n", error);
+    assert(0 == strcmp("CpuFeature not-a-feature not recognized", error));
+    free(error);
+    wasmer_cpu_features_delete(cpu_features);
+  }
+
+  printf("Compiling for aarch64...\n");
+  {
+    own wasmer_triple_t *triple = new_triple("aarch64-unknown-linux-gnu");
+    assert(triple);
+    own wasmer_target_t *target =
+        wasmer_target_new(triple, wasmer_cpu_features_new());
+    assert(target);
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, target);
+    assert(compile(config, add_one_wat_string));
+  }
+
+  printf("Compiling SIMD without the SIMD feature...\n");
+  {
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, new_host_target());
+    own wasmer_features_t *features = wasmer_features_new();
+    assert(wasmer_features_simd(features, false));
+    wasm_config_set_features(config, features);
+    assert(!compile(config, simd_wat_string));
+  }
+
+  printf("Compiling SIMD with the SIMD feature...\n");
+  {
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, new_host_target());
+    own wasmer_features_t *features = wasmer_features_new();
+    assert(wasmer_features_simd(features, true));
+    wasm_config_set_features(config, features);
+    assert(compile(config, simd_wat_string));
+  }
+
+  printf("Compiling bulk memory without the bulk memory feature...\n");
+  {
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, new_host_target());
+    own wasmer_features_t *features = wasmer_features_new();
+    assert(wasmer_features_bulk_memory(features, false));
+    wasm_config_set_features(config, features);
+    assert(!compile(config, bulk_memory_wat_string));
+  }
+
+  printf("Compiling bulk memory with the default features...\n");
+  {
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, new_host_target());
+    own wasmer_features_t *features = wasmer_features_new();
+    wasm_config_set_features(config, features);
+    assert(compile(config, bulk_memory_wat_string));
+  }
+
+  printf("Compiling atomics with the default features...\n");
+  {
+    own wasm_config_t *config = wasm_config_new();
+    wasm_config_set_target(config, new_host_target());
+    own wasmer_features_t *features = wasmer_features_new();
+    wasm_config_set_features(config, features);
+    assert(!compile(config, threads_wat_string));
+  }
+
+  printf("Done.\n");
+  return 0;
+}
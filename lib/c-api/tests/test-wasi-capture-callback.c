@@ -0,0 +1,168 @@
+#include <assert.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "wasmer_wasm.h"
+
+#define MAX_CALLS 8
+#define own
+
+// Use the last_error API to retrieve error messages
+void print_wasmer_error() {
+  int error_len = wasmer_last_error_length();
+  printf("Error len: `%d`\n", error_len);
+  char *error_str = malloc(error_len);
+  wasmer_last_error_message(error_str, error_len);
+  printf("Error str: `%s`\n", error_str);
+  free(error_str);
+}
+
+// What a callback received, one string per call.
+typedef struct {
+  char *calls[MAX_CALLS];
+  int num_calls;
+} captured_t;
+
+void capture(void *env, const char *data, uintptr_t data_len) {
+  captured_t *captured = (captured_t *)env;
+  assert(captured->num_calls < MAX_CALLS);
+  char *call = malloc(data_len + 1);
+  memcpy(call, data, data_len);
+  call[data_len] = '\0';
+  captured->calls[captured->num_calls++] = call;
+}
+
+void free_captured(captured_t *captured) {
+  for (int i = 0; i < captured->num_calls; ++i) {
+    free(captured->calls[i]);
+  }
+  captured->num_calls = 0;
+}
+
+// Writes a line in 2 parts and 2 more lines, the last one unfinished, to
+// stdout, then `err` twice to stderr.
+const char *wat_string =
+    "(module\n"
+    "  (import \"wasi_snapshot_preview1\" \"fd_write\"\n"
+    "    (func $fd_write (param i32 i32 i32 i32) (result i32)))\n"
+    "  (memory (export \"memory\") 1)\n"
+    "  (data (i32.const 100) \"hello, \")\n"
+    "  (data (i32.const 120) \"world\\nsecond\\nthird\")\n"
+    "  (data (i32.const 160) \"err\")\n"
+    "  (func $write (param $fd i32) (param $ptr i32) (param $len i32)\n"
+    "    (i32.store (i32.const 0) (local.get $ptr))\n"
+    "    (i32.store (i32.const 4) (local.get $len))\n"
+    "    (drop (call $fd_write (local.get $fd) (i32.const 0) (i32.const 1)\n"
+    "                          (i32.const 8))))\n"
+    "  (func (export \"_start\")\n"
+    "    (call $write (i32.const 1) (i32.const 100) (i32.const 7))\n"
+    "    (call $write (i32.const 1) (i32.const 120) (i32.const 18))\n"
+    "    (call $write (i32.const 2) (i32.const 160) (i32.const 3))\n"
+    "    (call $write (i32.const 2) (i32.const 160) (i32.const 3))))";
+
+int main(int argc, const char *argv[]) {
+  // Initialize.
+  printf("Initializing...\n");
+  own wasm_engine_t *engine = wasm_engine_new();
+  own wasm_store_t *store = wasm_store_new(engine);
+
+  // Compile.
+  printf("Compiling module...\n");
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new(&wat, strlen(wat_string), wat_string);
+  own wasm_byte_vec_t wasm;
+  wat2wasm(&wat, &wasm);
+  own wasm_module_t *module = wasm_module_new(store, &wasm);
+  if (!module) {
+    printf("> Error compiling module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  printf("Setting up WASI...\n");
+  captured_t stdout_captured = {0};
+  captured_t stderr_captured = {0};
+  wasi_config_t *config = wasi_config_new("example_program");
+  wasi_config_capture_stdout_callback(config, capture, &stdout_captured, true);
+  wasi_config_capture_stderr_callback(config, capture, &stderr_captured,
+                                      false);
+
+  wasi_env_t *wasi_env = wasi_env_new(config);
+  if (!wasi_env) {
+    printf("> Error building WASI env!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Instantiate.
+  printf("Instantiating module...\n");
+  wasm_importtype_vec_t import_types;
+  wasm_module_imports(module, &import_types);
+  wasm_extern_vec_t imports;
+  wasm_extern_vec_new_uninitialized(&imports, import_types.size);
+  wasm_importtype_vec_delete(&import_types);
+
+  if (!wasi_get_imports(store, module, wasi_env, &imports)) {
+    printf("> Error getting WASI imports!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  own wasm_instance_t *instance =
+      wasm_instance_new(store, module, &imports, NULL);
+  if (!instance) {
+    printf("> Error instantiating module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  wasm_func_t *run_func = wasi_get_start_function(instance);
+  if (run_func == NULL) {
+    printf("> Error accessing export!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Call.
+  printf("Calling export...\n");
+  wasm_val_vec_t args = WASM_EMPTY_VEC;
+  wasm_val_vec_t res = WASM_EMPTY_VEC;
+  if (wasm_func_call(run_func, &args, &res)) {
+    printf("> Error calling function!\n");
+    return 1;
+  }
+
+  printf("Checking the captured output...\n");
+  // stdout is passed one finished line at a time.
+  assert(stdout_captured.num_calls == 2);
+  assert(0 == strcmp("hello, world\n", stdout_captured.calls[0]));
+  assert(0 == strcmp("second\n", stdout_captured.calls[1]));
+  // stderr is passed as it's written.
+  assert(stderr_captured.num_calls == 2);
+  assert(0 == strcmp("err", stderr_captured.calls[0]));
+  assert(0 == strcmp("err", stderr_captured.calls[1]));
+
+  // Shut down.
+  printf("Shutting down...\n");
+  wasm_func_delete(run_func);
+  wasm_extern_vec_delete(&imports);
+  wasm_instance_delete(instance);
+  wasm_module_delete(module);
+  wasm_byte_vec_delete(&wasm);
+  wasm_byte_vec_delete(&wat);
+  wasi_env_delete(wasi_env);
+  wasm_store_delete(store);
+  wasm_engine_delete(engine);
+
+  // The unfinished last line is passed when the output is dropped.
+  assert(stdout_captured.num_calls == 3);
+  assert(0 == strcmp("third", stdout_captured.calls[2]));
+  free_captured(&stdout_captured);
+  free_captured(&stderr_captured);
+
+  // All done.
+  printf("Done.\n");
+  return 0;
+}
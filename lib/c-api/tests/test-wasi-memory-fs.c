@@ -0,0 +1,153 @@
+#include <assert.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "wasmer_wasm.h"
+
+#define BUF_SIZE 128
+#define own
+
+// Use the last_error API to retrieve error messages
+void print_wasmer_error() {
+  int error_len = wasmer_last_error_length();
+  printf("Error len: `%d`\n", error_len);
+  char *error_str = malloc(error_len);
+  wasmer_last_error_message(error_str, error_len);
+  printf("Error str: `%s`\n", error_str);
+  free(error_str);
+}
+
+// Opens `input/data.txt` in the first preopened directory, and copies
+// its contents to stdout.
+const char *wat_string =
+    "(module\n"
+    "  (import \"wasi_snapshot_preview1\" \"path_open\"\n"
+    "    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))\n"
+    "  (import \"wasi_snapshot_preview1\" \"fd_read\"\n"
+    "    (func $fd_read (param i32 i32 i32 i32) (result i32)))\n"
+    "  (import \"wasi_snapshot_preview1\" \"fd_write\"\n"
+    "    (func $fd_write (param i32 i32 i32 i32) (result i32)))\n"
+    "  (memory (export \"memory\") 1)\n"
+    "  (data (i32.const 0) \"input/data.txt\")\n"
+    "  (func (export \"_start\")\n"
+    "    (if (call $path_open (i32.const 4) (i32.const 0) (i32.const 0) (i32.const 14)\n"
+    "                         (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0)\n"
+    "                         (i32.const 16))\n"
+    "      (then unreachable))\n"
+    "    (i32.store (i32.const 32) (i32.const 64))\n"
+    "    (i32.store (i32.const 36) (i32.const 256))\n"
+    "    (if (call $fd_read (i32.load (i32.const 16)) (i32.const 32) (i32.const 1)\n"
+    "                       (i32.const 24))\n"
+    "      (then unreachable))\n"
+    "    (i32.store (i32.const 36) (i32.load (i32.const 24)))\n"
+    "    (drop (call $fd_write (i32.const 1) (i32.const 32) (i32.const 1)\n"
+    "                          (i32.const 24)))))";
+
+int main(int argc, const char *argv[]) {
+  // Initialize.
+  printf("Initializing...\n");
+  own wasm_engine_t *engine = wasm_engine_new();
+  own wasm_store_t *store = wasm_store_new(engine);
+
+  // Compile.
+  printf("Compiling module...\n");
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new(&wat, strlen(wat_string), wat_string);
+  own wasm_byte_vec_t wasm;
+  wat2wasm(&wat, &wasm);
+  own wasm_module_t *module = wasm_module_new(store, &wasm);
+  if (!module) {
+    printf("> Error compiling module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  printf("Setting up WASI...\n");
+  wasi_config_t *config = wasi_config_new("example_program");
+  // Makes stdout readable with `wasi_env_read_stdout`.
+  wasi_config_inherit_stdout(config);
+
+  const char *data_string = "Hello from the in-memory filesystem!";
+  wasm_byte_vec_t data;
+  wasm_byte_vec_new(&data, strlen(data_string), data_string);
+
+  // Files can only be written once the in-memory filesystem is enabled.
+  assert(!wasi_config_memory_fs_write_file(config, "/data/input/data.txt",
+                                           &data));
+
+  wasi_config_memory_fs(config);
+  assert(wasi_config_preopen_dir(config, "/data"));
+  assert(wasi_config_memory_fs_write_file(config, "/data/input/data.txt",
+                                          &data));
+  wasm_byte_vec_delete(&data);
+
+  wasi_env_t *wasi_env = wasi_env_new(config);
+  if (!wasi_env) {
+    printf("> Error building WASI env!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Instantiate.
+  printf("Instantiating module...\n");
+  wasm_importtype_vec_t import_types;
+  wasm_module_imports(module, &import_types);
+  wasm_extern_vec_t imports;
+  wasm_extern_vec_new_uninitialized(&imports, import_types.size);
+  wasm_importtype_vec_delete(&import_types);
+
+  if (!wasi_get_imports(store, module, wasi_env, &imports)) {
+    printf("> Error getting WASI imports!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  own wasm_instance_t *instance =
+      wasm_instance_new(store, module, &imports, NULL);
+  if (!instance) {
+    printf("> Error instantiating module!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  wasm_func_t *run_func = wasi_get_start_function(instance);
+  if (run_func == NULL) {
+    printf("> Error accessing export!\n");
+    print_wasmer_error();
+    return 1;
+  }
+
+  // Call.
+  printf("Calling export...\n");
+  wasm_val_vec_t args = WASM_EMPTY_VEC;
+  wasm_val_vec_t res = WASM_EMPTY_VEC;
+  if (wasm_func_call(run_func, &args, &res)) {
+    printf("> Error calling function!\n");
+    return 1;
+  }
+
+  printf("Checking stdout...\n");
+  char buffer[BUF_SIZE] = {0};
+  intptr_t read = wasi_env_read_stdout(wasi_env, buffer, BUF_SIZE - 1);
+  printf("Read: `%s`\n", buffer);
+  assert(read == (intptr_t)strlen(data_string));
+  assert(0 == strcmp(data_string, buffer));
+
+  // Shut down.
+  printf("Shutting down...\n");
+  wasm_func_delete(run_func);
+  wasm_extern_vec_delete(&imports);
+  wasm_instance_delete(instance);
+  wasm_module_delete(module);
+  wasm_byte_vec_delete(&wasm);
+  wasm_byte_vec_delete(&wat);
+  wasi_env_delete(wasi_env);
+  wasm_store_delete(store);
+  wasm_engine_delete(engine);
+
+  // All done.
+  printf("Done.\n");
+  return 0;
+}
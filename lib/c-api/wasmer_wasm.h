@@ -38,6 +38,9 @@
 // The `wasi` feature has been enabled for this build.
 #define WASMER_WASI_ENABLED
 
+// The `middlewares` feature has been enabled for this build.
+#define WASMER_MIDDLEWARES_ENABLED
+
 // This file corresponds to the following Wasmer version.
 #define WASMER_VERSION "1.0.1"
 #define WASMER_VERSION_MAJOR 1
@@ -112,6 +115,480 @@ typedef enum {
   OBJECT_FILE = 2,
 } wasmer_engine_t;
 
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * A WebAssembly operator, without its immediates.
+ *
+ * This is a Wasmer-specific type, which mirrors the `Operator` type of
+ * the [`wasmparser`] Rust crate.
+ */
+typedef enum {
+  Unreachable,
+  Nop,
+  Block,
+  Loop,
+  If,
+  Else,
+  End,
+  Br,
+  BrIf,
+  BrTable,
+  Return,
+  Call,
+  CallIndirect,
+  ReturnCall,
+  ReturnCallIndirect,
+  Drop,
+  Select,
+  TypedSelect,
+  LocalGet,
+  LocalSet,
+  LocalTee,
+  GlobalGet,
+  GlobalSet,
+  I32Load,
+  I64Load,
+  F32Load,
+  F64Load,
+  I32Load8S,
+  I32Load8U,
+  I32Load16S,
+  I32Load16U,
+  I64Load8S,
+  I64Load8U,
+  I64Load16S,
+  I64Load16U,
+  I64Load32S,
+  I64Load32U,
+  I32Store,
+  I64Store,
+  F32Store,
+  F64Store,
+  I32Store8,
+  I32Store16,
+  I64Store8,
+  I64Store16,
+  I64Store32,
+  MemorySize,
+  MemoryGrow,
+  I32Const,
+  I64Const,
+  F32Const,
+  F64Const,
+  RefNull,
+  RefIsNull,
+  RefFunc,
+  I32Eqz,
+  I32Eq,
+  I32Ne,
+  I32LtS,
+  I32LtU,
+  I32GtS,
+  I32GtU,
+  I32LeS,
+  I32LeU,
+  I32GeS,
+  I32GeU,
+  I64Eqz,
+  I64Eq,
+  I64Ne,
+  I64LtS,
+  I64LtU,
+  I64GtS,
+  I64GtU,
+  I64LeS,
+  I64LeU,
+  I64GeS,
+  I64GeU,
+  F32Eq,
+  F32Ne,
+  F32Lt,
+  F32Gt,
+  F32Le,
+  F32Ge,
+  F64Eq,
+  F64Ne,
+  F64Lt,
+  F64Gt,
+  F64Le,
+  F64Ge,
+  I32Clz,
+  I32Ctz,
+  I32Popcnt,
+  I32Add,
+  I32Sub,
+  I32Mul,
+  I32DivS,
+  I32DivU,
+  I32RemS,
+  I32RemU,
+  I32And,
+  I32Or,
+  I32Xor,
+  I32Shl,
+  I32ShrS,
+  I32ShrU,
+  I32Rotl,
+  I32Rotr,
+  I64Clz,
+  I64Ctz,
+  I64Popcnt,
+  I64Add,
+  I64Sub,
+  I64Mul,
+  I64DivS,
+  I64DivU,
+  I64RemS,
+  I64RemU,
+  I64And,
+  I64Or,
+  I64Xor,
+  I64Shl,
+  I64ShrS,
+  I64ShrU,
+  I64Rotl,
+  I64Rotr,
+  F32Abs,
+  F32Neg,
+  F32Ceil,
+  F32Floor,
+  F32Trunc,
+  F32Nearest,
+  F32Sqrt,
+  F32Add,
+  F32Sub,
+  F32Mul,
+  F32Div,
+  F32Min,
+  F32Max,
+  F32Copysign,
+  F64Abs,
+  F64Neg,
+  F64Ceil,
+  F64Floor,
+  F64Trunc,
+  F64Nearest,
+  F64Sqrt,
+  F64Add,
+  F64Sub,
+  F64Mul,
+  F64Div,
+  F64Min,
+  F64Max,
+  F64Copysign,
+  I32WrapI64,
+  I32TruncF32S,
+  I32TruncF32U,
+  I32TruncF64S,
+  I32TruncF64U,
+  I64ExtendI32S,
+  I64ExtendI32U,
+  I64TruncF32S,
+  I64TruncF32U,
+  I64TruncF64S,
+  I64TruncF64U,
+  F32ConvertI32S,
+  F32ConvertI32U,
+  F32ConvertI64S,
+  F32ConvertI64U,
+  F32DemoteF64,
+  F64ConvertI32S,
+  F64ConvertI32U,
+  F64ConvertI64S,
+  F64ConvertI64U,
+  F64PromoteF32,
+  I32ReinterpretF32,
+  I64ReinterpretF64,
+  F32ReinterpretI32,
+  F64ReinterpretI64,
+  I32Extend8S,
+  I32Extend16S,
+  I64Extend8S,
+  I64Extend16S,
+  I64Extend32S,
+  I32TruncSatF32S,
+  I32TruncSatF32U,
+  I32TruncSatF64S,
+  I32TruncSatF64U,
+  I64TruncSatF32S,
+  I64TruncSatF32U,
+  I64TruncSatF64S,
+  I64TruncSatF64U,
+  MemoryInit,
+  DataDrop,
+  MemoryCopy,
+  MemoryFill,
+  TableInit,
+  ElemDrop,
+  TableCopy,
+  TableFill,
+  TableGet,
+  TableSet,
+  TableGrow,
+  TableSize,
+  MemoryAtomicNotify,
+  MemoryAtomicWait32,
+  MemoryAtomicWait64,
+  AtomicFence,
+  I32AtomicLoad,
+  I64AtomicLoad,
+  I32AtomicLoad8U,
+  I32AtomicLoad16U,
+  I64AtomicLoad8U,
+  I64AtomicLoad16U,
+  I64AtomicLoad32U,
+  I32AtomicStore,
+  I64AtomicStore,
+  I32AtomicStore8,
+  I32AtomicStore16,
+  I64AtomicStore8,
+  I64AtomicStore16,
+  I64AtomicStore32,
+  I32AtomicRmwAdd,
+  I64AtomicRmwAdd,
+  I32AtomicRmw8AddU,
+  I32AtomicRmw16AddU,
+  I64AtomicRmw8AddU,
+  I64AtomicRmw16AddU,
+  I64AtomicRmw32AddU,
+  I32AtomicRmwSub,
+  I64AtomicRmwSub,
+  I32AtomicRmw8SubU,
+  I32AtomicRmw16SubU,
+  I64AtomicRmw8SubU,
+  I64AtomicRmw16SubU,
+  I64AtomicRmw32SubU,
+  I32AtomicRmwAnd,
+  I64AtomicRmwAnd,
+  I32AtomicRmw8AndU,
+  I32AtomicRmw16AndU,
+  I64AtomicRmw8AndU,
+  I64AtomicRmw16AndU,
+  I64AtomicRmw32AndU,
+  I32AtomicRmwOr,
+  I64AtomicRmwOr,
+  I32AtomicRmw8OrU,
+  I32AtomicRmw16OrU,
+  I64AtomicRmw8OrU,
+  I64AtomicRmw16OrU,
+  I64AtomicRmw32OrU,
+  I32AtomicRmwXor,
+  I64AtomicRmwXor,
+  I32AtomicRmw8XorU,
+  I32AtomicRmw16XorU,
+  I64AtomicRmw8XorU,
+  I64AtomicRmw16XorU,
+  I64AtomicRmw32XorU,
+  I32AtomicRmwXchg,
+  I64AtomicRmwXchg,
+  I32AtomicRmw8XchgU,
+  I32AtomicRmw16XchgU,
+  I64AtomicRmw8XchgU,
+  I64AtomicRmw16XchgU,
+  I64AtomicRmw32XchgU,
+  I32AtomicRmwCmpxchg,
+  I64AtomicRmwCmpxchg,
+  I32AtomicRmw8CmpxchgU,
+  I32AtomicRmw16CmpxchgU,
+  I64AtomicRmw8CmpxchgU,
+  I64AtomicRmw16CmpxchgU,
+  I64AtomicRmw32CmpxchgU,
+  V128Load,
+  V128Store,
+  V128Const,
+  I8x16Splat,
+  I8x16ExtractLaneS,
+  I8x16ExtractLaneU,
+  I8x16ReplaceLane,
+  I16x8Splat,
+  I16x8ExtractLaneS,
+  I16x8ExtractLaneU,
+  I16x8ReplaceLane,
+  I32x4Splat,
+  I32x4ExtractLane,
+  I32x4ReplaceLane,
+  I64x2Splat,
+  I64x2ExtractLane,
+  I64x2ReplaceLane,
+  F32x4Splat,
+  F32x4ExtractLane,
+  F32x4ReplaceLane,
+  F64x2Splat,
+  F64x2ExtractLane,
+  F64x2ReplaceLane,
+  I8x16Eq,
+  I8x16Ne,
+  I8x16LtS,
+  I8x16LtU,
+  I8x16GtS,
+  I8x16GtU,
+  I8x16LeS,
+  I8x16LeU,
+  I8x16GeS,
+  I8x16GeU,
+  I16x8Eq,
+  I16x8Ne,
+  I16x8LtS,
+  I16x8LtU,
+  I16x8GtS,
+  I16x8GtU,
+  I16x8LeS,
+  I16x8LeU,
+  I16x8GeS,
+  I16x8GeU,
+  I32x4Eq,
+  I32x4Ne,
+  I32x4LtS,
+  I32x4LtU,
+  I32x4GtS,
+  I32x4GtU,
+  I32x4LeS,
+  I32x4LeU,
+  I32x4GeS,
+  I32x4GeU,
+  F32x4Eq,
+  F32x4Ne,
+  F32x4Lt,
+  F32x4Gt,
+  F32x4Le,
+  F32x4Ge,
+  F64x2Eq,
+  F64x2Ne,
+  F64x2Lt,
+  F64x2Gt,
+  F64x2Le,
+  F64x2Ge,
+  V128Not,
+  V128And,
+  V128AndNot,
+  V128Or,
+  V128Xor,
+  V128Bitselect,
+  I8x16Abs,
+  I8x16Neg,
+  I8x16AnyTrue,
+  I8x16AllTrue,
+  I8x16Bitmask,
+  I8x16Shl,
+  I8x16ShrS,
+  I8x16ShrU,
+  I8x16Add,
+  I8x16AddSatS,
+  I8x16AddSatU,
+  I8x16Sub,
+  I8x16SubSatS,
+  I8x16SubSatU,
+  I8x16MinS,
+  I8x16MinU,
+  I8x16MaxS,
+  I8x16MaxU,
+  I16x8Abs,
+  I16x8Neg,
+  I16x8AnyTrue,
+  I16x8AllTrue,
+  I16x8Bitmask,
+  I16x8Shl,
+  I16x8ShrS,
+  I16x8ShrU,
+  I16x8Add,
+  I16x8AddSatS,
+  I16x8AddSatU,
+  I16x8Sub,
+  I16x8SubSatS,
+  I16x8SubSatU,
+  I16x8Mul,
+  I16x8MinS,
+  I16x8MinU,
+  I16x8MaxS,
+  I16x8MaxU,
+  I32x4Abs,
+  I32x4Neg,
+  I32x4AnyTrue,
+  I32x4AllTrue,
+  I32x4Bitmask,
+  I32x4Shl,
+  I32x4ShrS,
+  I32x4ShrU,
+  I32x4Add,
+  I32x4Sub,
+  I32x4Mul,
+  I32x4MinS,
+  I32x4MinU,
+  I32x4MaxS,
+  I32x4MaxU,
+  I32x4DotI16x8S,
+  I64x2Neg,
+  I64x2Shl,
+  I64x2ShrS,
+  I64x2ShrU,
+  I64x2Add,
+  I64x2Sub,
+  I64x2Mul,
+  F32x4Ceil,
+  F32x4Floor,
+  F32x4Trunc,
+  F32x4Nearest,
+  F64x2Ceil,
+  F64x2Floor,
+  F64x2Trunc,
+  F64x2Nearest,
+  F32x4Abs,
+  F32x4Neg,
+  F32x4Sqrt,
+  F32x4Add,
+  F32x4Sub,
+  F32x4Mul,
+  F32x4Div,
+  F32x4Min,
+  F32x4Max,
+  F32x4PMin,
+  F32x4PMax,
+  F64x2Abs,
+  F64x2Neg,
+  F64x2Sqrt,
+  F64x2Add,
+  F64x2Sub,
+  F64x2Mul,
+  F64x2Div,
+  F64x2Min,
+  F64x2Max,
+  F64x2PMin,
+  F64x2PMax,
+  I32x4TruncSatF32x4S,
+  I32x4TruncSatF32x4U,
+  F32x4ConvertI32x4S,
+  F32x4ConvertI32x4U,
+  I8x16Swizzle,
+  I8x16Shuffle,
+  V128Load8Splat,
+  V128Load16Splat,
+  V128Load32Splat,
+  V128Load32Zero,
+  V128Load64Splat,
+  V128Load64Zero,
+  I8x16NarrowI16x8S,
+  I8x16NarrowI16x8U,
+  I16x8NarrowI32x4S,
+  I16x8NarrowI32x4U,
+  I16x8WidenLowI8x16S,
+  I16x8WidenHighI8x16S,
+  I16x8WidenLowI8x16U,
+  I16x8WidenHighI8x16U,
+  I32x4WidenLowI16x8S,
+  I32x4WidenHighI16x8S,
+  I32x4WidenLowI16x8U,
+  I32x4WidenHighI16x8U,
+  V128Load8x8S,
+  V128Load8x8U,
+  V128Load16x4S,
+  V128Load16x4U,
+  V128Load32x2S,
+  V128Load32x2U,
+  I8x16RoundingAverageU,
+  I16x8RoundingAverageU,
+} wasmer_parser_operator_t;
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 typedef struct wasi_config_t wasi_config_t;
 #endif
@@ -124,10 +601,172 @@ typedef struct wasi_env_t wasi_env_t;
 typedef struct wasi_version_t wasi_version_t;
 #endif
 
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Wasmer-specific type wrapping a set of CPU features, which
+ * the compiled code is allowed to use.
+ *
+ * The known CPU features are `sse2`, `sse3`, `ssse3`, `sse4.1`,
+ * `sse4.2`, `popcnt`, `avx`, `bmi`, `bmi2`, `avx2`, `avx512dq`,
+ * `avx512vl` and `lzcnt`.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef struct wasmer_cpu_features_t wasmer_cpu_features_t;
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Wasmer-specific type wrapping the WebAssembly features.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef struct wasmer_features_t wasmer_features_t;
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Opaque type representing a metering middleware.
+ *
+ * To transform this specific middleware into a generic one, please
+ * see [`wasmer_metering_as_middleware`].
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef struct wasmer_metering_t wasmer_metering_t;
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Opaque type representing a middleware.
+ */
+typedef struct wasmer_middleware_t wasmer_middleware_t;
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Wasmer-specific type wrapping a target, made of a triple and
+ * a set of CPU features.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef struct wasmer_target_t wasmer_target_t;
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Wasmer-specific type wrapping a target triple, which
+ * describes the architecture, the vendor, the operating system and the
+ * environment to compile for.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef struct wasmer_triple_t wasmer_triple_t;
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Writes the resolution of the clock `clock_id` into `resolution`, in
+ * nanoseconds, and returns a WASI errno (0 on success).
+ */
+typedef uint16_t (*wasi_clock_res_get_callback_t)(void *env, uint32_t clock_id, uint64_t *resolution);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Reads the clock `clock_id` into `time`, in nanoseconds, and returns a
+ * WASI errno (0 on success).
+ */
+typedef uint16_t (*wasi_clock_time_get_callback_t)(void *env, uint32_t clock_id, uint64_t precision, uint64_t *time);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Receives `data_len` bytes of output at `data`, which are only valid
+ * during the call.
+ */
+typedef void (*wasi_output_callback_t)(void *env, const char *data, uintptr_t data_len);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Fills the `buf_len` bytes of `buf` with random bytes and returns a WASI
+ * errno (0 on success).
+ */
+typedef uint16_t (*wasi_random_get_callback_t)(void *env, uint8_t *buf, uintptr_t buf_len);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Function type to represent a user-defined cost function
+ * implemented in C, which gives the number of points an operator
+ * costs.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+typedef uint64_t (*wasmer_metering_cost_function_t)(wasmer_parser_operator_t wasm_operator);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_config_arg(wasi_config_t *config, const char *arg);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Pass what the WASI program writes to stderr to `callback` as it runs,
+ * one line at a time if `line_buffered`. An unfinished last line is passed
+ * when the `wasi_env_t` is deleted.
+ *
+ * `env` is passed to the callback as is. The callback may be called from
+ * any thread. This replaces `wasi_config_inherit_stderr`.
+ */
+void wasi_config_capture_stderr_callback(wasi_config_t *config,
+                                         wasi_output_callback_t callback,
+                                         void *env,
+                                         bool line_buffered);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Pass what the WASI program writes to stdout to `callback` as it runs,
+ * one line at a time if `line_buffered`. An unfinished last line is passed
+ * when the `wasi_env_t` is deleted.
+ *
+ * `env` is passed to the callback as is. The callback may be called from
+ * any thread. This replaces `wasi_config_inherit_stdout`.
+ */
+void wasi_config_capture_stdout_callback(wasi_config_t *config,
+                                         wasi_output_callback_t callback,
+                                         void *env,
+                                         bool line_buffered);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Make the WASI program get the time from `time_get` and `res_get`
+ * instead of the clocks of the host.
+ *
+ * `env` is passed to both callbacks as is. The callbacks may be called
+ * from any thread, and return a WASI errno: 0 on success.
+ */
+void wasi_config_clock(wasi_config_t *config,
+                       wasi_clock_time_get_callback_t time_get,
+                       wasi_clock_res_get_callback_t res_get,
+                       void *env);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_config_env(wasi_config_t *config, const char *key, const char *value);
 #endif
@@ -148,6 +787,31 @@ void wasi_config_inherit_stdout(wasi_config_t *config);
 bool wasi_config_mapdir(wasi_config_t *config, const char *alias, const char *dir);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Use an in-memory filesystem instead of the host filesystem.
+ *
+ * The directories given to `wasi_config_preopen_dir` and
+ * `wasi_config_mapdir` after this call are created in the in-memory
+ * filesystem; nothing is read from or written to the host.
+ */
+void wasi_config_memory_fs(wasi_config_t *config);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Create or replace the file at `path` in the in-memory filesystem with
+ * `bytes`, creating its missing parent directories.
+ *
+ * `wasi_config_memory_fs` must have been called before. The function
+ * returns `false` on error, and the error can be read with
+ * `wasmer_last_error_message`.
+ */
+bool wasi_config_memory_fs_write_file(wasi_config_t *config,
+                                      const char *path,
+                                      const wasm_byte_vec_t *bytes);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 wasi_config_t *wasi_config_new(const char *program_name);
 #endif
@@ -156,6 +820,17 @@ wasi_config_t *wasi_config_new(const char *program_name);
 bool wasi_config_preopen_dir(wasi_config_t *config, const char *dir);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Make the WASI program get random numbers from `random_get` instead of
+ * the random number generator of the operating system.
+ *
+ * `env` is passed to the callback as is. The callback may be called from
+ * any thread, and returns a WASI errno: 0 on success.
+ */
+void wasi_config_random(wasi_config_t *config, wasi_random_get_callback_t random_get, void *env);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_env_delete(wasi_env_t *_state);
 #endif
@@ -209,6 +884,22 @@ wasm_func_t *wasi_get_start_function(wasm_instance_t *instance);
 wasi_version_t wasi_get_wasi_version(const wasm_module_t *module);
 #endif
 
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Updates the configuration to add a middleware to the chain of
+ * middlewares of the compiler.
+ *
+ * This function takes ownership of `middleware`.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the [`metering`] module.
+ */
+void wasm_config_push_middleware(wasm_config_t *config, wasmer_middleware_t *middleware);
+#endif
+
 #if defined(WASMER_COMPILER_ENABLED)
 /**
  * Updates the configuration to specify a particular compiler to use.
@@ -287,6 +978,45 @@ void wasm_config_set_compiler(wasm_config_t *config, wasmer_compiler_t compiler)
  */
 void wasm_config_set_engine(wasm_config_t *config, wasmer_engine_t engine);
 
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Updates the configuration to specify the WebAssembly features to
+ * enable. By default, the compiler enables the features it supports
+ * for the target.
+ *
+ * This function takes ownership of `features`.
+ *
+ * The object-file engine doesn't support features:
+ * [`wasm_engine_new_with_config`] fails for it when they are set.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the [`features`][super::features] module.
+ */
+void wasm_config_set_features(wasm_config_t *config, wasmer_features_t *features);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Updates the configuration to specify a particular target to
+ * compile for. The host is the target by default.
+ *
+ * This function takes ownership of `target`.
+ *
+ * The object-file engine doesn't support a target:
+ * [`wasm_engine_new_with_config`] fails for it when one is set.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the [`target`][super::target] module.
+ */
+void wasm_config_set_target(wasm_config_t *config, wasmer_target_t *target);
+#endif
+
 /**
  * Non-standard Wasmer-specific API to get the module's name,
  * otherwise `out->size` is set to `0` and `out->data` to `NULL`.
@@ -407,6 +1137,211 @@ void wasm_module_name(const wasm_module_t *module, wasm_name_t *out);
  */
 bool wasm_module_set_name(wasm_module_t *module, const wasm_name_t *name);
 
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Adds the CPU feature `feature`, like `sse4.2`, to the set. The
+ * function returns `false` if the feature isn't known, and the error
+ * can be read with
+ * [`wasmer_last_error_message`][crate::error::wasmer_last_error_message].
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_cpu_features_add(wasmer_cpu_features_t *cpu_features, const wasm_name_t *feature);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Deletes a set of CPU features.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+void wasmer_cpu_features_delete(wasmer_cpu_features_t *_cpu_features);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Creates a new empty set of CPU features.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_cpu_features_t *wasmer_cpu_features_new(void);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [bulk memory proposal] is enabled.
+ * Disabling it also disables the reference types proposal.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [bulk memory proposal]: https://github.com/webassembly/bulk-memory-operations
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_bulk_memory(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Deletes features.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+void wasmer_features_delete(wasmer_features_t *_features);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [64-bit memory proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [64-bit memory proposal]: https://github.com/webassembly/memory64
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_memory64(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [module linking proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [module linking proposal]: https://github.com/webassembly/module-linking
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_module_linking(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [multi-memory proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [multi-memory proposal]: https://github.com/webassembly/multi-memory
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_multi_memory(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [multi-value proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [multi-value proposal]: https://github.com/webassembly/multi-value
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_multi_value(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Creates the default features, where only the bulk memory and the
+ * multi-value proposals are enabled.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_features_t *wasmer_features_new(void);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [reference types proposal] is enabled.
+ * Enabling it also enables the bulk memory proposal.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [reference types proposal]: https://github.com/webassembly/reference-types
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_reference_types(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [SIMD proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [SIMD proposal]: https://github.com/webassembly/simd
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_simd(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [tail call proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [tail call proposal]: https://github.com/webassembly/tail-call
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_tail_call(wasmer_features_t *features, bool enable);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Configures whether the [threads proposal] is enabled.
+ *
+ * The function returns `false` if `features` is `NULL`, `true`
+ * otherwise.
+ *
+ * [threads proposal]: https://github.com/webassembly/threads
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_features_threads(wasmer_features_t *features, bool enable);
+#endif
+
 /**
  * Gets the length in bytes of the last error if any, zero otherwise.
  *
@@ -445,6 +1380,172 @@ int wasmer_last_error_length(void);
  */
 int wasmer_last_error_message(char *buffer, int length);
 
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Transforms a [`wasmer_metering_t`] into a generic
+ * [`wasmer_middleware_t`], to then be pushed in the configuration with
+ * [`wasm_config_push_middleware`][super::wasm_config_push_middleware].
+ *
+ * This function takes ownership of `metering`.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_middleware_t *wasmer_metering_as_middleware(wasmer_metering_t *metering);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Deletes a [`wasmer_metering_t`].
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+void wasmer_metering_delete(wasmer_metering_t *_metering);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Writes the remaining metering points in `remaining_points`. `0`
+ * means there are no points left.
+ *
+ * Returns false, and sets the last error, if the module of the
+ * instance wasn't compiled with the metering middleware.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_metering_get_remaining_points(const wasm_instance_t *instance,
+                                          uint64_t *remaining_points);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Creates a new metering middleware with an initial limit of points
+ * and a cost function.
+ *
+ * The middleware must be used by a single module: the points are
+ * tracked in the instances of the first module compiled with it,
+ * and the other modules fail to compile. An engine with it can thus
+ * only compile a single module.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_metering_t *wasmer_metering_new(uint64_t initial_limit,
+                                       wasmer_metering_cost_function_t cost_function);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Returns true if the remaining points are exhausted, false otherwise.
+ *
+ * Returns false too, and sets the last error, if the module of the
+ * instance wasn't compiled with the metering middleware.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_metering_points_are_exhausted(const wasm_instance_t *instance);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Sets the remaining metering points, which also makes them not
+ * exhausted anymore.
+ *
+ * Returns false, and sets the last error, if the module of the
+ * instance wasn't compiled with the metering middleware.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_metering_set_remaining_points(const wasm_instance_t *instance, uint64_t new_limit);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Deletes a target.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+void wasmer_target_delete(wasmer_target_t *_target);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Creates a new target from a triple and a set of CPU features.
+ *
+ * This function takes ownership of `triple` and `cpu_features`.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_target_t *wasmer_target_new(wasmer_triple_t *triple, wasmer_cpu_features_t *cpu_features);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Deletes a triple.
+ *
+ * # Example
+ *
+ * See [`wasmer_triple_new_from_host`].
+ */
+void wasmer_triple_delete(wasmer_triple_t *_triple);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Creates a new triple by parsing `triple`, like
+ * `aarch64-unknown-linux-gnu`. The function returns `NULL` if the
+ * triple isn't valid, and the error can be read with
+ * [`wasmer_last_error_message`][crate::error::wasmer_last_error_message].
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_triple_t *wasmer_triple_new(const wasm_name_t *triple);
+#endif
+
+#if defined(WASMER_COMPILER_ENABLED)
+/**
+ * Creates the triple of the host.
+ *
+ * # Example
+ *
+ * ```rust
+ * # use inline_c::assert_c;
+ * # fn main() {
+ * #    (assert_c! {
+ * # #include "tests/wasmer_wasm.h"
+ * #
+ * int main() {
+ *     wasmer_triple_t* triple = wasmer_triple_new_from_host();
+ *     assert(triple);
+ *
+ *     wasmer_triple_delete(triple);
+ *
+ *     return 0;
+ * }
+ * #    })
+ * #    .success();
+ * # }
+ * ```
+ */
+wasmer_triple_t *wasmer_triple_new_from_host(void);
+#endif
+
 /**
  * Get the version of the Wasmer C API.
  *
@@ -3,6 +3,8 @@ use crate::hash::Hash;
 use std::fs::{create_dir_all, File};
 use std::io::{self, Write};
 use std::path::PathBuf;
+#[cfg(feature = "signing")]
+use wasmer::signing::Keypair;
 use wasmer::{DeserializeError, Module, SerializeError, Store};
 
 /// Representation of a directory that contains compiled wasm artifacts.
@@ -33,6 +35,8 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    #[cfg(feature = "signing")]
+    keypair: Option<Keypair>,
 }
 
 impl FileSystemCache {
@@ -43,7 +47,12 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        #[cfg(feature = "signing")]
+                        keypair: None,
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -64,7 +73,12 @@ impl FileSystemCache {
         } else {
             // Create the directory and any parent directories if they don't yet exist.
             create_dir_all(&path)?;
-            Ok(Self { path, ext: None })
+            Ok(Self {
+                path,
+                ext: None,
+                #[cfg(feature = "signing")]
+                keypair: None,
+            })
         }
     }
 
@@ -75,6 +89,16 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
+
+    /// Sign the cached modules with the given ed25519 `keypair`.
+    ///
+    /// Once set, modules are stored with [`Module::serialize_signed`]
+    /// and only loaded back if their signature matches the public key
+    /// of the `keypair`, so tampered cache entries are rejected.
+    #[cfg(feature = "signing")]
+    pub fn set_signing_keypair(&mut self, keypair: Option<Keypair>) {
+        self.keypair = keypair;
+    }
 }
 
 impl Cache for FileSystemCache {
@@ -88,6 +112,10 @@ impl Cache for FileSystemCache {
             key.to_string()
         };
         let path = self.path.join(filename);
+        #[cfg(feature = "signing")]
+        if let Some(ref keypair) = self.keypair {
+            return Module::deserialize_verified_from_file(&store, path, &keypair.public);
+        }
         Module::deserialize_from_file(&store, path)
     }
 
@@ -100,6 +128,12 @@ impl Cache for FileSystemCache {
         let path = self.path.join(filename);
         let mut file = File::create(path)?;
 
+        #[cfg(feature = "signing")]
+        let buffer = match self.keypair {
+            Some(ref keypair) => module.serialize_signed(keypair)?,
+            None => module.serialize()?,
+        };
+        #[cfg(not(feature = "signing"))]
         let buffer = module.serialize()?;
         file.write_all(&buffer)?;
 
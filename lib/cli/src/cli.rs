@@ -4,6 +4,8 @@
 use crate::commands::Compile;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 use crate::commands::CreateExe;
+#[cfg(feature = "compiler")]
+use crate::commands::SnapshotInit;
 #[cfg(feature = "wast")]
 use crate::commands::Wast;
 use crate::commands::{Cache, Config, Inspect, Run, SelfUpdate, Validate};
@@ -49,6 +51,12 @@ enum WasmerCLIOptions {
     #[structopt(name = "create-exe")]
     CreateExe(CreateExe),
 
+    /// Run the initialization function of a WebAssembly module and write a
+    /// module starting in the state it left
+    #[cfg(feature = "compiler")]
+    #[structopt(name = "snapshot-init")]
+    SnapshotInit(SnapshotInit),
+
     /// Get various configuration information needed
     /// to compile programs which use Wasmer
     #[structopt(name = "config")]
@@ -79,6 +87,8 @@ impl WasmerCLIOptions {
             Self::Compile(compile) => compile.execute(),
             #[cfg(all(feature = "object-file", feature = "compiler"))]
             Self::CreateExe(create_exe) => create_exe.execute(),
+            #[cfg(feature = "compiler")]
+            Self::SnapshotInit(snapshot_init) => snapshot_init.execute(),
             Self::Config(config) => config.execute(),
             Self::Inspect(inspect) => inspect.execute(),
             #[cfg(feature = "wast")]
@@ -101,7 +111,7 @@ pub fn wasmer_main() {
     let command = args.get(1);
     let options = match command.unwrap_or(&"".to_string()).as_ref() {
         "cache" | "compile" | "config" | "create-exe" | "help" | "inspect" | "run"
-        | "self-update" | "validate" | "wast" => WasmerCLIOptions::from_args(),
+        | "self-update" | "snapshot-init" | "validate" | "wast" => WasmerCLIOptions::from_args(),
         _ => {
             WasmerCLIOptions::from_iter_safe(args.iter()).unwrap_or_else(|e| {
                 match e.kind {
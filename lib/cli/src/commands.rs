@@ -8,6 +8,8 @@ mod create_exe;
 mod inspect;
 mod run;
 mod self_update;
+#[cfg(feature = "compiler")]
+mod snapshot_init;
 mod validate;
 #[cfg(feature = "wast")]
 mod wast;
@@ -16,6 +18,8 @@ mod wast;
 pub use compile::*;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 pub use create_exe::*;
+#[cfg(feature = "compiler")]
+pub use snapshot_init::*;
 #[cfg(feature = "wast")]
 pub use wast::*;
 pub use {cache::*, config::*, inspect::*, run::*, self_update::*, validate::*};
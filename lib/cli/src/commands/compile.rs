@@ -29,6 +29,11 @@ pub struct Compile {
 
     #[structopt(short = "m", multiple = true)]
     cpu_features: Vec<CpuFeature>,
+
+    /// Sign the compiled artifact with the ed25519 keypair stored in the given file
+    #[cfg(feature = "signing")]
+    #[structopt(long = "signing-key", parse(from_os_str))]
+    signing_key: Option<PathBuf>,
 }
 
 impl Compile {
@@ -97,6 +102,14 @@ impl Compile {
         println!("Target: {}", target.triple());
 
         let module = Module::from_file(&store, &self.path)?;
+        #[cfg(feature = "signing")]
+        if let Some(ref signing_key) = self.signing_key {
+            let keypair = crate::utils::read_keypair(signing_key)?;
+            std::fs::write(&self.output, module.serialize_signed(&keypair)?)?;
+        } else {
+            module.serialize_to_file(&self.output)?;
+        }
+        #[cfg(not(feature = "signing"))]
         let _ = module.serialize_to_file(&self.output)?;
         eprintln!(
             "✔ File compiled successfully to `{}`.",
@@ -13,11 +13,14 @@ use wasmer_cache::{Cache, FileSystemCache, Hash};
 
 use structopt::StructOpt;
 
+mod record;
 #[cfg(feature = "wasi")]
 mod wasi;
 
+use record::RecordOptions;
+
 #[cfg(feature = "wasi")]
-use wasi::Wasi;
+pub(crate) use wasi::Wasi;
 
 #[derive(Debug, StructOpt, Clone)]
 /// The options for the `wasmer run` subcommand
@@ -46,9 +49,18 @@ pub struct Run {
     #[structopt(long = "cache-key", hidden = true)]
     cache_key: Option<String>,
 
+    /// Only run signed artifacts whose signature matches the ed25519
+    /// public key stored in the given file
+    #[cfg(feature = "signing")]
+    #[structopt(long = "verify-key", parse(from_os_str))]
+    verify_key: Option<PathBuf>,
+
     #[structopt(flatten)]
     store: StoreOptions,
 
+    #[structopt(flatten)]
+    record: RecordOptions,
+
     // TODO: refactor WASI structure to allow shared options with Emscripten
     #[cfg(feature = "wasi")]
     #[structopt(flatten)]
@@ -94,7 +106,7 @@ impl Run {
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
             let imports = imports! {};
-            let instance = Instance::new(&module, &imports)?;
+            let instance = self.record.instantiate(&module, imports)?;
             let result = self.invoke_function(&instance, &invoke, &self.args)?;
             println!(
                 "{}",
@@ -165,14 +177,14 @@ impl Run {
                     .unwrap_or_default();
                 return self
                     .wasi
-                    .execute(module, program_name, self.args.clone())
+                    .execute(module, program_name, self.args.clone(), &self.record)
                     .with_context(|| "WASI execution failed");
             }
         }
 
         // Try to instantiate the wasm file, with no provided imports
         let imports = imports! {};
-        let instance = Instance::new(&module, &imports)?;
+        let instance = self.record.instantiate(&module, imports)?;
         let start: Function = self.try_find_function(&instance, "_start", &[])?;
         start.call(&[])?;
 
@@ -181,24 +193,30 @@ impl Run {
 
     fn get_module(&self) -> Result<Module> {
         let contents = std::fs::read(self.path.clone())?;
-        #[cfg(feature = "native")]
+        #[cfg(feature = "signing")]
         {
-            if wasmer_engine_native::NativeArtifact::is_deserializable(&contents) {
-                let engine = wasmer_engine_native::Native::headless().engine();
-                let store = Store::new(&engine);
-                let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
-                return Ok(module);
+            if let Some(ref verify_key) = self.verify_key {
+                let public_key = crate::utils::read_public_key(verify_key)?;
+                // The signature is verified when deserializing, this only
+                // finds out which engine can load the artifact
+                let payload = signing::unverified_payload(&contents)?;
+                let store = Self::get_headless_store(payload).ok_or_else(|| {
+                    anyhow!("the signed artifact can't be loaded by any of the enabled engines")
+                })?;
+                return Ok(Module::deserialize_verified(
+                    &store,
+                    &contents,
+                    &public_key,
+                )?);
             }
-        }
-        #[cfg(feature = "jit")]
-        {
-            if wasmer_engine_jit::JITArtifact::is_deserializable(&contents) {
-                let engine = wasmer_engine_jit::JIT::headless().engine();
-                let store = Store::new(&engine);
-                let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
-                return Ok(module);
+            if signing::is_signed(&contents) {
+                bail!("the artifact is signed, please provide the public key with `--verify-key`");
             }
         }
+        if let Some(store) = Self::get_headless_store(&contents) {
+            let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
+            return Ok(module);
+        }
         let (store, engine_type, compiler_type) = self.store.get_store()?;
         #[cfg(feature = "cache")]
         let module_result: Result<Module> = if !self.disable_cache && contents.len() > 0x1000 {
@@ -222,6 +240,27 @@ impl Run {
         Ok(module)
     }
 
+    /// Returns a headless store able to deserialize the given artifact,
+    /// if any of the enabled engines recognize it
+    #[allow(unused_variables)]
+    fn get_headless_store(contents: &[u8]) -> Option<Store> {
+        #[cfg(feature = "native")]
+        {
+            if wasmer_engine_native::NativeArtifact::is_deserializable(contents) {
+                let engine = wasmer_engine_native::Native::headless().engine();
+                return Some(Store::new(&engine));
+            }
+        }
+        #[cfg(feature = "jit")]
+        {
+            if wasmer_engine_jit::JITArtifact::is_deserializable(contents) {
+                let engine = wasmer_engine_jit::JIT::headless().engine();
+                return Some(Store::new(&engine));
+            }
+        }
+        None
+    }
+
     #[cfg(feature = "cache")]
     fn get_module_from_cache(
         &self,
@@ -249,6 +288,10 @@ impl Run {
                     DeserializeError::Io(_) => {
                         // Do not notify on IO errors
                     }
+                    DeserializeError::Incompatible { .. } => {
+                        // The cached module was produced by another Wasmer
+                        // version or host, so we just recompile it
+                    }
                     err => {
                         warning!("cached module is corrupted: {}", err);
                     }
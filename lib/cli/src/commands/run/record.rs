@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use wasmer::record::{RecordingResolver, ReplayResolver};
+use wasmer::{Instance, Module, Resolver};
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt, Clone, Default)]
+/// Options to record and replay the calls to the imported functions
+pub struct RecordOptions {
+    /// Record the calls to the imported functions to a trace file
+    #[structopt(long = "record", name = "TRACE_FILE", parse(from_os_str))]
+    record: Option<PathBuf>,
+
+    /// Answer the calls to the imported functions with a trace file
+    /// written by `--record`, rather than calling them
+    #[structopt(
+        long = "replay",
+        name = "RECORDED_TRACE_FILE",
+        parse(from_os_str),
+        conflicts_with = "TRACE_FILE"
+    )]
+    replay: Option<PathBuf>,
+}
+
+impl RecordOptions {
+    /// Instantiate `module` with the imports of `resolver`, recording or
+    /// replaying the calls to them if asked to.
+    pub fn instantiate(&self, module: &Module, resolver: impl Resolver) -> Result<Instance> {
+        if let Some(path) = &self.record {
+            let trace = File::create(path)
+                .with_context(|| format!("failed to create the trace `{}`", path.display()))?;
+            let resolver = RecordingResolver::new(module.store(), resolver, BufWriter::new(trace))?;
+            return Ok(Instance::new(module, &resolver)?);
+        }
+        if let Some(path) = &self.replay {
+            let trace = File::open(path)
+                .with_context(|| format!("failed to open the trace `{}`", path.display()))?;
+            let resolver = ReplayResolver::new(module.store(), resolver, BufReader::new(trace))?;
+            return Ok(Instance::new(module, &resolver)?);
+        }
+        Ok(Instance::new(module, &resolver)?)
+    }
+}
@@ -1,8 +1,14 @@
-use crate::utils::{parse_envvar, parse_mapdir};
+use super::RecordOptions;
+use crate::utils::{parse_archive_mount, parse_envvar, parse_mapdir, parse_overlay};
 use anyhow::{Context, Result};
 use std::path::PathBuf;
-use wasmer::{Instance, Module};
-use wasmer_wasi::{get_wasi_version, WasiError, WasiState, WasiVersion};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use wasmer::{ImportObject, Instance, Module};
+use wasmer_wasi::{
+    get_wasi_version, host_signal, FileSystem, HostFileSystem, MemFileSystem, PathPolicy,
+    TraceFormat, TraceWriter, WasiError, WasiState, WasiVersion,
+};
 
 use structopt::StructOpt;
 
@@ -17,10 +23,48 @@ pub struct Wasi {
     #[structopt(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
     mapped_dirs: Vec<(String, PathBuf)>,
 
+    /// Map a host directory read-only for the wasm module, with all changes
+    /// going to UPPER (or to memory if UPPER is omitted)
+    #[structopt(long = "overlay", name = "GUEST:LOWER:UPPER", multiple = true, parse(try_from_str = parse_overlay))]
+    overlays: Vec<(String, PathBuf, Option<PathBuf>)>,
+
+    /// Map the contents of a tar, tar.gz or zip archive read-only to a
+    /// directory for the wasm module
+    #[structopt(long = "mount-archive", name = "GUEST_DIR=ARCHIVE", multiple = true, parse(try_from_str = parse_archive_mount))]
+    mounted_archives: Vec<(String, PathBuf)>,
+
+    /// Allow or deny operations on the paths of the preopened directories
+    /// with the glob-based rules of a TOML file
+    #[structopt(long = "dir-policy", name = "POLICY_FILE", parse(from_os_str))]
+    dir_policy: Option<PathBuf>,
+
     /// Pass custom environment variables
     #[structopt(long = "env", name = "KEY=VALUE", multiple = true, parse(try_from_str = parse_envvar))]
     env_vars: Vec<(String, String)>,
 
+    /// Run deterministically, with a virtual clock and random numbers
+    /// generated from SEED
+    #[structopt(long = "deterministic", name = "SEED")]
+    deterministic: Option<u64>,
+
+    /// The time of the virtual clock when starting, in seconds since the
+    /// Unix epoch
+    #[structopt(long = "start-time", name = "SECONDS", requires = "SEED")]
+    start_time: Option<u64>,
+
+    /// How much the virtual clock advances each time it's read
+    #[structopt(long = "clock-step", name = "NANOSECONDS", requires = "SEED")]
+    clock_step: Option<u64>,
+
+    /// Print the syscalls made by the module to stderr, like `strace`
+    #[structopt(long = "strace")]
+    strace: bool,
+
+    /// Print the syscalls made by the module to stderr as JSON, one object
+    /// per line
+    #[structopt(long = "strace-json", conflicts_with = "strace")]
+    strace_json: bool,
+
     /// Enable experimental IO devices
     #[cfg(feature = "experimental-io-devices")]
     #[structopt(long = "enable-experimental-io-devices")]
@@ -44,27 +88,15 @@ impl Wasi {
     }
 
     /// Helper function for executing Wasi from the `Run` command.
-    pub fn execute(&self, module: Module, program_name: String, args: Vec<String>) -> Result<()> {
-        let args = args.iter().cloned().map(|arg| arg.into_bytes());
-
-        let mut wasi_state_builder = WasiState::new(program_name);
-        wasi_state_builder
-            .args(args)
-            .envs(self.env_vars.clone())
-            .preopen_dirs(self.pre_opened_directories.clone())?
-            .map_dirs(self.mapped_dirs.clone())?;
-
-        #[cfg(feature = "experimental-io-devices")]
-        {
-            if self.enable_experimental_io_devices {
-                wasi_state_builder
-                    .setup_fs(Box::new(wasmer_wasi_experimental_io_devices::initialize));
-            }
-        }
-
-        let mut wasi_env = wasi_state_builder.finalize()?;
-        let import_object = wasi_env.import_object(&module)?;
-        let instance = Instance::new(&module, &import_object)?;
+    pub fn execute(
+        &self,
+        module: Module,
+        program_name: String,
+        args: Vec<String>,
+        record: &RecordOptions,
+    ) -> Result<()> {
+        let import_object = self.import_object(&module, program_name, args)?;
+        let instance = record.instantiate(&module, import_object)?;
 
         let start = instance.exports.get_function("_start")?;
         let result = start.call(&[]);
@@ -77,6 +109,13 @@ impl Wasi {
                         // We should exit with the provided exit code
                         std::process::exit(exit_code as _);
                     }
+                    Ok(WasiError::Signal(sig)) => {
+                        // Like the shells do for a process killed by a
+                        // signal, with the host's number of the signal, or
+                        // the WASI one if the host doesn't have it
+                        let sig = host_signal(sig).unwrap_or_else(|| sig.into());
+                        std::process::exit(128 + sig);
+                    }
                     Ok(err) => err.into(),
                     Err(err) => err.into(),
                 };
@@ -85,4 +124,90 @@ impl Wasi {
         }
         .with_context(|| "failed to run WASI `_start` function")
     }
+
+    /// Instantiate `module` with the WASI imports set up from the options.
+    pub fn instantiate(
+        &self,
+        module: &Module,
+        program_name: String,
+        args: Vec<String>,
+    ) -> Result<Instance> {
+        let import_object = self.import_object(module, program_name, args)?;
+        Ok(Instance::new(module, &import_object)?)
+    }
+
+    /// The WASI imports of `module`, set up from the options.
+    fn import_object(
+        &self,
+        module: &Module,
+        program_name: String,
+        args: Vec<String>,
+    ) -> Result<ImportObject> {
+        let args = args.iter().cloned().map(|arg| arg.into_bytes());
+
+        let mut wasi_state_builder = WasiState::new(program_name);
+        wasi_state_builder
+            .args(args)
+            .envs(self.env_vars.clone())
+            .preopen_dirs(self.pre_opened_directories.clone())?
+            .map_dirs(self.mapped_dirs.clone())?;
+
+        for (guest, lower, upper) in self.overlays.iter() {
+            wasi_state_builder.preopen(|p| {
+                let (upper_fs, upper_dir): (Box<dyn FileSystem>, _) = match upper {
+                    Some(upper) => (Box::new(HostFileSystem), upper.clone()),
+                    None => (Box::new(MemFileSystem::new()), PathBuf::from("/")),
+                };
+                p.directory(lower)
+                    .alias(guest)
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .overlay(upper_fs, upper_dir)
+            })?;
+        }
+
+        for (guest, archive) in self.mounted_archives.iter() {
+            wasi_state_builder.preopen(|p| p.archive(archive).alias(guest).read(true))?;
+        }
+
+        if let Some(path) = &self.dir_policy {
+            let policy = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read the path policy {}", path.display()))?;
+            let policy: PathPolicy = toml::from_str(&policy)
+                .with_context(|| format!("invalid path policy {}", path.display()))?;
+            wasi_state_builder.path_policy(policy);
+        }
+
+        if let Some(seed) = self.deterministic {
+            let start_time = self.start_time.unwrap_or(0);
+            let start_time = UNIX_EPOCH
+                .checked_add(Duration::from_secs(start_time))
+                .with_context(|| format!("the start time {} is out of range", start_time))?;
+            wasi_state_builder.deterministic(seed, start_time);
+            if let Some(clock_step) = self.clock_step {
+                wasi_state_builder.deterministic_clock_step(Duration::from_nanos(clock_step));
+            }
+        }
+
+        if self.strace || self.strace_json {
+            let format = if self.strace_json {
+                TraceFormat::Json
+            } else {
+                TraceFormat::Text
+            };
+            wasi_state_builder.tracer(Arc::new(TraceWriter::stderr(format)));
+        }
+
+        #[cfg(feature = "experimental-io-devices")]
+        {
+            if self.enable_experimental_io_devices {
+                wasi_state_builder
+                    .setup_fs(Box::new(wasmer_wasi_experimental_io_devices::initialize));
+            }
+        }
+
+        let mut wasi_env = wasi_state_builder.finalize()?;
+        Ok(wasi_env.import_object(module)?)
+    }
 }
@@ -0,0 +1,70 @@
+#[cfg(feature = "wasi")]
+use super::run::Wasi;
+use crate::store::StoreOptions;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use wasmer::*;
+
+#[derive(Debug, StructOpt)]
+/// The options for the `wasmer snapshot-init` subcommand
+pub struct SnapshotInit {
+    /// Input file
+    #[structopt(name = "FILE", parse(from_os_str))]
+    path: PathBuf,
+
+    /// Output file
+    #[structopt(name = "OUTPUT PATH", short = "o", parse(from_os_str))]
+    output: PathBuf,
+
+    /// The exported function initializing the module
+    #[structopt(long = "init-func", default_value = "wizer.initialize")]
+    init_func: String,
+
+    #[structopt(flatten)]
+    store: StoreOptions,
+
+    #[cfg(feature = "wasi")]
+    #[structopt(flatten)]
+    wasi: Wasi,
+}
+
+impl SnapshotInit {
+    /// Runs logic for the `snapshot-init` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute().context(format!(
+            "failed to pre-initialize `{}`",
+            self.path.display()
+        ))
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
+        let wasm = std::fs::read(&self.path)?;
+        #[cfg(feature = "wat")]
+        let wasm = wat2wasm(&wasm)?.to_vec();
+        let module = Module::new(&store, &wasm)?;
+
+        #[cfg(feature = "wasi")]
+        let instance = if Wasi::has_wasi_imports(&module) {
+            let program_name = self
+                .path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.wasi.instantiate(&module, program_name, vec![])?
+        } else {
+            Instance::new(&module, &imports! {})?
+        };
+        #[cfg(not(feature = "wasi"))]
+        let instance = Instance::new(&module, &imports! {})?;
+
+        let initialized = instance.snapshot_init(&wasm, &self.init_func)?;
+        std::fs::write(&self.output, initialized)?;
+        eprintln!(
+            "✔ Module pre-initialized successfully to `{}`.",
+            self.output.display(),
+        );
+        Ok(())
+    }
+}
@@ -40,6 +40,41 @@ pub fn parse_mapdir(entry: &str) -> Result<(String, PathBuf)> {
     }
 }
 
+/// Parses an overlay of the form `GUEST:LOWER[:UPPER]`
+pub fn parse_overlay(entry: &str) -> Result<(String, PathBuf, Option<PathBuf>)> {
+    match entry.split(':').collect::<Vec<&str>>()[..] {
+        [guest, lower] => {
+            let (guest, lower) = retrieve_alias_pathbuf(guest, lower)?;
+            Ok((guest, lower, None))
+        }
+        [guest, lower, upper] => {
+            let (guest, lower) = retrieve_alias_pathbuf(guest, lower)?;
+            let (_, upper) = retrieve_alias_pathbuf(guest.as_str(), upper)?;
+            Ok((guest, lower, Some(upper)))
+        }
+        _ => bail!(
+            "Overlays must be of the form `GUEST:LOWER:UPPER` or `GUEST:LOWER`. Found {}",
+            &entry
+        ),
+    }
+}
+
+/// Parses an archive mount of the form `GUEST_DIR=ARCHIVE`
+pub fn parse_archive_mount(entry: &str) -> Result<(String, PathBuf)> {
+    if let [alias, archive] = entry.splitn(2, '=').collect::<Vec<&str>>()[..] {
+        let pb = PathBuf::from(archive);
+        if !pb.is_file() {
+            bail!("Archive \"{}\" does not exist", archive);
+        }
+        Ok((alias.to_string(), pb))
+    } else {
+        bail!(
+            "Archive mounts must be of the form <guest_dir>=<archive>. Found {}",
+            &entry
+        )
+    }
+}
+
 /// Parses a mapdir from an env var
 pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     if let [env_var, value] = entry.split('=').collect::<Vec<&str>>()[..] {
@@ -51,3 +86,32 @@ pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
         );
     }
 }
+
+/// Reads an ed25519 public key from a file containing the 32 raw key bytes
+#[cfg(feature = "signing")]
+pub fn read_public_key(path: &std::path::Path) -> Result<wasmer::signing::PublicKey> {
+    let bytes = std::fs::read(path)?;
+    match wasmer::signing::PublicKey::from_bytes(&bytes) {
+        Ok(public_key) => Ok(public_key),
+        Err(_) => bail!(
+            "\"{}\" is not a valid public key (expected {} raw bytes)",
+            path.display(),
+            wasmer::signing::PUBLIC_KEY_LENGTH
+        ),
+    }
+}
+
+/// Reads an ed25519 keypair from a file containing the 64 raw bytes
+/// of the secret key followed by the public key
+#[cfg(feature = "signing")]
+pub fn read_keypair(path: &std::path::Path) -> Result<wasmer::signing::Keypair> {
+    let bytes = std::fs::read(path)?;
+    match wasmer::signing::Keypair::from_bytes(&bytes) {
+        Ok(keypair) => Ok(keypair),
+        Err(_) => bail!(
+            "\"{}\" is not a valid keypair (expected {} raw bytes)",
+            path.display(),
+            wasmer::signing::KEYPAIR_LENGTH
+        ),
+    }
+}
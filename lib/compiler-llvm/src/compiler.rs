@@ -206,6 +206,10 @@ impl LLVMCompiler {
 }
 
 impl Compiler for LLVMCompiler {
+    fn name(&self) -> &str {
+        "llvm"
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -59,6 +59,14 @@ where
 
 /// An implementation of a Compiler from parsed WebAssembly module to Compiled native code.
 pub trait Compiler: Send {
+    /// Returns the name of the compiler (eg. `cranelift`).
+    ///
+    /// It's embedded in the serialized artifacts, which can only be
+    /// loaded by an engine using a compiler with the same name.
+    fn name(&self) -> &str {
+        "unknown"
+    }
+
     /// Validates a module.
     ///
     /// It returns the a succesful Result in case is valid, `CompileError` in case is not.
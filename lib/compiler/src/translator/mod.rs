@@ -8,6 +8,7 @@
 mod environ;
 mod middleware;
 mod module;
+mod rewrite;
 mod state;
 #[macro_use]
 mod error;
@@ -19,5 +20,6 @@ pub use self::middleware::{
     ModuleMiddlewareChain,
 };
 pub use self::module::translate_module;
+pub use self::rewrite::{rewrite_module, ModuleInitialization};
 pub use self::sections::wptype_to_type;
 pub use self::state::ModuleTranslationState;
@@ -0,0 +1,478 @@
+//! Rewriting a WebAssembly module so that it starts in the state another
+//! instance of it reached, e.g. after running an initialization function.
+//!
+//! Sections are copied as they are, except the ones holding the initial
+//! state: the memory and table sections get the new sizes, the global
+//! section the new initializers, the data and element sections the new
+//! contents of the memories and tables. The start section is removed,
+//! since the start function already ran.
+//!
+//! The indices of the data and element segments are kept, so the code
+//! using passive segments stays valid: the active segments of the original
+//! module, which are dropped once they've been applied, and the passive
+//! segments the module dropped become empty passive segments, and the new
+//! contents are written as active segments after them.
+
+use crate::wasm_unsupported;
+use crate::WasmResult;
+use std::collections::HashMap;
+use std::string::String;
+use std::vec::Vec;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{
+    DataIndex, DataInitializer, ElemIndex, GlobalIndex, GlobalInit, Pages, TableInitializer,
+};
+use wasmparser::{
+    BinaryReader, DataKind, DataSectionReader, ElementItem, ElementKind, ElementSectionReader,
+    ExportSectionReader, ExternalKind, GlobalSectionReader, ImportSectionEntryType,
+    ImportSectionReader, MemorySectionReader, MemoryType, ResizableLimits, TableSectionReader,
+    Type,
+};
+
+const SECTION_IMPORT: u8 = 2;
+const SECTION_TABLE: u8 = 4;
+const SECTION_MEMORY: u8 = 5;
+const SECTION_GLOBAL: u8 = 6;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_START: u8 = 8;
+const SECTION_ELEMENT: u8 = 9;
+const SECTION_CODE: u8 = 10;
+const SECTION_DATA: u8 = 11;
+const SECTION_DATA_COUNT: u8 = 12;
+
+/// The state a module starts in after [`rewrite_module`].
+#[derive(Debug, Default)]
+pub struct ModuleInitialization<'data> {
+    /// The sizes of the memories the module defines, by local index
+    pub memory_sizes: Vec<Pages>,
+    /// The sizes of the tables the module defines, by local index
+    pub table_sizes: Vec<u32>,
+    /// The new initializers of the globals the module defines
+    pub globals: Vec<(GlobalIndex, GlobalInit)>,
+    /// The contents of the memories, replacing the active data segments
+    pub data_initializers: Vec<DataInitializer<'data>>,
+    /// The contents of the tables, replacing the active element segments
+    pub table_initializers: Vec<TableInitializer>,
+    /// The passive element segments the module dropped
+    pub dropped_elements: Vec<ElemIndex>,
+    /// The passive data segments the module dropped
+    pub dropped_data: Vec<DataIndex>,
+    /// The names of the exports to remove, like the initialization function
+    pub removed_exports: Vec<String>,
+}
+
+/// Rewrite the module `data` so that it starts in the state described by
+/// `initialization`, see the [module documentation](self).
+///
+/// Modules importing memories or tables aren't supported: their contents
+/// aren't the module's to snapshot.
+pub fn rewrite_module(data: &[u8], initialization: &ModuleInitialization) -> WasmResult<Vec<u8>> {
+    let mut reader = BinaryReader::new(data);
+    let mut output = reader.read_bytes(8)?.to_vec();
+    let globals = initialization
+        .globals
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+    let mut imported_globals = 0;
+    let mut wrote_elements = false;
+    let mut wrote_data = false;
+
+    while !reader.eof() {
+        let id = reader.read_u8()? as u8;
+        let size = reader.read_var_u32()? as usize;
+        let offset = reader.original_position();
+        let body = reader.read_bytes(size)?;
+
+        // The element section goes before the data count and code
+        // sections, the data section after the code section.
+        if !wrote_elements && (id == SECTION_DATA_COUNT || id == SECTION_CODE || id == SECTION_DATA)
+        {
+            write_element_section(&mut output, None, initialization)?;
+            wrote_elements = true;
+        }
+
+        match id {
+            SECTION_IMPORT => {
+                for import in ImportSectionReader::new(body, offset)? {
+                    match import?.ty {
+                        ImportSectionEntryType::Global(_) => imported_globals += 1,
+                        ImportSectionEntryType::Memory(_) | ImportSectionEntryType::Table(_) => {
+                            return Err(wasm_unsupported!(
+                                "rewriting a module importing memories or tables"
+                            ))
+                        }
+                        _ => {}
+                    }
+                }
+                write_section(&mut output, id, body);
+            }
+            SECTION_TABLE => {
+                let mut section = Vec::new();
+                let tables = TableSectionReader::new(body, offset)?;
+                write_u32(&mut section, tables.get_count());
+                for (index, table) in tables.into_iter().enumerate() {
+                    let table = table?;
+                    section.push(encode_type(table.element_type)?);
+                    let initial = initialization.table_sizes.get(index).copied();
+                    write_limits(&mut section, table.limits, initial, false);
+                }
+                write_section(&mut output, id, &section);
+            }
+            SECTION_MEMORY => {
+                let mut section = Vec::new();
+                let memories = MemorySectionReader::new(body, offset)?;
+                write_u32(&mut section, memories.get_count());
+                for (index, memory) in memories.into_iter().enumerate() {
+                    match memory? {
+                        MemoryType::M32 { limits, shared } => {
+                            let initial =
+                                initialization.memory_sizes.get(index).map(|pages| pages.0);
+                            write_limits(&mut section, limits, initial, shared);
+                        }
+                        MemoryType::M64 { .. } => {
+                            return Err(wasm_unsupported!("rewriting 64-bit memories"))
+                        }
+                    }
+                }
+                write_section(&mut output, id, &section);
+            }
+            SECTION_GLOBAL => {
+                let mut section = Vec::new();
+                let section_globals = GlobalSectionReader::new(body, offset)?;
+                write_u32(&mut section, section_globals.get_count());
+                for (index, global) in section_globals.into_iter().enumerate() {
+                    let global = global?;
+                    section.push(encode_type(global.ty.content_type)?);
+                    section.push(global.ty.mutable as u8);
+                    match globals.get(&GlobalIndex::new(imported_globals + index)) {
+                        Some(init) => write_global_init(&mut section, init)?,
+                        None => {
+                            let mut expr = global.init_expr.get_binary_reader();
+                            section.extend_from_slice(expr.read_bytes(expr.bytes_remaining())?);
+                        }
+                    }
+                }
+                write_section(&mut output, id, &section);
+            }
+            SECTION_EXPORT => {
+                let mut exports = Vec::new();
+                for export in ExportSectionReader::new(body, offset)? {
+                    let export = export?;
+                    if initialization
+                        .removed_exports
+                        .iter()
+                        .any(|name| name == export.field)
+                    {
+                        continue;
+                    }
+                    exports.push(export);
+                }
+                let mut section = Vec::new();
+                write_u32(&mut section, exports.len() as u32);
+                for export in exports {
+                    write_name(&mut section, export.field);
+                    section.push(encode_external_kind(export.kind)?);
+                    write_u32(&mut section, export.index);
+                }
+                write_section(&mut output, id, &section);
+            }
+            SECTION_START => {}
+            SECTION_ELEMENT => {
+                write_element_section(&mut output, Some((body, offset)), initialization)?;
+                wrote_elements = true;
+            }
+            SECTION_DATA_COUNT => {
+                let count = BinaryReader::new_with_offset(body, offset).read_var_u32()?;
+                let mut section = Vec::new();
+                write_u32(
+                    &mut section,
+                    count + initialization.data_initializers.len() as u32,
+                );
+                write_section(&mut output, id, &section);
+            }
+            SECTION_DATA => {
+                write_data_section(&mut output, Some((body, offset)), initialization)?;
+                wrote_data = true;
+            }
+            // custom sections, and the ones which don't hold any state
+            _ => write_section(&mut output, id, body),
+        }
+    }
+
+    if !wrote_elements {
+        write_element_section(&mut output, None, initialization)?;
+    }
+    if !wrote_data {
+        write_data_section(&mut output, None, initialization)?;
+    }
+
+    Ok(output)
+}
+
+/// Write the element section, made of the segments of `original`
+/// followed by `initialization.table_initializers`.
+fn write_element_section(
+    output: &mut Vec<u8>,
+    original: Option<(&[u8], usize)>,
+    initialization: &ModuleInitialization,
+) -> WasmResult<()> {
+    let mut count = 0;
+    let mut section = Vec::new();
+
+    if let Some((body, offset)) = original {
+        for (index, element) in ElementSectionReader::new(body, offset)?
+            .into_iter()
+            .enumerate()
+        {
+            let element = element?;
+            let dropped = initialization
+                .dropped_elements
+                .contains(&ElemIndex::new(index));
+            match element.kind {
+                ElementKind::Passive if !dropped => {
+                    // passive, with expressions
+                    section.push(0x05);
+                    section.push(encode_type(element.ty)?);
+                    let items = element.items.get_items_reader()?;
+                    write_u32(&mut section, items.get_count());
+                    for item in items {
+                        match item? {
+                            ElementItem::Func(index) => {
+                                section.push(0xd2);
+                                write_u32(&mut section, index);
+                            }
+                            ElementItem::Null(ty) => {
+                                section.push(0xd0);
+                                section.push(encode_type(ty)?);
+                            }
+                        }
+                        section.push(0x0b);
+                    }
+                }
+                ElementKind::Passive | ElementKind::Active { .. } => {
+                    // passive and empty
+                    section.extend_from_slice(&[0x01, 0x00, 0x00]);
+                }
+                ElementKind::Declared => {
+                    return Err(wasm_unsupported!("element kind declared"));
+                }
+            }
+            count += 1;
+        }
+    }
+
+    for initializer in initialization.table_initializers.iter() {
+        // active, with an explicit table index
+        section.push(0x02);
+        write_u32(&mut section, initializer.table_index.as_u32());
+        write_offset(&mut section, initializer.base, initializer.offset);
+        section.push(0x00);
+        write_u32(&mut section, initializer.elements.len() as u32);
+        for function_index in initializer.elements.iter() {
+            write_u32(&mut section, function_index.as_u32());
+        }
+        count += 1;
+    }
+
+    if count > 0 {
+        let mut counted = Vec::new();
+        write_u32(&mut counted, count);
+        counted.extend_from_slice(&section);
+        write_section(output, SECTION_ELEMENT, &counted);
+    }
+    Ok(())
+}
+
+/// Write the data section, made of the segments of `original` followed by
+/// `initialization.data_initializers`.
+fn write_data_section(
+    output: &mut Vec<u8>,
+    original: Option<(&[u8], usize)>,
+    initialization: &ModuleInitialization,
+) -> WasmResult<()> {
+    let mut count = 0;
+    let mut section = Vec::new();
+
+    if let Some((body, offset)) = original {
+        for (index, data) in DataSectionReader::new(body, offset)?
+            .into_iter()
+            .enumerate()
+        {
+            let data = data?;
+            let dropped = initialization.dropped_data.contains(&DataIndex::new(index));
+            section.push(0x01);
+            match data.kind {
+                DataKind::Passive if !dropped => {
+                    write_u32(&mut section, data.data.len() as u32);
+                    section.extend_from_slice(data.data);
+                }
+                DataKind::Passive | DataKind::Active { .. } => write_u32(&mut section, 0),
+            }
+            count += 1;
+        }
+    }
+
+    for initializer in initialization.data_initializers.iter() {
+        // active, with an explicit memory index
+        section.push(0x02);
+        write_u32(&mut section, initializer.location.memory_index.as_u32());
+        write_offset(
+            &mut section,
+            initializer.location.base,
+            initializer.location.offset,
+        );
+        write_u32(&mut section, initializer.data.len() as u32);
+        section.extend_from_slice(initializer.data);
+        count += 1;
+    }
+
+    if count > 0 {
+        let mut counted = Vec::new();
+        write_u32(&mut counted, count);
+        counted.extend_from_slice(&section);
+        write_section(output, SECTION_DATA, &counted);
+    }
+    Ok(())
+}
+
+fn write_section(output: &mut Vec<u8>, id: u8, body: &[u8]) {
+    output.push(id);
+    write_u32(output, body.len() as u32);
+    output.extend_from_slice(body);
+}
+
+fn write_u32(output: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn write_i64(output: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn write_name(output: &mut Vec<u8>, name: &str) {
+    write_u32(output, name.len() as u32);
+    output.extend_from_slice(name.as_bytes());
+}
+
+fn write_limits(output: &mut Vec<u8>, limits: ResizableLimits, initial: Option<u32>, shared: bool) {
+    let flags = limits.maximum.is_some() as u8 | (shared as u8) << 1;
+    output.push(flags);
+    write_u32(output, initial.unwrap_or(limits.initial));
+    if let Some(maximum) = limits.maximum {
+        write_u32(output, maximum);
+    }
+}
+
+/// Write the constant expression of an offset in a table or memory.
+fn write_offset(output: &mut Vec<u8>, base: Option<GlobalIndex>, offset: usize) {
+    match base {
+        Some(global_index) => {
+            output.push(0x23);
+            write_u32(output, global_index.as_u32());
+        }
+        None => {
+            output.push(0x41);
+            write_i64(output, offset as u32 as i32 as i64);
+        }
+    }
+    output.push(0x0b);
+}
+
+fn write_global_init(output: &mut Vec<u8>, init: &GlobalInit) -> WasmResult<()> {
+    match init {
+        GlobalInit::I32Const(value) => {
+            output.push(0x41);
+            write_i64(output, *value as i64);
+        }
+        GlobalInit::I64Const(value) => {
+            output.push(0x42);
+            write_i64(output, *value);
+        }
+        GlobalInit::F32Const(value) => {
+            output.push(0x43);
+            output.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+        GlobalInit::F64Const(value) => {
+            output.push(0x44);
+            output.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+        GlobalInit::V128Const(value) => {
+            output.extend_from_slice(&[0xfd, 0x0c]);
+            output.extend_from_slice(value.bytes());
+        }
+        GlobalInit::GetGlobal(global_index) => {
+            output.push(0x23);
+            write_u32(output, global_index.as_u32());
+        }
+        GlobalInit::RefFunc(function_index) => {
+            output.push(0xd2);
+            write_u32(output, function_index.as_u32());
+        }
+        GlobalInit::RefNullConst => {
+            return Err(wasm_unsupported!("rewriting a null reference global"));
+        }
+    }
+    output.push(0x0b);
+    Ok(())
+}
+
+fn encode_type(ty: Type) -> WasmResult<u8> {
+    Ok(match ty {
+        Type::I32 => 0x7f,
+        Type::I64 => 0x7e,
+        Type::F32 => 0x7d,
+        Type::F64 => 0x7c,
+        Type::V128 => 0x7b,
+        Type::FuncRef => 0x70,
+        Type::ExternRef => 0x6f,
+        ty => return Err(wasm_unsupported!("rewriting the type {:?}", ty)),
+    })
+}
+
+fn encode_external_kind(kind: ExternalKind) -> WasmResult<u8> {
+    Ok(match kind {
+        ExternalKind::Function => 0x00,
+        ExternalKind::Table => 0x01,
+        ExternalKind::Memory => 0x02,
+        ExternalKind::Global => 0x03,
+        kind => return Err(wasm_unsupported!("rewriting the export kind {:?}", kind)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128() {
+        let mut output = Vec::new();
+        write_u32(&mut output, 624_485);
+        assert_eq!(output, [0xe5, 0x8e, 0x26]);
+
+        output.clear();
+        write_i64(&mut output, -123_456);
+        assert_eq!(output, [0xc0, 0xbb, 0x78]);
+
+        output.clear();
+        write_i64(&mut output, 64);
+        assert_eq!(output, [0xc0, 0x00]);
+    }
+}
@@ -137,17 +137,27 @@ pub fn _getnameinfo(
 // They save the stack to allow unwinding
 
 // Macro definitions
+/// Call a function of the table like the `invoke_*` trampolines of
+/// Emscripten, catching a `longjmp` out of it.
+///
+/// The data isn't kept locked during the call, as the function may call
+/// back into the host.
 macro_rules! invoke {
     ($ctx: ident, $name:ident, $name_ref:ident, $( $arg:ident ),*) => {{
-        let sp = get_emscripten_data($ctx).stack_save_ref().expect("stack_save is None").call().expect("stack_save call failed");
-        let result = get_emscripten_data($ctx).$name_ref().expect(concat!("Dynamic call is None: ", stringify!($name))).call($($arg),*);
-        match result {
+        let (stack_save, stack_restore, dyn_call) = {
+            let data = get_emscripten_data($ctx);
+            (
+                data.stack_save_ref().expect("stack_save is None").clone(),
+                data.stack_restore_ref().expect("stack_restore is None").clone(),
+                data.$name_ref().expect(concat!("Dynamic call is None: ", stringify!($name))).clone(),
+            )
+        };
+        let sp = stack_save.call().expect("stack_save call failed");
+        match dyn_call.call($($arg),*) {
             Ok(v) => v,
-            Err(_e) => {
-                get_emscripten_data($ctx).stack_restore_ref().expect("stack_restore is None").call(sp).expect("stack_restore call failed");
-                // TODO: We should check if _e != "longjmp" and if that's the case, re-throw the error
-                // JS version is: if (e !== e+0 && e !== 'longjmp') throw e;
-                get_emscripten_data($ctx).set_threw_ref().expect("set_threw is None").call(1, 0).expect("set_threw call failed");
+            Err(e) => {
+                stack_restore.call(sp).expect("stack_restore call failed");
+                crate::jmp::catch_longjmp($ctx, e);
                 0 as _
             }
         }
@@ -155,16 +165,18 @@ macro_rules! invoke {
 }
 macro_rules! invoke_no_return {
     ($ctx: ident, $name:ident, $name_ref:ident, $( $arg:ident ),*) => {{
-        let sp = get_emscripten_data($ctx).stack_save_ref().expect("stack_save is None").call().expect("stack_save call failed");
-        let result = get_emscripten_data($ctx).$name_ref().expect(concat!("Dynamic call is None: ", stringify!($name))).call($($arg),*);
-        match result {
-            Ok(v) => v,
-            Err(_e) => {
-                get_emscripten_data($ctx).stack_restore_ref().expect("stack_restore is None").call(sp).expect("stack_restore call failed");
-                // TODO: We should check if _e != "longjmp" and if that's the case, re-throw the error
-                // JS version is: if (e !== e+0 && e !== 'longjmp') throw e;
-                get_emscripten_data($ctx).set_threw_ref().expect("set_threw is None").call(1, 0).expect("set_threw call failed");
-            }
+        let (stack_save, stack_restore, dyn_call) = {
+            let data = get_emscripten_data($ctx);
+            (
+                data.stack_save_ref().expect("stack_save is None").clone(),
+                data.stack_restore_ref().expect("stack_restore is None").clone(),
+                data.$name_ref().expect(concat!("Dynamic call is None: ", stringify!($name))).clone(),
+            )
+        };
+        let sp = stack_save.call().expect("stack_save call failed");
+        if let Err(e) = dyn_call.call($($arg),*) {
+            stack_restore.call(sp).expect("stack_restore call failed");
+            crate::jmp::catch_longjmp($ctx, e);
         }
     }};
 }
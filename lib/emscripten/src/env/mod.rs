@@ -22,14 +22,12 @@ use std::os::raw::c_int;
 use std::sync::MutexGuard;
 
 use crate::EmEnv;
-use wasmer::ValueType;
+use wasmer::{RuntimeError, ValueType};
 
 pub fn call_malloc(ctx: &EmEnv, size: u32) -> u32 {
-    get_emscripten_data(ctx)
-        .malloc_ref()
-        .unwrap()
-        .call(size)
-        .unwrap()
+    // `malloc` may call `sbrk`, which needs the data too
+    let malloc = get_emscripten_data(ctx).malloc_ref().unwrap().clone();
+    malloc.call(size).unwrap()
 }
 
 #[warn(dead_code)]
@@ -37,6 +35,15 @@ pub fn call_malloc_with_cast<T: Copy, Ty>(ctx: &EmEnv, size: u32) -> WasmPtr<T,
     WasmPtr::new(call_malloc(ctx, size))
 }
 
+pub fn call_realloc(ctx: &EmEnv, pointer: u32, size: u32) -> u32 {
+    // `realloc` may call `sbrk`, which needs the data too
+    let realloc = get_emscripten_data(ctx).realloc_ref().cloned();
+    let realloc = realloc.unwrap_or_else(|| abort_guest("missing function: realloc"));
+    realloc
+        .call(pointer, size)
+        .unwrap_or_else(|error| RuntimeError::raise(Box::new(error)))
+}
+
 pub fn call_memalign(ctx: &EmEnv, alignment: u32, size: u32) -> u32 {
     if let Some(memalign) = &get_emscripten_data(ctx).memalign_ref() {
         memalign.call(alignment, size).unwrap()
@@ -53,6 +60,12 @@ pub fn call_memset(ctx: &EmEnv, pointer: u32, value: u32, size: u32) -> u32 {
         .unwrap()
 }
 
+/// Abort the guest with `message`, returning it to the host as a
+/// `RuntimeError` rather than aborting the process.
+pub(crate) fn abort_guest(message: &str) -> ! {
+    RuntimeError::raise(Box::new(RuntimeError::new(message)))
+}
+
 pub(crate) fn get_emscripten_data(ctx: &EmEnv) -> MutexGuard<EmscriptenData> {
     ctx.data.lock().unwrap()
 }
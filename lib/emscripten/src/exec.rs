@@ -5,6 +5,11 @@ use std::cell::Cell;
 use std::ffi::CString;
 
 pub fn execvp(ctx: &EmEnv, command_name_offset: u32, argv_offset: u32) -> i32 {
+    // The command would run on the host, outside of the sandbox
+    if crate::sandbox::is_sandboxed(ctx) {
+        return -1;
+    }
+
     // a single reference to re-use
     let emscripten_memory = ctx.memory(0);
 
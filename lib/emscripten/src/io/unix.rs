@@ -3,6 +3,7 @@ use super::super::utils::copy_cstr_into_wasm;
 use libc::{chroot as _chroot, getpwuid as _getpwuid, printf as _printf};
 use std::mem;
 
+use crate::sandbox::host_path;
 use crate::EmEnv;
 
 /// putchar
@@ -23,7 +24,11 @@ pub fn printf(ctx: &EmEnv, memory_offset: i32, extra: i32) -> i32 {
 pub fn chroot(ctx: &EmEnv, name_ptr: i32) -> i32 {
     debug!("emscripten::chroot");
     let name = emscripten_memory_pointer!(ctx.memory(0), name_ptr) as *const i8;
-    unsafe { _chroot(name as *const _) }
+    let name = match host_path(ctx, name as *const _) {
+        Ok(name) => name,
+        Err(errno) => return errno,
+    };
+    unsafe { _chroot(name.as_ptr()) }
 }
 
 /// getpwuid
@@ -1,8 +1,18 @@
-use super::env::get_emscripten_data;
+//! `setjmp` and `longjmp`.
+//!
+//! Emscripten lowers `setjmp` in the module itself: the calls which may
+//! `longjmp` go through the `invoke_*` trampolines, and the jump buffers
+//! registered with `saveSetjmp` hold an identifier of the `setjmp` and its
+//! label. `longjmp` records the buffer and the value with `setThrew`, then
+//! unwinds the stack with a `LongJumpRet` trap up to the trampoline, which
+//! lets the caller find the matching `setjmp` with `testSetjmp`.
+
+use super::env::{abort_guest, call_realloc, get_emscripten_data};
 use super::process::abort_with_message;
-use libc::c_int;
-// use std::cell::UnsafeCell;
+use crate::ptr::{Array, WasmPtr};
 use crate::EmEnv;
+use libc::c_int;
+use std::cell::Cell;
 use std::error::Error;
 use std::fmt;
 
@@ -11,38 +21,18 @@ use wasmer::RuntimeError;
 /// setjmp
 pub fn __setjmp(ctx: &EmEnv, _env_addr: u32) -> c_int {
     debug!("emscripten::__setjmp (setjmp)");
-    abort_with_message(ctx, "missing function: _setjmp");
+    // `setjmp` can only return twice in modules where Emscripten lowered it
+    abort_with_message(
+        ctx,
+        "missing function: _setjmp, the module must be built with Emscripten's setjmp support",
+    );
     unreachable!()
-    // unsafe {
-    //     // Rather than using the env as the holder of the jump buffer pointer,
-    //     // we use the environment address to store the index relative to jumps
-    //     // so the address of the jump it's outside the wasm memory itself.
-    //     let jump_index = emscripten_memory_pointer!(ctx.memory(0), env_addr) as *mut i8;
-    //     // We create the jump buffer outside of the wasm memory
-    //     let jump_buf: UnsafeCell<[u32; 27]> = UnsafeCell::new([0; 27]);
-    //     let jumps = &mut get_emscripten_data(ctx).jumps;
-    //     let result = setjmp(jump_buf.get() as _);
-    //     // We set the jump index to be the last 3value of jumps
-    //     *jump_index = jumps.len() as _;
-    //     // We hold the reference of the jump buffer
-    //     jumps.push(jump_buf);
-    //     result
-    // }
 }
 
 /// longjmp
-#[allow(unreachable_code)]
-pub fn __longjmp(ctx: &EmEnv, _env_addr: u32, _val: c_int) {
+pub fn __longjmp(ctx: &EmEnv, env_addr: u32, val: c_int) {
     debug!("emscripten::__longjmp (longmp)");
-    abort_with_message(ctx, "missing function: _longjmp");
-    // unsafe {
-    //     // We retrieve the jump index from the env address
-    //     let jump_index = emscripten_memory_pointer!(ctx.memory(0), env_addr) as *mut i8;
-    //     let jumps = &mut get_emscripten_data(ctx).jumps;
-    //     // We get the real jump buffer from the jumps vector, using the retrieved index
-    //     let jump_buf = &jumps[*jump_index as usize];
-    //     longjmp(jump_buf.get() as _, val)
-    // };
+    _longjmp(ctx, env_addr as i32, val);
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -57,21 +47,96 @@ impl fmt::Display for LongJumpRet {
 impl Error for LongJumpRet {}
 
 /// _longjmp
-// This function differs from the js implementation, it should return Result<(), &'static str>
-#[allow(unreachable_code)]
 pub fn _longjmp(ctx: &EmEnv, env_addr: i32, val: c_int) {
+    debug!("emscripten::_longjmp {}, {}", env_addr, val);
     let val = if val == 0 { 1 } else { val };
-    get_emscripten_data(ctx)
-        .set_threw_ref()
-        .expect("set_threw is None")
-        .call(env_addr, val)
-        .expect("set_threw failed to call");
-    // TODO: return Err("longjmp")
+    set_threw(ctx, env_addr, val);
     RuntimeError::raise(Box::new(LongJumpRet));
-    unreachable!();
 }
 
-// extern "C" {
-//     fn setjmp(env: *mut c_void) -> c_int;
-//     fn longjmp(env: *mut c_void, val: c_int) -> !;
-// }
+/// Handle the error raised by the function called by an `invoke_*`
+/// trampoline: a `longjmp` stops there, any other error keeps unwinding.
+pub(crate) fn catch_longjmp(ctx: &EmEnv, error: RuntimeError) {
+    if !error.is::<LongJumpRet>() {
+        RuntimeError::raise(Box::new(error));
+    }
+    // `setThrew` keeps the buffer and the value set by `longjmp`
+    set_threw(ctx, 1, 0);
+}
+
+fn set_threw(ctx: &EmEnv, threw: i32, value: i32) {
+    let set_threw = get_emscripten_data(ctx).set_threw_ref().cloned();
+    let set_threw = set_threw.unwrap_or_else(|| abort_guest("missing function: setThrew"));
+    if let Err(error) = set_threw.call(threw, value) {
+        RuntimeError::raise(Box::new(error));
+    }
+}
+
+/// The `size` entries of the `setjmp` table at `table`, and the one after
+/// them, as pairs of words: the identifier of a `setjmp` and its label.
+fn setjmp_entries(ctx: &EmEnv, table: u32, size: u32) -> &[Cell<u32>] {
+    size.checked_add(1)
+        .and_then(|entries| entries.checked_mul(2))
+        .and_then(|words| WasmPtr::<u32, Array>::new(table).deref(ctx.memory(0), 0, words))
+        .unwrap_or_else(|| abort_guest("the setjmp table is out of bounds"))
+}
+
+/// _saveSetjmp
+///
+/// Register the `setjmp` of the buffer `env_addr` under a new identifier,
+/// with its `label` in the function, in the first free entry of `table`.
+/// `table` is reallocated with twice its `size` if it's full; the new table
+/// is returned, and its size through `tempRet0`.
+#[allow(non_snake_case)]
+pub fn _saveSetjmp(ctx: &EmEnv, env_addr: u32, label: u32, mut table: u32, mut size: u32) -> u32 {
+    debug!("emscripten::_saveSetjmp {}, {}", env_addr, label);
+    let setjmp_id = {
+        let mut data = get_emscripten_data(ctx);
+        data.setjmp_id += 1;
+        data.setjmp_id
+    };
+    WasmPtr::<u32>::new(env_addr)
+        .deref(ctx.memory(0))
+        .unwrap_or_else(|| abort_guest("the setjmp buffer is out of bounds"))
+        .set(setjmp_id);
+    loop {
+        let entries = setjmp_entries(ctx, table, size);
+        if let Some(entry) = entries
+            .chunks(2)
+            .take(size as usize)
+            .position(|entry| entry[0].get() == 0)
+        {
+            entries[2 * entry].set(setjmp_id);
+            entries[2 * entry + 1].set(label);
+            // the entry after it marks the end of the table
+            entries[2 * entry + 2].set(0);
+            get_emscripten_data(ctx).temp_ret_0 = size as i32;
+            return table;
+        }
+        size = size
+            .checked_mul(2)
+            .filter(|size| *size < u32::MAX / 8)
+            .unwrap_or_else(|| abort_guest("the setjmp table is too large"));
+        table = call_realloc(ctx, table, 8 * (size + 1));
+    }
+}
+
+/// _testSetjmp
+///
+/// The label of the `setjmp` with the identifier `id` in `table`, or 0 if
+/// it isn't one of the function.
+#[allow(non_snake_case)]
+pub fn _testSetjmp(ctx: &EmEnv, id: u32, table: u32, size: u32) -> u32 {
+    debug!("emscripten::_testSetjmp {}", id);
+    for entry in setjmp_entries(ctx, table, size)
+        .chunks(2)
+        .take(size as usize)
+    {
+        match entry[0].get() {
+            0 => break,
+            current if current == id => return entry[1].get(),
+            _ => {}
+        }
+    }
+    0
+}
@@ -53,6 +53,7 @@ mod memory;
 mod process;
 mod pthread;
 mod ptr;
+mod sandbox;
 mod signal;
 mod storage;
 mod syscalls;
@@ -62,6 +63,8 @@ mod unistd;
 mod utils;
 mod varargs;
 
+#[cfg(feature = "pthreads")]
+pub use self::pthread::ThreadExit;
 pub use self::storage::{align_memory, static_alloc};
 pub use self::utils::{
     allocate_cstr_on_stack, allocate_on_stack, get_emscripten_memory_size, get_emscripten_metadata,
@@ -91,6 +94,17 @@ impl EmEnv {
         }
     }
 
+    /// Like [`EmEnv::new`], but the syscalls taking a path can only reach
+    /// the files in `mapped_dirs`, rather than all the files of the host.
+    pub fn new_sandboxed(
+        data: &EmscriptenGlobalsData,
+        mapped_dirs: HashMap<String, PathBuf>,
+    ) -> Self {
+        let env = Self::new(data, mapped_dirs);
+        env.data.lock().unwrap().sandboxed = true;
+        env
+    }
+
     pub fn set_memory(&mut self, memory: Memory) {
         let ptr = Arc::as_ptr(&self.memory) as *mut _;
         unsafe {
@@ -143,6 +157,8 @@ pub struct EmscriptenData {
     pub malloc: LazyInit<NativeFunc<u32, u32>>,
     #[wasmer(export(alias = "_free", optional = true))]
     pub free: LazyInit<NativeFunc<u32>>,
+    #[wasmer(export(alias = "_realloc", optional = true))]
+    pub realloc: LazyInit<NativeFunc<(u32, u32), u32>>,
     #[wasmer(export(alias = "_memalign", optional = true))]
     pub memalign: LazyInit<NativeFunc<(u32, u32), u32>>,
     #[wasmer(export(alias = "_memset", optional = true))]
@@ -267,6 +283,8 @@ pub struct EmscriptenData {
     pub dyn_call_viidddddddd:
         LazyInit<NativeFunc<(i32, i32, i32, f64, f64, f64, f64, f64, f64, f64, f64)>>,
     pub temp_ret_0: i32,
+    /// The identifier of the last `setjmp` buffer saved by `saveSetjmp`
+    pub setjmp_id: u32,
 
     #[wasmer(export(name = "stackSave", optional = true))]
     pub stack_save: LazyInit<NativeFunc<(), i32>>,
@@ -275,6 +293,17 @@ pub struct EmscriptenData {
     #[wasmer(export(name = "setThrew", alias = "_setThrew", optional = true))]
     pub set_threw: LazyInit<NativeFunc<(i32, i32)>>,
     pub mapped_dirs: HashMap<String, PathBuf>,
+    /// Whether the syscalls taking a path are confined to `mapped_dirs`
+    pub sandboxed: bool,
+    /// The current directory of the guest, when it's sandboxed
+    pub(crate) current_dir: PathBuf,
+
+    /// The threads of the instance, shared by all of them
+    #[cfg(feature = "pthreads")]
+    pub(crate) pthreads: Option<Arc<crate::pthread::Pthreads>>,
+    /// The thread running the instance
+    #[cfg(feature = "pthreads")]
+    pub(crate) thread_id: u32,
 }
 
 impl EmscriptenData {
@@ -286,6 +315,7 @@ impl EmscriptenData {
             globals,
             temp_ret_0: 0,
             mapped_dirs,
+            current_dir: PathBuf::from("/"),
             ..Default::default()
         }
     }
@@ -374,7 +404,7 @@ pub fn run_emscripten_instance(
 
     // println!("running emscripten instance");
 
-    if let Some(ep) = entrypoint {
+    let result = if let Some(ep) = entrypoint {
         debug!("Running entry point: {}", &ep);
         let arg = unsafe { allocate_cstr_on_stack(env, args[0]).0 };
         //let (argc, argv) = store_module_arguments(instance.context_mut(), args);
@@ -382,10 +412,13 @@ pub fn run_emscripten_instance(
             .exports
             .get(&ep)
             .map_err(|e| RuntimeError::new(e.to_string()))?;
-        func.call(&[Val::I32(arg as i32)])?;
+        func.call(&[Val::I32(arg as i32)]).map(drop)
     } else {
-        emscripten_call_main(instance, env, path, &args)?;
-    }
+        emscripten_call_main(instance, env, path, &args)
+    };
+    #[cfg(feature = "pthreads")]
+    let result = crate::pthread::main_thread_result(env, result);
+    result?;
 
     // TODO atexit for emscripten
     // println!("{:?}", data);
@@ -448,6 +481,9 @@ pub struct EmscriptenGlobals {
     pub memory_min: Pages,
     pub memory_max: Option<Pages>,
     pub null_function_names: Vec<String>,
+    /// The module, instantiated again for each thread
+    #[cfg(feature = "pthreads")]
+    module: Module,
 }
 
 impl EmscriptenGlobals {
@@ -535,6 +571,8 @@ impl EmscriptenGlobals {
             memory_min,
             memory_max,
             null_function_names,
+            #[cfg(feature = "pthreads")]
+            module: module.clone(),
         })
     }
 }
@@ -851,6 +889,8 @@ pub fn generate_emscripten_env(
         "__longjmp" => Function::new_native_with_env(store, env.clone(), crate::jmp::__longjmp),
         "_longjmp" => Function::new_native_with_env(store, env.clone(), crate::jmp::_longjmp),
         "_emscripten_longjmp" => Function::new_native_with_env(store, env.clone(), crate::jmp::_longjmp),
+        "_saveSetjmp" => Function::new_native_with_env(store, env.clone(), crate::jmp::_saveSetjmp),
+        "_testSetjmp" => Function::new_native_with_env(store, env.clone(), crate::jmp::_testSetjmp),
 
         // Bitwise
         "_llvm_bswap_i64" => Function::new_native_with_env(store, env.clone(), crate::bitwise::_llvm_bswap_i64),
@@ -999,6 +1039,75 @@ pub fn generate_emscripten_env(
         "_confstr" => Function::new_native_with_env(store, env.clone(), crate::unistd::confstr),
     };
 
+    #[cfg(feature = "pthreads")]
+    {
+        let mut data = env.data.lock().unwrap();
+        // The threads created later set up their data themselves
+        if data.pthreads.is_none() {
+            data.pthreads = Some(Arc::new(crate::pthread::Pthreads::new(
+                globals.module.clone(),
+                globals.memory.clone(),
+                data.mapped_dirs.clone(),
+                data.sandboxed,
+            )));
+            data.thread_id = crate::pthread::MAIN_THREAD_ID;
+        }
+        drop(data);
+
+        for (name, function) in vec![
+            (
+                "_pthread_attr_setdetachstate",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_attr_setdetachstate,
+                ),
+            ),
+            (
+                "_pthread_cond_broadcast",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_cond_broadcast,
+                ),
+            ),
+            (
+                "_pthread_key_delete",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_key_delete,
+                ),
+            ),
+            (
+                "_pthread_mutex_lock",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_mutex_lock,
+                ),
+            ),
+            (
+                "_pthread_mutex_trylock",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_mutex_trylock,
+                ),
+            ),
+            (
+                "_pthread_mutex_unlock",
+                Function::new_native_with_env(
+                    store,
+                    env.clone(),
+                    crate::pthread::_pthread_mutex_unlock,
+                ),
+            ),
+        ] {
+            env_ns.insert(name, function);
+        }
+    }
+
     // Compatibility with newer versions of Emscripten
     let mut to_insert: Vec<(String, _)> = vec![];
     for (k, v) in env_ns.iter() {
@@ -52,7 +52,11 @@ pub fn killpg(_a: i32, _b: i32) -> i32 {
 pub fn pathconf(ctx: &EmEnv, path_ptr: i32, name: i32) -> i32 {
     debug!("emscripten::pathconf");
     let path = emscripten_memory_pointer!(ctx.memory(0), path_ptr) as *const i8;
-    unsafe { libc::pathconf(path as *const _, name).try_into().unwrap() }
+    let path = match crate::sandbox::host_path(ctx, path as *const _) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    unsafe { libc::pathconf(path.as_ptr(), name).try_into().unwrap() }
 }
 
 #[cfg(not(unix))]
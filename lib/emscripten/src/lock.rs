@@ -1,12 +1,18 @@
 use crate::EmEnv;
+#[cfg(not(feature = "pthreads"))]
 use libc::c_int;
 
+#[cfg(feature = "pthreads")]
+pub use crate::pthread::{___lock, ___unlock};
+
 // NOTE: Not implemented by Emscripten
+#[cfg(not(feature = "pthreads"))]
 pub fn ___lock(_ctx: &EmEnv, _what: c_int) {
     debug!("emscripten::___lock {}", _what);
 }
 
 // NOTE: Not implemented by Emscripten
+#[cfg(not(feature = "pthreads"))]
 pub fn ___unlock(_ctx: &EmEnv, _what: c_int) {
     debug!("emscripten::___unlock {}", _what);
 }
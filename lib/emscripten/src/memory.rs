@@ -40,6 +40,22 @@ fn align_up(mut val: usize, multiple: usize) -> usize {
 /// Note: this function only allows growing the size of heap
 pub fn _emscripten_resize_heap(ctx: &EmEnv, requested_size: u32) -> u32 {
     debug!("emscripten::_emscripten_resize_heap {}", requested_size);
+    #[cfg(feature = "pthreads")]
+    {
+        let pthreads = get_emscripten_data(ctx).pthreads.clone();
+        if let Some(pthreads) = pthreads {
+            return pthreads
+                .grow_alone(|| resize_heap(ctx, requested_size))
+                .unwrap_or_else(|| {
+                    debug!("=> the memory can't grow while other threads are running");
+                    0
+                });
+        }
+    }
+    resize_heap(ctx, requested_size)
+}
+
+fn resize_heap(ctx: &EmEnv, requested_size: u32) -> u32 {
     let current_memory_pages = ctx.memory(0).size();
     let current_memory = current_memory_pages.bytes().0 as u32;
 
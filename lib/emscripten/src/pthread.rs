@@ -1,5 +1,25 @@
 use crate::EmEnv;
 
+#[cfg(feature = "pthreads")]
+mod threads;
+
+#[cfg(feature = "pthreads")]
+pub use self::threads::ThreadExit;
+#[cfg(feature = "pthreads")]
+pub use self::threads::{
+    ___lock, ___unlock, _pthread_attr_init, _pthread_attr_setdetachstate,
+    _pthread_attr_setstacksize, _pthread_cond_broadcast, _pthread_cond_destroy, _pthread_cond_init,
+    _pthread_cond_signal, _pthread_cond_timedwait, _pthread_cond_wait, _pthread_create,
+    _pthread_detach, _pthread_equal, _pthread_exit, _pthread_getspecific, _pthread_join,
+    _pthread_key_create, _pthread_key_delete, _pthread_mutex_destroy, _pthread_mutex_init,
+    _pthread_mutex_lock, _pthread_mutex_trylock, _pthread_mutex_unlock, _pthread_mutexattr_init,
+    _pthread_mutexattr_settype, _pthread_once, _pthread_rwlock_destroy, _pthread_rwlock_init,
+    _pthread_rwlock_rdlock, _pthread_rwlock_unlock, _pthread_rwlock_wrlock, _pthread_self,
+    _pthread_setspecific,
+};
+#[cfg(feature = "pthreads")]
+pub(crate) use self::threads::{main_thread_result, Pthreads, MAIN_THREAD_ID};
+
 pub fn _pthread_attr_destroy(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_attr_destroy");
     0
@@ -18,11 +38,13 @@ pub fn _pthread_attr_getstack(_ctx: &EmEnv, _stackaddr: i32, _stacksize: i32, _o
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_attr_init(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_attr_init({})", _a);
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_attr_setstacksize(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_attr_setstacksize");
     0
@@ -36,26 +58,31 @@ pub fn _pthread_cleanup_push(_ctx: &EmEnv, _a: i32, _b: i32) {
     trace!("emscripten::_pthread_cleanup_push");
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_cond_destroy(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_cond_destroy");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_cond_init(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_cond_init");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_cond_signal(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_cond_signal");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_cond_timedwait(_ctx: &EmEnv, _a: i32, _b: i32, _c: i32) -> i32 {
     trace!("emscripten::_pthread_cond_timedwait");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_cond_wait(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_cond_wait");
     0
@@ -76,22 +103,26 @@ pub fn _pthread_condattr_setclock(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_create(_ctx: &EmEnv, _a: i32, _b: i32, _c: i32, _d: i32) -> i32 {
     trace!("emscripten::_pthread_create");
     // 11 seems to mean "no"
     11
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_detach(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_detach");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_equal(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_equal");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_exit(_ctx: &EmEnv, _a: i32) {
     trace!("emscripten::_pthread_exit");
 }
@@ -101,31 +132,37 @@ pub fn _pthread_getattr_np(_ctx: &EmEnv, _thread: i32, _attr: i32) -> i32 {
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_getspecific(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_getspecific");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_join(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_join");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_self(_ctx: &EmEnv) -> i32 {
     trace!("emscripten::_pthread_self");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_key_create(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_key_create");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_mutex_destroy(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_mutex_destroy");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_mutex_init(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_mutex_init");
     0
@@ -136,41 +173,49 @@ pub fn _pthread_mutexattr_destroy(_ctx: &EmEnv, _a: i32) -> i32 {
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_mutexattr_init(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_mutexattr_init");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_mutexattr_settype(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_mutexattr_settype");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_once(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_once");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_rwlock_destroy(_ctx: &EmEnv, _rwlock: i32) -> i32 {
     trace!("emscripten::_pthread_rwlock_destroy({})", _rwlock);
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_rwlock_init(_ctx: &EmEnv, _rwlock: i32, _attr: i32) -> i32 {
     trace!("emscripten::_pthread_rwlock_init({}, {})", _rwlock, _attr);
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_rwlock_rdlock(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_rwlock_rdlock");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_rwlock_unlock(_ctx: &EmEnv, _a: i32) -> i32 {
     trace!("emscripten::_pthread_rwlock_unlock");
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_rwlock_wrlock(_ctx: &EmEnv, _rwlock: i32) -> i32 {
     trace!("emscripten::_pthread_rwlock_wrlock({})", _rwlock);
     0
@@ -181,6 +226,7 @@ pub fn _pthread_setcancelstate(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     0
 }
 
+#[cfg(not(feature = "pthreads"))]
 pub fn _pthread_setspecific(_ctx: &EmEnv, _a: i32, _b: i32) -> i32 {
     trace!("emscripten::_pthread_setspecific");
     0
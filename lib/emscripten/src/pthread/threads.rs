@@ -0,0 +1,905 @@
+//! Threads backed by host threads, with the `pthreads` feature.
+//!
+//! Each thread runs in a new instance of the module, importing the memory
+//! of the main instance, and its own table and Emscripten globals. The
+//! stack of the thread is allocated in the shared memory and passed to the
+//! instance through `STACKTOP` and `STACK_MAX`, so the values of the globals
+//! of the instance, like the stack pointer, are local to the thread. The
+//! start routine is called through the `dynCall_ii` export of the module.
+//!
+//! The mutexes, condition variables, read-write locks and keys are managed
+//! by the host, by their address in the memory. Their state in the memory
+//! isn't used, so they must only be used through the imports.
+//!
+//! The memory of a module built with pthreads isn't initialized by its data
+//! segments, so instantiating it again doesn't undo what the other threads
+//! wrote. The memory only grows while the main thread is the only one
+//! running, as the instances of the other threads would use it while it
+//! moves: `emscripten_resize_heap` fails otherwise.
+//!
+//! A thread which traps brings the instance down like it would a native
+//! process: the trap is raised again in the thread joining it, and in the
+//! main thread when it next waits or when its entry point returns.
+
+use crate::env::{call_malloc, get_emscripten_data};
+use crate::{generate_emscripten_env, EmEnv, EmscriptenGlobals};
+use ::libc::{EAGAIN, EBUSY, EDEADLK, EFAULT, EINVAL, EPERM, ESRCH, ETIMEDOUT};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasmer::{Memory, Module, RuntimeError};
+
+/// The identifier of the thread running the instance created by the
+/// embedder.
+pub(crate) const MAIN_THREAD_ID: u32 = 1;
+
+/// The stack size of the threads created without one, like Emscripten's.
+const DEFAULT_STACK_SIZE: u32 = 2 * 1024 * 1024;
+
+/// The `PTHREAD_MUTEX_RECURSIVE` mutex type.
+const MUTEX_RECURSIVE: u32 = 1;
+
+/// The threads of an instance and their synchronization primitives.
+pub(crate) struct Pthreads {
+    module: Module,
+    memory: Memory,
+    mapped_dirs: HashMap<String, PathBuf>,
+    sandboxed: bool,
+    state: Mutex<State>,
+    /// Notified whenever `state` changes in a way a thread may wait for
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    next_thread_id: u32,
+    threads: HashMap<u32, Thread>,
+    mutexes: HashMap<u32, MutexState>,
+    conds: HashMap<u32, CondState>,
+    rwlocks: HashMap<u32, RwLockState>,
+    /// Whether the `pthread_once` of a control is done, by address
+    onces: HashMap<u32, bool>,
+    next_key: u32,
+    specific: HashMap<(u32, u32), u32>,
+    /// The first trap of a thread, to raise in the main thread
+    trap: Option<RuntimeError>,
+}
+
+struct Thread {
+    /// The value the thread returned or its trap, once it's finished
+    result: Option<Result<i32, RuntimeError>>,
+    detached: bool,
+    stack: u32,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct MutexState {
+    kind: u32,
+    owner: Option<u32>,
+    count: u32,
+}
+
+#[derive(Default)]
+struct CondState {
+    waiters: u32,
+    /// The waiters allowed to wake up
+    signals: u32,
+}
+
+#[derive(Default)]
+struct RwLockState {
+    readers: u32,
+    writer: Option<u32>,
+}
+
+/// Raised by `pthread_exit` to unwind the thread.
+///
+/// In the main thread, it's raised once the other threads are finished,
+/// and the entry point of the instance returns it as a `RuntimeError`.
+#[derive(Debug)]
+pub struct ThreadExit(pub i32);
+
+impl fmt::Display for ThreadExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "thread exited with {}", self.0)
+    }
+}
+
+impl Error for ThreadExit {}
+
+impl Pthreads {
+    pub(crate) fn new(
+        module: Module,
+        memory: Memory,
+        mapped_dirs: HashMap<String, PathBuf>,
+        sandboxed: bool,
+    ) -> Self {
+        Self {
+            module,
+            memory,
+            mapped_dirs,
+            sandboxed,
+            state: Mutex::new(State {
+                next_thread_id: MAIN_THREAD_ID + 1,
+                next_key: 1,
+                ..Default::default()
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Call `grow` if no thread other than the main one is running, so that
+    /// no instance uses the memory while it grows. No thread can be created
+    /// meanwhile.
+    pub(crate) fn grow_alone<T>(&self, grow: impl FnOnce() -> T) -> Option<T> {
+        let state = self.state();
+        if state.threads.values().any(|thread| thread.result.is_none()) {
+            return None;
+        }
+        let grown = grow();
+        drop(state);
+        Some(grown)
+    }
+
+    /// The trap of a thread, once one has trapped.
+    fn trap(&self) -> Option<RuntimeError> {
+        self.state().trap.clone()
+    }
+
+    /// Raise the trap of a thread in the main thread, which would
+    /// otherwise wait for it forever.
+    fn check_trap<'a>(&self, state: MutexGuard<'a, State>, thread: u32) -> MutexGuard<'a, State> {
+        if thread == MAIN_THREAD_ID {
+            if let Some(trap) = state.trap.clone() {
+                // The guard isn't dropped by the unwinding
+                drop(state);
+                RuntimeError::raise(Box::new(trap));
+            }
+        }
+        state
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>, thread: u32) -> MutexGuard<'a, State> {
+        let state = self.check_trap(state, thread);
+        let state = self.changed.wait(state).unwrap();
+        self.check_trap(state, thread)
+    }
+
+    /// Wait until `deadline` at most, returning whether it's passed.
+    fn wait_until<'a>(
+        &self,
+        state: MutexGuard<'a, State>,
+        thread: u32,
+        deadline: SystemTime,
+    ) -> (MutexGuard<'a, State>, bool) {
+        let state = self.check_trap(state, thread);
+        match deadline.duration_since(SystemTime::now()) {
+            Ok(timeout) if timeout > Duration::from_secs(0) => {
+                let state = self.changed.wait_timeout(state, timeout).unwrap().0;
+                (self.check_trap(state, thread), false)
+            }
+            _ => (state, true),
+        }
+    }
+
+    fn lock_mutex<'a>(
+        &'a self,
+        mut state: MutexGuard<'a, State>,
+        thread: u32,
+        address: u32,
+        count: u32,
+    ) -> (MutexGuard<'a, State>, i32) {
+        loop {
+            let mutex = state.mutexes.entry(address).or_default();
+            match mutex.owner {
+                None => {
+                    mutex.owner = Some(thread);
+                    mutex.count = count;
+                    return (state, 0);
+                }
+                Some(owner) if owner == thread => {
+                    if mutex.kind != MUTEX_RECURSIVE {
+                        return (state, EDEADLK);
+                    }
+                    mutex.count += count;
+                    return (state, 0);
+                }
+                Some(_) => state = self.wait(state, thread),
+            }
+        }
+    }
+
+    /// Release the mutex at `address` entirely, returning how many times
+    /// `thread` had locked it.
+    fn release_mutex(&self, state: &mut State, thread: u32, address: u32) -> Result<u32, i32> {
+        match state.mutexes.get_mut(&address) {
+            Some(mutex) if mutex.owner == Some(thread) => {
+                let count = mutex.count;
+                mutex.owner = None;
+                mutex.count = 0;
+                self.changed.notify_all();
+                Ok(count)
+            }
+            _ => Err(EPERM),
+        }
+    }
+}
+
+/// What the entry point of the main thread returns, given what its call
+/// returned: a `pthread_exit` is a normal exit, and the trap of another
+/// thread is returned if there was no other error.
+pub(crate) fn main_thread_result(
+    ctx: &EmEnv,
+    result: Result<(), RuntimeError>,
+) -> Result<(), RuntimeError> {
+    let result = result.or_else(|e| if e.is::<ThreadExit>() { Ok(()) } else { Err(e) });
+    let pthreads = get_emscripten_data(ctx).pthreads.clone();
+    match pthreads.and_then(|pthreads| pthreads.trap()) {
+        Some(trap) if result.is_ok() => Err(trap),
+        _ => result,
+    }
+}
+
+fn pthreads(ctx: &EmEnv) -> (Arc<Pthreads>, u32) {
+    let data = get_emscripten_data(ctx);
+    let pthreads = data
+        .pthreads
+        .clone()
+        .expect("the pthreads are set up with the imports");
+    (pthreads, data.thread_id)
+}
+
+/// Read the word at `address`, failing with `EINVAL` if it's misaligned
+/// and `EFAULT` if it's out of bounds.
+fn read_u32(ctx: &EmEnv, address: u32) -> Result<u32, i32> {
+    if address % 4 != 0 {
+        return Err(EINVAL);
+    }
+    let view = ctx.memory(0).view::<u32>();
+    view.get((address / 4) as usize)
+        .map(Cell::get)
+        .ok_or(EFAULT)
+}
+
+/// Write the word at `address`, failing like [`read_u32`].
+fn write_u32(ctx: &EmEnv, address: u32, value: u32) -> Result<(), i32> {
+    if address % 4 != 0 {
+        return Err(EINVAL);
+    }
+    let view = ctx.memory(0).view::<u32>();
+    let word = view.get((address / 4) as usize).ok_or(EFAULT)?;
+    word.set(value);
+    Ok(())
+}
+
+pub fn _pthread_attr_init(ctx: &EmEnv, attr: i32) -> i32 {
+    trace!("emscripten::_pthread_attr_init({})", attr);
+    // pthread_attr_t is 9 words
+    let result = (0..9).try_for_each(|index| write_u32(ctx, attr as u32 + index * 4, 0));
+    result.err().unwrap_or(0)
+}
+
+pub fn _pthread_attr_setstacksize(ctx: &EmEnv, attr: i32, stacksize: i32) -> i32 {
+    trace!(
+        "emscripten::_pthread_attr_setstacksize({}, {})",
+        attr,
+        stacksize
+    );
+    write_u32(ctx, attr as u32, stacksize as u32)
+        .err()
+        .unwrap_or(0)
+}
+
+pub fn _pthread_attr_setdetachstate(ctx: &EmEnv, attr: i32, detachstate: i32) -> i32 {
+    trace!(
+        "emscripten::_pthread_attr_setdetachstate({}, {})",
+        attr,
+        detachstate
+    );
+    write_u32(ctx, attr as u32 + 12, detachstate as u32)
+        .err()
+        .unwrap_or(0)
+}
+
+pub fn _pthread_create(
+    ctx: &EmEnv,
+    thread_ptr: i32,
+    attr: i32,
+    start_routine: i32,
+    arg: i32,
+) -> i32 {
+    trace!(
+        "emscripten::_pthread_create({}, {}, {}, {})",
+        thread_ptr,
+        attr,
+        start_routine,
+        arg
+    );
+    let attributes = match attr {
+        0 => Ok((0, false)),
+        attr => read_u32(ctx, attr as u32).and_then(|stack_size| {
+            let detached = read_u32(ctx, attr as u32 + 12)? != 0;
+            Ok((stack_size, detached))
+        }),
+    };
+    // The thread is written before it starts, so it can't fail after
+    let (stack_size, detached) = match attributes.and_then(|attributes| {
+        write_u32(ctx, thread_ptr as u32, 0)?;
+        Ok(attributes)
+    }) {
+        Ok(attributes) => attributes,
+        Err(errno) => return errno,
+    };
+    let (pthreads, _) = pthreads(ctx);
+    let stack_size = if stack_size == 0 {
+        DEFAULT_STACK_SIZE
+    } else {
+        stack_size
+    };
+    let stack = call_malloc(ctx, stack_size);
+    if stack == 0 {
+        return EAGAIN;
+    }
+    let current_dir = get_emscripten_data(ctx).current_dir.clone();
+
+    let id = {
+        let mut state = pthreads.state();
+        let id = state.next_thread_id;
+        state.next_thread_id += 1;
+        state.threads.insert(
+            id,
+            Thread {
+                result: None,
+                detached,
+                stack,
+                handle: None,
+            },
+        );
+        id
+    };
+
+    let (started, start) = mpsc::channel();
+    let thread_pthreads = pthreads.clone();
+    let handle = thread::spawn(move || {
+        let pthreads = thread_pthreads;
+        let module = &pthreads.module;
+        let mut globals = match EmscriptenGlobals::new(module.store(), module) {
+            Ok(globals) => globals,
+            Err(e) => {
+                started.send(Err(e)).unwrap();
+                return;
+            }
+        };
+        globals.memory = pthreads.memory.clone();
+        globals.data.stacktop = stack;
+        globals.data.stack_max = stack + stack_size;
+
+        let mut env = EmEnv::new(&globals.data, pthreads.mapped_dirs.clone());
+        {
+            let mut data = get_emscripten_data(&env);
+            data.pthreads = Some(pthreads.clone());
+            data.thread_id = id;
+            data.sandboxed = pthreads.sandboxed;
+            data.current_dir = current_dir;
+        }
+        let import_object = generate_emscripten_env(module.store(), &mut globals, &env);
+        let instance = match wasmer::Instance::new(module, &import_object) {
+            Ok(instance) => instance,
+            Err(e) => {
+                started.send(Err(e.to_string())).unwrap();
+                return;
+            }
+        };
+        env.set_memory(globals.memory.clone());
+        started.send(Ok(())).unwrap();
+
+        // The functions of the table can only be called through the module
+        let dyn_call_ii = get_emscripten_data(&env).dyn_call_ii_ref().cloned();
+        let result = match dyn_call_ii {
+            Some(dyn_call_ii) => dyn_call_ii.call(start_routine, arg),
+            None => Err(RuntimeError::new("`dynCall_ii` isn't exported")),
+        };
+        let result = result.or_else(|e| match e.downcast::<ThreadExit>() {
+            Ok(ThreadExit(value)) => Ok(value),
+            Err(e) => {
+                debug!("emscripten::_pthread_create: thread {} trapped: {}", id, e);
+                Err(e)
+            }
+        });
+        drop(instance);
+
+        let mut state = pthreads.state();
+        let specific = &mut state.specific;
+        specific.retain(|(thread, _), _| *thread != id);
+        if let Err(e) = &result {
+            state.trap.get_or_insert_with(|| e.clone());
+        }
+        let thread = state.threads.get_mut(&id).unwrap();
+        if thread.detached {
+            state.threads.remove(&id);
+        } else {
+            thread.result = Some(result);
+        }
+        pthreads.changed.notify_all();
+    });
+
+    match start
+        .recv()
+        .unwrap_or_else(|_| Err("the thread panicked".to_string()))
+    {
+        Ok(()) => {
+            let mut state = pthreads.state();
+            if let Some(thread) = state.threads.get_mut(&id) {
+                thread.handle = Some(handle);
+            }
+            drop(state);
+            // It was checked before
+            let _ = write_u32(ctx, thread_ptr as u32, id);
+            0
+        }
+        Err(e) => {
+            debug!("emscripten::_pthread_create failed: {}", e);
+            pthreads.state().threads.remove(&id);
+            EAGAIN
+        }
+    }
+}
+
+pub fn _pthread_join(ctx: &EmEnv, thread: i32, retval: i32) -> i32 {
+    trace!("emscripten::_pthread_join({}, {})", thread, retval);
+    let (pthreads, id) = pthreads(ctx);
+    let thread = thread as u32;
+    if thread == id {
+        return EDEADLK;
+    }
+    if retval != 0 {
+        if let Err(errno) = read_u32(ctx, retval as u32) {
+            return errno;
+        }
+    }
+    let mut state = pthreads.state();
+    let finished = loop {
+        match state.threads.get(&thread) {
+            None => return ESRCH,
+            Some(joined) if joined.detached => return EINVAL,
+            Some(joined) if joined.result.is_some() => {
+                break state.threads.remove(&thread).unwrap()
+            }
+            Some(_) => state = pthreads.wait(state, id),
+        }
+    };
+    drop(state);
+    if let Some(handle) = finished.handle {
+        let _ = handle.join();
+    }
+    // The stack can't be freed by the thread, which runs on it
+    let free = get_emscripten_data(ctx).free_ref().cloned();
+    if let Some(free) = free {
+        let _ = free.call(finished.stack);
+    }
+    let value = match finished.result.unwrap() {
+        Ok(value) => value,
+        // The joining thread can't go on without the joined one
+        Err(trap) => {
+            drop(pthreads);
+            RuntimeError::raise(Box::new(trap));
+        }
+    };
+    if retval != 0 {
+        // It was checked before
+        let _ = write_u32(ctx, retval as u32, value as u32);
+    }
+    0
+}
+
+pub fn _pthread_detach(ctx: &EmEnv, thread: i32) -> i32 {
+    trace!("emscripten::_pthread_detach({})", thread);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    let finished = match state.threads.get_mut(&(thread as u32)) {
+        Some(detached) if !detached.detached => {
+            detached.detached = true;
+            detached.result.is_some()
+        }
+        Some(_) => return EINVAL,
+        None => return ESRCH,
+    };
+    if finished {
+        state.threads.remove(&(thread as u32));
+    }
+    0
+}
+
+pub fn _pthread_self(ctx: &EmEnv) -> i32 {
+    trace!("emscripten::_pthread_self");
+    pthreads(ctx).1 as i32
+}
+
+pub fn _pthread_equal(_ctx: &EmEnv, a: i32, b: i32) -> i32 {
+    trace!("emscripten::_pthread_equal({}, {})", a, b);
+    (a == b) as i32
+}
+
+pub fn _pthread_exit(ctx: &EmEnv, value: i32) {
+    trace!("emscripten::_pthread_exit({})", value);
+    let (pthreads, id) = pthreads(ctx);
+    if id == MAIN_THREAD_ID {
+        // The process ends with the last thread
+        let mut state = pthreads.state();
+        while state.threads.values().any(|thread| thread.result.is_none()) {
+            state = pthreads.wait(state, id);
+        }
+    }
+    drop(pthreads);
+    RuntimeError::raise(Box::new(ThreadExit(value)));
+}
+
+pub fn _pthread_mutexattr_init(ctx: &EmEnv, attr: i32) -> i32 {
+    trace!("emscripten::_pthread_mutexattr_init({})", attr);
+    write_u32(ctx, attr as u32, 0).err().unwrap_or(0)
+}
+
+pub fn _pthread_mutexattr_settype(ctx: &EmEnv, attr: i32, kind: i32) -> i32 {
+    trace!("emscripten::_pthread_mutexattr_settype({}, {})", attr, kind);
+    if !(0..=2).contains(&kind) {
+        return EINVAL;
+    }
+    read_u32(ctx, attr as u32)
+        .and_then(|value| write_u32(ctx, attr as u32, (value & !3) | kind as u32))
+        .err()
+        .unwrap_or(0)
+}
+
+pub fn _pthread_mutex_init(ctx: &EmEnv, mutex: i32, attr: i32) -> i32 {
+    trace!("emscripten::_pthread_mutex_init({}, {})", mutex, attr);
+    let kind = if attr == 0 {
+        0
+    } else {
+        match read_u32(ctx, attr as u32) {
+            Ok(value) => value & 3,
+            Err(errno) => return errno,
+        }
+    };
+    let (pthreads, _) = pthreads(ctx);
+    pthreads.state().mutexes.insert(
+        mutex as u32,
+        MutexState {
+            kind,
+            ..Default::default()
+        },
+    );
+    0
+}
+
+pub fn _pthread_mutex_destroy(ctx: &EmEnv, mutex: i32) -> i32 {
+    trace!("emscripten::_pthread_mutex_destroy({})", mutex);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    match state.mutexes.get(&(mutex as u32)) {
+        Some(locked) if locked.owner.is_some() => EBUSY,
+        _ => {
+            state.mutexes.remove(&(mutex as u32));
+            0
+        }
+    }
+}
+
+pub fn _pthread_mutex_lock(ctx: &EmEnv, mutex: i32) -> i32 {
+    trace!("emscripten::_pthread_mutex_lock({})", mutex);
+    let (pthreads, id) = pthreads(ctx);
+    let state = pthreads.state();
+    let errno = pthreads.lock_mutex(state, id, mutex as u32, 1).1;
+    errno
+}
+
+pub fn _pthread_mutex_trylock(ctx: &EmEnv, mutex: i32) -> i32 {
+    trace!("emscripten::_pthread_mutex_trylock({})", mutex);
+    let (pthreads, id) = pthreads(ctx);
+    let state = pthreads.state();
+    match state.mutexes.get(&(mutex as u32)) {
+        Some(locked) if locked.owner.is_some() && locked.owner != Some(id) => EBUSY,
+        Some(locked) if locked.owner == Some(id) && locked.kind != MUTEX_RECURSIVE => EBUSY,
+        _ => {
+            let errno = pthreads.lock_mutex(state, id, mutex as u32, 1).1;
+            errno
+        }
+    }
+}
+
+pub fn _pthread_mutex_unlock(ctx: &EmEnv, mutex: i32) -> i32 {
+    trace!("emscripten::_pthread_mutex_unlock({})", mutex);
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    match state.mutexes.get_mut(&(mutex as u32)) {
+        Some(locked) if locked.owner == Some(id) => {
+            locked.count -= 1;
+            if locked.count == 0 {
+                locked.owner = None;
+                pthreads.changed.notify_all();
+            }
+            0
+        }
+        _ => EPERM,
+    }
+}
+
+/// ___lock
+///
+/// The internal locks of the C library, like the mutexes.
+pub fn ___lock(ctx: &EmEnv, what: i32) {
+    trace!("emscripten::___lock({})", what);
+    _pthread_mutex_lock(ctx, what);
+}
+
+/// ___unlock
+pub fn ___unlock(ctx: &EmEnv, what: i32) {
+    trace!("emscripten::___unlock({})", what);
+    _pthread_mutex_unlock(ctx, what);
+}
+
+pub fn _pthread_cond_init(ctx: &EmEnv, cond: i32, _attr: i32) -> i32 {
+    trace!("emscripten::_pthread_cond_init({})", cond);
+    let (pthreads, _) = pthreads(ctx);
+    pthreads
+        .state()
+        .conds
+        .insert(cond as u32, CondState::default());
+    0
+}
+
+pub fn _pthread_cond_destroy(ctx: &EmEnv, cond: i32) -> i32 {
+    trace!("emscripten::_pthread_cond_destroy({})", cond);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    match state.conds.get(&(cond as u32)) {
+        Some(waited) if waited.waiters > 0 => EBUSY,
+        _ => {
+            state.conds.remove(&(cond as u32));
+            0
+        }
+    }
+}
+
+fn cond_wait(ctx: &EmEnv, cond: u32, mutex: u32, deadline: Option<SystemTime>) -> i32 {
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    let count = match pthreads.release_mutex(&mut state, id, mutex) {
+        Ok(count) => count,
+        Err(errno) => return errno,
+    };
+    state.conds.entry(cond).or_default().waiters += 1;
+
+    let mut timed_out = false;
+    loop {
+        let waited = state.conds.get_mut(&cond).unwrap();
+        if waited.signals > 0 {
+            waited.signals -= 1;
+            break;
+        }
+        match deadline {
+            None => state = pthreads.wait(state, id),
+            Some(deadline) => {
+                let (next, passed) = pthreads.wait_until(state, id, deadline);
+                state = next;
+                if passed {
+                    timed_out = true;
+                    break;
+                }
+            }
+        }
+    }
+    state.conds.get_mut(&cond).unwrap().waiters -= 1;
+
+    let errno = pthreads.lock_mutex(state, id, mutex, count).1;
+    if errno != 0 {
+        errno
+    } else if timed_out {
+        ETIMEDOUT
+    } else {
+        0
+    }
+}
+
+pub fn _pthread_cond_wait(ctx: &EmEnv, cond: i32, mutex: i32) -> i32 {
+    trace!("emscripten::_pthread_cond_wait({}, {})", cond, mutex);
+    cond_wait(ctx, cond as u32, mutex as u32, None)
+}
+
+pub fn _pthread_cond_timedwait(ctx: &EmEnv, cond: i32, mutex: i32, abstime: i32) -> i32 {
+    trace!(
+        "emscripten::_pthread_cond_timedwait({}, {}, {})",
+        cond,
+        mutex,
+        abstime
+    );
+    let (seconds, nanoseconds) = match read_u32(ctx, abstime as u32)
+        .and_then(|seconds| Ok((seconds as i32, read_u32(ctx, abstime as u32 + 4)?)))
+    {
+        Ok(abstime) => abstime,
+        Err(errno) => return errno,
+    };
+    if seconds < 0 || nanoseconds >= 1_000_000_000 {
+        return EINVAL;
+    }
+    let deadline = UNIX_EPOCH + Duration::new(seconds as u64, nanoseconds);
+    cond_wait(ctx, cond as u32, mutex as u32, Some(deadline))
+}
+
+pub fn _pthread_cond_signal(ctx: &EmEnv, cond: i32) -> i32 {
+    trace!("emscripten::_pthread_cond_signal({})", cond);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    if let Some(waited) = state.conds.get_mut(&(cond as u32)) {
+        if waited.signals < waited.waiters {
+            waited.signals += 1;
+            pthreads.changed.notify_all();
+        }
+    }
+    0
+}
+
+pub fn _pthread_cond_broadcast(ctx: &EmEnv, cond: i32) -> i32 {
+    trace!("emscripten::_pthread_cond_broadcast({})", cond);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    if let Some(waited) = state.conds.get_mut(&(cond as u32)) {
+        waited.signals = waited.waiters;
+        pthreads.changed.notify_all();
+    }
+    0
+}
+
+pub fn _pthread_rwlock_init(ctx: &EmEnv, rwlock: i32, _attr: i32) -> i32 {
+    trace!("emscripten::_pthread_rwlock_init({})", rwlock);
+    let (pthreads, _) = pthreads(ctx);
+    pthreads
+        .state()
+        .rwlocks
+        .insert(rwlock as u32, RwLockState::default());
+    0
+}
+
+pub fn _pthread_rwlock_destroy(ctx: &EmEnv, rwlock: i32) -> i32 {
+    trace!("emscripten::_pthread_rwlock_destroy({})", rwlock);
+    let (pthreads, _) = pthreads(ctx);
+    pthreads.state().rwlocks.remove(&(rwlock as u32));
+    0
+}
+
+pub fn _pthread_rwlock_rdlock(ctx: &EmEnv, rwlock: i32) -> i32 {
+    trace!("emscripten::_pthread_rwlock_rdlock({})", rwlock);
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    loop {
+        let lock = state.rwlocks.entry(rwlock as u32).or_default();
+        if lock.writer.is_none() {
+            lock.readers += 1;
+            return 0;
+        }
+        state = pthreads.wait(state, id);
+    }
+}
+
+pub fn _pthread_rwlock_wrlock(ctx: &EmEnv, rwlock: i32) -> i32 {
+    trace!("emscripten::_pthread_rwlock_wrlock({})", rwlock);
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    loop {
+        let lock = state.rwlocks.entry(rwlock as u32).or_default();
+        if lock.writer == Some(id) {
+            return EDEADLK;
+        }
+        if lock.writer.is_none() && lock.readers == 0 {
+            lock.writer = Some(id);
+            return 0;
+        }
+        state = pthreads.wait(state, id);
+    }
+}
+
+pub fn _pthread_rwlock_unlock(ctx: &EmEnv, rwlock: i32) -> i32 {
+    trace!("emscripten::_pthread_rwlock_unlock({})", rwlock);
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    let lock = match state.rwlocks.get_mut(&(rwlock as u32)) {
+        Some(lock) => lock,
+        None => return EPERM,
+    };
+    if lock.writer == Some(id) {
+        lock.writer = None;
+    } else if lock.readers > 0 {
+        lock.readers -= 1;
+    } else {
+        return EPERM;
+    }
+    pthreads.changed.notify_all();
+    0
+}
+
+pub fn _pthread_once(ctx: &EmEnv, once_control: i32, init_routine: i32) -> i32 {
+    trace!(
+        "emscripten::_pthread_once({}, {})",
+        once_control,
+        init_routine
+    );
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    loop {
+        match state.onces.get(&(once_control as u32)) {
+            Some(true) => return 0,
+            Some(false) => state = pthreads.wait(state, id),
+            None => break,
+        }
+    }
+    state.onces.insert(once_control as u32, false);
+    drop(state);
+
+    let dyn_call_v = get_emscripten_data(ctx)
+        .dyn_call_v_ref()
+        .expect("Dynamic call is None: dyn_call_v")
+        .clone();
+    let result = dyn_call_v.call(init_routine);
+
+    let mut state = pthreads.state();
+    match result {
+        Ok(()) => state.onces.insert(once_control as u32, true),
+        // another call may run it
+        Err(_) => state.onces.remove(&(once_control as u32)),
+    };
+    pthreads.changed.notify_all();
+    drop(state);
+    if let Err(e) = result {
+        RuntimeError::raise(Box::new(e));
+    }
+    0
+}
+
+pub fn _pthread_key_create(ctx: &EmEnv, key: i32, _destructor: i32) -> i32 {
+    trace!("emscripten::_pthread_key_create({})", key);
+    let (pthreads, _) = pthreads(ctx);
+    let mut state = pthreads.state();
+    if let Err(errno) = write_u32(ctx, key as u32, state.next_key) {
+        return errno;
+    }
+    state.next_key += 1;
+    0
+}
+
+pub fn _pthread_key_delete(ctx: &EmEnv, key: i32) -> i32 {
+    trace!("emscripten::_pthread_key_delete({})", key);
+    let (pthreads, _) = pthreads(ctx);
+    pthreads
+        .state()
+        .specific
+        .retain(|(_, other), _| *other != key as u32);
+    0
+}
+
+pub fn _pthread_getspecific(ctx: &EmEnv, key: i32) -> i32 {
+    trace!("emscripten::_pthread_getspecific({})", key);
+    let (pthreads, id) = pthreads(ctx);
+    let state = pthreads.state();
+    state.specific.get(&(id, key as u32)).copied().unwrap_or(0) as i32
+}
+
+pub fn _pthread_setspecific(ctx: &EmEnv, key: i32, value: i32) -> i32 {
+    trace!("emscripten::_pthread_setspecific({}, {})", key, value);
+    let (pthreads, id) = pthreads(ctx);
+    let mut state = pthreads.state();
+    if key as u32 == 0 || key as u32 >= state.next_key {
+        return EINVAL;
+    }
+    state.specific.insert((id, key as u32), value as u32);
+    0
+}
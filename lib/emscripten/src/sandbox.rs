@@ -0,0 +1,381 @@
+//! Confining the path syscalls to the mapped directories, for the
+//! environments created with [`EmEnv::new_sandboxed`].
+//!
+//! The paths of the guest are normalized without looking at the host: `.`
+//! is dropped, `..` removes the previous component but stops at the root,
+//! and relative paths start from the current directory of the guest, which
+//! `chdir` changes without changing the one of the host. The path must then
+//! be in one of the mapped directories, the deepest one being used, and
+//! isn't found otherwise.
+//!
+//! On Linux, the rest of the path is then opened one component at a time
+//! from the mapped directory, with `O_NOFOLLOW`. The symbolic links met on
+//! the way are followed by hand, and the path is refused if one of them
+//! leads outside of the mapped directory. The syscall is given a path
+//! through `/proc/self/fd` to what was opened, so that the host can't be
+//! made to look elsewhere by changing the directories in the meantime.
+//!
+//! Elsewhere, the deepest part of the path which exists is canonicalized
+//! instead, and the path is refused if it ends up outside of the mapped
+//! directory; what's found there may still change before the syscall.
+
+use crate::env::get_emscripten_data;
+use crate::utils::get_cstr_path;
+use crate::EmEnv;
+use ::libc::{c_char, c_int, ENOENT, ENOTDIR};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+
+/// A path on the host given to a syscall, see [`host_path`].
+pub(crate) struct HostPath {
+    path: CString,
+    /// What the path goes through, kept open while it's used
+    #[cfg(target_os = "linux")]
+    _fd: Option<beneath::Fd>,
+}
+
+impl HostPath {
+    fn unconfined(path: CString) -> Self {
+        Self {
+            path,
+            #[cfg(target_os = "linux")]
+            _fd: None,
+        }
+    }
+}
+
+impl Deref for HostPath {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        &self.path
+    }
+}
+
+/// The path on the host of the path `path` of the guest, or the error the
+/// syscall returns. When sandboxed, symbolic links are followed all the way
+/// and the path must exist.
+pub(crate) fn host_path(ctx: &EmEnv, path: *const c_char) -> Result<HostPath, c_int> {
+    resolve(ctx, path, true)
+}
+
+/// The path on the host of the path `path` of the guest, without following
+/// its last component if it's a symbolic link, for the syscalls working on
+/// the link itself or creating something there.
+pub(crate) fn host_path_nofollow(ctx: &EmEnv, path: *const c_char) -> Result<HostPath, c_int> {
+    resolve(ctx, path, false)
+}
+
+/// The path on the host of the path `path` of the guest for `open`, and the
+/// flags to open it with: a file created in a sandbox is opened with
+/// `O_NOFOLLOW`, so that a symbolic link put there in the meantime isn't
+/// followed.
+#[cfg(unix)]
+pub(crate) fn host_path_to_open(
+    ctx: &EmEnv,
+    path: *const c_char,
+    flags: c_int,
+) -> Result<(HostPath, c_int), c_int> {
+    if flags & ::libc::O_NOFOLLOW != 0 {
+        return Ok((host_path_nofollow(ctx, path)?, flags));
+    }
+    match host_path(ctx, path) {
+        Err(errno) if errno == -ENOENT && flags & ::libc::O_CREAT != 0 => {
+            Ok((host_path_nofollow(ctx, path)?, flags | ::libc::O_NOFOLLOW))
+        }
+        result => Ok((result?, flags)),
+    }
+}
+
+/// The target on the host of a symbolic link to the path `path` of the
+/// guest. Nothing is resolved: the link is only followed when it's used.
+pub(crate) fn link_target(ctx: &EmEnv, path: *const c_char) -> Result<HostPath, c_int> {
+    let data = get_emscripten_data(ctx);
+    if !data.sandboxed {
+        drop(data);
+        return host_path(ctx, path);
+    }
+    let guest_path = unsafe { CStr::from_ptr(path) }
+        .to_str()
+        .map_err(|_| -ENOENT)?;
+    let (host_dir, relative, _) = confine(&data.mapped_dirs, &data.current_dir, guest_path)?;
+    let target = host_dir.join(relative);
+    let target = CString::new(target.to_string_lossy().as_bytes()).map_err(|_| -ENOENT)?;
+    Ok(HostPath::unconfined(target))
+}
+
+fn resolve(ctx: &EmEnv, path: *const c_char, follow: bool) -> Result<HostPath, c_int> {
+    let guest_path = unsafe { CStr::from_ptr(path) };
+    let data = get_emscripten_data(ctx);
+    if !data.sandboxed {
+        drop(data);
+        let path = get_cstr_path(ctx, path as *const _).unwrap_or_else(|| guest_path.into());
+        return Ok(HostPath::unconfined(path));
+    }
+    let guest_path = guest_path.to_str().map_err(|_| -ENOENT)?;
+    let (host_dir, relative, _) = confine(&data.mapped_dirs, &data.current_dir, guest_path)?;
+    beneath::resolve(host_dir, &relative, follow)
+}
+
+/// Whether the path syscalls of `ctx` are confined to the mapped
+/// directories.
+pub(crate) fn is_sandboxed(ctx: &EmEnv) -> bool {
+    get_emscripten_data(ctx).sandboxed
+}
+
+/// `chdir` in a sandbox, which only changes the current directory of the
+/// guest.
+pub(crate) fn change_dir(ctx: &EmEnv, path: *const c_char) -> c_int {
+    let mut data = get_emscripten_data(ctx);
+    let guest_path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(guest_path) => guest_path,
+        Err(_) => return -ENOENT,
+    };
+    let (host_dir, relative, guest_path) =
+        match confine(&data.mapped_dirs, &data.current_dir, guest_path) {
+            Ok(paths) => paths,
+            Err(errno) => return errno,
+        };
+    let host_path = match beneath::resolve(host_dir, &relative, true) {
+        Ok(host_path) => host_path,
+        Err(errno) => return errno,
+    };
+    match Path::new(&*host_path.to_string_lossy()).metadata() {
+        Ok(metadata) if metadata.is_dir() => {
+            data.current_dir = guest_path;
+            0
+        }
+        Ok(_) => -ENOTDIR,
+        Err(_) => -ENOENT,
+    }
+}
+
+/// The mapped directory on the host, the path relative to it and the
+/// normalized path of the guest for the path `path` of the guest, in the
+/// current directory `current_dir`.
+fn confine<'a>(
+    mapped_dirs: &'a HashMap<String, PathBuf>,
+    current_dir: &Path,
+    path: &str,
+) -> Result<(&'a Path, PathBuf, PathBuf), c_int> {
+    let guest_path = normalize(current_dir, Path::new(path));
+    let (guest_dir, host_dir) = mapped_dirs
+        .iter()
+        .map(|(guest_dir, host_dir)| (normalize(Path::new("/"), Path::new(guest_dir)), host_dir))
+        .filter(|(guest_dir, _)| guest_path.starts_with(guest_dir))
+        .max_by_key(|(guest_dir, _)| guest_dir.components().count())
+        .ok_or(-ENOENT)?;
+    // Only normal components are left, which can't leave `host_dir`
+    let relative = guest_path.strip_prefix(&guest_dir).unwrap().to_path_buf();
+    Ok((host_dir, relative, guest_path))
+}
+
+/// Normalize `path` into an absolute path, see the
+/// [module documentation](self).
+fn normalize(current_dir: &Path, path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    if !path.has_root() {
+        normalized.push(current_dir);
+    }
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    normalized
+}
+
+#[cfg(target_os = "linux")]
+mod beneath {
+    use super::HostPath;
+    use ::libc::{c_int, EACCES, ELOOP, ENOENT, ENOTDIR, O_CLOEXEC, O_NOFOLLOW, O_PATH};
+    use std::ffi::{CString, OsStr, OsString};
+    use std::io::Error;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Component, Path};
+
+    /// How many symbolic links may be followed in a path, like Linux does.
+    const MAX_SYMLINKS: usize = 40;
+
+    /// An open file descriptor, closed when dropped.
+    pub(crate) struct Fd(c_int);
+
+    impl Drop for Fd {
+        fn drop(&mut self) {
+            unsafe { ::libc::close(self.0) };
+        }
+    }
+
+    fn last_errno() -> c_int {
+        -Error::last_os_error().raw_os_error().unwrap_or(EACCES)
+    }
+
+    /// Open `name` in `dir` without following it if it's a symbolic link.
+    fn open_at(dir: &Fd, name: &OsStr) -> Result<Fd, c_int> {
+        let name = CString::new(name.as_bytes()).map_err(|_| -ENOENT)?;
+        match unsafe { ::libc::openat(dir.0, name.as_ptr(), O_PATH | O_NOFOLLOW | O_CLOEXEC) } {
+            -1 => Err(last_errno()),
+            fd => Ok(Fd(fd)),
+        }
+    }
+
+    fn file_type(fd: &Fd) -> Result<::libc::mode_t, c_int> {
+        let mut stat = unsafe { std::mem::zeroed::<::libc::stat>() };
+        match unsafe { ::libc::fstat(fd.0, &mut stat) } {
+            -1 => Err(last_errno()),
+            _ => Ok(stat.st_mode & ::libc::S_IFMT),
+        }
+    }
+
+    /// The target of the symbolic link opened as `link`.
+    fn read_link(link: &Fd) -> Result<OsString, c_int> {
+        let mut buf = vec![0u8; ::libc::PATH_MAX as usize];
+        let empty = b"\0".as_ptr() as *const _;
+        match unsafe { ::libc::readlinkat(link.0, empty, buf.as_mut_ptr() as *mut _, buf.len()) } {
+            -1 => Err(last_errno()),
+            len => {
+                buf.truncate(len as usize);
+                Ok(OsString::from_vec(buf))
+            }
+        }
+    }
+
+    fn proc_path(fd: &Fd, name: Option<&OsStr>) -> CString {
+        let mut path = format!("/proc/self/fd/{}", fd.0).into_bytes();
+        if let Some(name) = name {
+            path.push(b'/');
+            path.extend_from_slice(name.as_bytes());
+        }
+        // The names come from a `CStr`, or from a link read by `read_link`
+        CString::new(path).unwrap()
+    }
+
+    /// Push the components of `path` on `pending`, to be taken in order.
+    fn push_components(pending: &mut Vec<OsString>, path: &Path) {
+        for component in path.components().rev() {
+            match component {
+                Component::Normal(name) => pending.push(name.to_os_string()),
+                Component::ParentDir => pending.push(OsString::from("..")),
+                Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            }
+        }
+    }
+
+    /// Open `relative` beneath `host_dir`, following the symbolic links as
+    /// long as they stay in `host_dir`, the last one only if `follow`.
+    pub(super) fn resolve(
+        host_dir: &Path,
+        relative: &Path,
+        follow: bool,
+    ) -> Result<HostPath, c_int> {
+        let root = {
+            let host_dir = CString::new(host_dir.as_os_str().as_bytes()).map_err(|_| -ENOENT)?;
+            match unsafe { ::libc::open(host_dir.as_ptr(), O_PATH | O_CLOEXEC) } {
+                -1 => return Err(last_errno()),
+                fd => Fd(fd),
+            }
+        };
+        let canonical_root = host_dir.canonicalize().map_err(|_| -ENOENT)?;
+        // The directories from `host_dir` to where the walk is
+        let mut dirs = vec![root];
+        let mut pending = vec![];
+        push_components(&mut pending, relative);
+        let mut symlinks = 0;
+
+        while let Some(name) = pending.pop() {
+            if name == ".." {
+                if dirs.len() == 1 {
+                    return Err(-EACCES);
+                }
+                dirs.pop();
+                continue;
+            }
+            let dir = dirs.last().unwrap();
+            if pending.is_empty() && !follow {
+                return Ok(HostPath {
+                    path: proc_path(dir, Some(&name)),
+                    _fd: dirs.pop(),
+                });
+            }
+            let fd = open_at(dir, &name)?;
+            match file_type(&fd)? {
+                ::libc::S_IFLNK => {
+                    symlinks += 1;
+                    if symlinks > MAX_SYMLINKS {
+                        return Err(-ELOOP);
+                    }
+                    let target = read_link(&fd)?;
+                    let target = Path::new(&target);
+                    // Absolute links are only followed into `host_dir`
+                    let target = if target.has_root() {
+                        dirs.truncate(1);
+                        target.strip_prefix(&canonical_root).map_err(|_| -EACCES)?
+                    } else {
+                        target
+                    };
+                    push_components(&mut pending, target);
+                }
+                ::libc::S_IFDIR => dirs.push(fd),
+                _ if pending.is_empty() => dirs.push(fd),
+                _ => return Err(-ENOTDIR),
+            }
+        }
+        // `relative` was empty or led back to `host_dir`
+        let fd = dirs.pop().unwrap();
+        Ok(HostPath {
+            path: proc_path(&fd, None),
+            _fd: Some(fd),
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod beneath {
+    use super::HostPath;
+    use ::libc::{c_int, EACCES, ENOENT};
+    use std::ffi::CString;
+    use std::path::Path;
+
+    /// `relative` in `host_dir`, if it doesn't lead outside of it through
+    /// symbolic links.
+    pub(super) fn resolve(
+        host_dir: &Path,
+        relative: &Path,
+        _follow: bool,
+    ) -> Result<HostPath, c_int> {
+        let host_path = host_dir.join(relative);
+        if escapes(host_dir, &host_path) {
+            return Err(-EACCES);
+        }
+        let host_path =
+            CString::new(host_path.to_string_lossy().as_bytes()).map_err(|_| -ENOENT)?;
+        Ok(HostPath::unconfined(host_path))
+    }
+
+    /// Whether `path`, which is in `host_dir`, leads outside of it through
+    /// symbolic links.
+    fn escapes(host_dir: &Path, path: &Path) -> bool {
+        let host_dir = match host_dir.canonicalize() {
+            Ok(host_dir) => host_dir,
+            // Nothing can be found in it anyway
+            Err(_) => return false,
+        };
+        match path
+            .ancestors()
+            .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        {
+            Some(existing) => match existing.canonicalize() {
+                Ok(existing) => !existing.starts_with(&host_dir),
+                // A dangling symbolic link, which could point anywhere
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
+}
@@ -12,7 +12,8 @@ pub use self::windows::*;
 
 use crate::{
     ptr::{Array, WasmPtr},
-    utils::{copy_stat_into_wasm, get_cstr_path, get_current_directory},
+    sandbox::{self, host_path, host_path_nofollow},
+    utils::{copy_stat_into_wasm, get_current_directory},
     EmEnv,
 };
 
@@ -97,12 +98,14 @@ pub fn ___syscall6(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
 pub fn ___syscall12(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall12 (chdir) {}", _which);
     let path_ptr = varargs.get_str(ctx);
-    let real_path_owned = get_cstr_path(ctx, path_ptr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path_ptr
+    if sandbox::is_sandboxed(ctx) {
+        return sandbox::change_dir(ctx, path_ptr);
+    }
+    let real_path_owned = match host_path(ctx, path_ptr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let ret = unsafe { chdir(real_path) };
     debug!(
         "=> path: {:?}, ret: {}",
@@ -168,18 +171,16 @@ pub fn ___syscall38(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> i32 {
     debug!("emscripten::___syscall38 (rename)");
     let old_path = varargs.get_str(ctx);
     let new_path = varargs.get_str(ctx);
-    let real_old_path_owned = get_cstr_path(ctx, old_path as *const _);
-    let real_old_path = if let Some(ref rp) = real_old_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        old_path
+    let real_old_path_owned = match host_path_nofollow(ctx, old_path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
-    let real_new_path_owned = get_cstr_path(ctx, new_path as *const _);
-    let real_new_path = if let Some(ref rp) = real_new_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        new_path
+    let real_old_path = real_old_path_owned.as_ptr();
+    let real_new_path_owned = match host_path_nofollow(ctx, new_path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_new_path = real_new_path_owned.as_ptr();
     let result = unsafe { rename(real_old_path, real_new_path) };
     debug!(
         "=> old_path: {}, new_path: {}, result: {}",
@@ -194,12 +195,11 @@ pub fn ___syscall38(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> i32 {
 pub fn ___syscall40(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall40 (rmdir)");
     let pathname_addr = varargs.get_str(ctx);
-    let real_path_owned = get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path_nofollow(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     unsafe { rmdir(real_path) }
 }
 
@@ -535,12 +535,11 @@ pub fn ___syscall195(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
     let pathname_addr = varargs.get_str(ctx);
     let buf: u32 = varargs.get(ctx);
 
-    let real_path_owned = get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
 
     unsafe {
         let mut _stat: stat = std::mem::zeroed();
@@ -19,7 +19,6 @@ use libc::{
     fchmod,
     fchown,
     fcntl,
-    // ENOTTY,
     fsync,
     getegid,
     geteuid,
@@ -52,7 +51,6 @@ use libc::{
     // readv,
     recvfrom,
     recvmsg,
-    // ENOTTY,
     rusage,
     sa_family_t,
     // writev,
@@ -70,51 +68,72 @@ use libc::{
     uname,
     utsname,
     EINVAL,
+    ENOTTY,
     // sockaddr_in,
     FIOCLEX,
     FIONBIO,
+    FIONCLEX,
+    FIONREAD,
     F_GETFD,
+    F_GETFL,
     F_SETFD,
+    F_SETFL,
+    O_NONBLOCK,
     SOL_SOCKET,
+    TIOCGPGRP,
     TIOCGWINSZ,
     TIOCSPGRP,
+    TIOCSWINSZ,
     // TCGETS,
     // TCSETSW,
 };
 
 // They are not exposed in in Rust libc in macOS
 const TCGETS: u64 = 0x5401;
+const TCSETS: u64 = 0x5402;
 const TCSETSW: u64 = 0x5403;
+const TCSETSF: u64 = 0x5404;
 
 // `libc` constants as provided by `emscripten`. Maybe move to own file?
 const WASM_FIONBIO: u32 = 0x5421;
+const WASM_FIONCLEX: u32 = 0x5450;
 const WASM_FIOCLEX: u32 = 0x5451;
+const WASM_FIONREAD: u32 = 0x541B;
+const WASM_TIOCGPGRP: u32 = 0x540F;
 const WASM_TIOCSPGRP: u32 = 0x5410;
 const WASM_TIOCGWINSZ: u32 = 0x5413;
+const WASM_TIOCSWINSZ: u32 = 0x5414;
 const WASM_TCGETS: u32 = 0x5401;
+const WASM_TCSETS: u32 = 0x5402;
 const WASM_TCSETSW: u32 = 0x5403;
+const WASM_TCSETSF: u32 = 0x5404;
 
 // Based on @syrusakbary sugerence at
 // https://github.com/wasmerio/wasmer/pull/532#discussion_r300837800
-fn translate_ioctl(wasm_ioctl: u32) -> c_ulong {
-    match wasm_ioctl {
+fn translate_ioctl(wasm_ioctl: u32) -> Option<c_ulong> {
+    Some(match wasm_ioctl {
+        WASM_FIONCLEX => FIONCLEX as _,
         WASM_FIOCLEX => FIOCLEX as _,
-        WASM_TIOCGWINSZ => TIOCGWINSZ as _,
+        WASM_FIONREAD => FIONREAD as _,
+        WASM_TIOCGPGRP => TIOCGPGRP as _,
         WASM_TIOCSPGRP => TIOCSPGRP as _,
+        WASM_TIOCGWINSZ => TIOCGWINSZ as _,
+        WASM_TIOCSWINSZ => TIOCSWINSZ as _,
         WASM_FIONBIO => FIONBIO as _,
         WASM_TCGETS => TCGETS as _,
+        WASM_TCSETS => TCSETS as _,
         WASM_TCSETSW => TCSETSW as _,
-        _otherwise => {
-            unimplemented!("The ioctl {} is not yet implemented", wasm_ioctl);
-        }
-    }
+        WASM_TCSETSF => TCSETSF as _,
+        _otherwise => return None,
+    })
 }
 
 #[allow(unused_imports)]
 use std::ffi::CStr;
 
 use crate::env::EmSockAddr;
-use crate::utils::{self, get_cstr_path};
+use crate::sandbox::{host_path, host_path_nofollow, host_path_to_open, link_target};
+use crate::utils;
 use crate::EmEnv;
 #[allow(unused_imports)]
 use std::io::Error;
@@ -161,12 +180,11 @@ pub fn ___syscall5(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     let pathname_addr = varargs.get_str(ctx);
     let flags: i32 = varargs.get(ctx);
     let mode: u32 = varargs.get(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let (real_path_owned, flags) = match host_path_to_open(ctx, pathname_addr, flags) {
+        Ok(path_and_flags) => path_and_flags,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let _path_str = unsafe { std::ffi::CStr::from_ptr(real_path).to_str().unwrap() };
     let fd = unsafe { open(real_path, flags, mode) };
     debug!(
@@ -185,6 +203,16 @@ pub fn ___syscall9(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
 
     let oldname_ptr = varargs.get_str(ctx);
     let newname_ptr = varargs.get_str(ctx);
+    let oldname_owned = match host_path_nofollow(ctx, oldname_ptr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let oldname_ptr = oldname_owned.as_ptr();
+    let newname_owned = match host_path_nofollow(ctx, newname_ptr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let newname_ptr = newname_owned.as_ptr();
     let result = unsafe { link(oldname_ptr, newname_ptr) };
     debug!(
         "=> oldname: {}, newname: {}, result: {}",
@@ -213,18 +241,16 @@ pub fn ___syscall83(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
 
     let path1 = varargs.get_str(ctx);
     let path2 = varargs.get_str(ctx);
-    let real_path1_owned = utils::get_cstr_path(ctx, path1 as *const _);
-    let real_path1 = if let Some(ref rp) = real_path1_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path1
+    let real_path1_owned = match link_target(ctx, path1) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
-    let real_path2_owned = utils::get_cstr_path(ctx, path2 as *const _);
-    let real_path2 = if let Some(ref rp) = real_path2_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path2
+    let real_path1 = real_path1_owned.as_ptr();
+    let real_path2_owned = match host_path_nofollow(ctx, path2) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path2 = real_path2_owned.as_ptr();
     let result = unsafe { symlink(real_path1, real_path2) };
     debug!(
         "=> path1: {}, path2: {}, result: {}",
@@ -242,12 +268,11 @@ pub fn ___syscall85(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> i32 {
     let buf = varargs.get_str(ctx);
     // let buf_addr: i32 = varargs.get(ctx);
     let buf_size: i32 = varargs.get(ctx);
-    let real_path_owned = get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path_nofollow(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
 
     let ret = unsafe { libc::readlink(real_path, buf as _, buf_size as _) as i32 };
     if ret == -1 {
@@ -267,30 +292,34 @@ pub fn ___syscall85(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> i32 {
 /// ftruncate64
 pub fn ___syscall194(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall194 (ftruncate64) {}", _which);
-    let _fd: c_int = varargs.get(ctx);
-    let _length: i64 = varargs.get(ctx);
+    let fd: c_int = varargs.get(ctx);
+    // The 64 bits length is aligned on 8 bytes
+    let _zero: u32 = varargs.get(ctx);
+    let length: i64 = varargs.get(ctx);
+    debug!("=> fd: {}, length: {}", fd, length);
     #[cfg(not(any(target_os = "freebsd", target_os = "macos")))]
     unsafe {
-        ftruncate64(_fd, _length)
+        ftruncate64(fd, length)
     }
     #[cfg(target_os = "freebsd")]
     unsafe {
-        ftruncate(_fd, _length)
+        ftruncate(fd, length)
     }
     #[cfg(target_os = "macos")]
-    unimplemented!("emscripten::___syscall194 (ftruncate64) {}", _which)
+    unsafe {
+        libc::ftruncate(fd, length)
+    }
 }
 
 /// lchown
 pub fn ___syscall198(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall198 (lchown) {}", _which);
     let path_ptr = varargs.get_str(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, path_ptr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path_ptr
+    let real_path_owned = match host_path_nofollow(ctx, path_ptr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let uid: uid_t = varargs.get(ctx);
     let gid: gid_t = varargs.get(ctx);
     let result = unsafe { lchown(real_path, uid, gid) };
@@ -326,12 +355,11 @@ pub fn ___syscall212(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
     debug!("emscripten::___syscall212 (chown) {}", _which);
 
     let pathname_addr = varargs.get_str(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let owner: u32 = varargs.get(ctx);
     let group: u32 = varargs.get(ctx);
 
@@ -355,12 +383,11 @@ pub fn ___syscall219(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
 pub fn ___syscall33(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall33 (access) {}", _which);
     let path = varargs.get_str(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, path as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path
+    let real_path_owned = match host_path(ctx, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let amode: c_int = varargs.get(ctx);
     let result = unsafe { access(real_path, amode) };
     debug!(
@@ -383,12 +410,11 @@ pub fn ___syscall34(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
 pub fn ___syscall39(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall39 (mkdir) {}", _which);
     let pathname_addr = varargs.get_str(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, pathname_addr as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path_nofollow(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let mode: u32 = varargs.get(ctx);
     unsafe { mkdir(real_path, mode as _) }
 }
@@ -477,12 +503,10 @@ pub fn ___syscall54(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("=> fd: {}, op: {}", fd, request);
 
     // Got the equivalents here: https://code.woboq.org/linux/linux/include/uapi/asm-generic/ioctls.h.html
-    match request {
-        WASM_FIOCLEX | WASM_FIONBIO | WASM_TIOCGWINSZ | WASM_TIOCSPGRP | WASM_TCGETS
-        | WASM_TCSETSW => {
+    match translate_ioctl(request) {
+        Some(translated_request) => {
             let argp: u32 = varargs.get(ctx);
             let argp_ptr = emscripten_memory_pointer!(ctx.memory(0), argp) as *mut c_void;
-            let translated_request = translate_ioctl(request);
             let ret = unsafe { ioctl(fd, translated_request as _, argp_ptr) };
             debug!(
                 " => request: {}, translated: {}, return: {}",
@@ -496,12 +520,9 @@ pub fn ___syscall54(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
             }
             ret
         }
-        _ => {
-            debug!(
-                " => not implemented case {} (noop, hardcoded to 0)",
-                request
-            );
-            0
+        None => {
+            debug!(" => not implemented case {}", request);
+            -ENOTTY
         }
     }
 }
@@ -544,13 +565,15 @@ pub fn ___syscall102(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
             if ty_and_flags & SOCK_CLOEXC != 0 {
                 // set_cloexec
                 unsafe {
-                    ioctl(fd, translate_ioctl(WASM_FIOCLEX) as _);
+                    ioctl(fd, FIOCLEX as _);
                 };
             }
 
             if ty_and_flags & SOCK_NON_BLOCK != 0 {
-                // do something here
-                unimplemented!("non blocking sockets");
+                unsafe {
+                    let flags = fcntl(fd, F_GETFL);
+                    fcntl(fd, F_SETFL, flags | O_NONBLOCK);
+                };
             }
 
             // why is this here?
@@ -651,7 +674,7 @@ pub fn ___syscall102(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
             // why is this here?
             // set_cloexec
             unsafe {
-                ioctl(fd, translate_ioctl(WASM_FIOCLEX) as _);
+                ioctl(fd, FIOCLEX as _);
             };
 
             debug!(
@@ -1005,12 +1028,11 @@ pub fn ___syscall122(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
 pub fn ___syscall196(ctx: &EmEnv, _which: i32, mut varargs: VarArgs) -> i32 {
     debug!("emscripten::___syscall196 (lstat64) {}", _which);
     let path = varargs.get_str(ctx);
-    let real_path_owned = utils::get_cstr_path(ctx, path as *const _);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        path
+    let real_path_owned = match host_path_nofollow(ctx, path) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let buf_ptr: u32 = varargs.get(ctx);
     unsafe {
         let mut stat: stat = std::mem::zeroed();
@@ -1129,16 +1151,44 @@ pub fn ___syscall221(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int
 /// fallocate
 pub fn ___syscall324(ctx: &EmEnv, _which: c_int, mut varargs: VarArgs) -> c_int {
     debug!("emscripten::___syscall324 (fallocate) {}", _which);
-    let _fd: c_int = varargs.get(ctx);
-    let _mode: c_int = varargs.get(ctx);
-    let _offset: off_t = varargs.get(ctx);
-    let _len: off_t = varargs.get(ctx);
+    let fd: c_int = varargs.get(ctx);
+    let mode: c_int = varargs.get(ctx);
+    let offset: off_t = varargs.get(ctx);
+    let len: off_t = varargs.get(ctx);
+    debug!(
+        "=> fd: {}, mode: {}, offset: {}, len: {}",
+        fd, mode, offset, len
+    );
     #[cfg(not(any(target_os = "freebsd", target_os = "macos", target_os = "android")))]
     unsafe {
-        fallocate(_fd, _mode, _offset, _len)
+        fallocate(fd, mode, offset, len)
     }
     #[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "android"))]
     {
-        unimplemented!("emscripten::___syscall324 (fallocate) {}", _which)
+        extend_file(fd, mode, offset, len)
+    }
+}
+
+/// `fallocate` for the systems without it, by extending the file if it's
+/// shorter than `offset + len`. Only the default mode is supported.
+#[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "android"))]
+fn extend_file(fd: c_int, mode: c_int, offset: off_t, len: off_t) -> c_int {
+    if mode != 0 {
+        return -libc::EOPNOTSUPP;
+    }
+    if offset < 0 || len <= 0 {
+        return -EINVAL;
+    }
+    let end = match offset.checked_add(len) {
+        Some(end) => end,
+        None => return -libc::EFBIG,
+    };
+    let mut stat: stat = unsafe { mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } == -1 {
+        return -1;
+    }
+    if stat.st_size >= end {
+        return 0;
     }
+    unsafe { libc::ftruncate(fd, end) }
 }
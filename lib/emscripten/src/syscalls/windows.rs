@@ -1,4 +1,5 @@
-use crate::utils::{copy_cstr_into_wasm, get_cstr_path};
+use crate::sandbox::{host_path, host_path_nofollow};
+use crate::utils::copy_cstr_into_wasm;
 use crate::varargs::VarArgs;
 use crate::EmEnv;
 use libc::mkdir;
@@ -18,12 +19,11 @@ pub fn ___syscall5(ctx: &EmEnv, which: c_int, mut varargs: VarArgs) -> c_int {
     #[cfg(not(feature = "debug"))]
     let _ = which;
     let pathname_addr = varargs.get_str(ctx);
-    let real_path_owned = get_cstr_path(ctx, pathname_addr);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     let flags: i32 = varargs.get(ctx);
     let mode: u32 = varargs.get(ctx);
     let path_str = unsafe { std::ffi::CStr::from_ptr(real_path).to_str().unwrap() };
@@ -101,12 +101,11 @@ pub fn ___syscall39(ctx: &EmEnv, which: c_int, mut varargs: VarArgs) -> c_int {
     #[cfg(not(feature = "debug"))]
     let _ = which;
     let pathname_addr = varargs.get_str(ctx);
-    let real_path_owned = get_cstr_path(ctx, pathname_addr);
-    let real_path = if let Some(ref rp) = real_path_owned {
-        rp.as_c_str().as_ptr()
-    } else {
-        pathname_addr
+    let real_path_owned = match host_path_nofollow(ctx, pathname_addr) {
+        Ok(path) => path,
+        Err(errno) => return errno,
     };
+    let real_path = real_path_owned.as_ptr();
     unsafe { mkdir(real_path) }
 }
 
@@ -261,6 +261,9 @@ pub fn get_cstr_path(ctx: &EmEnv, path: *const i8) -> Option<std::ffi::CString>
 /// gets the current directory
 /// handles mapdir logic
 pub fn get_current_directory(ctx: &EmEnv) -> Option<PathBuf> {
+    if crate::sandbox::is_sandboxed(ctx) {
+        return Some(get_emscripten_data(ctx).current_dir.clone());
+    }
     if let Some(val) = get_emscripten_data(ctx).mapped_dirs.get(".") {
         return Some(val.clone());
     }
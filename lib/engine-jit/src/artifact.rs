@@ -11,11 +11,11 @@ use wasmer_compiler::{CompileError, Features, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{CompileModuleInfo, ModuleEnvironment};
 use wasmer_engine::{
-    register_frame_info, Artifact, DeserializeError, FunctionExtent, GlobalFrameInfoRegistration,
-    SerializeError,
+    register_frame_info, Artifact, ArtifactHeader, DeserializeError, Engine, FunctionExtent,
+    GlobalFrameInfoRegistration, SerializeError,
 };
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, SerializableFunctionFrameInfo, Tunables};
+use wasmer_engine::{SerializableFunctionFrameInfo, Tunables};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
@@ -27,6 +27,7 @@ use wasmer_vm::{
 
 /// A compiled wasm module, ready to be instantiated.
 pub struct JITArtifact {
+    header: ArtifactHeader,
     serializable: SerializableModule,
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
     finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
@@ -39,6 +40,9 @@ pub struct JITArtifact {
 impl JITArtifact {
     const MAGIC_HEADER: &'static [u8] = b"\0wasmer-jit";
 
+    /// The engine name recorded in the [`ArtifactHeader`].
+    const ENGINE_NAME: &'static str = "jit";
+
     /// Check if the provided bytes look like a serialized `JITArtifact`.
     pub fn is_deserializable(bytes: &[u8]) -> bool {
         bytes.starts_with(Self::MAGIC_HEADER)
@@ -121,7 +125,8 @@ impl JITArtifact {
             compile_info,
             data_initializers,
         };
-        Self::from_parts(&mut inner_jit, serializable)
+        let header = ArtifactHeader::new(Self::ENGINE_NAME, compiler.name(), jit.target());
+        Self::from_parts(&mut inner_jit, header, serializable)
     }
 
     /// Compile a data buffer into a `JITArtifact`, which may then be instantiated.
@@ -135,12 +140,16 @@ impl JITArtifact {
     /// Deserialize a JITArtifact
     pub fn deserialize(jit: &JITEngine, bytes: &[u8]) -> Result<Self, DeserializeError> {
         if !Self::is_deserializable(bytes) {
-            return Err(DeserializeError::Incompatible(
-                "The provided bytes are not wasmer-jit".to_string(),
-            ));
+            return Err(DeserializeError::Incompatible {
+                property: "format".to_string(),
+                expected: "wasmer-jit".to_string(),
+                found: "an unknown format".to_string(),
+            });
         }
 
-        let inner_bytes = &bytes[Self::MAGIC_HEADER.len()..];
+        let (header, inner_bytes) =
+            ArtifactHeader::deserialize(&bytes[Self::MAGIC_HEADER.len()..])?;
+        header.check_compatibility(Self::ENGINE_NAME, jit.inner().compiler_name(), jit.target())?;
 
         // let r = flexbuffers::Reader::get_root(bytes).map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
         // let serializable = SerializableModule::deserialize(r).map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
@@ -148,12 +157,14 @@ impl JITArtifact {
         let serializable: SerializableModule = bincode::deserialize(inner_bytes)
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
 
-        Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
+        Self::from_parts(&mut jit.inner_mut(), header, serializable)
+            .map_err(DeserializeError::Compiler)
     }
 
     /// Construct a `JITArtifact` from component parts.
     pub fn from_parts(
         inner_jit: &mut JITEngineInner,
+        header: ArtifactHeader,
         serializable: SerializableModule,
     ) -> Result<Self, CompileError> {
         let (
@@ -225,6 +236,7 @@ impl JITArtifact {
         let signatures = signatures.into_boxed_slice();
 
         Ok(Self {
+            header,
             serializable,
             finished_functions,
             finished_function_call_trampolines,
@@ -318,8 +330,9 @@ impl Artifact for JITArtifact {
         let bytes = bincode::serialize(&self.serializable)
             .map_err(|e| SerializeError::Generic(format!("{:?}", e)))?;
 
-        // Prepend the header.
+        // Prepend the magic and compatibility headers.
         let mut serialized = Self::MAGIC_HEADER.to_vec();
+        serialized.extend(self.header.serialize()?);
         serialized.extend(bytes);
         Ok(serialized)
     }
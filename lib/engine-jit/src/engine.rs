@@ -153,6 +153,19 @@ pub struct JITEngineInner {
 }
 
 impl JITEngineInner {
+    /// Gets the name of the compiler associated to this engine, or
+    /// `None` if the engine is headless.
+    pub fn compiler_name(&self) -> Option<&str> {
+        #[cfg(feature = "compiler")]
+        {
+            self.compiler.as_ref().map(|compiler| compiler.name())
+        }
+        #[cfg(not(feature = "compiler"))]
+        {
+            None
+        }
+    }
+
     /// Gets the compiler associated to this engine.
     #[cfg(feature = "compiler")]
     pub fn compiler(&self) -> Result<&dyn Compiler, CompileError> {
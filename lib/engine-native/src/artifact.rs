@@ -19,9 +19,11 @@ use wasmer_compiler::{CompileError, Features, OperatingSystem, Symbol, SymbolReg
 use wasmer_compiler::{
     CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
 };
-use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, Tunables};
+use wasmer_engine::Tunables;
+use wasmer_engine::{
+    Artifact, ArtifactHeader, DeserializeError, Engine, InstantiationError, SerializeError,
+};
 #[cfg(feature = "compiler")]
 use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
@@ -53,6 +55,9 @@ fn to_compile_error(err: impl Error) -> CompileError {
 const WASMER_METADATA_SYMBOL: &[u8] = b"WASMER_METADATA";
 
 impl NativeArtifact {
+    /// The engine name recorded in the [`ArtifactHeader`].
+    const ENGINE_NAME: &'static str = "native";
+
     // Mach-O header in Mac
     #[allow(dead_code)]
     const MAGIC_HEADER_MH_CIGAM_64: &'static [u8] = &[207, 250, 237, 254];
@@ -183,7 +188,12 @@ impl NativeArtifact {
             function_body_lengths,
         };
 
-        let serialized_data = bincode::serialize(&metadata).map_err(to_compile_error)?;
+        // The compatibility header goes first, so it can be validated
+        // before trying to read the rest of the metadata.
+        let mut serialized_data = ArtifactHeader::new(Self::ENGINE_NAME, compiler.name(), &target)
+            .serialize()
+            .map_err(to_compile_error)?;
+        serialized_data.extend(bincode::serialize(&metadata).map_err(to_compile_error)?);
         let mut metadata_binary = vec![0; 10];
         let mut writable = &mut metadata_binary[..];
         leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
@@ -477,9 +487,11 @@ impl NativeArtifact {
         bytes: &[u8],
     ) -> Result<Self, DeserializeError> {
         if !Self::is_deserializable(&bytes) {
-            return Err(DeserializeError::Incompatible(
-                "The provided bytes are not in any native format Wasmer can understand".to_string(),
-            ));
+            return Err(DeserializeError::Incompatible {
+                property: "format".to_string(),
+                expected: "a native shared object".to_string(),
+                found: "an unknown format".to_string(),
+            });
         }
         // Dump the bytes into a file, so we can read it with our `dlopen`
         let named_file = NamedTempFile::new()?;
@@ -504,9 +516,11 @@ impl NativeArtifact {
         // read up to 5 bytes
         file.read_exact(&mut buffer)?;
         if !Self::is_deserializable(&buffer) {
-            return Err(DeserializeError::Incompatible(
-                "The provided bytes are not in any native format Wasmer can understand".to_string(),
-            ));
+            return Err(DeserializeError::Incompatible {
+                property: "format".to_string(),
+                expected: "a native shared object".to_string(),
+                found: "an unknown format".to_string(),
+            });
         }
         Self::deserialize_from_file_unchecked(&engine, &path)
     }
@@ -545,6 +559,12 @@ impl NativeArtifact {
         })?;
         let metadata_slice: &'static [u8] =
             slice::from_raw_parts(&size[10] as *const u8, metadata_len as usize);
+        let (header, metadata_slice) = ArtifactHeader::deserialize(metadata_slice)?;
+        header.check_compatibility(
+            Self::ENGINE_NAME,
+            engine.inner().compiler_name(),
+            engine.target(),
+        )?;
         let metadata: ModuleMetadata = bincode::deserialize(metadata_slice)
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
         let mut engine_inner = engine.inner_mut();
@@ -27,12 +27,24 @@ pub enum DeserializeError {
     /// A generic deserialization error
     #[error("{0}")]
     Generic(String),
-    /// Incompatible serialized binary
-    #[error("incompatible binary: {0}")]
-    Incompatible(String),
+    /// The serialized binary was produced by a different Wasmer version,
+    /// engine, target or CPU feature set than the current one
+    #[error("incompatible binary: expected {property} to be {expected}, but found {found}")]
+    Incompatible {
+        /// The property that doesn't match (eg. the target triple)
+        property: String,
+        /// The value expected by the current `Engine` and host
+        expected: String,
+        /// The value found in the serialized binary
+        found: String,
+    },
     /// The provided binary is corrupted
     #[error("corrupted binary: {0}")]
     CorruptedBinary(String),
+    /// The signature of the binary is missing or doesn't match
+    /// the expected public key
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
     /// The binary was valid, but we got an error when
     /// trying to allocate the required resources.
     #[error(transparent)]
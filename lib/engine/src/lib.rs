@@ -41,7 +41,7 @@ pub use crate::resolver::{
     resolve_imports, ChainableNamedResolver, NamedResolver, NamedResolverChain, NullResolver,
     Resolver,
 };
-pub use crate::serialize::SerializableFunctionFrameInfo;
+pub use crate::serialize::{ArtifactHeader, SerializableFunctionFrameInfo};
 pub use crate::trap::*;
 pub use crate::tunables::Tunables;
 
@@ -1,8 +1,10 @@
+use crate::{DeserializeError, SerializeError};
 use serde::de::{Deserializer, Visitor};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
 use std::fmt;
-use wasmer_compiler::CompiledFunctionFrameInfo;
+use wasmer_compiler::{CompiledFunctionFrameInfo, Target};
 
 /// This is the unserialized verison of `CompiledFunctionFrameInfo`.
 #[derive(Clone, Serialize, Deserialize)]
@@ -102,3 +104,236 @@ impl<'de> Deserialize<'de> for SerializableFunctionFrameInfo {
         ))
     }
 }
+
+/// The compatibility header embedded in every serialized artifact.
+///
+/// It records the environment the artifact was produced in, so it can
+/// be validated against the current `Engine` and host before any of
+/// the compiled code is loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    /// The version of the header format.
+    pub format_version: u32,
+    /// The Wasmer version that produced the artifact.
+    pub wasmer_version: String,
+    /// The engine that produced the artifact (eg. `jit` or `native`).
+    pub engine: String,
+    /// The compiler that produced the artifact (eg. `cranelift`).
+    pub compiler: String,
+    /// The target triple the artifact was compiled for.
+    pub triple: String,
+    /// The CPU features the compiled code relies on.
+    pub cpu_features: Vec<String>,
+}
+
+impl ArtifactHeader {
+    /// The current version of the header format.
+    ///
+    /// It must be bumped every time the layout of the header changes.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Creates a new header for an artifact produced by the given
+    /// `engine` and `compiler` for the provided `target`.
+    pub fn new(engine: &str, compiler: &str, target: &Target) -> Self {
+        let mut cpu_features = target
+            .cpu_features()
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect::<Vec<_>>();
+        cpu_features.sort();
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            wasmer_version: crate::VERSION.to_string(),
+            engine: engine.to_string(),
+            compiler: compiler.to_string(),
+            triple: target.triple().to_string(),
+            cpu_features,
+        }
+    }
+
+    /// Serializes the header, prefixed with its length.
+    pub fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
+        let header =
+            bincode::serialize(self).map_err(|e| SerializeError::Generic(format!("{:?}", e)))?;
+        let mut bytes = (header.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(header);
+        Ok(bytes)
+    }
+
+    /// Deserializes a header previously serialized with
+    /// [`ArtifactHeader::serialize`], returning it along with the
+    /// remaining bytes.
+    pub fn deserialize(bytes: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let unreadable = |found: &str| DeserializeError::Incompatible {
+            property: "artifact header".to_string(),
+            expected: format!("format version {}", Self::FORMAT_VERSION),
+            found: found.to_string(),
+        };
+        if bytes.len() < 4 {
+            return Err(unreadable("no header"));
+        }
+        let (len, rest) = bytes.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if rest.len() < len {
+            return Err(unreadable("a truncated header"));
+        }
+        let (header, rest) = rest.split_at(len);
+        // The format version is always the first field, so we can report
+        // it even if the rest of the header changed its layout.
+        if header.len() >= 4 {
+            let format_version = u32::from_le_bytes(header[..4].try_into().unwrap());
+            if format_version != Self::FORMAT_VERSION {
+                return Err(unreadable(&format!("format version {}", format_version)));
+            }
+        }
+        let header =
+            bincode::deserialize(header).map_err(|_| unreadable("an unreadable header"))?;
+        Ok((header, rest))
+    }
+
+    /// Checks that the artifact described by this header can be loaded
+    /// by the given `engine`, using `compiler` unless it's headless, and
+    /// targeting `target`.
+    pub fn check_compatibility(
+        &self,
+        engine: &str,
+        compiler: Option<&str>,
+        target: &Target,
+    ) -> Result<(), DeserializeError> {
+        let incompatible = |property: &str, expected: String, found: String| {
+            Err(DeserializeError::Incompatible {
+                property: property.to_string(),
+                expected,
+                found,
+            })
+        };
+        if self.wasmer_version != crate::VERSION {
+            return incompatible(
+                "Wasmer version",
+                crate::VERSION.to_string(),
+                self.wasmer_version.clone(),
+            );
+        }
+        if self.engine != engine {
+            return incompatible("engine", engine.to_string(), self.engine.clone());
+        }
+        // Headless engines can load the artifacts of any compiler.
+        if let Some(compiler) = compiler {
+            if self.compiler != compiler {
+                return incompatible("compiler", compiler.to_string(), self.compiler.clone());
+            }
+        }
+        let triple = target.triple().to_string();
+        if self.triple != triple {
+            return incompatible("target triple", triple, self.triple.clone());
+        }
+        let target_features = target
+            .cpu_features()
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect::<Vec<_>>();
+        let missing_features = self
+            .cpu_features
+            .iter()
+            .filter(|feature| !target_features.contains(feature))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing_features.is_empty() {
+            return incompatible(
+                "CPU features",
+                format!("a subset of [{}]", target_features.join(", ")),
+                format!(
+                    "[{}] which are not enabled in the target",
+                    missing_features.join(", ")
+                ),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_compiler::{CpuFeature, Triple};
+
+    fn target() -> Target {
+        Target::new(Triple::host(), CpuFeature::set())
+    }
+
+    #[test]
+    fn artifact_header_roundtrip() {
+        let header = ArtifactHeader::new("jit", "cranelift", &target());
+        let mut bytes = header.serialize().unwrap();
+        bytes.extend(b"rest");
+        let (deserialized, rest) = ArtifactHeader::deserialize(&bytes).unwrap();
+        assert_eq!(deserialized, header);
+        assert_eq!(rest, b"rest");
+        assert!(header
+            .check_compatibility("jit", Some("cranelift"), &target())
+            .is_ok());
+        assert!(header.check_compatibility("jit", None, &target()).is_ok());
+    }
+
+    #[test]
+    fn artifact_header_incompatible() {
+        let header = ArtifactHeader::new("jit", "cranelift", &target());
+        match header.check_compatibility("native", Some("cranelift"), &target()) {
+            Err(DeserializeError::Incompatible {
+                property,
+                expected,
+                found,
+            }) => {
+                assert_eq!(property, "engine");
+                assert_eq!(expected, "native");
+                assert_eq!(found, "jit");
+            }
+            _ => panic!("expected an incompatible engine error"),
+        }
+
+        match header.check_compatibility("jit", Some("llvm"), &target()) {
+            Err(DeserializeError::Incompatible { property, .. }) => {
+                assert_eq!(property, "compiler");
+            }
+            _ => panic!("expected an incompatible compiler error"),
+        }
+
+        let header = ArtifactHeader::new(
+            "jit",
+            "cranelift",
+            &Target::new(Triple::host(), CpuFeature::SSE2 | CpuFeature::AVX),
+        );
+        let target = Target::new(Triple::host(), CpuFeature::SSE2.into());
+        match header.check_compatibility("jit", Some("cranelift"), &target) {
+            Err(DeserializeError::Incompatible {
+                property, found, ..
+            }) => {
+                assert_eq!(property, "CPU features");
+                assert_eq!(found, "[avx] which are not enabled in the target");
+            }
+            _ => panic!("expected incompatible CPU features"),
+        }
+
+        let mut header = header;
+        header.wasmer_version = "0.0.1".to_string();
+        assert!(matches!(
+            header.check_compatibility("jit", Some("cranelift"), &target),
+            Err(DeserializeError::Incompatible { .. })
+        ));
+    }
+
+    #[test]
+    fn artifact_header_unknown_format_version() {
+        let mut header = ArtifactHeader::new("jit", "cranelift", &target());
+        header.format_version = ArtifactHeader::FORMAT_VERSION + 1;
+        let bytes = header.serialize().unwrap();
+        assert!(matches!(
+            ArtifactHeader::deserialize(&bytes),
+            Err(DeserializeError::Incompatible { .. })
+        ));
+        assert!(matches!(
+            ArtifactHeader::deserialize(&[]),
+            Err(DeserializeError::Incompatible { .. })
+        ));
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::convert::TryInto;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
 use wasmer::{
@@ -42,11 +43,12 @@ impl fmt::Debug for MeteringGlobalIndexes {
 
 /// The module-level metering middleware.
 ///
-/// # Panic
+/// # Errors
 ///
 /// An instance of `Metering` should not be shared among different modules, since it tracks
-/// module-specific information like the global index to store metering state. Attempts to use
-/// a `Metering` instance from multiple modules will result in a panic.
+/// module-specific information like the global index to store metering state. The modules
+/// compiled with a `Metering` instance after the first one fail to compile, so an engine
+/// with a `Metering` middleware can only compile a single module.
 pub struct Metering<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> {
     /// Initial limit of points.
     initial_limit: u64,
@@ -56,6 +58,9 @@ pub struct Metering<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> {
 
     /// The global indexes for metering points.
     global_indexes: Mutex<Option<MeteringGlobalIndexes>>,
+
+    /// Whether another module was compiled after the first one.
+    reused: AtomicBool,
 }
 
 /// The function-level metering middleware.
@@ -87,6 +92,7 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> Metering<F> {
             initial_limit,
             cost_function,
             global_indexes: Mutex::new(None),
+            reused: AtomicBool::new(false),
         }
     }
 }
@@ -97,6 +103,7 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> fmt::Debug for Meteri
             .field("initial_limit", &self.initial_limit)
             .field("cost_function", &"<function>")
             .field("global_indexes", &self.global_indexes)
+            .field("reused", &self.reused)
             .finish()
     }
 }
@@ -106,6 +113,9 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync + 'static> ModuleMiddl
 {
     /// Generates a `FunctionMiddleware` for a given function.
     fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        if self.reused.load(Ordering::SeqCst) {
+            return Box::new(ReusedMetering);
+        }
         Box::new(FunctionMetering {
             cost_function: self.cost_function,
             global_indexes: self.global_indexes.lock().unwrap().clone().unwrap(),
@@ -118,7 +128,9 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync + 'static> ModuleMiddl
         let mut global_indexes = self.global_indexes.lock().unwrap();
 
         if global_indexes.is_some() {
-            panic!("Metering::transform_module_info: Attempting to use a `Metering` middleware from multiple modules.");
+            // Its functions fail to compile.
+            self.reused.store(true, Ordering::SeqCst);
+            return;
         }
 
         // Append a global for remaining points and initialize it.
@@ -156,6 +168,24 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync + 'static> ModuleMiddl
     }
 }
 
+/// The function-level middleware of a `Metering` used by another module
+/// than the first one, which fails to compile the functions.
+#[derive(Debug)]
+struct ReusedMetering;
+
+impl FunctionMiddleware for ReusedMetering {
+    fn feed<'a>(
+        &mut self,
+        _operator: Operator<'a>,
+        _state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        Err(MiddlewareError::new(
+            "metering",
+            "a `Metering` middleware can't be used by multiple modules",
+        ))
+    }
+}
+
 impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> fmt::Debug for FunctionMetering<F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FunctionMetering")
@@ -407,4 +437,15 @@ mod tests {
             MeteringPoints::Remaining(4)
         );
     }
+
+    #[test]
+    fn second_module_fails_to_compile() {
+        let metering = Arc::new(Metering::new(10, cost_function));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(metering);
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        Module::new(&store, bytecode()).unwrap();
+
+        assert!(Module::new(&store, bytecode()).is_err());
+    }
 }
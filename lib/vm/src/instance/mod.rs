@@ -8,8 +8,10 @@
 //! wrapper around an `InstanceRef`.
 
 mod allocator;
+mod snapshot;
 
 pub use allocator::InstanceAllocator;
+pub use snapshot::{InstanceSnapshot, SnapshotError};
 
 use crate::export::VMExport;
 use crate::global::Global;
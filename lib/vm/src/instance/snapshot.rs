@@ -0,0 +1,243 @@
+//! Snapshots of the state of an instance, to restore it later or in
+//! another process.
+//!
+//! A snapshot holds what the instance defines itself: the contents of its
+//! memories, the values of its mutable globals, the elements of its tables
+//! and which passive segments it dropped. Imported memories, tables and
+//! globals belong to someone else and aren't part of it. Functions in
+//! tables are recorded as function indices, so a snapshot can be restored
+//! in an instance of the same module wherever the module is loaded.
+
+use super::{Instance, InstanceHandle};
+use crate::vmcontext::VMCallerCheckedAnyfunc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::slice;
+use thiserror::Error;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{
+    DataIndex, ElemIndex, FunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex,
+    Pages, Type, WASM_PAGE_SIZE,
+};
+
+/// The state of an instance, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    /// The contents of the memories, by local memory index
+    pub memories: Vec<Vec<u8>>,
+    /// The bits of the mutable globals, by local global index
+    pub globals: Vec<(u32, [u8; 16])>,
+    /// The elements of the tables as function indices, by local table index
+    pub tables: Vec<Vec<Option<u32>>>,
+    /// The passive element segments which were dropped
+    pub dropped_elements: Vec<u32>,
+    /// The passive data segments which were dropped
+    pub dropped_data: Vec<u32>,
+}
+
+/// An error while taking or restoring an [`InstanceSnapshot`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// A mutable global holds a reference, which only makes sense in this
+    /// process.
+    #[error("global {0} holds a reference and can't be snapshotted")]
+    ReferenceGlobal(u32),
+    /// A table holds a function which isn't one of the instance.
+    #[error("element {index} of table {table} is a function of another instance")]
+    ForeignFunction {
+        /// The local index of the table
+        table: u32,
+        /// The index of the element
+        index: u32,
+    },
+    /// The snapshot wasn't taken from an instance of the same module.
+    #[error("the snapshot doesn't match the module: {0}")]
+    Mismatch(String),
+}
+
+impl Instance {
+    /// The function index of `anyfunc`, by the function pointer and the
+    /// environment of all the functions of the instance.
+    fn function_indices(&self) -> HashMap<(usize, usize), FunctionIndex> {
+        self.module
+            .functions
+            .keys()
+            .map(|index| {
+                let anyfunc = self.get_caller_checked_anyfunc(index);
+                (anyfunc_key(&anyfunc), index)
+            })
+            .collect()
+    }
+}
+
+fn anyfunc_key(anyfunc: &VMCallerCheckedAnyfunc) -> (usize, usize) {
+    (anyfunc.func_ptr as usize, unsafe {
+        anyfunc.vmctx.host_env as usize
+    })
+}
+
+impl InstanceHandle {
+    /// Take a snapshot of the state of the instance.
+    ///
+    /// The instance mustn't be running, or the snapshot may be torn.
+    pub fn snapshot(&self) -> Result<InstanceSnapshot, SnapshotError> {
+        let instance = self.instance().as_ref();
+        let module = &instance.module;
+
+        let memories = instance
+            .memories
+            .values()
+            .map(|memory| unsafe {
+                let definition = memory.vmmemory().as_ref();
+                slice::from_raw_parts(definition.base, definition.current_length as usize).to_vec()
+            })
+            .collect();
+
+        let mut globals = Vec::new();
+        for local_index in instance.globals.keys() {
+            let global_type = module.globals[module.global_index(local_index)];
+            if !global_type.mutability.is_mutable() {
+                continue;
+            }
+            if let Type::ExternRef | Type::FuncRef = global_type.ty {
+                return Err(SnapshotError::ReferenceGlobal(local_index.as_u32()));
+            }
+            globals.push((
+                local_index.as_u32(),
+                instance.global(local_index).to_bytes(),
+            ));
+        }
+
+        let function_indices = instance.function_indices();
+        let mut tables = Vec::new();
+        for (local_index, table) in instance.tables.iter() {
+            let elements = (0..table.size())
+                .map(|index| match table.get(index) {
+                    Some(anyfunc) if !anyfunc.func_ptr.is_null() => function_indices
+                        .get(&anyfunc_key(&anyfunc))
+                        .map(|function_index| Some(function_index.as_u32()))
+                        .ok_or(SnapshotError::ForeignFunction {
+                            table: local_index.as_u32(),
+                            index,
+                        }),
+                    _ => Ok(None),
+                })
+                .collect::<Result<_, _>>()?;
+            tables.push(elements);
+        }
+
+        let passive_elements = instance.passive_elements.borrow();
+        let mut dropped_elements = module
+            .passive_elements
+            .iter()
+            .filter(|(index, segment)| !segment.is_empty() && !passive_elements.contains_key(index))
+            .map(|(index, _)| index.as_u32())
+            .collect::<Vec<_>>();
+        dropped_elements.sort_unstable();
+        let passive_data = instance.passive_data.borrow();
+        let mut dropped_data = module
+            .passive_data
+            .keys()
+            .filter(|index| !passive_data.contains_key(index))
+            .map(|index| index.as_u32())
+            .collect::<Vec<_>>();
+        dropped_data.sort_unstable();
+
+        Ok(InstanceSnapshot {
+            memories,
+            globals,
+            tables,
+            dropped_elements,
+            dropped_data,
+        })
+    }
+
+    /// Restore the state of the instance from `snapshot`, which must have
+    /// been taken from an instance of the same module.
+    ///
+    /// Memories and tables are grown to the size they had in the snapshot;
+    /// they can't be larger already.
+    pub fn restore(&self, snapshot: &InstanceSnapshot) -> Result<(), SnapshotError> {
+        let instance = self.instance().as_ref();
+        let module = &instance.module;
+        let mismatch = |what: String| Err(SnapshotError::Mismatch(what));
+
+        if snapshot.memories.len() != instance.memories.len() {
+            return mismatch(format!(
+                "{} memories instead of {}",
+                snapshot.memories.len(),
+                instance.memories.len()
+            ));
+        }
+        if snapshot.tables.len() != instance.tables.len() {
+            return mismatch(format!(
+                "{} tables instead of {}",
+                snapshot.tables.len(),
+                instance.tables.len()
+            ));
+        }
+
+        for (index, contents) in snapshot.memories.iter().enumerate() {
+            let memory = &instance.memories[LocalMemoryIndex::new(index)];
+            let current = memory.size();
+            let pages = u32::try_from(contents.len() / WASM_PAGE_SIZE).unwrap_or(u32::MAX);
+            if contents.len() % WASM_PAGE_SIZE != 0 || pages < current.0 {
+                return mismatch(format!("memory {} has the wrong size", index));
+            }
+            if memory.grow(Pages(pages - current.0)).is_err() {
+                return mismatch(format!("memory {} can't grow to {} pages", index, pages));
+            }
+            unsafe {
+                let definition = memory.vmmemory().as_ref();
+                slice::from_raw_parts_mut(definition.base, definition.current_length as usize)
+                    .copy_from_slice(contents);
+            }
+        }
+
+        for (index, bytes) in snapshot.globals.iter() {
+            let local_index = LocalGlobalIndex::from_u32(*index);
+            if local_index.index() >= instance.globals.len()
+                || !module.globals[module.global_index(local_index)]
+                    .mutability
+                    .is_mutable()
+            {
+                return mismatch(format!("global {} isn't a mutable global", index));
+            }
+            unsafe {
+                *instance.global_ptr(local_index).as_mut().as_bytes_mut() = *bytes;
+            }
+        }
+
+        for (index, elements) in snapshot.tables.iter().enumerate() {
+            let table = &instance.tables[LocalTableIndex::new(index)];
+            let size = u32::try_from(elements.len()).unwrap_or(u32::MAX);
+            if size < table.size() || table.grow(size - table.size()).is_none() {
+                return mismatch(format!("table {} can't have {} elements", index, size));
+            }
+            for (element_index, function_index) in elements.iter().enumerate() {
+                let anyfunc = match function_index {
+                    Some(function_index) if (*function_index as usize) < module.functions.len() => {
+                        instance
+                            .get_caller_checked_anyfunc(FunctionIndex::from_u32(*function_index))
+                    }
+                    Some(function_index) => {
+                        return mismatch(format!("there's no function {}", function_index))
+                    }
+                    None => VMCallerCheckedAnyfunc::default(),
+                };
+                // the table has the right size, so this can't fail
+                table.set(element_index as u32, anyfunc).unwrap();
+            }
+        }
+
+        for index in snapshot.dropped_elements.iter() {
+            instance.elem_drop(ElemIndex::from_u32(*index));
+        }
+        for index in snapshot.dropped_data.iter() {
+            instance.data_drop(DataIndex::from_u32(*index));
+        }
+
+        Ok(())
+    }
+}
@@ -41,6 +41,7 @@ pub use crate::global::*;
 pub use crate::imports::Imports;
 pub use crate::instance::{
     ImportFunctionEnv, ImportInitializerFuncPtr, InstanceAllocator, InstanceHandle,
+    InstanceSnapshot, SnapshotError,
 };
 pub use crate::memory::{LinearMemory, Memory, MemoryError, MemoryStyle};
 pub use crate::mmap::Mmap;
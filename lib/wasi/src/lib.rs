@@ -15,17 +15,29 @@
 #[macro_use]
 mod macros;
 mod ptr;
+mod snapshot;
 mod state;
 mod syscalls;
+mod trace;
 mod utils;
 
 use crate::syscalls::*;
 
+pub use crate::snapshot::{WasiSnapshot, WasiSnapshotError};
+#[cfg(feature = "archive")]
+pub use crate::state::{ArchiveFile, ArchiveFileSystem, ArchiveFormat};
 pub use crate::state::{
-    Fd, Pipe, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState, WasiStateBuilder,
-    WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
+    CallbackOutput, DirEntry, Fd, FileSystem, FileType, HostClock, HostFileSystem, HostRandom,
+    MemFile, MemFileSystem, Metadata, MountFileSystem, OpenOptions, OverlayFile, OverlayFileSystem,
+    PathOperation, PathPolicy, Pipe, PolicyAction, PolicyRule, SeededRandom, Stderr, Stdin, Stdout,
+    VirtualClock, WasiClock, WasiFile, WasiFs, WasiFsError, WasiQuotas, WasiRandom, WasiSocket,
+    WasiState, WasiStateBuilder, WasiStateCreationError, WasiUsage, ALL_RIGHTS, DEFAULT_CLOCK_STEP,
+    VIRTUAL_ROOT_FD,
+};
+pub use crate::syscalls::{host_signal, types};
+pub use crate::trace::{
+    errno_name, ArgValue, SyscallArg, SyscallRecord, SyscallTracer, TraceFormat, TraceWriter,
 };
-pub use crate::syscalls::types;
 pub use crate::utils::{get_wasi_version, is_wasi_module, WasiVersion};
 
 use thiserror::Error;
@@ -41,6 +53,9 @@ use std::sync::{Arc, Mutex, MutexGuard};
 pub enum WasiError {
     #[error("WASI exited with code: {0}")]
     Exit(syscalls::types::__wasi_exitcode_t),
+    /// The WASI number of the signal, see [`host_signal`] for the host's.
+    #[error("WASI was terminated by signal {0}")]
+    Signal(syscalls::types::__wasi_signal_t),
     #[error("The WASI version could not be determined")]
     UnknownWasiVersion,
 }
@@ -57,6 +72,8 @@ pub struct WasiEnv {
     pub state: Arc<Mutex<WasiState>>,
     #[wasmer(export)]
     memory: LazyInit<Memory>,
+    /// Where the syscalls are reported, if they're traced
+    tracer: Option<Arc<dyn SyscallTracer>>,
 }
 
 impl WasiEnv {
@@ -64,9 +81,17 @@ impl WasiEnv {
         Self {
             state: Arc::new(Mutex::new(state)),
             memory: LazyInit::new(),
+            tracer: None,
         }
     }
 
+    /// Report the syscalls made by the module to `tracer`.
+    ///
+    /// This must be set before creating the import object.
+    pub fn set_tracer(&mut self, tracer: Arc<dyn SyscallTracer>) {
+        self.tracer = Some(tracer);
+    }
+
     pub fn import_object(&mut self, module: &Module) -> Result<ImportObject, WasiError> {
         let wasi_version = get_wasi_version(module, false).ok_or(WasiError::UnknownWasiVersion)?;
         Ok(generate_import_object_from_env(
@@ -92,6 +117,22 @@ impl WasiEnv {
         self.state.lock().unwrap()
     }
 
+    /// Pass what the module writes to stdout to `output` from now on.
+    pub fn capture_stdout(&self, output: CallbackOutput) -> Result<(), WasiFsError> {
+        self.state()
+            .fs
+            .swap_file(types::__WASI_STDOUT_FILENO, Box::new(output))?;
+        Ok(())
+    }
+
+    /// Pass what the module writes to stderr to `output` from now on.
+    pub fn capture_stderr(&self, output: CallbackOutput) -> Result<(), WasiFsError> {
+        self.state()
+            .fs
+            .swap_file(types::__WASI_STDERR_FILENO, Box::new(output))?;
+        Ok(())
+    }
+
     /// Get a reference to the memory
     pub fn memory(&self) -> &Memory {
         self.memory_ref()
@@ -124,11 +165,23 @@ pub fn generate_import_object_from_env(
     }
 }
 
+/// Import the syscall `$func`, or the wrapper of `syscalls::traced` reporting
+/// it to the tracer if there is one.
+macro_rules! wasi_import {
+    ($store:expr, $env:expr, $($func:ident)::+) => {
+        if $env.tracer.is_some() {
+            Function::new_native_with_env($store, $env.clone(), traced::$($func)::+)
+        } else {
+            Function::new_native_with_env($store, $env.clone(), $($func)::+)
+        }
+    };
+}
+
 // Note: we use this wrapper because native functions with more than 9 params
-// fail on Apple Silicon (with Cranelift).
+// fail on Apple Silicon (with Cranelift); `path_open` isn't traced there.
 fn get_path_open_for_store(store: &Store, env: WasiEnv) -> Function {
     #[cfg(not(all(target_os = "macos", target_arch = "aarch64",)))]
-    let path_open = Function::new_native_with_env(store, env.clone(), path_open);
+    let path_open = wasi_import!(store, env, path_open);
     #[cfg(all(target_os = "macos", target_arch = "aarch64",))]
     let path_open = Function::new_with_env(
         store,
@@ -156,51 +209,51 @@ fn get_path_open_for_store(store: &Store, env: WasiEnv) -> Function {
 fn generate_import_object_snapshot0(store: &Store, env: WasiEnv) -> ImportObject {
     imports! {
         "wasi_unstable" => {
-            "args_get" => Function::new_native_with_env(store, env.clone(), args_get),
-            "args_sizes_get" => Function::new_native_with_env(store, env.clone(), args_sizes_get),
-            "clock_res_get" => Function::new_native_with_env(store, env.clone(), clock_res_get),
-            "clock_time_get" => Function::new_native_with_env(store, env.clone(), clock_time_get),
-            "environ_get" => Function::new_native_with_env(store, env.clone(), environ_get),
-            "environ_sizes_get" => Function::new_native_with_env(store, env.clone(), environ_sizes_get),
-            "fd_advise" => Function::new_native_with_env(store, env.clone(), fd_advise),
-            "fd_allocate" => Function::new_native_with_env(store, env.clone(), fd_allocate),
-            "fd_close" => Function::new_native_with_env(store, env.clone(), fd_close),
-            "fd_datasync" => Function::new_native_with_env(store, env.clone(), fd_datasync),
-            "fd_fdstat_get" => Function::new_native_with_env(store, env.clone(), fd_fdstat_get),
-            "fd_fdstat_set_flags" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_flags),
-            "fd_fdstat_set_rights" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_rights),
-            "fd_filestat_get" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::fd_filestat_get),
-            "fd_filestat_set_size" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_size),
-            "fd_filestat_set_times" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_times),
-            "fd_pread" => Function::new_native_with_env(store, env.clone(), fd_pread),
-            "fd_prestat_get" => Function::new_native_with_env(store, env.clone(), fd_prestat_get),
-            "fd_prestat_dir_name" => Function::new_native_with_env(store, env.clone(), fd_prestat_dir_name),
-            "fd_pwrite" => Function::new_native_with_env(store, env.clone(), fd_pwrite),
-            "fd_read" => Function::new_native_with_env(store, env.clone(), fd_read),
-            "fd_readdir" => Function::new_native_with_env(store, env.clone(), fd_readdir),
-            "fd_renumber" => Function::new_native_with_env(store, env.clone(), fd_renumber),
-            "fd_seek" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::fd_seek),
-            "fd_sync" => Function::new_native_with_env(store, env.clone(), fd_sync),
-            "fd_tell" => Function::new_native_with_env(store, env.clone(), fd_tell),
-            "fd_write" => Function::new_native_with_env(store, env.clone(), fd_write),
-            "path_create_directory" => Function::new_native_with_env(store, env.clone(), path_create_directory),
-            "path_filestat_get" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::path_filestat_get),
-            "path_filestat_set_times" => Function::new_native_with_env(store, env.clone(), path_filestat_set_times),
-            "path_link" => Function::new_native_with_env(store, env.clone(), path_link),
+            "args_get" => wasi_import!(store, env, args_get),
+            "args_sizes_get" => wasi_import!(store, env, args_sizes_get),
+            "clock_res_get" => wasi_import!(store, env, clock_res_get),
+            "clock_time_get" => wasi_import!(store, env, clock_time_get),
+            "environ_get" => wasi_import!(store, env, environ_get),
+            "environ_sizes_get" => wasi_import!(store, env, environ_sizes_get),
+            "fd_advise" => wasi_import!(store, env, fd_advise),
+            "fd_allocate" => wasi_import!(store, env, fd_allocate),
+            "fd_close" => wasi_import!(store, env, fd_close),
+            "fd_datasync" => wasi_import!(store, env, fd_datasync),
+            "fd_fdstat_get" => wasi_import!(store, env, fd_fdstat_get),
+            "fd_fdstat_set_flags" => wasi_import!(store, env, fd_fdstat_set_flags),
+            "fd_fdstat_set_rights" => wasi_import!(store, env, fd_fdstat_set_rights),
+            "fd_filestat_get" => wasi_import!(store, env, legacy::snapshot0::fd_filestat_get),
+            "fd_filestat_set_size" => wasi_import!(store, env, fd_filestat_set_size),
+            "fd_filestat_set_times" => wasi_import!(store, env, fd_filestat_set_times),
+            "fd_pread" => wasi_import!(store, env, fd_pread),
+            "fd_prestat_get" => wasi_import!(store, env, fd_prestat_get),
+            "fd_prestat_dir_name" => wasi_import!(store, env, fd_prestat_dir_name),
+            "fd_pwrite" => wasi_import!(store, env, fd_pwrite),
+            "fd_read" => wasi_import!(store, env, fd_read),
+            "fd_readdir" => wasi_import!(store, env, fd_readdir),
+            "fd_renumber" => wasi_import!(store, env, fd_renumber),
+            "fd_seek" => wasi_import!(store, env, legacy::snapshot0::fd_seek),
+            "fd_sync" => wasi_import!(store, env, fd_sync),
+            "fd_tell" => wasi_import!(store, env, fd_tell),
+            "fd_write" => wasi_import!(store, env, fd_write),
+            "path_create_directory" => wasi_import!(store, env, path_create_directory),
+            "path_filestat_get" => wasi_import!(store, env, legacy::snapshot0::path_filestat_get),
+            "path_filestat_set_times" => wasi_import!(store, env, path_filestat_set_times),
+            "path_link" => wasi_import!(store, env, path_link),
             "path_open" => get_path_open_for_store(store, env.clone()),
-            "path_readlink" => Function::new_native_with_env(store, env.clone(), path_readlink),
-            "path_remove_directory" => Function::new_native_with_env(store, env.clone(), path_remove_directory),
-            "path_rename" => Function::new_native_with_env(store, env.clone(), path_rename),
-            "path_symlink" => Function::new_native_with_env(store, env.clone(), path_symlink),
-            "path_unlink_file" => Function::new_native_with_env(store, env.clone(), path_unlink_file),
-            "poll_oneoff" => Function::new_native_with_env(store, env.clone(), legacy::snapshot0::poll_oneoff),
-            "proc_exit" => Function::new_native_with_env(store, env.clone(), proc_exit),
-            "proc_raise" => Function::new_native_with_env(store, env.clone(), proc_raise),
-            "random_get" => Function::new_native_with_env(store, env.clone(), random_get),
-            "sched_yield" => Function::new_native_with_env(store, env.clone(), sched_yield),
-            "sock_recv" => Function::new_native_with_env(store, env.clone(), sock_recv),
-            "sock_send" => Function::new_native_with_env(store, env.clone(), sock_send),
-            "sock_shutdown" => Function::new_native_with_env(store, env.clone(), sock_shutdown),
+            "path_readlink" => wasi_import!(store, env, path_readlink),
+            "path_remove_directory" => wasi_import!(store, env, path_remove_directory),
+            "path_rename" => wasi_import!(store, env, path_rename),
+            "path_symlink" => wasi_import!(store, env, path_symlink),
+            "path_unlink_file" => wasi_import!(store, env, path_unlink_file),
+            "poll_oneoff" => wasi_import!(store, env, legacy::snapshot0::poll_oneoff),
+            "proc_exit" => wasi_import!(store, env, proc_exit),
+            "proc_raise" => wasi_import!(store, env, proc_raise),
+            "random_get" => wasi_import!(store, env, random_get),
+            "sched_yield" => wasi_import!(store, env, sched_yield),
+            "sock_recv" => wasi_import!(store, env, sock_recv),
+            "sock_send" => wasi_import!(store, env, sock_send),
+            "sock_shutdown" => wasi_import!(store, env, sock_shutdown),
         },
     }
 }
@@ -209,51 +262,51 @@ fn generate_import_object_snapshot0(store: &Store, env: WasiEnv) -> ImportObject
 fn generate_import_object_snapshot1(store: &Store, env: WasiEnv) -> ImportObject {
     imports! {
         "wasi_snapshot_preview1" => {
-            "args_get" => Function::new_native_with_env(store, env.clone(), args_get),
-            "args_sizes_get" => Function::new_native_with_env(store, env.clone(), args_sizes_get),
-            "clock_res_get" => Function::new_native_with_env(store, env.clone(), clock_res_get),
-            "clock_time_get" => Function::new_native_with_env(store, env.clone(), clock_time_get),
-            "environ_get" => Function::new_native_with_env(store, env.clone(), environ_get),
-            "environ_sizes_get" => Function::new_native_with_env(store, env.clone(), environ_sizes_get),
-            "fd_advise" => Function::new_native_with_env(store, env.clone(), fd_advise),
-            "fd_allocate" => Function::new_native_with_env(store, env.clone(), fd_allocate),
-            "fd_close" => Function::new_native_with_env(store, env.clone(), fd_close),
-            "fd_datasync" => Function::new_native_with_env(store, env.clone(), fd_datasync),
-            "fd_fdstat_get" => Function::new_native_with_env(store, env.clone(), fd_fdstat_get),
-            "fd_fdstat_set_flags" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_flags),
-            "fd_fdstat_set_rights" => Function::new_native_with_env(store, env.clone(), fd_fdstat_set_rights),
-            "fd_filestat_get" => Function::new_native_with_env(store, env.clone(), fd_filestat_get),
-            "fd_filestat_set_size" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_size),
-            "fd_filestat_set_times" => Function::new_native_with_env(store, env.clone(), fd_filestat_set_times),
-            "fd_pread" => Function::new_native_with_env(store, env.clone(), fd_pread),
-            "fd_prestat_get" => Function::new_native_with_env(store, env.clone(), fd_prestat_get),
-            "fd_prestat_dir_name" => Function::new_native_with_env(store, env.clone(), fd_prestat_dir_name),
-            "fd_pwrite" => Function::new_native_with_env(store, env.clone(), fd_pwrite),
-            "fd_read" => Function::new_native_with_env(store, env.clone(), fd_read),
-            "fd_readdir" => Function::new_native_with_env(store, env.clone(), fd_readdir),
-            "fd_renumber" => Function::new_native_with_env(store, env.clone(), fd_renumber),
-            "fd_seek" => Function::new_native_with_env(store, env.clone(), fd_seek),
-            "fd_sync" => Function::new_native_with_env(store, env.clone(), fd_sync),
-            "fd_tell" => Function::new_native_with_env(store, env.clone(), fd_tell),
-            "fd_write" => Function::new_native_with_env(store, env.clone(), fd_write),
-            "path_create_directory" => Function::new_native_with_env(store, env.clone(), path_create_directory),
-            "path_filestat_get" => Function::new_native_with_env(store, env.clone(), path_filestat_get),
-            "path_filestat_set_times" => Function::new_native_with_env(store, env.clone(), path_filestat_set_times),
-            "path_link" => Function::new_native_with_env(store, env.clone(), path_link),
+            "args_get" => wasi_import!(store, env, args_get),
+            "args_sizes_get" => wasi_import!(store, env, args_sizes_get),
+            "clock_res_get" => wasi_import!(store, env, clock_res_get),
+            "clock_time_get" => wasi_import!(store, env, clock_time_get),
+            "environ_get" => wasi_import!(store, env, environ_get),
+            "environ_sizes_get" => wasi_import!(store, env, environ_sizes_get),
+            "fd_advise" => wasi_import!(store, env, fd_advise),
+            "fd_allocate" => wasi_import!(store, env, fd_allocate),
+            "fd_close" => wasi_import!(store, env, fd_close),
+            "fd_datasync" => wasi_import!(store, env, fd_datasync),
+            "fd_fdstat_get" => wasi_import!(store, env, fd_fdstat_get),
+            "fd_fdstat_set_flags" => wasi_import!(store, env, fd_fdstat_set_flags),
+            "fd_fdstat_set_rights" => wasi_import!(store, env, fd_fdstat_set_rights),
+            "fd_filestat_get" => wasi_import!(store, env, fd_filestat_get),
+            "fd_filestat_set_size" => wasi_import!(store, env, fd_filestat_set_size),
+            "fd_filestat_set_times" => wasi_import!(store, env, fd_filestat_set_times),
+            "fd_pread" => wasi_import!(store, env, fd_pread),
+            "fd_prestat_get" => wasi_import!(store, env, fd_prestat_get),
+            "fd_prestat_dir_name" => wasi_import!(store, env, fd_prestat_dir_name),
+            "fd_pwrite" => wasi_import!(store, env, fd_pwrite),
+            "fd_read" => wasi_import!(store, env, fd_read),
+            "fd_readdir" => wasi_import!(store, env, fd_readdir),
+            "fd_renumber" => wasi_import!(store, env, fd_renumber),
+            "fd_seek" => wasi_import!(store, env, fd_seek),
+            "fd_sync" => wasi_import!(store, env, fd_sync),
+            "fd_tell" => wasi_import!(store, env, fd_tell),
+            "fd_write" => wasi_import!(store, env, fd_write),
+            "path_create_directory" => wasi_import!(store, env, path_create_directory),
+            "path_filestat_get" => wasi_import!(store, env, path_filestat_get),
+            "path_filestat_set_times" => wasi_import!(store, env, path_filestat_set_times),
+            "path_link" => wasi_import!(store, env, path_link),
             "path_open" => get_path_open_for_store(store, env.clone()),
-            "path_readlink" => Function::new_native_with_env(store, env.clone(), path_readlink),
-            "path_remove_directory" => Function::new_native_with_env(store, env.clone(), path_remove_directory),
-            "path_rename" => Function::new_native_with_env(store, env.clone(), path_rename),
-            "path_symlink" => Function::new_native_with_env(store, env.clone(), path_symlink),
-            "path_unlink_file" => Function::new_native_with_env(store, env.clone(), path_unlink_file),
-            "poll_oneoff" => Function::new_native_with_env(store, env.clone(), poll_oneoff),
-            "proc_exit" => Function::new_native_with_env(store, env.clone(), proc_exit),
-            "proc_raise" => Function::new_native_with_env(store, env.clone(), proc_raise),
-            "random_get" => Function::new_native_with_env(store, env.clone(), random_get),
-            "sched_yield" => Function::new_native_with_env(store, env.clone(), sched_yield),
-            "sock_recv" => Function::new_native_with_env(store, env.clone(), sock_recv),
-            "sock_send" => Function::new_native_with_env(store, env.clone(), sock_send),
-            "sock_shutdown" => Function::new_native_with_env(store, env.clone(), sock_shutdown),
+            "path_readlink" => wasi_import!(store, env, path_readlink),
+            "path_remove_directory" => wasi_import!(store, env, path_remove_directory),
+            "path_rename" => wasi_import!(store, env, path_rename),
+            "path_symlink" => wasi_import!(store, env, path_symlink),
+            "path_unlink_file" => wasi_import!(store, env, path_unlink_file),
+            "poll_oneoff" => wasi_import!(store, env, poll_oneoff),
+            "proc_exit" => wasi_import!(store, env, proc_exit),
+            "proc_raise" => wasi_import!(store, env, proc_raise),
+            "random_get" => wasi_import!(store, env, random_get),
+            "sched_yield" => wasi_import!(store, env, sched_yield),
+            "sock_recv" => wasi_import!(store, env, sock_recv),
+            "sock_send" => wasi_import!(store, env, sock_send),
+            "sock_shutdown" => wasi_import!(store, env, sock_shutdown),
         }
     }
 }
@@ -0,0 +1,80 @@
+//! Snapshots of a paused WASI program, to resume it later or on another
+//! machine.
+//!
+//! A [`WasiSnapshot`] is the state of the instance (see
+//! [`wasmer::InstanceSnapshot`]) together with the frozen [`WasiState`]:
+//! arguments, environment variables, open file descriptors and the
+//! contents of in-memory files.
+
+use crate::{WasiEnv, WasiError, WasiState};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use wasmer::{Instance, InstanceSnapshot, Module, RestoreError, SnapshotError};
+
+/// An error while taking or restoring a [`WasiSnapshot`].
+#[derive(Error, Debug)]
+pub enum WasiSnapshotError {
+    #[error("failed to snapshot the instance: {0}")]
+    Instance(SnapshotError),
+    #[error("failed to restore the instance: {0}")]
+    Restore(RestoreError),
+    #[error("the WASI state could not be frozen")]
+    Freeze,
+    #[error("the WASI state could not be unfrozen")]
+    Unfreeze,
+    #[error("the snapshot could not be encoded or decoded: {0}")]
+    Encoding(String),
+    #[error(transparent)]
+    Wasi(WasiError),
+}
+
+/// A paused WASI program, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasiSnapshot {
+    /// The memories, globals and tables of the instance
+    pub instance: InstanceSnapshot,
+    /// The WASI state, as frozen by [`WasiState::freeze`]
+    pub state: Vec<u8>,
+}
+
+impl WasiSnapshot {
+    /// Encode the snapshot, e.g. to write it to a file.
+    pub fn serialize(&self) -> Result<Vec<u8>, WasiSnapshotError> {
+        bincode::serialize(self).map_err(|e| WasiSnapshotError::Encoding(e.to_string()))
+    }
+
+    /// Decode a snapshot encoded by [`WasiSnapshot::serialize`].
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, WasiSnapshotError> {
+        bincode::deserialize(bytes).map_err(|e| WasiSnapshotError::Encoding(e.to_string()))
+    }
+}
+
+impl WasiEnv {
+    /// Take a snapshot of the program running in `instance`, which must
+    /// have been created with the import object of this environment.
+    ///
+    /// The program must be paused, i.e. none of its functions running, for
+    /// example between two calls of its exports.
+    pub fn snapshot(&self, instance: &Instance) -> Result<WasiSnapshot, WasiSnapshotError> {
+        let instance = instance.snapshot().map_err(WasiSnapshotError::Instance)?;
+        let state = self.state().freeze().ok_or(WasiSnapshotError::Freeze)?;
+        Ok(WasiSnapshot { instance, state })
+    }
+
+    /// Resume the program of `snapshot` in a new instance of `module`,
+    /// which must be the module the snapshot was taken from.
+    ///
+    /// The start function isn't called again: the program continues from
+    /// where it was when the snapshot was taken.
+    pub fn restore(
+        module: &Module,
+        snapshot: &WasiSnapshot,
+    ) -> Result<(Self, Instance), WasiSnapshotError> {
+        let state = WasiState::unfreeze(&snapshot.state).ok_or(WasiSnapshotError::Unfreeze)?;
+        let mut env = Self::new(state);
+        let import_object = env.import_object(module).map_err(WasiSnapshotError::Wasi)?;
+        let instance = Instance::restore(module, &snapshot.instance, &import_object)
+            .map_err(WasiSnapshotError::Restore)?;
+        Ok((env, instance))
+    }
+}
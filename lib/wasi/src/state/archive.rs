@@ -0,0 +1,738 @@
+//! Read-only [`FileSystem`] serving the contents of a tar, tar.gz or zip
+//! archive.
+//!
+//! The archive is indexed once when the [`ArchiveFileSystem`] is created,
+//! file contents are only read from the archive when a file is read.  The
+//! archive must not be modified while it's mounted.
+//!
+//! Compressed tar streams can't be read from the middle, so tar.gz archives
+//! are decompressed in memory once, and all their files are read from there.
+
+use crate::state::file_system::normalize_path;
+use crate::state::{
+    DirEntry, FileSystem, FileType, Metadata, OpenOptions, WasiFile, WasiFsError, MAX_SYMLINKS,
+};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The formats of archives an [`ArchiveFileSystem`] can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guess the format of an archive from its first bytes.
+    fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            ArchiveFormat::TarGz
+        } else if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+            ArchiveFormat::Zip
+        } else {
+            ArchiveFormat::Tar
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ArchiveNode {
+    Dir {
+        modified: __wasi_timestamp_t,
+    },
+    File {
+        /// The offset of the contents in the (uncompressed) tar stream, or
+        /// the index of the file in a zip archive.
+        location: u64,
+        len: u64,
+        modified: __wasi_timestamp_t,
+    },
+    Symlink {
+        target: PathBuf,
+        modified: __wasi_timestamp_t,
+    },
+}
+
+impl ArchiveNode {
+    fn metadata(&self) -> Metadata {
+        let (file_type, len, modified) = match self {
+            ArchiveNode::Dir { modified } => (FileType::Directory, 0, *modified),
+            ArchiveNode::File { len, modified, .. } => (FileType::RegularFile, *len, *modified),
+            ArchiveNode::Symlink { target, modified } => (
+                FileType::Symlink,
+                target.as_os_str().len() as u64,
+                *modified,
+            ),
+        };
+        Metadata {
+            file_type,
+            len,
+            accessed: modified,
+            modified,
+            created: modified,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveIndex {
+    archive: PathBuf,
+    format: ArchiveFormat,
+    nodes: BTreeMap<PathBuf, ArchiveNode>,
+    /// The decompressed tar stream of a tar.gz archive, decompressed again
+    /// after deserialization
+    #[serde(skip)]
+    unpacked: UnpackedTar,
+}
+
+#[derive(Default)]
+struct UnpackedTar(Mutex<Option<Arc<Vec<u8>>>>);
+
+impl fmt::Debug for UnpackedTar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.0.lock().unwrap() {
+            Some(bytes) => write!(f, "UnpackedTar({} bytes)", bytes.len()),
+            None => write!(f, "UnpackedTar(None)"),
+        }
+    }
+}
+
+/// A read-only [`FileSystem`] backed by an archive on the host.
+///
+/// The root of the archive is the root of the filesystem, `/`.  All
+/// operations modifying the filesystem fail with
+/// [`WasiFsError::PermissionDenied`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveFileSystem {
+    index: Arc<ArchiveIndex>,
+}
+
+/// The file type bits of a unix mode, and their value for symlinks.
+const S_IFMT: u32 = 0o170_000;
+const S_IFLNK: u32 = 0o120_000;
+
+fn seconds_to_nanos(seconds: u64) -> __wasi_timestamp_t {
+    seconds.saturating_mul(1_000_000_000)
+}
+
+/// Convert the MS-DOS time of a zip entry, which has no time zone, to a
+/// timestamp as if it was in UTC.
+fn zip_time_to_nanos(time: zip::DateTime) -> __wasi_timestamp_t {
+    // days since the epoch of a date of the proleptic Gregorian calendar
+    let (year, month, day) = (
+        i64::from(time.year()),
+        i64::from(time.month()),
+        i64::from(time.day()),
+    );
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86400
+        + i64::from(time.hour()) * 3600
+        + i64::from(time.minute()) * 60
+        + i64::from(time.second());
+    seconds_to_nanos(seconds.max(0) as u64)
+}
+
+fn zip_error(error: zip::result::ZipError) -> WasiFsError {
+    match error {
+        zip::result::ZipError::Io(e) => e.into(),
+        zip::result::ZipError::FileNotFound => WasiFsError::EntityNotFound,
+        _ => WasiFsError::InvalidData,
+    }
+}
+
+impl ArchiveFileSystem {
+    /// Index the archive at `archive` on the host.
+    ///
+    /// The format of the archive is detected from its contents.
+    pub fn new<P: AsRef<Path>>(archive: P) -> Result<Self, WasiFsError> {
+        let archive = archive.as_ref().to_path_buf();
+        let mut file = fs::File::open(&archive)?;
+        let mut magic = [0; 4];
+        let magic_len = file.read(&mut magic)?;
+        let format = ArchiveFormat::detect(&magic[..magic_len]);
+        file.seek(io::SeekFrom::Start(0))?;
+
+        let mut nodes = BTreeMap::new();
+        nodes.insert(PathBuf::from("/"), ArchiveNode::Dir { modified: 0 });
+        let unpacked = UnpackedTar::default();
+        match format {
+            ArchiveFormat::Tar => Self::index_tar(file, &mut nodes)?,
+            ArchiveFormat::TarGz => {
+                let bytes = Self::unpack(file)?;
+                Self::index_tar(&bytes[..], &mut nodes)?;
+                *unpacked.0.lock().unwrap() = Some(Arc::new(bytes));
+            }
+            ArchiveFormat::Zip => Self::index_zip(file, &mut nodes)?,
+        }
+
+        Ok(Self {
+            index: Arc::new(ArchiveIndex {
+                archive,
+                format,
+                nodes,
+                unpacked,
+            }),
+        })
+    }
+
+    /// The format of the archive.
+    pub fn format(&self) -> ArchiveFormat {
+        self.index.format
+    }
+
+    /// Add `node` at `path`, creating the directories leading to it if the
+    /// archive doesn't list them.  Later entries replace earlier ones.
+    fn insert(nodes: &mut BTreeMap<PathBuf, ArchiveNode>, path: PathBuf, node: ArchiveNode) {
+        for ancestor in path.ancestors().skip(1) {
+            if nodes.contains_key(ancestor) {
+                break;
+            }
+            nodes.insert(ancestor.to_path_buf(), ArchiveNode::Dir { modified: 0 });
+        }
+        nodes.insert(path, node);
+    }
+
+    fn index_tar<R: Read>(
+        reader: R,
+        nodes: &mut BTreeMap<PathBuf, ArchiveNode>,
+    ) -> Result<(), WasiFsError> {
+        let mut archive = tar::Archive::new(reader);
+        let mut hard_links = vec![];
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = normalize_path(&entry.path()?);
+            let modified = seconds_to_nanos(entry.header().mtime().unwrap_or(0));
+            let entry_type = entry.header().entry_type();
+            let node = if entry_type.is_dir() {
+                ArchiveNode::Dir { modified }
+            } else if entry_type.is_symlink() {
+                let target = entry.link_name()?.ok_or(WasiFsError::InvalidData)?;
+                ArchiveNode::Symlink {
+                    target: target.into_owned(),
+                    modified,
+                }
+            } else if entry_type.is_hard_link() {
+                // hard links are resolved once all the entries are known
+                let target = entry.link_name()?.ok_or(WasiFsError::InvalidData)?;
+                hard_links.push((path, normalize_path(&target)));
+                continue;
+            } else if entry_type.is_file() || entry_type.is_contiguous() {
+                ArchiveNode::File {
+                    location: entry.raw_file_position(),
+                    len: entry.size(),
+                    modified,
+                }
+            } else {
+                // devices and fifos can't be represented
+                continue;
+            };
+            Self::insert(nodes, path, node);
+        }
+        for (path, target) in hard_links {
+            if let Some(node) = nodes.get(&target).cloned() {
+                Self::insert(nodes, path, node);
+            }
+        }
+        Ok(())
+    }
+
+    fn index_zip(
+        file: fs::File,
+        nodes: &mut BTreeMap<PathBuf, ArchiveNode>,
+    ) -> Result<(), WasiFsError> {
+        let mut archive = zip::ZipArchive::new(file).map_err(zip_error)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(zip_error)?;
+            let path = normalize_path(Path::new(entry.name()));
+            let modified = zip_time_to_nanos(entry.last_modified());
+            let is_symlink = entry
+                .unix_mode()
+                .map(|mode| mode & S_IFMT == S_IFLNK)
+                .unwrap_or(false);
+            let node = if entry.is_dir() {
+                ArchiveNode::Dir { modified }
+            } else if is_symlink {
+                let mut target = String::new();
+                entry.read_to_string(&mut target)?;
+                ArchiveNode::Symlink {
+                    target: PathBuf::from(target),
+                    modified,
+                }
+            } else {
+                ArchiveNode::File {
+                    location: i as u64,
+                    len: entry.size(),
+                    modified,
+                }
+            };
+            Self::insert(nodes, path, node);
+        }
+        Ok(())
+    }
+
+    /// Follow the symlinks at `path` until something else is found.
+    fn resolve(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        let mut path = normalize_path(path);
+        for _ in 0..MAX_SYMLINKS {
+            match self.index.nodes.get(&path) {
+                Some(ArchiveNode::Symlink { target, .. }) => {
+                    let mut next = path.parent().unwrap_or_else(|| Path::new("/")).to_owned();
+                    next.push(target);
+                    path = normalize_path(&next);
+                }
+                Some(_) => return Ok(path),
+                None => return Err(WasiFsError::EntityNotFound),
+            }
+        }
+        Err(WasiFsError::InvalidInput)
+    }
+
+    /// Decompress the tar stream of a tar.gz archive.
+    ///
+    /// The buffer grows as the stream is read, the sizes in the archive
+    /// aren't trusted.
+    fn unpack(file: fs::File) -> Result<Vec<u8>, WasiFsError> {
+        let mut bytes = vec![];
+        flate2::read::GzDecoder::new(file).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Get the decompressed tar stream of a tar.gz archive.
+    fn unpacked(&self) -> Result<Arc<Vec<u8>>, WasiFsError> {
+        let mut unpacked = self.index.unpacked.0.lock().unwrap();
+        if let Some(bytes) = &*unpacked {
+            return Ok(bytes.clone());
+        }
+        let bytes = Arc::new(Self::unpack(fs::File::open(&self.index.archive)?)?);
+        *unpacked = Some(bytes.clone());
+        Ok(bytes)
+    }
+
+    /// Get access to the contents of the file at `location` in the archive.
+    fn open_contents(&self, location: u64, len: u64) -> Result<FileContents, WasiFsError> {
+        match self.index.format {
+            ArchiveFormat::Tar => Ok(FileContents::Host(fs::File::open(&self.index.archive)?)),
+            ArchiveFormat::TarGz => Ok(FileContents::Memory {
+                bytes: self.unpacked()?,
+                start: location,
+            }),
+            ArchiveFormat::Zip => {
+                let file = fs::File::open(&self.index.archive)?;
+                let mut archive = zip::ZipArchive::new(file).map_err(zip_error)?;
+                let entry = archive.by_index(location as usize).map_err(zip_error)?;
+                // grown while reading, like for tar.gz archives
+                let mut bytes = vec![];
+                entry.take(len).read_to_end(&mut bytes)?;
+                Ok(FileContents::Memory {
+                    bytes: Arc::new(bytes),
+                    start: 0,
+                })
+            }
+        }
+    }
+}
+
+#[typetag::serde]
+impl FileSystem for ArchiveFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let path = self.resolve(path)?;
+        Ok(self.index.nodes[&path].metadata())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        self.index
+            .nodes
+            .get(&normalize_path(path))
+            .map(ArchiveNode::metadata)
+            .ok_or(WasiFsError::EntityNotFound)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        match self.index.nodes.get(&normalize_path(path)) {
+            Some(ArchiveNode::Symlink { target, .. }) => Ok(target.clone()),
+            Some(_) => Err(WasiFsError::InvalidInput),
+            None => Err(WasiFsError::EntityNotFound),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        let path = self.resolve(path)?;
+        if !matches!(self.index.nodes[&path], ArchiveNode::Dir { .. }) {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+        Ok(self
+            .index
+            .nodes
+            .range(path.clone()..)
+            .skip(1)
+            .take_while(|(child, _)| child.starts_with(&path))
+            .filter(|(child, _)| child.parent() == Some(path.as_path()))
+            .map(|(child, node)| DirEntry {
+                // unwrap is safe because children always have a name
+                name: child.file_name().unwrap().to_string_lossy().into_owned(),
+                file_type: node.metadata().file_type,
+            })
+            .collect())
+    }
+
+    fn create_dir(&self, _path: &Path) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &Path) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn remove_file(&self, _path: &Path) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let path = match self.resolve(path) {
+            Ok(_) if options.create_new => return Err(WasiFsError::AlreadyExists),
+            Ok(path) => path,
+            Err(WasiFsError::EntityNotFound) if options.create || options.create_new => {
+                return Err(WasiFsError::PermissionDenied)
+            }
+            Err(e) => return Err(e),
+        };
+        if options.write || options.append || options.truncate {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        match self.index.nodes[&path] {
+            ArchiveNode::File {
+                location,
+                len,
+                modified,
+            } => Ok(Box::new(ArchiveFile {
+                fs: self.clone(),
+                location,
+                len,
+                modified,
+                cursor: 0,
+                contents: None,
+            })),
+            _ => Err(WasiFsError::NotAFile),
+        }
+    }
+}
+
+/// Where the contents of an [`ArchiveFile`] are read from.
+enum FileContents {
+    /// The archive itself, for uncompressed tar archives
+    Host(fs::File),
+    /// The extracted contents of the file, starting at `start` in `bytes`
+    Memory { bytes: Arc<Vec<u8>>, start: u64 },
+}
+
+impl fmt::Debug for FileContents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileContents::Host(file) => f.debug_tuple("Host").field(file).finish(),
+            FileContents::Memory { bytes, start } => {
+                write!(f, "Memory({} bytes from {})", bytes.len(), start)
+            }
+        }
+    }
+}
+
+/// A file opened from an [`ArchiveFileSystem`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    fs: ArchiveFileSystem,
+    location: u64,
+    len: u64,
+    modified: __wasi_timestamp_t,
+    cursor: u64,
+    /// Opened on the first read, and again after deserialization
+    #[serde(skip)]
+    contents: Option<FileContents>,
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.cursor);
+        let max = (buf.len() as u64).min(remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+        if self.contents.is_none() {
+            let contents = self
+                .fs
+                .open_contents(self.location, self.len)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            self.contents = Some(contents);
+        }
+        let read = match self.contents.as_mut().unwrap() {
+            FileContents::Host(file) => {
+                file.seek(io::SeekFrom::Start(self.location + self.cursor))?;
+                file.read(&mut buf[..max])?
+            }
+            FileContents::Memory { bytes, start } => {
+                let start = start.saturating_add(self.cursor);
+                let available = (bytes.len() as u64).saturating_sub(start);
+                let read = (max as u64).min(available) as usize;
+                if read > 0 {
+                    let start = start as usize;
+                    buf[..read].copy_from_slice(&bytes[start..start + read]);
+                }
+                read
+            }
+        };
+        self.cursor += read as u64;
+        Ok(read)
+    }
+}
+
+impl Write for ArchiveFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "archives are read-only",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ArchiveFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let cursor = match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => (self.len as i64).checked_add(offset).map(|c| c as u64),
+            io::SeekFrom::Current(offset) => {
+                (self.cursor as i64).checked_add(offset).map(|c| c as u64)
+            }
+        };
+        match cursor {
+            Some(cursor) if (cursor as i64) >= 0 => {
+                self.cursor = cursor;
+                Ok(cursor)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for ArchiveFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.modified
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.modified
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.modified
+    }
+
+    fn size(&self) -> u64 {
+        self.len
+    }
+
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.len.saturating_sub(self.cursor) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An archive in the temporary directory, removed when dropped.
+    struct TempArchive(PathBuf);
+
+    impl TempArchive {
+        fn new(name: &str, contents: Vec<u8>) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "wasmer-wasi-archive-{}-{}",
+                std::process::id(),
+                name
+            ));
+            fs::write(&path, contents).unwrap();
+            TempArchive(path)
+        }
+    }
+
+    impl Drop for TempArchive {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn tar_bytes() -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        let mut append = |path: &str, entry_type: tar::EntryType, contents: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(contents.len() as u64);
+            header.set_mtime(1_600_000_000);
+            header.set_mode(0o644);
+            if entry_type == tar::EntryType::Symlink {
+                header
+                    .set_link_name(std::str::from_utf8(contents).unwrap())
+                    .unwrap();
+                header.set_size(0);
+                builder.append_data(&mut header, path, &[][..]).unwrap();
+            } else {
+                builder.append_data(&mut header, path, contents).unwrap();
+            }
+        };
+        append("./app/", tar::EntryType::Directory, b"");
+        append(
+            "./app/main.py",
+            tar::EntryType::Regular,
+            b"print('hello')\n",
+        );
+        append(
+            "./app/data/numbers.txt",
+            tar::EntryType::Regular,
+            b"0123456789",
+        );
+        append("./app/latest", tar::EntryType::Symlink, b"data/numbers.txt");
+        builder.into_inner().unwrap()
+    }
+
+    fn names(fs: &ArchiveFileSystem, path: &str) -> Vec<String> {
+        fs.read_dir(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    fn check_contents(fs: &ArchiveFileSystem) {
+        assert_eq!(names(fs, "/"), vec!["app"]);
+        assert!(fs.metadata(Path::new("/app/data")).unwrap().is_dir());
+        let metadata = fs.metadata(Path::new("/app/data/numbers.txt")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, 10);
+
+        let mut file = fs
+            .open(
+                Path::new("/app/data/numbers.txt"),
+                OpenOptions::new().read(true),
+            )
+            .unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(file.seek(io::SeekFrom::End(-3)).unwrap(), 7);
+        assert_eq!(file.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"789");
+        assert_eq!(file.seek(io::SeekFrom::Start(2)).unwrap(), 2);
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"2345");
+        assert!(file.seek(io::SeekFrom::Current(-10)).is_err());
+        assert_eq!(file.seek(io::SeekFrom::Start(20)).unwrap(), 20);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+        assert!(file.write(b"x").is_err());
+
+        let mut contents = String::new();
+        fs.open(Path::new("/app/main.py"), OpenOptions::new().read(true))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "print('hello')\n");
+
+        assert!(fs
+            .open(
+                Path::new("/app/main.py"),
+                OpenOptions::new().read(true).write(true)
+            )
+            .is_err());
+        assert!(fs.remove_file(Path::new("/app/main.py")).is_err());
+    }
+
+    fn check_symlink(fs: &ArchiveFileSystem) {
+        assert_eq!(names(fs, "/app"), vec!["data", "latest", "main.py"]);
+        assert!(fs
+            .symlink_metadata(Path::new("/app/latest"))
+            .unwrap()
+            .is_symlink());
+        assert_eq!(
+            fs.metadata(Path::new("/app/latest")).unwrap().len,
+            fs.metadata(Path::new("/app/data/numbers.txt")).unwrap().len
+        );
+        let mut contents = String::new();
+        fs.open(Path::new("/app/latest"), OpenOptions::new().read(true))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "0123456789");
+    }
+
+    #[test]
+    fn tar_archive() {
+        let archive = TempArchive::new("test.tar", tar_bytes());
+        let fs = ArchiveFileSystem::new(&archive.0).unwrap();
+        assert_eq!(fs.format(), ArchiveFormat::Tar);
+        check_contents(&fs);
+        check_symlink(&fs);
+    }
+
+    #[test]
+    fn tar_gz_archive() {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&tar_bytes()).unwrap();
+        let archive = TempArchive::new("test.tar.gz", encoder.finish().unwrap());
+        let fs = ArchiveFileSystem::new(&archive.0).unwrap();
+        assert_eq!(fs.format(), ArchiveFormat::TarGz);
+        // the archive was decompressed once, while indexing it
+        drop(archive);
+        check_contents(&fs);
+        check_symlink(&fs);
+    }
+
+    #[test]
+    fn zip_archive() {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        let options = zip::write::FileOptions::default();
+        writer.add_directory("app/", options).unwrap();
+        writer.start_file("app/main.py", options).unwrap();
+        writer.write_all(b"print('hello')\n").unwrap();
+        writer.start_file("app/data/numbers.txt", options).unwrap();
+        writer.write_all(b"0123456789").unwrap();
+        let archive = TempArchive::new("test.zip", writer.finish().unwrap().into_inner());
+        let fs = ArchiveFileSystem::new(&archive.0).unwrap();
+        assert_eq!(fs.format(), ArchiveFormat::Zip);
+        check_contents(&fs);
+        assert_eq!(names(&fs, "/app"), vec!["data", "main.py"]);
+    }
+
+    #[test]
+    fn zip_times() {
+        let time = zip::DateTime::from_date_and_time(2021, 3, 1, 12, 30, 15).unwrap();
+        assert_eq!(zip_time_to_nanos(time), seconds_to_nanos(1_614_601_815));
+    }
+}
@@ -1,9 +1,19 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{WasiFile, WasiFs, WasiFsError, WasiState};
-use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
-use crate::WasiEnv;
+#[cfg(feature = "archive")]
+use crate::state::ArchiveFileSystem;
+use crate::state::{
+    FileSystem, HostClock, HostFileSystem, HostRandom, MountFileSystem, OverlayFileSystem,
+    PathPolicy, SeededRandom, SharedFileSystem, VirtualClock, WasiClock, WasiFile, WasiFs,
+    WasiFsError, WasiQuotas, WasiRandom, WasiSocket, WasiState,
+};
+use crate::syscalls::types::{
+    __wasi_fd_t, __WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO,
+};
+use crate::{SyscallTracer, WasiEnv};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 
 /// Creates an empty [`WasiStateBuilder`].
@@ -42,6 +52,15 @@ pub struct WasiStateBuilder {
     stdout_override: Option<Box<dyn WasiFile>>,
     stderr_override: Option<Box<dyn WasiFile>>,
     stdin_override: Option<Box<dyn WasiFile>>,
+    fs_backend: Option<Box<dyn FileSystem>>,
+    sockets: Vec<(__wasi_fd_t, WasiSocket)>,
+    clock: Option<Box<dyn WasiClock>>,
+    random: Option<Box<dyn WasiRandom>>,
+    deterministic: Option<(u64, SystemTime)>,
+    clock_step: Option<Duration>,
+    tracer: Option<Arc<dyn SyscallTracer>>,
+    quotas: WasiQuotas,
+    path_policy: PathPolicy,
 }
 
 impl std::fmt::Debug for WasiStateBuilder {
@@ -54,6 +73,15 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
             .field("stdin_override exists", &self.stdin_override.is_some())
+            .field("fs_backend", &self.fs_backend)
+            .field("sockets", &self.sockets)
+            .field("clock", &self.clock)
+            .field("random", &self.random)
+            .field("deterministic", &self.deterministic)
+            .field("clock_step", &self.clock_step)
+            .field("tracer", &self.tracer)
+            .field("quotas", &self.quotas)
+            .field("path_policy", &self.path_policy)
             .finish()
     }
 }
@@ -69,6 +97,8 @@ pub enum WasiStateCreationError {
     PreopenedDirectoryNotFound(PathBuf),
     #[error("preopened directory error: `{0}`")]
     PreopenedDirectoryError(String),
+    #[error("preopened socket error: `{0}`")]
+    PreopenedSocketError(String),
     #[error("mapped dir alias has wrong format: `{0}`")]
     MappedDirAliasFormattingError(String),
     #[error("wasi filesystem creation error: `{0}`")]
@@ -258,6 +288,37 @@ impl WasiStateBuilder {
         Ok(self)
     }
 
+    /// Give the WASI module access to a socket opened by the host as the
+    /// file descriptor `fd`.
+    ///
+    /// WASI modules can't open sockets themselves, so this is how they get
+    /// to serve or make connections. wasi-libc looks for preopened
+    /// directories from fd 3 until it finds an unused fd, so `fd` should
+    /// come after the preopened directories.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use std::net::TcpListener;
+    /// # use wasmer_wasi::{WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+    ///
+    /// WasiState::new("program_name")
+    ///    .preopen_socket(3, listener)
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn preopen_socket<S>(&mut self, fd: __wasi_fd_t, socket: S) -> &mut Self
+    where
+        S: Into<WasiSocket>,
+    {
+        self.sockets.push((fd, socket.into()));
+
+        self
+    }
+
     /// Overwrite the default WASI `stdout`, if you want to hold on to the
     /// original `stdout` use [`WasiFs::swap_file`] after building.
     pub fn stdout(&mut self, new_file: Box<dyn WasiFile>) -> &mut Self {
@@ -282,6 +343,161 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Use `fs_backend` as the storage behind the preopened directories
+    /// instead of the filesystem of the host.
+    ///
+    /// The paths given to [`WasiStateBuilder::preopen`] and friends are then
+    /// looked up in `fs_backend`.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use wasmer_wasi::{MemFileSystem, WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// let fs = MemFileSystem::new();
+    /// fs.create_dir_all("/data").unwrap();
+    /// fs.write_file("/data/input.txt", b"hello").unwrap();
+    ///
+    /// WasiState::new("program_name")
+    ///    .fs_backend(Box::new(fs.clone()))
+    ///    .map_dir("data", "/data")?
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fs_backend(&mut self, fs_backend: Box<dyn FileSystem>) -> &mut Self {
+        self.fs_backend = Some(fs_backend);
+
+        self
+    }
+
+    /// Run the WASI module deterministically, so that running it again with
+    /// the same inputs gives the same results.
+    ///
+    /// The clocks are replaced by a virtual clock starting at `start_time`
+    /// which advances by a fixed step each time it's read (see
+    /// [`WasiStateBuilder::deterministic_clock_step`]), `random_get` returns
+    /// numbers generated from `seed`, directories are listed in sorted order
+    /// and the timestamps of all files are `start_time`.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use std::time::{Duration, UNIX_EPOCH};
+    /// # use wasmer_wasi::{WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// WasiState::new("program_name")
+    ///    .deterministic(42, UNIX_EPOCH)
+    ///    .deterministic_clock_step(Duration::from_micros(1))
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deterministic(&mut self, seed: u64, start_time: SystemTime) -> &mut Self {
+        self.deterministic = Some((seed, start_time));
+
+        self
+    }
+
+    /// Set how much the virtual clock advances each time it's read in
+    /// deterministic mode; it's 1 millisecond by default.
+    pub fn deterministic_clock_step(&mut self, step: Duration) -> &mut Self {
+        self.clock_step = Some(step);
+
+        self
+    }
+
+    /// Set where the WASI module gets the time from, e.g. a simulated clock.
+    ///
+    /// This takes precedence over the virtual clock of
+    /// [`WasiStateBuilder::deterministic`]. The clocks of the host are used
+    /// by default.
+    pub fn clock(&mut self, clock: Box<dyn WasiClock>) -> &mut Self {
+        self.clock = Some(clock);
+
+        self
+    }
+
+    /// Set where the WASI module gets random numbers from.
+    ///
+    /// This takes precedence over the seeded generator of
+    /// [`WasiStateBuilder::deterministic`]. The random number generator of
+    /// the operating system is used by default.
+    pub fn random(&mut self, random: Box<dyn WasiRandom>) -> &mut Self {
+        self.random = Some(random);
+
+        self
+    }
+
+    /// Report the syscalls made by the WASI module to `tracer`.
+    ///
+    /// Only the [`WasiEnv`] created by [`WasiStateBuilder::finalize`] gets
+    /// the tracer; see [`WasiEnv::set_tracer`] otherwise.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use wasmer_wasi::{TraceFormat, TraceWriter, WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// WasiState::new("program_name")
+    ///    .tracer(Arc::new(TraceWriter::stderr(TraceFormat::Text)))
+    ///    .finalize()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tracer(&mut self, tracer: Arc<dyn SyscallTracer>) -> &mut Self {
+        self.tracer = Some(tracer);
+
+        self
+    }
+
+    /// Limit the resources the WASI module may use.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use wasmer_wasi::{WasiQuotas, WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// WasiState::new("program_name")
+    ///    .quotas(WasiQuotas {
+    ///        max_open_fds: Some(64),
+    ///        max_bytes_written: Some(16 * 1024 * 1024),
+    ///        ..WasiQuotas::default()
+    ///    })
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn quotas(&mut self, quotas: WasiQuotas) -> &mut Self {
+        self.quotas = quotas;
+
+        self
+    }
+
+    /// Restrict what the module may do on the paths of the preopened
+    /// directories, on top of the rights of the directories.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use wasmer_wasi::{PathOperation, PathPolicy, WasiState};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut policy = PathPolicy::allow_all();
+    /// policy.deny("/data/**", &[PathOperation::Write, PathOperation::Unlink])?;
+    /// WasiState::new("program_name")
+    ///    .preopen_dir("data")?
+    ///    .path_policy(policy)
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn path_policy(&mut self, policy: PathPolicy) -> &mut Self {
+        self.path_policy = policy;
+
+        self
+    }
+
     /// Setup the WASI filesystem before running
     // TODO: improve ergonomics on this function
     pub fn setup_fs(
@@ -359,11 +575,53 @@ impl WasiStateBuilder {
             }
         }
 
-        // self.preopens are checked in [`PreopenDirBuilder::build`]
+        // self.preopens are checked in [`PreopenDirBuilder::build`], except
+        // for their existence which depends on the filesystem backend
+        let mut fs_backend = self
+            .fs_backend
+            .take()
+            .unwrap_or_else(|| Box::new(HostFileSystem));
+        for preopen in self.preopens.iter() {
+            if preopen.fs.is_none() && fs_backend.metadata(&preopen.path).is_err() {
+                return Err(WasiStateCreationError::PreopenedDirectoryNotFound(
+                    preopen.path.clone(),
+                ));
+            }
+        }
+        // preopens with a filesystem of their own are mounted next to the
+        // backend, outside of any path the backend could be asked about
+        if self
+            .preopens
+            .iter()
+            .any(|preopen| preopen.fs.is_some() || preopen.overlay.is_some())
+        {
+            // overlays read from the backend, which the mounts also need
+            let backend = SharedFileSystem(Arc::from(fs_backend));
+            let mut mount_fs = MountFileSystem::new(Box::new(backend.clone()));
+            for (i, preopen) in self.preopens.iter_mut().enumerate() {
+                if let Some((upper_fs, upper_dir)) = preopen.overlay.take() {
+                    preopen.fs = Some(Box::new(OverlayFileSystem::new(
+                        Box::new(backend.clone()),
+                        &preopen.path,
+                        upper_fs,
+                        upper_dir,
+                    )));
+                }
+                if let Some(fs) = preopen.fs.take() {
+                    let mount_point = PathBuf::from(format!("/.wasmer-mount-{}", i));
+                    mount_fs.mount(&mount_point, fs);
+                    let path = std::mem::replace(&mut preopen.path, mount_point);
+                    if preopen.alias.is_none() {
+                        preopen.alias = Some(path.to_string_lossy().into_owned());
+                    }
+                }
+            }
+            fs_backend = Box::new(mount_fs);
+        }
 
         // this deprecation warning only applies to external callers
         #[allow(deprecated)]
-        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens)
+        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens, fs_backend)
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
         // set up the file system, overriding base files and calling the setup function
         if let Some(stdin_override) = self.stdin_override.take() {
@@ -381,9 +639,36 @@ impl WasiStateBuilder {
                 .swap_file(__WASI_STDERR_FILENO, stderr_override)
                 .map_err(WasiStateCreationError::WasiFsError)?;
         }
+        for (fd, socket) in self.sockets.drain(..) {
+            wasi_fs.create_socket_fd(socket, 0, Some(fd)).map_err(|_| {
+                WasiStateCreationError::PreopenedSocketError(format!(
+                    "file descriptor {} is already in use",
+                    fd
+                ))
+            })?;
+        }
         if let Some(f) = &self.setup_fs_fn {
             f(&mut wasi_fs).map_err(WasiStateCreationError::WasiFsSetupError)?;
         }
+        // the quotas only apply to what the module does
+        wasi_fs.quotas = self.quotas.clone();
+        wasi_fs.policy = self.path_policy.clone();
+        let clock: Box<dyn WasiClock> = match (self.clock.take(), self.deterministic) {
+            (Some(clock), _) => clock,
+            (None, Some((_, start_time))) => {
+                let mut clock = VirtualClock::new(start_time);
+                if let Some(step) = self.clock_step {
+                    clock.set_step(step);
+                }
+                Box::new(clock)
+            }
+            (None, None) => Box::new(HostClock),
+        };
+        let random: Box<dyn WasiRandom> = match (self.random.take(), self.deterministic) {
+            (Some(random), _) => random,
+            (None, Some((seed, _))) => Box::new(SeededRandom::new(seed)),
+            (None, None) => Box::new(HostRandom),
+        };
         Ok(WasiState {
             fs: wasi_fs,
             args: self.args.clone(),
@@ -399,6 +684,8 @@ impl WasiStateBuilder {
                     env
                 })
                 .collect(),
+            clock,
+            random,
         })
     }
 
@@ -407,7 +694,11 @@ impl WasiStateBuilder {
     /// Returns the error from `WasiFs::new` if there's an error
     pub fn finalize(&mut self) -> Result<WasiEnv, WasiStateCreationError> {
         let state = self.build()?;
-        Ok(WasiEnv::new(state))
+        let mut env = WasiEnv::new(state);
+        if let Some(tracer) = self.tracer.clone() {
+            env.set_tracer(tracer);
+        }
+        Ok(env)
     }
 }
 
@@ -419,6 +710,9 @@ pub struct PreopenDirBuilder {
     read: bool,
     write: bool,
     create: bool,
+    overlay: Option<(Box<dyn FileSystem>, PathBuf)>,
+    #[cfg(feature = "archive")]
+    archive: Option<PathBuf>,
 }
 
 /// The built version of `PreopenDirBuilder`
@@ -429,6 +723,10 @@ pub(crate) struct PreopenedDir {
     pub(crate) read: bool,
     pub(crate) write: bool,
     pub(crate) create: bool,
+    /// The filesystem to mount at `path` instead of the filesystem backend
+    pub(crate) fs: Option<Box<dyn FileSystem>>,
+    /// The upper layer of an overlay of `path` in the filesystem backend
+    pub(crate) overlay: Option<(Box<dyn FileSystem>, PathBuf)>,
 }
 
 impl PreopenDirBuilder {
@@ -458,6 +756,72 @@ impl PreopenDirBuilder {
         self
     }
 
+    /// Serve the contents of a tar, tar.gz or zip archive on the host as a
+    /// read-only directory
+    ///
+    /// The root of the archive becomes the preopened directory, the archive
+    /// is indexed when the directory is built.  A directory given to
+    /// [`PreopenDirBuilder::directory`] is ignored.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use wasmer_wasi::{WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// WasiState::new("program_name")
+    ///    .preopen(|p| p.archive("bundle.tar.gz").alias("/app").read(true))?
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "archive")]
+    pub fn archive<FilePath>(&mut self, archive: FilePath) -> &mut Self
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.archive = Some(archive.as_ref().to_path_buf());
+
+        self
+    }
+
+    /// Make the directory a copy-on-write overlay
+    ///
+    /// The directory given to [`PreopenDirBuilder::directory`] (or the
+    /// archive given to `PreopenDirBuilder::archive`) is read from the
+    /// filesystem backend and never modified, all changes go to the directory
+    /// `upper_dir` of `upper_fs` instead.  See [`OverlayFileSystem`] for how
+    /// deletions are recorded.
+    ///
+    /// Usage:
+    ///
+    /// ```no_run
+    /// # use wasmer_wasi::{MemFileSystem, WasiState, WasiStateCreationError};
+    /// # fn main() -> Result<(), WasiStateCreationError> {
+    /// WasiState::new("program_name")
+    ///    .preopen(|p| {
+    ///        p.directory("lib")
+    ///            .alias("/lib")
+    ///            .read(true)
+    ///            .create(true)
+    ///            .overlay(Box::new(MemFileSystem::new()), "/")
+    ///    })?
+    ///    .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn overlay<FilePath>(
+        &mut self,
+        upper_fs: Box<dyn FileSystem>,
+        upper_dir: FilePath,
+    ) -> &mut Self
+    where
+        FilePath: AsRef<Path>,
+    {
+        self.overlay = Some((upper_fs, upper_dir.as_ref().to_path_buf()));
+
+        self
+    }
+
     /// Set read permissions affecting files in the directory
     pub fn read(&mut self, toggle: bool) -> &mut Self {
         self.read = toggle;
@@ -484,12 +848,31 @@ impl PreopenDirBuilder {
         self
     }
 
-    pub(crate) fn build(&self) -> Result<PreopenedDir, WasiStateCreationError> {
+    pub(crate) fn build(&mut self) -> Result<PreopenedDir, WasiStateCreationError> {
         // ensure at least one is set
         if !(self.read || self.write || self.create) {
             return Err(WasiStateCreationError::PreopenedDirectoryError("Preopened directories must have at least one of read, write, create permissions set".to_string()));
         }
 
+        // the read-only storage of the directory, if it isn't the filesystem backend
+        #[allow(unused_mut)]
+        let mut lower: Option<Box<dyn FileSystem>> = None;
+        #[cfg(feature = "archive")]
+        {
+            if let Some(archive) = &self.archive {
+                let archive_fs = ArchiveFileSystem::new(archive).map_err(|e| {
+                    WasiStateCreationError::PreopenedDirectoryError(format!(
+                        "could not read archive `{}`: {}",
+                        archive.display(),
+                        e
+                    ))
+                })?;
+                lower = Some(Box::new(archive_fs));
+                // the archive stands in for the directory, e.g. as the default alias
+                self.path = Some(archive.clone());
+            }
+        }
+
         if self.path.is_none() {
             return Err(WasiStateCreationError::PreopenedDirectoryError(
                 "Preopened directories must point to a host directory".to_string(),
@@ -497,19 +880,36 @@ impl PreopenDirBuilder {
         }
         let path = self.path.clone().unwrap();
 
-        if !path.exists() {
-            return Err(WasiStateCreationError::PreopenedDirectoryNotFound(path));
-        }
         if let Some(alias) = &self.alias {
             validate_mapped_dir_alias(alias)?;
         }
 
+        let mut overlay = self.overlay.take();
+        if let Some((upper_fs, upper_dir)) = &overlay {
+            if upper_fs.metadata(upper_dir).is_err() {
+                return Err(WasiStateCreationError::PreopenedDirectoryNotFound(
+                    upper_dir.clone(),
+                ));
+            }
+        }
+        // overlays of the filesystem backend are set up with the backend, in
+        // `WasiStateBuilder::build`
+        let fs = lower.map(|lower| match overlay.take() {
+            Some((upper_fs, upper_dir)) => {
+                Box::new(OverlayFileSystem::new(lower, "/", upper_fs, upper_dir))
+                    as Box<dyn FileSystem>
+            }
+            None => lower,
+        });
+
         Ok(PreopenedDir {
             path,
             alias: self.alias.clone(),
             read: self.read,
             write: self.write,
             create: self.create,
+            fs,
+            overlay,
         })
     }
 }
@@ -572,4 +972,111 @@ mod test {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn preopen_from_fs_backend() {
+        use crate::state::MemFileSystem;
+
+        let fs = MemFileSystem::new();
+        fs.create_dir_all("/data").unwrap();
+
+        let output = create_wasi_state("test_prog")
+            .fs_backend(Box::new(fs.clone()))
+            .map_dir("data", "/data")
+            .unwrap()
+            .build();
+        assert!(output.is_ok());
+
+        let output = create_wasi_state("test_prog")
+            .fs_backend(Box::new(fs))
+            .map_dir("missing", "/missing")
+            .unwrap()
+            .build();
+        match output {
+            Err(WasiStateCreationError::PreopenedDirectoryNotFound(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn preopen_overlay() {
+        use crate::state::MemFileSystem;
+
+        let upper = MemFileSystem::new();
+        let output = create_wasi_state("test_prog")
+            .preopen(|p| {
+                p.directory(".")
+                    .alias("work")
+                    .read(true)
+                    .overlay(Box::new(upper.clone()), "/")
+            })
+            .unwrap()
+            .build();
+        assert!(output.is_ok());
+
+        let mut builder = create_wasi_state("test_prog");
+        let output = builder.preopen(|p| {
+            p.directory(".")
+                .read(true)
+                .overlay(Box::new(upper.clone()), "/missing")
+        });
+        match output {
+            Err(WasiStateCreationError::PreopenedDirectoryNotFound(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn preopen_overlay_of_fs_backend() {
+        use crate::state::MemFileSystem;
+
+        let lower = MemFileSystem::new();
+        lower.create_dir_all("/data").unwrap();
+        lower.write_file("/data/file.txt", b"hello").unwrap();
+        let upper = MemFileSystem::new();
+        let state = create_wasi_state("test_prog")
+            .fs_backend(Box::new(lower.clone()))
+            .preopen(|p| {
+                p.directory("/data")
+                    .read(true)
+                    .overlay(Box::new(upper.clone()), "/")
+            })
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let metadata = state
+            .fs
+            .fs_backend
+            .metadata(Path::new("/.wasmer-mount-0/file.txt"))
+            .unwrap();
+        assert!(metadata.is_file());
+        assert!(upper.read_dir(Path::new("/")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn preopen_socket() {
+        use crate::syscalls::types::__WASI_FILETYPE_SOCKET_STREAM;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let state = create_wasi_state("test_prog")
+            .preopen_socket(5, listener)
+            .build()
+            .unwrap();
+        let fdstat = state.fs.fdstat(5).unwrap();
+        assert_eq!(fdstat.fs_filetype, __WASI_FILETYPE_SOCKET_STREAM);
+        assert_eq!(state.fs.next_fd.get(), 6);
+
+        let output = create_wasi_state("test_prog")
+            .preopen_socket(
+                __WASI_STDOUT_FILENO,
+                TcpListener::bind("127.0.0.1:0").unwrap(),
+            )
+            .build();
+        match output {
+            Err(WasiStateCreationError::PreopenedSocketError(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
 }
@@ -0,0 +1,192 @@
+//! The clocks seen by a WASI module.
+//!
+//! `clock_time_get`, `clock_res_get` and the clock subscriptions of
+//! `poll_oneoff` all go through the [`WasiClock`] of the [`WasiState`].
+//! [`HostClock`] reads the clocks of the host and is the default;
+//! [`VirtualClock`] only advances when it's read or when the module waits,
+//! which makes the module deterministic (see
+//! [`WasiStateBuilder::deterministic`]).
+//!
+//! [`WasiState`]: crate::WasiState
+//! [`WasiStateBuilder::deterministic`]: crate::WasiStateBuilder::deterministic
+
+use crate::syscalls::types::*;
+use crate::syscalls::{platform_clock_res_get, platform_clock_time_get};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How much the virtual clock advances each time it's read, unless
+/// configured otherwise.
+pub const DEFAULT_CLOCK_STEP: Duration = Duration::from_millis(1);
+
+/// A source of time for a WASI module.
+#[typetag::serde(tag = "type")]
+pub trait WasiClock: fmt::Debug + Send + Sync + 'static {
+    /// The resolution of the clock `clock_id`, in nanoseconds.
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+
+    /// Read the clock `clock_id`, in nanoseconds; `precision` is the
+    /// maximum error the reading may have.
+    fn time(
+        &mut self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+
+    /// Whether time only passes when the clock is read or advanced.
+    ///
+    /// `poll_oneoff` doesn't sleep on virtual clocks: it advances them
+    /// instead.
+    fn is_virtual(&self) -> bool {
+        false
+    }
+
+    /// Advance a virtual clock by `nanos`.
+    fn advance(&mut self, _nanos: __wasi_timestamp_t) {}
+
+    /// The timestamp to report for all files instead of the ones of the
+    /// host, if any.
+    fn file_time(&self) -> Option<__wasi_timestamp_t> {
+        None
+    }
+}
+
+/// The clocks of the host.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct HostClock;
+
+#[typetag::serde]
+impl WasiClock for HostClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let resolution = Cell::new(0);
+        match platform_clock_res_get(clock_id, &resolution) {
+            __WASI_ESUCCESS => Ok(resolution.get()),
+            errno => Err(errno),
+        }
+    }
+
+    fn time(
+        &mut self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let time = Cell::new(0);
+        match platform_clock_time_get(clock_id, precision, &time) {
+            __WASI_ESUCCESS => Ok(time.get()),
+            errno => Err(errno),
+        }
+    }
+}
+
+/// A clock which advances by a fixed step each time it's read.
+///
+/// All clocks share the same virtual time: the realtime clock starts at the
+/// start time and the other clocks start at 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualClock {
+    /// The time of `__WASI_CLOCK_REALTIME` when the module starts, in nanoseconds
+    start_time: __wasi_timestamp_t,
+    /// The time that passed on the virtual clock, in nanoseconds
+    elapsed: __wasi_timestamp_t,
+    /// How much the virtual clock advances each time it's read, in nanoseconds
+    step: __wasi_timestamp_t,
+}
+
+impl VirtualClock {
+    /// Create a virtual clock starting at `start_time`.
+    pub fn new(start_time: SystemTime) -> Self {
+        let start_time = start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as __wasi_timestamp_t;
+        Self {
+            start_time,
+            elapsed: 0,
+            step: DEFAULT_CLOCK_STEP.as_nanos() as __wasi_timestamp_t,
+        }
+    }
+
+    /// Set how much the clock advances each time it's read.
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step.as_nanos() as __wasi_timestamp_t;
+    }
+
+    /// The time of `__WASI_CLOCK_REALTIME` when the module started, in
+    /// nanoseconds.
+    pub fn start_time(&self) -> __wasi_timestamp_t {
+        self.start_time
+    }
+}
+
+#[typetag::serde]
+impl WasiClock for VirtualClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        match clock_id {
+            __WASI_CLOCK_REALTIME
+            | __WASI_CLOCK_MONOTONIC
+            | __WASI_CLOCK_PROCESS_CPUTIME_ID
+            | __WASI_CLOCK_THREAD_CPUTIME_ID => Ok(self.step.max(1)),
+            _ => Err(__WASI_EINVAL),
+        }
+    }
+
+    fn time(
+        &mut self,
+        clock_id: __wasi_clockid_t,
+        _precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let offset = match clock_id {
+            __WASI_CLOCK_REALTIME => self.start_time,
+            __WASI_CLOCK_MONOTONIC
+            | __WASI_CLOCK_PROCESS_CPUTIME_ID
+            | __WASI_CLOCK_THREAD_CPUTIME_ID => 0,
+            _ => return Err(__WASI_EINVAL),
+        };
+        let time = offset.saturating_add(self.elapsed);
+        self.advance(self.step);
+        Ok(time)
+    }
+
+    fn is_virtual(&self) -> bool {
+        true
+    }
+
+    fn advance(&mut self, nanos: __wasi_timestamp_t) {
+        self.elapsed = self.elapsed.saturating_add(nanos);
+    }
+
+    fn file_time(&self) -> Option<__wasi_timestamp_t> {
+        Some(self.start_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_clock() {
+        let mut clock = VirtualClock::new(UNIX_EPOCH + Duration::from_secs(1_000));
+        clock.set_step(Duration::from_nanos(10));
+
+        assert_eq!(clock.time(__WASI_CLOCK_REALTIME, 1), Ok(1_000_000_000_000));
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 1), Ok(10));
+        clock.advance(100);
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 1), Ok(120));
+        assert_eq!(clock.resolution(__WASI_CLOCK_MONOTONIC), Ok(10));
+        assert_eq!(clock.time(4, 1), Err(__WASI_EINVAL));
+        assert_eq!(clock.file_time(), Some(1_000_000_000_000));
+    }
+
+    #[test]
+    fn host_clock() {
+        let mut clock = HostClock;
+        let first = clock.time(__WASI_CLOCK_MONOTONIC, 1).unwrap();
+        let second = clock.time(__WASI_CLOCK_MONOTONIC, 1).unwrap();
+        assert!(first <= second);
+        assert!(!clock.is_virtual());
+        assert_eq!(clock.time(4, 1), Err(__WASI_EINVAL));
+    }
+}
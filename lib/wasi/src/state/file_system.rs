@@ -0,0 +1,1220 @@
+//! Storage backends for the WASI filesystem.
+//!
+//! [`WasiFs`] keeps track of the inodes and file descriptors that the WASI
+//! module sees, but every operation that touches the underlying storage goes
+//! through a [`FileSystem`].  [`HostFileSystem`] forwards everything to
+//! `std::fs` and is the default; [`MemFileSystem`] keeps all directories and
+//! files in memory and never touches the host.
+//!
+//! The paths given to a [`FileSystem`] are the paths of the preopened
+//! directories joined with the path the WASI module asked for.
+//!
+//! [`WasiFs`]: crate::WasiFs
+
+use crate::state::{HostFile, WasiFile, WasiFsError, MAX_SYMLINKS};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// The storage behind the preopened directories of a [`WasiFs`].
+///
+/// [`WasiFs`]: crate::WasiFs
+#[typetag::serde(tag = "type")]
+pub trait FileSystem: fmt::Debug + Send + Sync + 'static {
+    /// Get the metadata of the entity at `path`, following symlinks.
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError>;
+
+    /// Get the metadata of the entity at `path` without following symlinks.
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError>;
+
+    /// Read the value of the symlink at `path`.
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError>;
+
+    /// List the entries of the directory at `path`, `.` and `..` excluded.
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError>;
+
+    /// Create an empty directory at `path`; its parent must exist.
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Remove the empty directory at `path`.
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Remove the file at `path`.
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError>;
+
+    /// Move the file or directory at `from` to `to`.
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError>;
+
+    /// Open the file at `path`, creating it if `options` allow it.
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError>;
+}
+
+/// The type of an entity in a [`FileSystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileType {
+    Directory,
+    RegularFile,
+    Symlink,
+    CharacterDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+    Unknown,
+}
+
+impl FileType {
+    pub fn is_dir(self) -> bool {
+        self == FileType::Directory
+    }
+
+    pub fn is_file(self) -> bool {
+        self == FileType::RegularFile
+    }
+
+    pub fn is_symlink(self) -> bool {
+        self == FileType::Symlink
+    }
+
+    pub fn to_wasi_file_type(self) -> __wasi_filetype_t {
+        match self {
+            FileType::Directory => __WASI_FILETYPE_DIRECTORY,
+            FileType::RegularFile => __WASI_FILETYPE_REGULAR_FILE,
+            FileType::Symlink => __WASI_FILETYPE_SYMBOLIC_LINK,
+            FileType::CharacterDevice => __WASI_FILETYPE_CHARACTER_DEVICE,
+            FileType::BlockDevice => __WASI_FILETYPE_BLOCK_DEVICE,
+            // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
+            // a `__WASI_FILETYPE_SOCKET_DGRAM`?
+            FileType::Socket => __WASI_FILETYPE_SOCKET_STREAM,
+            // FIFO doesn't seem to fit any other type, so unknown
+            FileType::Fifo | FileType::Unknown => __WASI_FILETYPE_UNKNOWN,
+        }
+    }
+}
+
+impl From<std::fs::FileType> for FileType {
+    fn from(file_type: std::fs::FileType) -> Self {
+        if file_type.is_dir() {
+            return FileType::Directory;
+        } else if file_type.is_file() {
+            return FileType::RegularFile;
+        } else if file_type.is_symlink() {
+            return FileType::Symlink;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_char_device() {
+                return FileType::CharacterDevice;
+            } else if file_type.is_block_device() {
+                return FileType::BlockDevice;
+            } else if file_type.is_fifo() {
+                return FileType::Fifo;
+            } else if file_type.is_socket() {
+                return FileType::Socket;
+            }
+        }
+        FileType::Unknown
+    }
+}
+
+/// Metadata about an entity in a [`FileSystem`].
+///
+/// All timestamps are in nanoseconds as UNIX timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub file_type: FileType,
+    pub len: u64,
+    pub accessed: __wasi_timestamp_t,
+    pub modified: __wasi_timestamp_t,
+    pub created: __wasi_timestamp_t,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type.is_file()
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type.is_symlink()
+    }
+}
+
+impl From<std::fs::Metadata> for Metadata {
+    fn from(md: std::fs::Metadata) -> Self {
+        fn to_nanos(time: io::Result<SystemTime>) -> __wasi_timestamp_t {
+            time.ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|t| t.as_nanos() as u64)
+                .unwrap_or(0)
+        }
+
+        Self {
+            file_type: md.file_type().into(),
+            len: md.len(),
+            accessed: to_nanos(md.accessed()),
+            modified: to_nanos(md.modified()),
+            created: to_nanos(md.created()),
+        }
+    }
+}
+
+/// An entry of a directory returned by [`FileSystem::read_dir`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub file_type: FileType,
+}
+
+/// Options used to open a file with [`FileSystem::open`], mirrors
+/// `std::fs::OpenOptions`.
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    pub read: bool,
+    pub write: bool,
+    pub append: bool,
+    pub truncate: bool,
+    pub create: bool,
+    pub create_new: bool,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+}
+
+/// A [`FileSystem`] backed by the filesystem of the host.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HostFileSystem;
+
+#[typetag::serde]
+impl FileSystem for HostFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        Ok(path.metadata()?.into())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        Ok(path.symlink_metadata()?.into())
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        path.read_link().map_err(Into::into)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        std::fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(DirEntry {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    file_type: entry.file_type()?.into(),
+                })
+            })
+            .collect()
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        std::fs::create_dir(path).map_err(Into::into)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        std::fs::remove_dir(path).map_err(Into::into)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        std::fs::remove_file(path).map_err(Into::into)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        std::fs::rename(from, to).map_err(Into::into)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let file = std::fs::OpenOptions::new()
+            .read(options.read)
+            .write(options.write)
+            .append(options.append)
+            .truncate(options.truncate)
+            .create(options.create)
+            .create_new(options.create_new)
+            .open(path)?;
+
+        Ok(Box::new(HostFile::new(
+            file,
+            path.to_path_buf(),
+            options.read,
+            options.write,
+            options.append,
+        )))
+    }
+}
+
+fn now() -> __wasi_timestamp_t {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|t| t.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Times {
+    accessed: __wasi_timestamp_t,
+    modified: __wasi_timestamp_t,
+    created: __wasi_timestamp_t,
+}
+
+impl Times {
+    fn now() -> Self {
+        let now = now();
+        Self {
+            accessed: now,
+            modified: now,
+            created: now,
+        }
+    }
+}
+
+/// Serialization of the `Arc`s shared by a [`MemFileSystem`], its clones and
+/// its open files, so that they are still shared once deserialized.
+///
+/// Within [`shared::scope`], an `Arc` is serialized only the first time it
+/// is met; the next times only its id is, and deserializing the id gives a
+/// clone of the `Arc` deserialized first.  The scope must go through the
+/// value only once.  Out of a scope, every `Arc` is serialized on its own.
+pub(crate) mod shared {
+    use serde::de::{self, DeserializeOwned, Deserializer};
+    use serde::{Deserialize, Serialize, Serializer};
+    use std::any::Any;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    thread_local! {
+        /// The ids of the `Arc`s serialized so far, by address
+        static SERIALIZED: RefCell<Option<HashMap<usize, u64>>> = RefCell::new(None);
+        /// The `Arc`s deserialized so far, by id
+        static DESERIALIZED: RefCell<Option<HashMap<u64, Box<dyn Any>>>> = RefCell::new(None);
+    }
+
+    /// An `Arc` as serialized: its id, and its value the first time.
+    #[derive(Serialize, Deserialize)]
+    struct Shared<T> {
+        id: u64,
+        value: Option<T>,
+    }
+
+    /// Ends a scope, even if the serialization panicked.
+    struct ScopeGuard {
+        serialized: Option<HashMap<usize, u64>>,
+        deserialized: Option<HashMap<u64, Box<dyn Any>>>,
+    }
+
+    impl Drop for ScopeGuard {
+        fn drop(&mut self) {
+            SERIALIZED.with(|ids| ids.replace(self.serialized.take()));
+            DESERIALIZED.with(|arcs| arcs.replace(self.deserialized.take()));
+        }
+    }
+
+    /// Run `f`, which serializes or deserializes a whole value, keeping the
+    /// `Arc`s shared within that value.
+    pub(crate) fn scope<R>(f: impl FnOnce() -> R) -> R {
+        let _guard = ScopeGuard {
+            serialized: SERIALIZED.with(|ids| ids.replace(Some(HashMap::new()))),
+            deserialized: DESERIALIZED.with(|arcs| arcs.replace(Some(HashMap::new()))),
+        };
+        f()
+    }
+
+    pub(crate) fn serialize<T, S>(value: &Arc<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        let address = Arc::as_ptr(value) as *const () as usize;
+        let (id, first) = SERIALIZED.with(|ids| match &mut *ids.borrow_mut() {
+            Some(ids) => {
+                let next_id = ids.len() as u64 + 1;
+                let id = *ids.entry(address).or_insert(next_id);
+                (id, id == next_id)
+            }
+            None => (0, true),
+        });
+        let value = if first { Some(&**value) } else { None };
+        Shared { id, value }.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, T, D>(deserializer: D) -> Result<Arc<T>, D::Error>
+    where
+        T: DeserializeOwned + 'static,
+        D: Deserializer<'de>,
+    {
+        let Shared { id, value } = Shared::<T>::deserialize(deserializer)?;
+        match value {
+            Some(value) => {
+                let value = Arc::new(value);
+                DESERIALIZED.with(|arcs| {
+                    if let Some(arcs) = &mut *arcs.borrow_mut() {
+                        arcs.insert(id, Box::new(value.clone()));
+                    }
+                });
+                Ok(value)
+            }
+            None => DESERIALIZED
+                .with(|arcs| {
+                    arcs.borrow()
+                        .as_ref()
+                        .and_then(|arcs| arcs.get(&id))
+                        .and_then(|arc| arc.downcast_ref::<Arc<T>>())
+                        .cloned()
+                })
+                .ok_or_else(|| de::Error::custom(format!("unknown shared value {}", id))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MemFileData {
+    bytes: Vec<u8>,
+    times: Times,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum MemNode {
+    Dir {
+        times: Times,
+    },
+    File {
+        #[serde(with = "shared")]
+        data: Arc<Mutex<MemFileData>>,
+    },
+    Symlink {
+        target: PathBuf,
+        times: Times,
+    },
+}
+
+impl MemNode {
+    fn metadata(&self) -> Metadata {
+        let (file_type, len, times) = match self {
+            MemNode::Dir { times } => (FileType::Directory, 0, *times),
+            MemNode::File { data } => {
+                let data = data.lock().unwrap();
+                (FileType::RegularFile, data.bytes.len() as u64, data.times)
+            }
+            MemNode::Symlink { target, times } => {
+                (FileType::Symlink, target.as_os_str().len() as u64, *times)
+            }
+        };
+        Metadata {
+            file_type,
+            len,
+            accessed: times.accessed,
+            modified: times.modified,
+            created: times.created,
+        }
+    }
+}
+
+/// A [`FileSystem`] that lives entirely in memory.
+///
+/// It starts out with an empty root directory, `/`, which can be populated
+/// with [`MemFileSystem::create_dir_all`] and [`MemFileSystem::write_file`]
+/// before handing the filesystem to [`WasiStateBuilder::fs_backend`].
+/// Cloning a `MemFileSystem` is cheap and the clones share their contents,
+/// which allows the host to inspect the files after the WASI module ran.
+///
+/// Serializing a `MemFileSystem` (for example with [`WasiState::freeze`])
+/// stores a copy of all its contents; within a frozen state, the clones and
+/// the open files still share their contents once unfrozen.
+///
+/// [`WasiStateBuilder::fs_backend`]: crate::WasiStateBuilder::fs_backend
+/// [`WasiState::freeze`]: crate::WasiState::freeze
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemFileSystem {
+    #[serde(with = "shared")]
+    nodes: Arc<Mutex<BTreeMap<PathBuf, MemNode>>>,
+}
+
+impl Default for MemFileSystem {
+    fn default() -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            PathBuf::from("/"),
+            MemNode::Dir {
+                times: Times::now(),
+            },
+        );
+        Self {
+            nodes: Arc::new(Mutex::new(nodes)),
+        }
+    }
+}
+
+/// Make `path` absolute and remove all `.` and `..` components from it.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+        }
+    }
+    normalized
+}
+
+impl MemFileSystem {
+    /// Create an in-memory filesystem containing only the root directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn nodes(&self) -> MutexGuard<'_, BTreeMap<PathBuf, MemNode>> {
+        self.nodes.lock().unwrap()
+    }
+
+    /// Follow the symlinks at the end of `path` until something that is not
+    /// a symlink is found.
+    fn resolve(nodes: &BTreeMap<PathBuf, MemNode>, path: &Path) -> Result<PathBuf, WasiFsError> {
+        let mut path = normalize_path(path);
+        for _ in 0..MAX_SYMLINKS {
+            match nodes.get(&path) {
+                Some(MemNode::Symlink { target, .. }) => {
+                    let mut next = path.parent().unwrap_or_else(|| Path::new("/")).to_owned();
+                    next.push(target);
+                    path = normalize_path(&next);
+                }
+                Some(_) => return Ok(path),
+                None => return Err(WasiFsError::EntityNotFound),
+            }
+        }
+        Err(WasiFsError::InvalidInput)
+    }
+
+    /// Check that the parent of `path` exists and is a directory.
+    fn check_parent(nodes: &BTreeMap<PathBuf, MemNode>, path: &Path) -> Result<(), WasiFsError> {
+        let parent = path.parent().ok_or(WasiFsError::AlreadyExists)?;
+        match nodes.get(parent) {
+            Some(MemNode::Dir { .. }) => Ok(()),
+            Some(_) => Err(WasiFsError::BaseNotDirectory),
+            None => Err(WasiFsError::EntityNotFound),
+        }
+    }
+
+    fn touch_parent(nodes: &mut BTreeMap<PathBuf, MemNode>, path: &Path) {
+        if let Some(MemNode::Dir { times }) = path.parent().and_then(|p| nodes.get_mut(p)) {
+            times.modified = now();
+        }
+    }
+
+    fn insert(&self, path: &Path, node: MemNode) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        let mut nodes = self.nodes();
+        Self::check_parent(&nodes, &path)?;
+        if nodes.contains_key(&path) {
+            return Err(WasiFsError::AlreadyExists);
+        }
+        nodes.insert(path.clone(), node);
+        Self::touch_parent(&mut nodes, &path);
+        Ok(())
+    }
+
+    /// Create a directory and all of its missing parents.
+    pub fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<(), WasiFsError> {
+        let path = normalize_path(path.as_ref());
+        let mut nodes = self.nodes();
+        for ancestor in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            match nodes.get(ancestor) {
+                Some(MemNode::Dir { .. }) => (),
+                Some(_) => return Err(WasiFsError::BaseNotDirectory),
+                None => {
+                    nodes.insert(
+                        ancestor.to_path_buf(),
+                        MemNode::Dir {
+                            times: Times::now(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create or replace the file at `path` with `contents`.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P, contents: &[u8]) -> Result<(), WasiFsError> {
+        let mut file = self.open(
+            path.as_ref(),
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )?;
+        file.write_all(contents).map_err(Into::into)
+    }
+
+    /// Read the full contents of the file at `path`.
+    pub fn read_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, WasiFsError> {
+        let nodes = self.nodes();
+        let path = Self::resolve(&nodes, path.as_ref())?;
+        match &nodes[&path] {
+            MemNode::File { data } => Ok(data.lock().unwrap().bytes.clone()),
+            _ => Err(WasiFsError::NotAFile),
+        }
+    }
+
+    /// Create a symlink at `link` pointing to `target`.
+    ///
+    /// Relative targets are resolved from the directory containing `link`.
+    pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        target: P,
+        link: Q,
+    ) -> Result<(), WasiFsError> {
+        self.insert(
+            link.as_ref(),
+            MemNode::Symlink {
+                target: target.as_ref().to_path_buf(),
+                times: Times::now(),
+            },
+        )
+    }
+}
+
+#[typetag::serde]
+impl FileSystem for MemFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let nodes = self.nodes();
+        let path = Self::resolve(&nodes, path)?;
+        Ok(nodes[&path].metadata())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        self.nodes()
+            .get(&normalize_path(path))
+            .map(MemNode::metadata)
+            .ok_or(WasiFsError::EntityNotFound)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        match self.nodes().get(&normalize_path(path)) {
+            Some(MemNode::Symlink { target, .. }) => Ok(target.clone()),
+            Some(_) => Err(WasiFsError::InvalidInput),
+            None => Err(WasiFsError::EntityNotFound),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        let nodes = self.nodes();
+        let path = Self::resolve(&nodes, path)?;
+        if !nodes[&path].metadata().is_dir() {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+        Ok(nodes
+            .iter()
+            .filter(|(child, _)| child.parent() == Some(path.as_path()))
+            .map(|(child, node)| DirEntry {
+                // unwrap is safe because normalized paths with a parent have a file name
+                name: child.file_name().unwrap().to_string_lossy().to_string(),
+                file_type: node.metadata().file_type,
+            })
+            .collect())
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.insert(
+            path,
+            MemNode::Dir {
+                times: Times::now(),
+            },
+        )
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        let mut nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(MemNode::Dir { .. }) => (),
+            Some(_) => return Err(WasiFsError::BaseNotDirectory),
+            None => return Err(WasiFsError::EntityNotFound),
+        }
+        if path.parent().is_none() {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        if nodes
+            .keys()
+            .any(|child| child.parent() == Some(path.as_path()))
+        {
+            return Err(WasiFsError::DirectoryNotEmpty);
+        }
+        nodes.remove(&path);
+        Self::touch_parent(&mut nodes, &path);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        let mut nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(MemNode::Dir { .. }) => return Err(WasiFsError::NotAFile),
+            Some(_) => (),
+            None => return Err(WasiFsError::EntityNotFound),
+        }
+        nodes.remove(&path);
+        Self::touch_parent(&mut nodes, &path);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let from = normalize_path(from);
+        let to = normalize_path(to);
+        let mut nodes = self.nodes();
+        let source_is_dir = match nodes.get(&from) {
+            Some(node) => node.metadata().is_dir(),
+            None => return Err(WasiFsError::EntityNotFound),
+        };
+        if from == to {
+            return Ok(());
+        }
+        if from.parent().is_none() || to.starts_with(&from) {
+            return Err(WasiFsError::InvalidInput);
+        }
+        Self::check_parent(&nodes, &to)?;
+        if let Some(existing) = nodes.get(&to) {
+            match (source_is_dir, existing.metadata().is_dir()) {
+                (true, true) => {
+                    if nodes
+                        .keys()
+                        .any(|child| child.parent() == Some(to.as_path()))
+                    {
+                        return Err(WasiFsError::DirectoryNotEmpty);
+                    }
+                }
+                (true, false) => return Err(WasiFsError::BaseNotDirectory),
+                (false, true) => return Err(WasiFsError::NotAFile),
+                (false, false) => (),
+            }
+            nodes.remove(&to);
+        }
+
+        // move the entity along with everything below it
+        let moved = nodes
+            .keys()
+            .filter(|path| path.starts_with(&from))
+            .cloned()
+            .collect::<Vec<_>>();
+        for old_path in moved {
+            let node = nodes.remove(&old_path).unwrap();
+            // unwrap is safe because of the filter above
+            let new_path = to.join(old_path.strip_prefix(&from).unwrap());
+            nodes.insert(new_path, node);
+        }
+        Self::touch_parent(&mut nodes, &from);
+        Self::touch_parent(&mut nodes, &to);
+        Ok(())
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let mut nodes = self.nodes();
+        let path = match Self::resolve(&nodes, path) {
+            Ok(path) => {
+                if options.create_new {
+                    return Err(WasiFsError::AlreadyExists);
+                }
+                path
+            }
+            Err(WasiFsError::EntityNotFound) if options.create || options.create_new => {
+                let path = normalize_path(path);
+                Self::check_parent(&nodes, &path)?;
+                nodes.insert(
+                    path.clone(),
+                    MemNode::File {
+                        data: Arc::new(Mutex::new(MemFileData {
+                            bytes: vec![],
+                            times: Times::now(),
+                        })),
+                    },
+                );
+                Self::touch_parent(&mut nodes, &path);
+                path
+            }
+            Err(e) => return Err(e),
+        };
+        let data = match &nodes[&path] {
+            MemNode::File { data } => data.clone(),
+            _ => return Err(WasiFsError::NotAFile),
+        };
+        if options.truncate && options.write {
+            let mut data = data.lock().unwrap();
+            data.bytes.clear();
+            data.times.modified = now();
+        }
+
+        Ok(Box::new(MemFile {
+            fs: self.clone(),
+            path,
+            data,
+            cursor: 0,
+            read: options.read,
+            write: options.write || options.append,
+            append: options.append,
+        }))
+    }
+}
+
+/// The largest a file of a [`MemFileSystem`] may grow, so that a WASI module
+/// can't make the host allocate without bound.
+pub const MAX_MEM_FILE_SIZE: u64 = 1 << 32;
+
+/// A file opened from a [`MemFileSystem`].
+#[derive(Serialize, Deserialize)]
+pub struct MemFile {
+    fs: MemFileSystem,
+    path: PathBuf,
+    #[serde(with = "shared")]
+    data: Arc<Mutex<MemFileData>>,
+    cursor: u64,
+    read: bool,
+    write: bool,
+    append: bool,
+}
+
+impl fmt::Debug for MemFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemFile")
+            .field("path", &self.path)
+            .field("cursor", &self.cursor)
+            .field("read", &self.read)
+            .field("write", &self.write)
+            .field("append", &self.append)
+            .finish()
+    }
+}
+
+impl MemFile {
+    fn data(&self) -> MutexGuard<'_, MemFileData> {
+        self.data.lock().unwrap()
+    }
+}
+
+impl Read for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.read {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file was not opened for reading",
+            ));
+        }
+        let mut data = self.data.lock().unwrap();
+        let start = std::cmp::min(self.cursor as usize, data.bytes.len());
+        let amt = std::cmp::min(buf.len(), data.bytes.len() - start);
+        buf[..amt].copy_from_slice(&data.bytes[start..start + amt]);
+        data.times.accessed = now();
+        self.cursor += amt as u64;
+        Ok(amt)
+    }
+}
+
+impl Write for MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.write {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file was not opened for writing",
+            ));
+        }
+        let mut data = self.data.lock().unwrap();
+        if self.append {
+            self.cursor = data.bytes.len() as u64;
+        }
+        let end = self
+            .cursor
+            .checked_add(buf.len() as u64)
+            .filter(|end| *end <= MAX_MEM_FILE_SIZE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, WasiFsError::FileTooLarge))?;
+        let start = self.cursor as usize;
+        let end = end as usize;
+        if data.bytes.len() < end {
+            data.bytes.resize(end, 0);
+        }
+        data.bytes[start..end].copy_from_slice(buf);
+        data.times.modified = now();
+        self.cursor = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(offset) => {
+                self.cursor = offset;
+                return Ok(self.cursor);
+            }
+            io::SeekFrom::End(offset) => (self.data().bytes.len() as i64, offset),
+            io::SeekFrom::Current(offset) => (self.cursor as i64, offset),
+        };
+        let new_cursor = base + offset;
+        if new_cursor < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+        self.cursor = new_cursor as u64;
+        Ok(self.cursor)
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for MemFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.data().times.accessed
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.data().times.accessed = last_accessed;
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.data().times.modified
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.data().times.modified = last_modified;
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.data().times.created
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.data().times.created = created_time;
+    }
+
+    fn size(&self) -> u64 {
+        self.data().bytes.len() as u64
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        if new_size > MAX_MEM_FILE_SIZE {
+            return Err(WasiFsError::FileTooLarge);
+        }
+        let mut data = self.data();
+        data.bytes.resize(new_size as usize, 0);
+        data.times.modified = now();
+        Ok(())
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.fs.remove_file(&self.path)
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        self.fs.rename(&self.path, new_name)
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.data().bytes.len().saturating_sub(self.cursor as usize))
+    }
+}
+
+/// A [`FileSystem`] made of other filesystems mounted at given paths.
+///
+/// Paths under a mount point are forwarded to the mounted filesystem, with
+/// the mount point replaced by `/`; all other paths go to the default
+/// filesystem.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MountFileSystem {
+    default: Box<dyn FileSystem>,
+    mounts: Vec<(PathBuf, Box<dyn FileSystem>)>,
+}
+
+impl MountFileSystem {
+    /// Create a `MountFileSystem` without mount points.
+    pub fn new(default: Box<dyn FileSystem>) -> Self {
+        Self {
+            default,
+            mounts: vec![],
+        }
+    }
+
+    /// Mount `fs` at `mount_point`, shadowing anything previously found there.
+    pub fn mount<P: Into<PathBuf>>(&mut self, mount_point: P, fs: Box<dyn FileSystem>) {
+        self.mounts.push((mount_point.into(), fs));
+    }
+
+    /// Find the filesystem responsible for `path`.  Returns the index of the
+    /// mount (if any), the filesystem and the path inside that filesystem.
+    fn route(&self, path: &Path) -> (Option<usize>, &dyn FileSystem, PathBuf) {
+        for (i, (mount_point, fs)) in self.mounts.iter().enumerate().rev() {
+            if let Ok(rest) = path.strip_prefix(mount_point) {
+                return (Some(i), fs.as_ref(), Path::new("/").join(rest));
+            }
+        }
+        (None, self.default.as_ref(), path.to_path_buf())
+    }
+}
+
+#[typetag::serde]
+impl FileSystem for MountFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.metadata(&path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.symlink_metadata(&path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.read_link(&path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.read_dir(&path)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.create_dir(&path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.remove_dir(&path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.remove_file(&path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let (from_mount, fs, from) = self.route(from);
+        let (to_mount, _, to) = self.route(to);
+        if from_mount != to_mount {
+            return Err(WasiFsError::UnknownError(__WASI_EXDEV));
+        }
+        fs.rename(&from, &to)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let (_, fs, path) = self.route(path);
+        fs.open(&path, options)
+    }
+}
+
+/// A [`FileSystem`] that can be used in several places at once, e.g. as the
+/// filesystem backend and as the lower layer of an [`OverlayFileSystem`].
+///
+/// [`OverlayFileSystem`]: crate::OverlayFileSystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SharedFileSystem(pub(crate) Arc<dyn FileSystem>);
+
+#[typetag::serde]
+impl FileSystem for SharedFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        self.0.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        self.0.symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        self.0.read_link(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        self.0.read_dir(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.0.create_dir(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.0.remove_dir(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        self.0.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        self.0.rename(from, to)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        self.0.open(path, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mem_fs_files_and_directories() {
+        let fs = MemFileSystem::new();
+        fs.create_dir_all("/a/b").unwrap();
+        fs.write_file("/a/b/file.txt", b"hello").unwrap();
+
+        let metadata = fs.metadata(Path::new("/a/b/file.txt")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, 5);
+        assert!(fs.metadata(Path::new("/a/b")).unwrap().is_dir());
+        assert_eq!(
+            fs.read_dir(Path::new("/a")).unwrap(),
+            vec![DirEntry {
+                name: "b".to_string(),
+                file_type: FileType::Directory,
+            }]
+        );
+
+        // the parent must exist
+        assert_eq!(
+            fs.create_dir(Path::new("/x/y")),
+            Err(WasiFsError::EntityNotFound)
+        );
+        assert_eq!(
+            fs.remove_dir(Path::new("/a/b")),
+            Err(WasiFsError::DirectoryNotEmpty)
+        );
+
+        fs.rename(Path::new("/a/b"), Path::new("/c")).unwrap();
+        assert_eq!(fs.read_file("/c/file.txt").unwrap(), b"hello");
+        assert!(fs.metadata(Path::new("/a/b/file.txt")).is_err());
+
+        fs.remove_file(Path::new("/c/file.txt")).unwrap();
+        fs.remove_dir(Path::new("/c")).unwrap();
+        assert_eq!(fs.read_dir(Path::new("/a")).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn mem_fs_open_options() {
+        let fs = MemFileSystem::new();
+        assert!(fs
+            .open(Path::new("/new"), OpenOptions::new().read(true))
+            .is_err());
+
+        let mut file = fs
+            .open(
+                Path::new("/new"),
+                OpenOptions::new().read(true).write(true).create_new(true),
+            )
+            .unwrap();
+        file.write_all(b"hello world").unwrap();
+        file.seek(io::SeekFrom::Start(6)).unwrap();
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).unwrap();
+        assert_eq!(buffer, "world");
+
+        assert_eq!(
+            fs.open(Path::new("/new"), OpenOptions::new().create_new(true))
+                .unwrap_err(),
+            WasiFsError::AlreadyExists
+        );
+
+        let mut appender = fs
+            .open(Path::new("/new"), OpenOptions::new().append(true))
+            .unwrap();
+        appender.write_all(b"!").unwrap();
+        assert_eq!(file.size(), 12);
+
+        fs.open(
+            Path::new("/new"),
+            OpenOptions::new().write(true).truncate(true),
+        )
+        .unwrap();
+        assert_eq!(fs.read_file("/new").unwrap(), b"");
+    }
+
+    #[test]
+    fn mem_fs_file_size_limit() {
+        let fs = MemFileSystem::new();
+        let mut file = fs
+            .open(
+                Path::new("/big"),
+                OpenOptions::new().write(true).create_new(true),
+            )
+            .unwrap();
+
+        file.seek(io::SeekFrom::Start(u64::MAX)).unwrap();
+        let error = file.write(b"data").unwrap_err();
+        assert_eq!(WasiFsError::from(error), WasiFsError::FileTooLarge);
+        file.seek(io::SeekFrom::Start(MAX_MEM_FILE_SIZE)).unwrap();
+        let error = file.write(b"data").unwrap_err();
+        assert_eq!(WasiFsError::from(error), WasiFsError::FileTooLarge);
+        assert_eq!(
+            file.set_len(MAX_MEM_FILE_SIZE + 1),
+            Err(WasiFsError::FileTooLarge)
+        );
+        assert_eq!(file.size(), 0);
+    }
+
+    #[test]
+    fn mem_fs_symlinks() {
+        let fs = MemFileSystem::new();
+        fs.create_dir_all("/dir").unwrap();
+        fs.write_file("/dir/target", b"data").unwrap();
+        fs.symlink("target", "/dir/link").unwrap();
+
+        assert!(fs
+            .symlink_metadata(Path::new("/dir/link"))
+            .unwrap()
+            .is_symlink());
+        assert!(fs.metadata(Path::new("/dir/link")).unwrap().is_file());
+        assert_eq!(
+            fs.read_link(Path::new("/dir/link")).unwrap(),
+            PathBuf::from("target")
+        );
+        assert_eq!(fs.read_file("/dir/link").unwrap(), b"data");
+    }
+}
@@ -15,10 +15,28 @@
 
 #![allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 
+#[cfg(feature = "archive")]
+mod archive;
 mod builder;
+mod clock;
+mod file_system;
+mod overlay;
+mod policy;
+mod quota;
+mod random;
+mod socket;
 mod types;
 
+#[cfg(feature = "archive")]
+pub use self::archive::*;
 pub use self::builder::*;
+pub use self::clock::*;
+pub use self::file_system::*;
+pub use self::overlay::*;
+pub use self::policy::*;
+pub use self::quota::*;
+pub use self::random::*;
+pub use self::socket::*;
 pub use self::types::*;
 use crate::syscalls::types::*;
 use generational_arena::Arena;
@@ -28,10 +46,8 @@ use std::collections::HashMap;
 use std::{
     borrow::Borrow,
     cell::Cell,
-    fs,
     io::Write,
     path::{Path, PathBuf},
-    time::SystemTime,
 };
 use tracing::debug;
 
@@ -52,6 +68,12 @@ const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_FILESTAT_GET
     | __WASI_RIGHT_POLL_FD_READWRITE;
 const STDERR_DEFAULT_RIGHTS: __wasi_rights_t = STDOUT_DEFAULT_RIGHTS;
+const SOCKET_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ
+    | __WASI_RIGHT_FD_WRITE
+    | __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+    | __WASI_RIGHT_FD_FILESTAT_GET
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
 
 /// A completely aribtrary "big enough" number used as the upper limit for
 /// the number of symlinks that can be traversed when resolving a path
@@ -109,12 +131,19 @@ pub enum Kind {
         base_po_dir: __wasi_fd_t,
         /// The path to the symlink from the `base_po_dir`
         path_to_symlink: PathBuf,
-        /// the value of the symlink as a relative path
+        /// the value of the symlink, either relative to the directory holding it or
+        /// absolute in the virtual root
         relative_path: PathBuf,
     },
     Buffer {
         buffer: Vec<u8>,
     },
+    /// A socket granted by the host
+    Socket {
+        /// The socket, which is lost when the state is serialized
+        #[serde(skip)]
+        handle: Option<WasiSocket>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -163,6 +192,14 @@ pub struct WasiFs {
     inode_counter: Cell<u64>,
     /// for fds still open after the file has been deleted
     pub orphan_fds: HashMap<Inode, InodeVal>,
+    /// The storage behind the preopened directories
+    pub fs_backend: Box<dyn FileSystem>,
+    /// The limits on the resources the module may use
+    pub quotas: WasiQuotas,
+    /// What the module used of the resources limited by the quotas
+    usage: WasiUsage,
+    /// The operations the module may do on the paths of the preopened directories
+    pub policy: PathPolicy,
 }
 
 impl WasiFs {
@@ -176,14 +213,14 @@ impl WasiFs {
         preopened_dirs: &[PathBuf],
         mapped_dirs: &[(String, PathBuf)],
     ) -> Result<Self, String> {
-        let (mut wasi_fs, root_inode) = Self::new_init()?;
+        let (mut wasi_fs, root_inode) = Self::new_init(Box::new(HostFileSystem))?;
 
         debug!("wasi::fs::preopen_dirs");
         for dir in preopened_dirs {
             debug!("Attempting to preopen {}", &dir.to_string_lossy());
             // TODO: think about this
             let default_rights = ALL_RIGHTS;
-            let cur_dir_metadata = dir.metadata().map_err(|e| {
+            let cur_dir_metadata = wasi_fs.fs_backend.metadata(dir).map_err(|e| {
                 format!(
                     "Could not get metadata for file {:?}: {}",
                     dir,
@@ -236,7 +273,7 @@ impl WasiFs {
             debug!("Attempting to open {:?} at {}", real_dir, alias);
             // TODO: think about this
             let default_rights = ALL_RIGHTS;
-            let cur_dir_metadata = real_dir.metadata().map_err(|e| {
+            let cur_dir_metadata = wasi_fs.fs_backend.metadata(real_dir).map_err(|e| {
                 format!(
                     "Could not get metadata for file {:?}: {}",
                     &real_dir,
@@ -290,8 +327,11 @@ impl WasiFs {
     }
 
     /// Created for the builder API. like `new` but with more information
-    pub(crate) fn new_with_preopen(preopens: &[PreopenedDir]) -> Result<Self, String> {
-        let (mut wasi_fs, root_inode) = Self::new_init()?;
+    pub(crate) fn new_with_preopen(
+        preopens: &[PreopenedDir],
+        fs_backend: Box<dyn FileSystem>,
+    ) -> Result<Self, String> {
+        let (mut wasi_fs, root_inode) = Self::new_init(fs_backend)?;
 
         for PreopenedDir {
             path,
@@ -299,6 +339,7 @@ impl WasiFs {
             read,
             write,
             create,
+            ..
         } in preopens
         {
             debug!(
@@ -306,7 +347,7 @@ impl WasiFs {
                 &path.to_string_lossy(),
                 &alias
             );
-            let cur_dir_metadata = path.metadata().map_err(|e| {
+            let cur_dir_metadata = wasi_fs.fs_backend.metadata(path).map_err(|e| {
                 format!(
                     "Could not get metadata for file {:?}: {}",
                     path,
@@ -364,7 +405,8 @@ impl WasiFs {
                         | __WASI_RIGHT_PATH_CREATE_FILE
                         | __WASI_RIGHT_PATH_LINK_TARGET
                         | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET;
+                        | __WASI_RIGHT_PATH_RENAME_TARGET
+                        | __WASI_RIGHT_PATH_SYMLINK;
                 }
 
                 rights
@@ -417,7 +459,7 @@ impl WasiFs {
 
     /// Private helper function to init the filesystem, called in `new` and
     /// `new_with_preopen`
-    fn new_init() -> Result<(Self, Inode), String> {
+    fn new_init(fs_backend: Box<dyn FileSystem>) -> Result<(Self, Inode), String> {
         debug!("Initializing WASI filesystem");
         let inodes = Arena::new();
         let mut wasi_fs = Self {
@@ -428,6 +470,10 @@ impl WasiFs {
             next_fd: Cell::new(3),
             inode_counter: Cell::new(1024),
             orphan_fds: HashMap::new(),
+            fs_backend,
+            quotas: WasiQuotas::default(),
+            usage: WasiUsage::default(),
+            policy: PathPolicy::default(),
         };
         wasi_fs.create_stdin();
         wasi_fs.create_stdout();
@@ -733,7 +779,7 @@ impl WasiFs {
         follow_symlinks: bool,
     ) -> Result<Inode, __wasi_errno_t> {
         if symlink_count > MAX_SYMLINKS {
-            return Err(__WASI_EMLINK);
+            return Err(__WASI_ELOOP);
         }
 
         let base_dir = self.get_fd(base)?;
@@ -742,221 +788,253 @@ impl WasiFs {
         let mut cur_inode = base_dir.inode;
         let n_components = path.components().count();
         // TODO: rights checks
-        'path_iter: for (i, component) in path.components().enumerate() {
-            // used to terminate symlink resolution properly
+        for (i, component) in path.components().enumerate() {
+            // symlinks are always followed, except for the last one when
+            // `follow_symlinks` is unset
             let last_component = i + 1 == n_components;
             // for each component traverse file structure
             // loading inodes as necessary
-            'symlink_resolution: while symlink_count < MAX_SYMLINKS {
-                match &mut self.inodes[cur_inode].kind {
-                    Kind::Buffer { .. } => unimplemented!("state::get_inode_at_path for buffers"),
-                    Kind::Dir {
-                        ref mut entries,
-                        ref path,
-                        ref parent,
-                        ..
-                    } => {
-                        match component.as_os_str().to_string_lossy().borrow() {
-                            ".." => {
-                                if let Some(p) = parent {
-                                    cur_inode = *p;
-                                    continue 'path_iter;
-                                } else {
-                                    return Err(__WASI_EACCES);
-                                }
-                            }
-                            "." => continue 'path_iter,
-                            _ => (),
-                        }
-                        // used for full resolution of symlinks
-                        let mut loop_for_symlink = false;
-                        if let Some(entry) =
-                            entries.get(component.as_os_str().to_string_lossy().as_ref())
-                        {
-                            cur_inode = *entry;
-                        } else {
-                            let file = {
-                                let mut cd = path.clone();
-                                cd.push(component);
-                                cd
-                            };
-                            let metadata = file.symlink_metadata().ok().ok_or(__WASI_EINVAL)?;
-                            let file_type = metadata.file_type();
-                            // we want to insert newly opened dirs and files, but not transient symlinks
-                            // TODO: explain why (think about this deeply when well rested)
-                            let mut should_insert = false;
-
-                            let kind = if file_type.is_dir() {
-                                should_insert = true;
-                                // load DIR
-                                Kind::Dir {
-                                    parent: Some(cur_inode),
-                                    path: file.clone(),
-                                    entries: Default::default(),
-                                }
-                            } else if file_type.is_file() {
-                                should_insert = true;
-                                // load file
-                                Kind::File {
-                                    handle: None,
-                                    path: file.clone(),
-                                    fd: None,
-                                }
-                            } else if file_type.is_symlink() {
-                                let link_value = file.read_link().ok().ok_or(__WASI_EIO)?;
-                                debug!("attempting to decompose path {:?}", link_value);
-
-                                let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(&file)?
-                                } else {
-                                    unimplemented!("Absolute symlinks are not yet supported");
-                                };
-                                loop_for_symlink = true;
-                                symlink_count += 1;
-                                Kind::Symlink {
-                                    base_po_dir: pre_open_dir_fd,
-                                    path_to_symlink: relative_path,
-                                    relative_path: link_value,
-                                }
+            match &mut self.inodes[cur_inode].kind {
+                Kind::Dir {
+                    ref mut entries,
+                    ref path,
+                    ref parent,
+                    ..
+                } => {
+                    match component.as_os_str().to_string_lossy().borrow() {
+                        ".." => {
+                            if let Some(p) = parent {
+                                cur_inode = *p;
+                                continue;
                             } else {
-                                #[cfg(unix)]
-                                {
-                                    use std::os::unix::fs::FileTypeExt;
-                                    let file_type: __wasi_filetype_t = if file_type.is_char_device()
-                                    {
-                                        __WASI_FILETYPE_CHARACTER_DEVICE
-                                    } else if file_type.is_block_device() {
-                                        __WASI_FILETYPE_BLOCK_DEVICE
-                                    } else if file_type.is_fifo() {
-                                        // FIFO doesn't seem to fit any other type, so unknown
-                                        __WASI_FILETYPE_UNKNOWN
-                                    } else if file_type.is_socket() {
-                                        // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
-                                        // a `__WASI_FILETYPE_SOCKET_DGRAM`?
-                                        __WASI_FILETYPE_SOCKET_STREAM
-                                    } else {
-                                        unimplemented!("state::get_inode_at_path unknown file type: not file, directory, symlink, char device, block device, fifo, or socket");
-                                    };
-
-                                    let kind = Kind::File {
-                                        handle: None,
-                                        path: file.clone(),
-                                        fd: None,
-                                    };
-                                    let new_inode = self.create_inode_with_stat(
-                                        kind,
-                                        false,
-                                        file.to_string_lossy().to_string(),
-                                        __wasi_filestat_t {
-                                            st_filetype: file_type,
-                                            ..__wasi_filestat_t::default()
-                                        },
-                                    );
-                                    if let Kind::Dir {
-                                        ref mut entries, ..
-                                    } = &mut self.inodes[cur_inode].kind
-                                    {
-                                        entries.insert(
-                                            component.as_os_str().to_string_lossy().to_string(),
-                                            new_inode,
-                                        );
-                                    } else {
-                                        unreachable!(
-                                            "Attempted to insert special device into non-directory"
-                                        );
-                                    }
-                                    // perhaps just continue with symlink resolution and return at the end
-                                    return Ok(new_inode);
-                                }
-                                #[cfg(not(unix))]
-                                unimplemented!("state::get_inode_at_path unknown file type: not file, directory, or symlink");
-                            };
-
-                            let new_inode =
-                                self.create_inode(kind, false, file.to_string_lossy().to_string())?;
-                            if should_insert {
-                                if let Kind::Dir {
-                                    ref mut entries, ..
-                                } = &mut self.inodes[cur_inode].kind
-                                {
-                                    entries.insert(
-                                        component.as_os_str().to_string_lossy().to_string(),
-                                        new_inode,
-                                    );
-                                }
-                            }
-                            cur_inode = new_inode;
-
-                            if loop_for_symlink && follow_symlinks {
-                                debug!("Following symlink to {:?}", cur_inode);
-                                continue 'symlink_resolution;
+                                return Err(__WASI_EACCES);
                             }
                         }
+                        "." => continue,
+                        _ => (),
                     }
-                    Kind::Root { entries } => {
-                        match component.as_os_str().to_string_lossy().borrow() {
-                            // the root's parent is the root
-                            ".." => continue 'path_iter,
-                            // the root's current directory is the root
-                            "." => continue 'path_iter,
-                            _ => (),
-                        }
-
-                        if let Some(entry) =
-                            entries.get(component.as_os_str().to_string_lossy().as_ref())
-                        {
-                            cur_inode = *entry;
+                    if let Some(entry) =
+                        entries.get(component.as_os_str().to_string_lossy().as_ref())
+                    {
+                        cur_inode = *entry;
+                    } else {
+                        let file = {
+                            let mut cd = path.clone();
+                            cd.push(component);
+                            cd
+                        };
+                        let metadata = self
+                            .fs_backend
+                            .symlink_metadata(&file)
+                            .ok()
+                            .ok_or(__WASI_EINVAL)?;
+                        let file_type = metadata.file_type;
+
+                        let new_inode = if file_type.is_dir() {
+                            // load DIR
+                            let kind = Kind::Dir {
+                                parent: Some(cur_inode),
+                                path: file.clone(),
+                                entries: Default::default(),
+                            };
+                            self.create_inode(kind, false, file.to_string_lossy().to_string())?
+                        } else if file_type.is_file() {
+                            // load file
+                            let kind = Kind::File {
+                                handle: None,
+                                path: file.clone(),
+                                fd: None,
+                            };
+                            self.create_inode(kind, false, file.to_string_lossy().to_string())?
+                        } else if file_type.is_symlink() {
+                            let link_value =
+                                self.fs_backend.read_link(&file).ok().ok_or(__WASI_EIO)?;
+                            debug!("attempting to decompose path {:?}", link_value);
+                            let kind = self.host_symlink_kind(&file, link_value)?;
+                            self.create_inode(kind, false, file.to_string_lossy().to_string())?
                         } else {
-                            return Err(__WASI_EINVAL);
+                            // special files such as devices, fifos, and sockets
+                            let kind = Kind::File {
+                                handle: None,
+                                path: file.clone(),
+                                fd: None,
+                            };
+                            self.create_inode_with_stat(
+                                kind,
+                                false,
+                                file.to_string_lossy().to_string(),
+                                __wasi_filestat_t {
+                                    st_filetype: file_type.to_wasi_file_type(),
+                                    ..__wasi_filestat_t::default()
+                                },
+                            )
+                        };
+                        if let Kind::Dir {
+                            ref mut entries, ..
+                        } = &mut self.inodes[cur_inode].kind
+                        {
+                            entries.insert(
+                                component.as_os_str().to_string_lossy().to_string(),
+                                new_inode,
+                            );
                         }
+                        cur_inode = new_inode;
                     }
-                    Kind::File { .. } => {
-                        return Err(__WASI_ENOTDIR);
+                }
+                Kind::Root { entries } => {
+                    match component.as_os_str().to_string_lossy().borrow() {
+                        // the root's parent is the root
+                        ".." => continue,
+                        // the root's current directory is the root
+                        "." => continue,
+                        _ => (),
                     }
-                    Kind::Symlink {
-                        base_po_dir,
-                        path_to_symlink,
-                        relative_path,
-                    } => {
-                        let new_base_dir = *base_po_dir;
-                        // allocate to reborrow mutabily to recur
-                        let new_path = {
-                            /*if let Kind::Root { .. } = self.inodes[base_po_dir].kind {
-                                assert!(false, "symlinks should never be relative to the root");
-                            }*/
-                            let mut base = path_to_symlink.clone();
-                            // remove the symlink file itself from the path, leaving just the path from the base
-                            // to the dir containing the symlink
-                            base.pop();
-                            base.push(relative_path);
-                            base.to_string_lossy().to_string()
-                        };
-                        debug!("Following symlink recursively");
-                        let symlink_inode = self.get_inode_at_path_inner(
-                            new_base_dir,
-                            &new_path,
-                            symlink_count + 1,
-                            follow_symlinks,
-                        )?;
-                        cur_inode = symlink_inode;
-                        // if we're at the very end and we found a file, then we're done
-                        // TODO: figure out if this should also happen for directories?
-                        if let Kind::File { .. } = &self.inodes[cur_inode].kind {
-                            // check if on last step
-                            if last_component {
-                                break 'symlink_resolution;
-                            }
-                        }
-                        continue 'symlink_resolution;
+
+                    if let Some(entry) =
+                        entries.get(component.as_os_str().to_string_lossy().as_ref())
+                    {
+                        cur_inode = *entry;
+                    } else {
+                        return Err(__WASI_EINVAL);
                     }
                 }
-                break 'symlink_resolution;
+                // symlinks are resolved as soon as they're found, so the
+                // current inode is never one of them
+                Kind::File { .. }
+                | Kind::Socket { .. }
+                | Kind::Buffer { .. }
+                | Kind::Symlink { .. } => {
+                    return Err(__WASI_ENOTDIR);
+                }
+            }
+
+            if let Kind::Symlink { .. } = self.inodes[cur_inode].kind {
+                if !last_component || follow_symlinks {
+                    debug!("Following symlink {:?}", cur_inode);
+                    symlink_count += 1;
+                    cur_inode = self.resolve_symlink(cur_inode, symlink_count)?;
+                }
             }
         }
 
         Ok(cur_inode)
     }
 
+    /// Resolves the symlink at `inode` to the inode it points to, following
+    /// any symlinks along the way.
+    ///
+    /// The target is looked up through the virtual file system, so it can't
+    /// leave the preopened directories: relative targets are resolved from
+    /// the directory holding the symlink, absolute ones from the virtual root.
+    fn resolve_symlink(
+        &mut self,
+        inode: Inode,
+        symlink_count: u32,
+    ) -> Result<Inode, __wasi_errno_t> {
+        let (base, target) = match &self.inodes[inode].kind {
+            Kind::Symlink {
+                base_po_dir,
+                path_to_symlink,
+                relative_path,
+            } => {
+                if relative_path.is_absolute() {
+                    let target = relative_path.strip_prefix("/").unwrap_or(relative_path);
+                    (VIRTUAL_ROOT_FD, target.to_string_lossy().to_string())
+                } else {
+                    let mut target = path_to_symlink.clone();
+                    // remove the symlink file itself from the path, leaving just the path from the base
+                    // to the dir containing the symlink
+                    target.pop();
+                    target.push(relative_path);
+                    (*base_po_dir, target.to_string_lossy().to_string())
+                }
+            }
+            _ => return Ok(inode),
+        };
+        self.get_inode_at_path_inner(base, &target, symlink_count, true)
+    }
+
+    /// Builds the inode kind for the symlink at `path` on the host, which
+    /// points to `link_value`.
+    ///
+    /// Absolute symlinks are only allowed to point inside of the preopened
+    /// directories; they are rewritten to be absolute in the virtual root.
+    fn host_symlink_kind(&self, path: &Path, link_value: PathBuf) -> Result<Kind, __wasi_errno_t> {
+        let (pre_open_dir_fd, relative_path) = self.path_into_pre_open_and_relative_path(path)?;
+        let link_value = if link_value.is_absolute() {
+            let (target_po_fd, target_rest) = self
+                .path_into_pre_open_and_relative_path(&link_value)
+                .map_err(|_| __WASI_ENOTCAPABLE)?;
+            let target_po_inode = self.fd_map[&target_po_fd].inode;
+            let mut virtual_path = PathBuf::from("/");
+            virtual_path.push(&self.inodes[target_po_inode].name);
+            virtual_path.push(target_rest);
+            virtual_path
+        } else {
+            link_value
+        };
+        Ok(Kind::Symlink {
+            base_po_dir: pre_open_dir_fd,
+            path_to_symlink: relative_path,
+            relative_path: link_value,
+        })
+    }
+
+    /// Returns the path on the host of the symlink at `inode`, if it was
+    /// loaded from the host rather than created by the module.
+    pub(crate) fn host_symlink_path(&self, inode: Inode) -> Option<PathBuf> {
+        if let Kind::Symlink {
+            base_po_dir,
+            path_to_symlink,
+            ..
+        } = &self.inodes[inode].kind
+        {
+            let base_po_inode = self.fd_map.get(base_po_dir)?.inode;
+            if let Kind::Dir { path, .. } = &self.inodes[base_po_inode].kind {
+                let host_path = path.join(path_to_symlink);
+                let metadata = self.fs_backend.symlink_metadata(&host_path).ok()?;
+                if metadata.file_type.is_symlink() {
+                    return Some(host_path);
+                }
+            }
+        }
+        None
+    }
+
+    /// Updates the host paths of `inode`, and of everything loaded below it
+    /// if it's a directory, after it was moved to `new_path`.
+    pub(crate) fn update_moved_paths(&mut self, inode: Inode, new_path: PathBuf) {
+        let mut to_update = vec![(inode, new_path)];
+        while let Some((inode, new_path)) = to_update.pop() {
+            let symlink_location = match &self.inodes[inode].kind {
+                Kind::Symlink { .. } => self.path_into_pre_open_and_relative_path(&new_path).ok(),
+                _ => None,
+            };
+            match &mut self.inodes[inode].kind {
+                Kind::Dir { path, entries, .. } => {
+                    for (name, child) in entries.iter() {
+                        to_update.push((*child, new_path.join(name)));
+                    }
+                    *path = new_path;
+                }
+                // special files aren't found through their path
+                Kind::File { fd: Some(_), .. } => (),
+                Kind::File { path, .. } => *path = new_path,
+                Kind::Symlink {
+                    base_po_dir,
+                    path_to_symlink,
+                    ..
+                } => {
+                    if let Some((po_fd, relative_path)) = symlink_location {
+                        *base_po_dir = po_fd;
+                        *path_to_symlink = relative_path;
+                    }
+                }
+                Kind::Buffer { .. } | Kind::Socket { .. } | Kind::Root { .. } => (),
+            }
+        }
+    }
+
     /// Splits a path into the first preopened directory that is a parent of it,
     /// if such a preopened directory exists, and the rest of the path.
     ///
@@ -974,7 +1052,8 @@ impl WasiFs {
             let po_inode = self.fd_map[po_fd].inode;
             let po_path = match &self.inodes[po_inode].kind {
                 Kind::Dir { path, .. } => &**path,
-                Kind::Root { .. } => Path::new("/"),
+                // the virtual root isn't backed by anything on the host
+                Kind::Root { .. } => continue,
                 _ => unreachable!("Preopened FD that's not a directory or the root"),
             };
             // stem path based on it
@@ -1020,39 +1099,11 @@ impl WasiFs {
         Ok(out)
     }
 
-    /// finds the number of directories between the fd and the inode if they're connected
-    /// expects inode to point to a directory
-    pub(crate) fn path_depth_from_fd(
-        &self,
-        fd: __wasi_fd_t,
-        inode: Inode,
-    ) -> Result<usize, __wasi_errno_t> {
-        let mut counter = 0;
-        let base_fd = self.get_fd(fd)?;
-        let base_inode = base_fd.inode;
-        let mut cur_inode = inode;
-
-        while cur_inode != base_inode {
-            counter += 1;
-            match &self.inodes[cur_inode].kind {
-                Kind::Dir { parent, .. } => {
-                    if let Some(p) = parent {
-                        cur_inode = *p;
-                    }
-                }
-                _ => return Err(__WASI_EINVAL),
-            }
-        }
-
-        Ok(counter)
-    }
-
     /// gets a host file from a base directory and a path
     /// this function ensures the fs remains sandboxed
-    // NOTE: follow symlinks is super weird right now
-    // even if it's false, it still follows symlinks, just not the last
-    // symlink so
-    // This will be resolved when we have tests asserting the correct behavior
+    ///
+    /// Symlinks in intermediate components are always resolved; the final
+    /// component is only resolved if `follow_symlinks` is set.
     pub(crate) fn get_inode_at_path(
         &mut self,
         base: __wasi_fd_t,
@@ -1064,11 +1115,12 @@ impl WasiFs {
 
     /// Returns the parent Dir or Root that the file at a given path is in and the file name
     /// stripped off
+    ///
+    /// Symlinks on the way to the parent are always followed, the file itself is left alone.
     pub(crate) fn get_parent_inode_at_path(
         &mut self,
         base: __wasi_fd_t,
         path: &Path,
-        follow_symlinks: bool,
     ) -> Result<(Inode, String), __wasi_errno_t> {
         let mut parent_dir = std::path::PathBuf::new();
         let mut components = path.components().rev();
@@ -1081,8 +1133,11 @@ impl WasiFs {
         for comp in components.rev() {
             parent_dir.push(comp);
         }
-        self.get_inode_at_path(base, &parent_dir.to_string_lossy(), follow_symlinks)
-            .map(|v| (v, new_entity_name))
+        let parent_inode = self.get_inode_at_path(base, &parent_dir.to_string_lossy(), true)?;
+        match &self.inodes[parent_inode].kind {
+            Kind::Dir { .. } | Kind::Root { .. } => Ok((parent_inode, new_entity_name)),
+            _ => Err(__WASI_ENOTDIR),
+        }
     }
 
     pub fn get_fd(&self, fd: __wasi_fd_t) -> Result<&Fd, __wasi_errno_t> {
@@ -1151,6 +1206,7 @@ impl WasiFs {
                 Kind::File { .. } => __WASI_FILETYPE_REGULAR_FILE,
                 Kind::Dir { .. } => __WASI_FILETYPE_DIRECTORY,
                 Kind::Symlink { .. } => __WASI_FILETYPE_SYMBOLIC_LINK,
+                Kind::Socket { .. } => self.inodes[fd.inode].stat.st_filetype,
                 _ => __WASI_FILETYPE_UNKNOWN,
             },
             fs_flags: fd.flags,
@@ -1212,7 +1268,7 @@ impl WasiFs {
                     }
                     // TODO: verify this behavior
                     Kind::Dir { .. } => return Err(__WASI_EISDIR),
-                    Kind::Symlink { .. } => unimplemented!("WasiFs::flush Kind::Symlink"),
+                    Kind::Symlink { .. } => return Err(__WASI_EBADF),
                     Kind::Buffer { .. } => (),
                     _ => return Err(__WASI_EIO),
                 }
@@ -1261,6 +1317,50 @@ impl WasiFs {
         })
     }
 
+    /// Give the WASI module access to `socket` as the file descriptor
+    /// `raw_fd`, or as the next free file descriptor if it's `None`.
+    pub(crate) fn create_socket_fd(
+        &mut self,
+        socket: WasiSocket,
+        flags: __wasi_fdflags_t,
+        raw_fd: Option<__wasi_fd_t>,
+    ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        if let Some(raw_fd) = raw_fd {
+            if self.fd_map.contains_key(&raw_fd) {
+                return Err(__WASI_EEXIST);
+            }
+        }
+        let stat = __wasi_filestat_t {
+            st_filetype: socket.file_type(),
+            ..__wasi_filestat_t::default()
+        };
+        let kind = Kind::Socket {
+            handle: Some(socket),
+        };
+        let inode = self.create_inode_with_stat(kind, false, "socket".to_string(), stat);
+        let open_flags = Fd::READ | Fd::WRITE;
+        match raw_fd {
+            Some(raw_fd) => {
+                self.fd_map.insert(
+                    raw_fd,
+                    Fd {
+                        rights: SOCKET_DEFAULT_RIGHTS,
+                        rights_inheriting: 0,
+                        flags,
+                        offset: 0,
+                        open_flags,
+                        inode,
+                    },
+                );
+                if raw_fd >= self.next_fd.get() {
+                    self.next_fd.set(raw_fd + 1);
+                }
+                Ok(raw_fd)
+            }
+            None => self.create_fd(SOCKET_DEFAULT_RIGHTS, 0, flags, open_flags, inode),
+        }
+    }
+
     pub fn create_fd(
         &mut self,
         rights: __wasi_rights_t,
@@ -1269,6 +1369,7 @@ impl WasiFs {
         open_flags: u16,
         inode: Inode,
     ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        self.check_open_fd()?;
         let idx = self.next_fd.get();
         self.next_fd.set(idx + 1);
         self.fd_map.insert(
@@ -1282,6 +1383,7 @@ impl WasiFs {
                 inode,
             },
         );
+        self.record_open_fd();
         Ok(idx)
     }
 
@@ -1395,9 +1497,18 @@ impl WasiFs {
                         ..__wasi_filestat_t::default()
                     })
                 }
-                None => path.metadata().ok()?,
+                None => self.fs_backend.metadata(path).ok()?,
             },
-            Kind::Dir { path, .. } => path.metadata().ok()?,
+            Kind::Socket { handle } => {
+                return Some(__wasi_filestat_t {
+                    st_filetype: handle
+                        .as_ref()
+                        .map(WasiSocket::file_type)
+                        .unwrap_or(__WASI_FILETYPE_UNKNOWN),
+                    ..__wasi_filestat_t::default()
+                })
+            }
+            Kind::Dir { path, .. } => self.fs_backend.metadata(path).ok()?,
             Kind::Symlink {
                 base_po_dir,
                 path_to_symlink,
@@ -1407,7 +1518,7 @@ impl WasiFs {
                 let base_po_inode_v = &self.inodes[*base_po_inode];
                 match &base_po_inode_v.kind {
                     Kind::Root { .. } => {
-                        path_to_symlink.clone().symlink_metadata().ok()?
+                        self.fs_backend.symlink_metadata(path_to_symlink).ok()?
                     }
                     Kind::Dir { path, .. } => {
                         let mut real_path = path.clone();
@@ -1418,7 +1529,7 @@ impl WasiFs {
                         // TODO: adjust size of symlink, too
                         //      for all paths adjusted think about this
                         real_path.push(path_to_symlink);
-                        real_path.symlink_metadata().ok()?
+                        self.fs_backend.symlink_metadata(&real_path).ok()?
                     }
                     // if this triggers, there's a bug in the symlink code
                     _ => unreachable!("Symlink pointing to something that's not a directory as its base preopened directory"),
@@ -1427,26 +1538,11 @@ impl WasiFs {
             _ => return None,
         };
         Some(__wasi_filestat_t {
-            st_filetype: host_file_type_to_wasi_file_type(md.file_type()),
-            st_size: md.len(),
-            st_atim: md
-                .accessed()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_mtim: md
-                .modified()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_ctim: md
-                .created()
-                .ok()
-                .and_then(|ct| ct.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|ct| ct.as_nanos() as u64)
-                .unwrap_or(0),
+            st_filetype: md.file_type.to_wasi_file_type(),
+            st_size: md.len,
+            st_atim: md.accessed,
+            st_mtim: md.modified,
+            st_ctim: md.created,
             ..__wasi_filestat_t::default()
         })
     }
@@ -1499,6 +1595,9 @@ impl WasiFs {
                     return Err(__WASI_EINVAL);
                 }
             }
+            Kind::Socket { handle } => {
+                handle.take();
+            }
             Kind::Root { .. } => return Err(__WASI_EACCES),
             Kind::Symlink { .. } | Kind::Buffer { .. } => return Err(__WASI_EINVAL),
         }
@@ -1540,6 +1639,10 @@ pub struct WasiState {
     pub fs: WasiFs,
     pub args: Vec<Vec<u8>>,
     pub envs: Vec<Vec<u8>>,
+    /// Where the module gets the time from
+    pub clock: Box<dyn WasiClock>,
+    /// Where the module gets random numbers from
+    pub random: Box<dyn WasiRandom>,
 }
 
 impl WasiState {
@@ -1552,24 +1655,15 @@ impl WasiState {
 
     /// Turn the WasiState into bytes
     pub fn freeze(&self) -> Option<Vec<u8>> {
-        bincode::serialize(self).ok()
+        // `bincode::serialize` would go through the state twice, the first
+        // time to compute its size, which the shared values can't tell apart
+        let mut bytes = Vec::new();
+        file_system::shared::scope(|| bincode::serialize_into(&mut bytes, self)).ok()?;
+        Some(bytes)
     }
 
     /// Get a WasiState from bytes
     pub fn unfreeze(bytes: &[u8]) -> Option<Self> {
-        bincode::deserialize(bytes).ok()
-    }
-}
-
-pub fn host_file_type_to_wasi_file_type(file_type: fs::FileType) -> __wasi_filetype_t {
-    // TODO: handle other file types
-    if file_type.is_dir() {
-        __WASI_FILETYPE_DIRECTORY
-    } else if file_type.is_file() {
-        __WASI_FILETYPE_REGULAR_FILE
-    } else if file_type.is_symlink() {
-        __WASI_FILETYPE_SYMBOLIC_LINK
-    } else {
-        __WASI_FILETYPE_UNKNOWN
+        file_system::shared::scope(|| bincode::deserialize(bytes).ok())
     }
 }
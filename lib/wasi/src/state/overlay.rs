@@ -0,0 +1,708 @@
+//! Copy-on-write overlay of two [`FileSystem`]s.
+//!
+//! An [`OverlayFileSystem`] combines a read-only lower directory with a
+//! writable upper directory.  Reads see the union of both, with the upper
+//! layer taking precedence; all modifications are made to the upper layer.
+//! Files of the lower layer are copied up the first time they're written to.
+//!
+//! Deleting something that exists in the lower layer leaves a whiteout in the
+//! upper layer: an empty file named `.wh.<name>` next to where the deleted
+//! entity would be.  A directory that is recreated after being deleted is
+//! marked as opaque with a `.wh..wh..opq` file so the contents of the lower
+//! layer don't show through.  These are the conventions used by OCI image
+//! layers, so an upper directory on the host can be exported as such.
+
+use crate::state::file_system::normalize_path;
+use crate::state::{
+    DirEntry, FileSystem, FileType, Metadata, OpenOptions, WasiFile, WasiFsError, MAX_SYMLINKS,
+};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The prefix of the name of a whiteout file.
+const WHITEOUT_PREFIX: &str = ".wh.";
+/// The name of the file marking a directory of the upper layer as opaque.
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OverlayLayers {
+    lower: Box<dyn FileSystem>,
+    lower_root: PathBuf,
+    upper: Box<dyn FileSystem>,
+    upper_root: PathBuf,
+}
+
+/// A [`FileSystem`] showing the directory `lower_root` of `lower` with all
+/// modifications redirected to the directory `upper_root` of `upper`.
+///
+/// The lower layer is never modified.  Paths given to an
+/// `OverlayFileSystem` are relative to the root of the overlay, `/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayFileSystem {
+    layers: Arc<OverlayLayers>,
+}
+
+fn whiteout_name(name: &str) -> String {
+    format!("{}{}", WHITEOUT_PREFIX, name)
+}
+
+fn is_reserved_name(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with(WHITEOUT_PREFIX))
+        .unwrap_or(false)
+}
+
+impl OverlayFileSystem {
+    /// Create an overlay of the directory `lower_root` of `lower` and the
+    /// directory `upper_root` of `upper`.
+    pub fn new<P, Q>(
+        lower: Box<dyn FileSystem>,
+        lower_root: P,
+        upper: Box<dyn FileSystem>,
+        upper_root: Q,
+    ) -> Self
+    where
+        P: Into<PathBuf>,
+        Q: Into<PathBuf>,
+    {
+        Self {
+            layers: Arc::new(OverlayLayers {
+                lower,
+                lower_root: lower_root.into(),
+                upper,
+                upper_root: upper_root.into(),
+            }),
+        }
+    }
+
+    fn lower(&self) -> &dyn FileSystem {
+        self.layers.lower.as_ref()
+    }
+
+    fn upper(&self) -> &dyn FileSystem {
+        self.layers.upper.as_ref()
+    }
+
+    fn lower_path(&self, path: &Path) -> PathBuf {
+        // unwrap is safe because normalized paths start with `/`
+        let relative = path.strip_prefix("/").unwrap();
+        if relative.as_os_str().is_empty() {
+            self.layers.lower_root.clone()
+        } else {
+            self.layers.lower_root.join(relative)
+        }
+    }
+
+    fn upper_path(&self, path: &Path) -> PathBuf {
+        // unwrap is safe because normalized paths start with `/`
+        let relative = path.strip_prefix("/").unwrap();
+        if relative.as_os_str().is_empty() {
+            self.layers.upper_root.clone()
+        } else {
+            self.layers.upper_root.join(relative)
+        }
+    }
+
+    fn upper_metadata(&self, path: &Path) -> Option<Metadata> {
+        self.upper().symlink_metadata(&self.upper_path(path)).ok()
+    }
+
+    fn lower_metadata(&self, path: &Path) -> Option<Metadata> {
+        if self.lower_visible(path) {
+            self.lower().symlink_metadata(&self.lower_path(path)).ok()
+        } else {
+            None
+        }
+    }
+
+    fn whiteout_path(path: &Path) -> Option<PathBuf> {
+        let name = path.file_name()?.to_string_lossy();
+        Some(path.parent()?.join(whiteout_name(&name)))
+    }
+
+    /// Whether the lower layer at `path` is not hidden by a whiteout or an
+    /// opaque directory of the upper layer.
+    fn lower_visible(&self, path: &Path) -> bool {
+        let mut current = PathBuf::from("/");
+        for component in path.components().skip(1) {
+            if self.upper_metadata(&current.join(OPAQUE_MARKER)).is_some() {
+                return false;
+            }
+            let name = component.as_os_str().to_string_lossy();
+            if self
+                .upper_metadata(&current.join(whiteout_name(&name)))
+                .is_some()
+            {
+                return false;
+            }
+            current.push(component);
+        }
+        true
+    }
+
+    /// Make sure all the directories up to and including `dir` exist in the
+    /// upper layer.
+    fn copy_up_dirs(&self, dir: &Path) -> Result<(), WasiFsError> {
+        let mut current = PathBuf::from("/");
+        for component in dir.components().skip(1) {
+            current.push(component);
+            match self.upper_metadata(&current) {
+                Some(metadata) if metadata.is_dir() => (),
+                Some(_) => return Err(WasiFsError::BaseNotDirectory),
+                None => self.upper().create_dir(&self.upper_path(&current))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the file at `path` from the lower layer to the upper layer, if it
+    /// isn't in the upper layer yet.
+    fn copy_up_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        if self.upper_metadata(path).is_some() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            self.copy_up_dirs(parent)?;
+        }
+        let mut source = self
+            .lower()
+            .open(&self.lower_path(path), OpenOptions::new().read(true))?;
+        let mut target = self.upper().open(
+            &self.upper_path(path),
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )?;
+        io::copy(&mut source, &mut target)?;
+        target.set_last_modified(source.last_modified());
+        Ok(())
+    }
+
+    /// Hide the entity at `path` of the lower layer.
+    fn create_whiteout(&self, path: &Path) -> Result<(), WasiFsError> {
+        if let Some(parent) = path.parent() {
+            self.copy_up_dirs(parent)?;
+        }
+        let whiteout = Self::whiteout_path(path).ok_or(WasiFsError::InvalidInput)?;
+        self.upper().open(
+            &self.upper_path(&whiteout),
+            OpenOptions::new().write(true).create(true),
+        )?;
+        Ok(())
+    }
+
+    /// Remove the whiteout hiding `path`, returns whether there was one.
+    fn remove_whiteout(&self, path: &Path) -> Result<bool, WasiFsError> {
+        let whiteout = Self::whiteout_path(path).ok_or(WasiFsError::InvalidInput)?;
+        if self.upper_metadata(&whiteout).is_some() {
+            self.upper().remove_file(&self.upper_path(&whiteout))?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn mark_opaque(&self, dir: &Path) -> Result<(), WasiFsError> {
+        self.upper().open(
+            &self.upper_path(&dir.join(OPAQUE_MARKER)),
+            OpenOptions::new().write(true).create(true),
+        )?;
+        Ok(())
+    }
+
+    /// Check that the parent of `path` is a directory of the overlay.
+    fn check_parent(&self, path: &Path) -> Result<(), WasiFsError> {
+        let parent = path.parent().ok_or(WasiFsError::AlreadyExists)?;
+        if self.metadata(parent)?.is_dir() {
+            Ok(())
+        } else {
+            Err(WasiFsError::BaseNotDirectory)
+        }
+    }
+
+    /// Copy the merged contents of `from` to `to` in the upper layer.
+    fn copy_tree(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let metadata = self.symlink_metadata(from)?;
+        match metadata.file_type {
+            FileType::Directory => {
+                self.upper().create_dir(&self.upper_path(to))?;
+                for entry in self.read_dir(from)? {
+                    self.copy_tree(&from.join(&entry.name), &to.join(&entry.name))?;
+                }
+            }
+            FileType::RegularFile => {
+                let mut source = self.open(from, OpenOptions::new().read(true))?;
+                let mut target = self.upper().open(
+                    &self.upper_path(to),
+                    OpenOptions::new().write(true).create_new(true),
+                )?;
+                io::copy(&mut source, &mut target)?;
+            }
+            // `FileSystem` has no way to create the other kinds of entities
+            _ => return Err(WasiFsError::InvalidInput),
+        }
+        Ok(())
+    }
+
+    /// Remove `path` and everything below it.
+    fn remove_tree(&self, path: &Path) -> Result<(), WasiFsError> {
+        if self.symlink_metadata(path)?.is_dir() {
+            for entry in self.read_dir(path)? {
+                self.remove_tree(&path.join(&entry.name))?;
+            }
+            self.remove_dir(path)
+        } else {
+            self.remove_file(path)
+        }
+    }
+}
+
+#[typetag::serde]
+impl FileSystem for OverlayFileSystem {
+    fn metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let mut path = normalize_path(path);
+        for _ in 0..MAX_SYMLINKS {
+            let metadata = self.symlink_metadata(&path)?;
+            if !metadata.is_symlink() {
+                return Ok(metadata);
+            }
+            let target = self.read_link(&path)?;
+            let mut next = path.parent().unwrap_or_else(|| Path::new("/")).to_owned();
+            next.push(target);
+            path = normalize_path(&next);
+        }
+        Err(WasiFsError::InvalidInput)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, WasiFsError> {
+        let path = normalize_path(path);
+        if is_reserved_name(&path) {
+            return Err(WasiFsError::EntityNotFound);
+        }
+        self.upper_metadata(&path)
+            .or_else(|| self.lower_metadata(&path))
+            .ok_or(WasiFsError::EntityNotFound)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, WasiFsError> {
+        let path = normalize_path(path);
+        if is_reserved_name(&path) {
+            return Err(WasiFsError::EntityNotFound);
+        }
+        if self.upper_metadata(&path).is_some() {
+            self.upper().read_link(&self.upper_path(&path))
+        } else if self.lower_metadata(&path).is_some() {
+            self.lower().read_link(&self.lower_path(&path))
+        } else {
+            Err(WasiFsError::EntityNotFound)
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>, WasiFsError> {
+        let path = normalize_path(path);
+        if !self.metadata(&path)?.is_dir() {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+
+        let mut entries = BTreeMap::new();
+        let mut whiteouts = HashSet::new();
+        let mut opaque = false;
+        if let Ok(upper_entries) = self.upper().read_dir(&self.upper_path(&path)) {
+            for entry in upper_entries {
+                if entry.name == OPAQUE_MARKER {
+                    opaque = true;
+                } else if entry.name.starts_with(WHITEOUT_PREFIX) {
+                    whiteouts.insert(entry.name[WHITEOUT_PREFIX.len()..].to_string());
+                } else {
+                    entries.insert(entry.name, entry.file_type);
+                }
+            }
+        }
+        if !opaque && self.lower_visible(&path) {
+            if let Ok(lower_entries) = self.lower().read_dir(&self.lower_path(&path)) {
+                for entry in lower_entries {
+                    if !whiteouts.contains(&entry.name) && !entries.contains_key(&entry.name) {
+                        entries.insert(entry.name, entry.file_type);
+                    }
+                }
+            }
+        }
+
+        Ok(entries
+            .into_iter()
+            .map(|(name, file_type)| DirEntry { name, file_type })
+            .collect())
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        if is_reserved_name(&path) {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        if self.symlink_metadata(&path).is_ok() {
+            return Err(WasiFsError::AlreadyExists);
+        }
+        self.check_parent(&path)?;
+        if let Some(parent) = path.parent() {
+            self.copy_up_dirs(parent)?;
+        }
+        let had_whiteout = self.remove_whiteout(&path)?;
+        self.upper().create_dir(&self.upper_path(&path))?;
+        if had_whiteout {
+            // the directory of the lower layer was deleted, don't resurrect its contents
+            self.mark_opaque(&path)?;
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        if !self.symlink_metadata(&path)?.is_dir() {
+            return Err(WasiFsError::BaseNotDirectory);
+        }
+        if path.parent().is_none() {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        if !self.read_dir(&path)?.is_empty() {
+            return Err(WasiFsError::DirectoryNotEmpty);
+        }
+        if self.upper_metadata(&path).is_some() {
+            let upper_path = self.upper_path(&path);
+            // only whiteouts and markers are left in the directory
+            for entry in self.upper().read_dir(&upper_path)? {
+                self.upper().remove_file(&upper_path.join(&entry.name))?;
+            }
+            self.upper().remove_dir(&upper_path)?;
+        }
+        if self.lower_metadata(&path).is_some() {
+            self.create_whiteout(&path)?;
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), WasiFsError> {
+        let path = normalize_path(path);
+        if self.symlink_metadata(&path)?.is_dir() {
+            return Err(WasiFsError::NotAFile);
+        }
+        if self.upper_metadata(&path).is_some() {
+            self.upper().remove_file(&self.upper_path(&path))?;
+        }
+        if self.lower_metadata(&path).is_some() {
+            self.create_whiteout(&path)?;
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), WasiFsError> {
+        let from = normalize_path(from);
+        let to = normalize_path(to);
+        if is_reserved_name(&to) {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        let source_is_dir = self.symlink_metadata(&from)?.is_dir();
+        if from == to {
+            return Ok(());
+        }
+        if from.parent().is_none() || to.starts_with(&from) {
+            return Err(WasiFsError::InvalidInput);
+        }
+        self.check_parent(&to)?;
+        if let Ok(existing) = self.symlink_metadata(&to) {
+            match (source_is_dir, existing.is_dir()) {
+                (true, true) => self.remove_dir(&to)?,
+                (true, false) => return Err(WasiFsError::BaseNotDirectory),
+                (false, true) => return Err(WasiFsError::NotAFile),
+                (false, false) => self.remove_file(&to)?,
+            }
+        }
+
+        if let Some(parent) = to.parent() {
+            self.copy_up_dirs(parent)?;
+        }
+        let had_whiteout = self.remove_whiteout(&to)?;
+        if self.lower_metadata(&from).is_none() {
+            // the entity only exists in the upper layer, it can simply be moved
+            self.upper()
+                .rename(&self.upper_path(&from), &self.upper_path(&to))?;
+        } else {
+            self.copy_tree(&from, &to)?;
+            self.remove_tree(&from)?;
+        }
+        if had_whiteout && source_is_dir {
+            self.mark_opaque(&to)?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> Result<Box<dyn WasiFile>, WasiFsError> {
+        let path = normalize_path(path);
+        if is_reserved_name(&path) {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        let modifies = options.write || options.append || options.truncate;
+
+        let upper = match self.metadata(&path) {
+            Ok(metadata) => {
+                if options.create_new {
+                    return Err(WasiFsError::AlreadyExists);
+                }
+                if !metadata.is_file() {
+                    return Err(WasiFsError::NotAFile);
+                }
+                if self.upper_metadata(&path).is_some() {
+                    true
+                } else if options.append || (options.truncate && options.write) {
+                    // the contents of the lower layer would be modified right away
+                    self.copy_up_file(&path)?;
+                    true
+                } else {
+                    false
+                }
+            }
+            Err(WasiFsError::EntityNotFound) if options.create || options.create_new => {
+                self.check_parent(&path)?;
+                if let Some(parent) = path.parent() {
+                    self.copy_up_dirs(parent)?;
+                }
+                self.remove_whiteout(&path)?;
+                true
+            }
+            Err(e) => return Err(e),
+        };
+
+        let inner = if upper {
+            self.upper().open(&self.upper_path(&path), options)?
+        } else {
+            self.lower()
+                .open(&self.lower_path(&path), OpenOptions::new().read(true))?
+        };
+        Ok(Box::new(OverlayFile {
+            fs: self.clone(),
+            path,
+            inner,
+            copied_up: upper || !modifies,
+            read: options.read,
+            append: options.append,
+        }))
+    }
+}
+
+/// A file opened from an [`OverlayFileSystem`].
+///
+/// Files of the lower layer opened for writing are only copied to the upper
+/// layer when they're first modified.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverlayFile {
+    fs: OverlayFileSystem,
+    path: PathBuf,
+    inner: Box<dyn WasiFile>,
+    /// Whether `inner` can be modified, either because it comes from the upper
+    /// layer or because the file was opened read-only.
+    copied_up: bool,
+    read: bool,
+    append: bool,
+}
+
+impl OverlayFile {
+    fn copy_up(&mut self) -> Result<(), WasiFsError> {
+        if self.copied_up {
+            return Ok(());
+        }
+        let position = self.inner.seek(io::SeekFrom::Current(0))?;
+        self.fs.copy_up_file(&self.path)?;
+        let mut inner = self.fs.upper().open(
+            &self.fs.upper_path(&self.path),
+            OpenOptions::new()
+                .read(self.read)
+                .write(true)
+                .append(self.append),
+        )?;
+        inner.seek(io::SeekFrom::Start(position))?;
+        self.inner = inner;
+        self.copied_up = true;
+        Ok(())
+    }
+}
+
+impl Read for OverlayFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Write for OverlayFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.copy_up()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for OverlayFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for OverlayFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.inner.last_accessed()
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.inner.set_last_accessed(last_accessed)
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.inner.last_modified()
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.inner.set_last_modified(last_modified)
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.inner.created_time()
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.inner.set_created_time(created_time)
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        self.copy_up()?;
+        self.inner.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.fs.remove_file(&self.path)
+    }
+
+    fn sync_to_disk(&self) -> Result<(), WasiFsError> {
+        self.inner.sync_to_disk()
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        self.fs.rename(&self.path, new_name)
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        self.inner.bytes_available()
+    }
+
+    fn get_raw_fd(&self) -> Option<i32> {
+        self.inner.get_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::MemFileSystem;
+
+    fn overlay() -> (MemFileSystem, MemFileSystem, OverlayFileSystem) {
+        let lower = MemFileSystem::new();
+        lower.create_dir_all("/lower/lib").unwrap();
+        lower.write_file("/lower/lib/a.py", b"lower a").unwrap();
+        lower.write_file("/lower/lib/b.py", b"lower b").unwrap();
+        let upper = MemFileSystem::new();
+        let overlay = OverlayFileSystem::new(
+            Box::new(lower.clone()),
+            "/lower",
+            Box::new(upper.clone()),
+            "/",
+        );
+        (lower, upper, overlay)
+    }
+
+    fn read(fs: &dyn FileSystem, path: &str) -> String {
+        let mut contents = String::new();
+        fs.open(Path::new(path), OpenOptions::new().read(true))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    fn names(fs: &dyn FileSystem, path: &str) -> Vec<String> {
+        fs.read_dir(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let (lower, upper, overlay) = overlay();
+        assert_eq!(read(&overlay, "/lib/a.py"), "lower a");
+
+        // opening for writing doesn't copy the file until it's modified
+        let mut file = overlay
+            .open(
+                Path::new("/lib/a.py"),
+                OpenOptions::new().read(true).write(true),
+            )
+            .unwrap();
+        assert!(upper.metadata(Path::new("/lib/a.py")).is_err());
+        file.seek(io::SeekFrom::Start(6)).unwrap();
+        file.write_all(b"A").unwrap();
+
+        assert_eq!(read(&overlay, "/lib/a.py"), "lower A");
+        assert_eq!(upper.read_file("/lib/a.py").unwrap(), b"lower A");
+        assert_eq!(lower.read_file("/lower/lib/a.py").unwrap(), b"lower a");
+
+        overlay
+            .open(
+                Path::new("/lib/new.py"),
+                OpenOptions::new().write(true).create_new(true),
+            )
+            .unwrap();
+        assert_eq!(names(&overlay, "/lib"), vec!["a.py", "b.py", "new.py"]);
+    }
+
+    #[test]
+    fn whiteouts() {
+        let (lower, upper, overlay) = overlay();
+        overlay.remove_file(Path::new("/lib/b.py")).unwrap();
+        assert_eq!(names(&overlay, "/lib"), vec!["a.py"]);
+        assert!(upper.metadata(Path::new("/lib/.wh.b.py")).is_ok());
+        assert!(lower.metadata(Path::new("/lower/lib/b.py")).is_ok());
+
+        // whiteouts can't be seen or created by the guest
+        assert!(overlay.metadata(Path::new("/lib/.wh.b.py")).is_err());
+        assert!(overlay.create_dir(Path::new("/lib/.wh.a.py")).is_err());
+
+        overlay.remove_file(Path::new("/lib/a.py")).unwrap();
+        overlay.remove_dir(Path::new("/lib")).unwrap();
+        assert_eq!(names(&overlay, "/"), Vec::<String>::new());
+
+        // a recreated directory doesn't show the contents of the lower layer
+        overlay.create_dir(Path::new("/lib")).unwrap();
+        assert_eq!(names(&overlay, "/lib"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rename_from_lower_layer() {
+        let (lower, _upper, overlay) = overlay();
+        overlay
+            .rename(Path::new("/lib"), Path::new("/moved"))
+            .unwrap();
+        assert_eq!(names(&overlay, "/"), vec!["moved"]);
+        assert_eq!(names(&overlay, "/moved"), vec!["a.py", "b.py"]);
+        assert_eq!(read(&overlay, "/moved/b.py"), "lower b");
+        assert!(lower.metadata(Path::new("/lower/lib/a.py")).is_ok());
+    }
+}
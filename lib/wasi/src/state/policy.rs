@@ -0,0 +1,363 @@
+//! Per-path permissions on top of the preopened directories.
+//!
+//! A [`PathPolicy`] is a list of rules allowing or denying operations on the
+//! paths matching a glob pattern, set with [`WasiStateBuilder::path_policy`].
+//! Paths are matched as the module sees them, starting at the virtual root:
+//! a file `data.txt` in the directory mapped to `assets` is `/assets/data.txt`.
+//! The last rule matching a path decides, and the default action of the
+//! policy applies when no rule matches.
+//!
+//! Denied operations fail with `__WASI_EACCES` and are logged as warnings.
+//!
+//! [`WasiStateBuilder::path_policy`]: crate::WasiStateBuilder::path_policy
+
+use crate::state::{Inode, Kind, WasiFs};
+use crate::syscalls::types::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+/// The rights which read the contents of a file or directory.
+const READ_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ | __WASI_RIGHT_FD_READDIR;
+/// The rights which change the contents of a file.
+const WRITE_RIGHTS: __wasi_rights_t =
+    __WASI_RIGHT_FD_WRITE | __WASI_RIGHT_FD_ALLOCATE | __WASI_RIGHT_FD_FILESTAT_SET_SIZE;
+
+/// An operation controlled by a [`PathPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathOperation {
+    /// Reading a file or listing a directory
+    Read,
+    /// Writing to or truncating a file
+    Write,
+    /// Creating a file, directory or link
+    Create,
+    /// Removing a file, directory or link
+    Unlink,
+    /// Renaming a file, directory or link, checked on both paths
+    Rename,
+}
+
+impl fmt::Display for PathOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Create => "create",
+            Self::Unlink => "unlink",
+            Self::Rename => "rename",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Whether a [`PolicyRule`] allows or denies operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// The operations are carried out
+    Allow,
+    /// The operations fail with `__WASI_EACCES`
+    Deny,
+}
+
+impl Default for PolicyAction {
+    fn default() -> Self {
+        Self::Allow
+    }
+}
+
+/// A rule of a [`PathPolicy`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Whether the operations are allowed or denied
+    pub action: PolicyAction,
+    /// The paths the rule applies to; `*` doesn't match `/` but `**` does,
+    /// and `dir/**` matches `dir` itself too
+    #[serde(with = "pattern_serde")]
+    pub path: glob::Pattern,
+    /// The operations the rule applies to; all of them when empty
+    #[serde(default)]
+    pub operations: Vec<PathOperation>,
+}
+
+impl PolicyRule {
+    /// Whether the rule applies to `op` on `path`.
+    fn matches(&self, path: &Path, op: PathOperation) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let matches_dir = || {
+            self.path
+                .as_str()
+                .strip_suffix("/**")
+                .and_then(|dir| glob::Pattern::new(dir).ok())
+                .map_or(false, |dir| dir.matches_path_with(path, options))
+        };
+        (self.operations.is_empty() || self.operations.contains(&op))
+            && (self.path.matches_path_with(path, options) || matches_dir())
+    }
+}
+
+/// Glob-based rules allowing or denying operations on the paths of the
+/// preopened directories.
+///
+/// ```
+/// # use wasmer_wasi::{PathOperation, PathPolicy};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut policy = PathPolicy::allow_all();
+/// policy
+///     .deny("/assets/**", &[PathOperation::Write, PathOperation::Unlink])?
+///     .deny("/secrets/**", &[])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PathPolicy {
+    /// What happens to the operations no rule applies to
+    #[serde(default)]
+    pub default: PolicyAction,
+    /// The rules, the last one matching a path wins
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl PathPolicy {
+    /// A policy allowing everything the rules don't deny.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// A policy denying everything the rules don't allow.
+    pub fn deny_all() -> Self {
+        Self {
+            default: PolicyAction::Deny,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Allow `operations` (all of them if empty) on the paths matching
+    /// `pattern`.
+    pub fn allow(
+        &mut self,
+        pattern: &str,
+        operations: &[PathOperation],
+    ) -> Result<&mut Self, glob::PatternError> {
+        self.push_rule(PolicyAction::Allow, pattern, operations)
+    }
+
+    /// Deny `operations` (all of them if empty) on the paths matching
+    /// `pattern`.
+    pub fn deny(
+        &mut self,
+        pattern: &str,
+        operations: &[PathOperation],
+    ) -> Result<&mut Self, glob::PatternError> {
+        self.push_rule(PolicyAction::Deny, pattern, operations)
+    }
+
+    fn push_rule(
+        &mut self,
+        action: PolicyAction,
+        pattern: &str,
+        operations: &[PathOperation],
+    ) -> Result<&mut Self, glob::PatternError> {
+        self.rules.push(PolicyRule {
+            action,
+            path: glob::Pattern::new(pattern)?,
+            operations: operations.to_vec(),
+        });
+        Ok(self)
+    }
+
+    /// Whether `op` is allowed on `path`, a path as the module sees it.
+    ///
+    /// Files the module can't name, like sockets, only get the default
+    /// action.
+    pub fn is_allowed(&self, path: Option<&Path>, op: PathOperation) -> bool {
+        let action = path
+            .and_then(|path| self.rules.iter().rev().find(|rule| rule.matches(path, op)))
+            .map_or(self.default, |rule| rule.action);
+        action == PolicyAction::Allow
+    }
+}
+
+mod pattern_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        pattern: &glob::Pattern,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        pattern.as_str().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<glob::Pattern, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        glob::Pattern::new(&pattern).map_err(de::Error::custom)
+    }
+}
+
+/// Append `path` to `base`, resolving `.` and `..` without going above `/`.
+fn push_normalized(base: &mut PathBuf, path: &Path) {
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => base.push(name),
+            Component::ParentDir => {
+                base.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => (),
+        }
+    }
+}
+
+impl WasiFs {
+    /// The path of `inode` as the module sees it, if it has one.
+    pub(crate) fn guest_path(&self, inode: Inode) -> Option<PathBuf> {
+        let inode_val = &self.inodes[inode];
+        match &inode_val.kind {
+            Kind::Root { .. } => Some(PathBuf::from("/")),
+            Kind::Dir {
+                parent: Some(parent),
+                ..
+            } => {
+                let mut path = self.guest_path(*parent)?;
+                push_normalized(&mut path, Path::new(&inode_val.name));
+                Some(path)
+            }
+            Kind::Dir { path, .. } | Kind::File { path, .. } => {
+                let (po_fd, rest) = self.path_into_pre_open_and_relative_path(path).ok()?;
+                let mut path = self.guest_path(self.fd_map[&po_fd].inode)?;
+                push_normalized(&mut path, &rest);
+                Some(path)
+            }
+            Kind::Symlink {
+                base_po_dir,
+                path_to_symlink,
+                ..
+            } => {
+                let mut path = self.guest_path(self.fd_map.get(base_po_dir)?.inode)?;
+                push_normalized(&mut path, path_to_symlink);
+                Some(path)
+            }
+            Kind::Buffer { .. } | Kind::Socket { .. } => None,
+        }
+    }
+
+    /// The path as the module sees it of the entry `name` of the directory
+    /// `parent`, whether it exists or not.
+    pub(crate) fn guest_entry_path(&self, parent: Inode, name: &str) -> Option<PathBuf> {
+        let mut path = self.guest_path(parent)?;
+        push_normalized(&mut path, Path::new(name));
+        Some(path)
+    }
+
+    /// Fail with `__WASI_EACCES` if the path policy denies `op` on `path`.
+    pub(crate) fn check_policy(
+        &self,
+        path: Option<&Path>,
+        op: PathOperation,
+    ) -> Result<(), __wasi_errno_t> {
+        if self.policy.is_allowed(path, op) {
+            Ok(())
+        } else {
+            let path = path.map_or("<unnamed>".into(), Path::to_string_lossy);
+            warn!("wasi: {} of `{}` denied by the path policy", op, path);
+            Err(__WASI_EACCES)
+        }
+    }
+
+    /// Remove from `rights` what the path policy denies on `path`, failing
+    /// with `__WASI_EACCES` if the module asked for it in `requested`.
+    pub(crate) fn restrict_rights(
+        &self,
+        path: Option<&Path>,
+        requested: __wasi_rights_t,
+        mut rights: __wasi_rights_t,
+    ) -> Result<__wasi_rights_t, __wasi_errno_t> {
+        for &(op, op_rights) in &[
+            (PathOperation::Read, READ_RIGHTS),
+            (PathOperation::Write, WRITE_RIGHTS),
+        ] {
+            if requested & op_rights != 0 {
+                self.check_policy(path, op)?;
+            } else if !self.policy.is_allowed(path, op) {
+                rights &= !op_rights;
+            }
+        }
+        Ok(rights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let mut policy = PathPolicy::allow_all();
+        policy
+            .deny("/data/**", &[PathOperation::Write, PathOperation::Unlink])
+            .unwrap()
+            .allow("/data/tmp/*", &[])
+            .unwrap();
+        let allowed = |path: &str, op| policy.is_allowed(Some(Path::new(path)), op);
+
+        assert!(allowed("/data/a.txt", PathOperation::Read));
+        assert!(!allowed("/data/a.txt", PathOperation::Write));
+        assert!(!allowed("/data/sub/a.txt", PathOperation::Unlink));
+        assert!(allowed("/data/tmp/a.txt", PathOperation::Write));
+        // `*` doesn't cross directories
+        assert!(!allowed("/data/tmp/sub/a.txt", PathOperation::Write));
+        assert!(allowed("/other/a.txt", PathOperation::Write));
+        // `/**` also matches the directory itself
+        assert!(!allowed("/data", PathOperation::Unlink));
+        assert!(allowed("/database", PathOperation::Unlink));
+
+        let policy = PathPolicy::deny_all();
+        assert!(!policy.is_allowed(None, PathOperation::Read));
+    }
+
+    #[test]
+    fn restrict_rights() {
+        let mut fs = crate::WasiState::new("policy").build().unwrap().fs;
+        fs.policy.deny("/ro/**", &[PathOperation::Write]).unwrap();
+        let rights = READ_RIGHTS | WRITE_RIGHTS | __WASI_RIGHT_FD_SEEK;
+        let path = Some(Path::new("/ro/a.txt"));
+
+        assert_eq!(
+            fs.restrict_rights(path, __WASI_RIGHT_FD_READ, rights),
+            Ok(READ_RIGHTS | __WASI_RIGHT_FD_SEEK)
+        );
+        assert_eq!(
+            fs.restrict_rights(path, __WASI_RIGHT_FD_WRITE, rights),
+            Err(__WASI_EACCES)
+        );
+        assert_eq!(
+            fs.restrict_rights(Some(Path::new("/rw/a.txt")), __WASI_RIGHT_FD_WRITE, rights),
+            Ok(rights)
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let policy: PathPolicy = serde_json::from_str(
+            r#"{
+                "default": "deny",
+                "rules": [{ "action": "allow", "path": "/data/**", "operations": ["read"] }]
+            }"#,
+        )
+        .unwrap();
+        assert!(policy.is_allowed(Some(Path::new("/data/a")), PathOperation::Read));
+        assert!(!policy.is_allowed(Some(Path::new("/data/a")), PathOperation::Write));
+
+        let invalid = r#"{ "rules": [{ "action": "deny", "path": "[" }] }"#;
+        assert!(serde_json::from_str::<PathPolicy>(invalid).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Limits on the resources a WASI module may use.
+//!
+//! The limits are set with [`WasiStateBuilder::quotas`] and checked by the
+//! syscalls: going over them fails with `__WASI_ENFILE` for file
+//! descriptors and with `__WASI_EDQUOT` for everything else.  What the
+//! module used so far can be read with [`WasiFs::usage`], e.g. after it
+//! ran.
+//!
+//! [`WasiStateBuilder::quotas`]: crate::WasiStateBuilder::quotas
+
+use crate::state::{Kind, WasiFs};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+
+/// Limits on the resources a WASI module may use; `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasiQuotas {
+    /// The maximum number of open file descriptors, counting stdin, stdout,
+    /// stderr and the preopened directories
+    pub max_open_fds: Option<u32>,
+    /// The maximum number of bytes written to files in total; writes to
+    /// stdout, stderr and sockets aren't counted
+    pub max_bytes_written: Option<u64>,
+    /// The maximum size of a file, when writing to it or resizing it
+    pub max_file_size: Option<u64>,
+    /// The maximum number of files, directories and links created
+    pub max_dir_entries_created: Option<u64>,
+}
+
+/// How much of the resources limited by [`WasiQuotas`] a WASI module used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasiUsage {
+    /// The number of file descriptors open right now
+    pub open_fds: u32,
+    /// The largest number of file descriptors open at the same time
+    pub peak_open_fds: u32,
+    /// The number of bytes written to files
+    pub bytes_written: u64,
+    /// The number of files, directories and links created
+    pub dir_entries_created: u64,
+}
+
+impl WasiFs {
+    /// How much of the resources limited by [`WasiQuotas`] the module used.
+    pub fn usage(&self) -> WasiUsage {
+        WasiUsage {
+            open_fds: self.fd_map.len() as u32,
+            ..self.usage
+        }
+    }
+
+    /// Fail if opening one more file descriptor would go over the quota.
+    pub(crate) fn check_open_fd(&self) -> Result<(), __wasi_errno_t> {
+        match self.quotas.max_open_fds {
+            Some(max) if self.fd_map.len() as u32 >= max => Err(__WASI_ENFILE),
+            _ => Ok(()),
+        }
+    }
+
+    /// Keep track of the largest number of file descriptors open at once.
+    pub(crate) fn record_open_fd(&mut self) {
+        let open_fds = self.fd_map.len() as u32;
+        self.usage.peak_open_fds = self.usage.peak_open_fds.max(open_fds);
+    }
+
+    /// Fail if writing `len` bytes at `offset` of a file would go over the
+    /// quotas.
+    pub(crate) fn check_write(&self, offset: u64, len: u64) -> Result<(), __wasi_errno_t> {
+        if let Some(max) = self.quotas.max_bytes_written {
+            if self.usage.bytes_written.saturating_add(len) > max {
+                return Err(__WASI_EDQUOT);
+            }
+        }
+        self.check_file_size(offset.saturating_add(len))
+    }
+
+    /// Fail if writing `len` bytes at `offset` through `fd` would go over
+    /// the quotas. The writes to a file opened for appending land at its
+    /// end, wherever the offset is.
+    pub(crate) fn check_fd_write(
+        &self,
+        fd: __wasi_fd_t,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), __wasi_errno_t> {
+        let fd = self.fd_map.get(&fd).ok_or(__WASI_EBADF)?;
+        let offset = match &self.inodes[fd.inode].kind {
+            Kind::File {
+                handle: Some(handle),
+                ..
+            } if fd.flags & __WASI_FDFLAG_APPEND != 0 => handle.size(),
+            Kind::File { .. } | Kind::Buffer { .. } => offset,
+            _ => return Ok(()),
+        };
+        self.check_write(offset, len)
+    }
+
+    /// Count `len` bytes written to a file.
+    pub(crate) fn record_write(&mut self, len: u64) {
+        self.usage.bytes_written = self.usage.bytes_written.saturating_add(len);
+    }
+
+    /// Fail if a file of `size` bytes would go over the quota.
+    pub(crate) fn check_file_size(&self, size: u64) -> Result<(), __wasi_errno_t> {
+        match self.quotas.max_file_size {
+            Some(max) if size > max => Err(__WASI_EDQUOT),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if creating one more file, directory or link would go over the
+    /// quota.
+    pub(crate) fn check_dir_entry(&self) -> Result<(), __wasi_errno_t> {
+        match self.quotas.max_dir_entries_created {
+            Some(max) if self.usage.dir_entries_created >= max => Err(__WASI_EDQUOT),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a file, directory or link created.
+    pub(crate) fn record_dir_entry(&mut self) {
+        self.usage.dir_entries_created += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{WasiState, VIRTUAL_ROOT_FD};
+
+    #[test]
+    fn quotas() {
+        let mut state = WasiState::new("quotas")
+            .quotas(WasiQuotas {
+                max_open_fds: Some(5),
+                max_bytes_written: Some(100),
+                max_file_size: Some(60),
+                max_dir_entries_created: Some(1),
+            })
+            .build()
+            .unwrap();
+        let fs = &mut state.fs;
+        // stdin, stdout, stderr and the virtual root
+        assert_eq!(fs.usage().open_fds, 4);
+
+        let root = fs.fd_map[&VIRTUAL_ROOT_FD].inode;
+        let fd = fs.create_fd(0, 0, 0, 0, root).unwrap();
+        assert_eq!(fs.create_fd(0, 0, 0, 0, root), Err(__WASI_ENFILE));
+        fs.fd_map.remove(&fd);
+        assert!(fs.create_fd(0, 0, 0, 0, root).is_ok());
+
+        assert_eq!(fs.check_write(0, 50), Ok(()));
+        fs.record_write(50);
+        assert_eq!(fs.check_write(50, 20), Err(__WASI_EDQUOT));
+        assert_eq!(fs.check_write(0, 60), Err(__WASI_EDQUOT));
+        assert_eq!(fs.check_file_size(61), Err(__WASI_EDQUOT));
+
+        assert_eq!(fs.check_dir_entry(), Ok(()));
+        fs.record_dir_entry();
+        assert_eq!(fs.check_dir_entry(), Err(__WASI_EDQUOT));
+
+        assert_eq!(
+            fs.usage(),
+            WasiUsage {
+                open_fds: 5,
+                peak_open_fds: 5,
+                bytes_written: 50,
+                dir_entries_created: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! The random numbers seen by a WASI module.
+//!
+//! `random_get` goes through the [`WasiRandom`] of the [`WasiState`].
+//! [`HostRandom`] asks the operating system and is the default;
+//! [`SeededRandom`] generates the same numbers for the same seed.
+//!
+//! [`WasiState`]: crate::WasiState
+
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A source of random numbers for a WASI module.
+#[typetag::serde(tag = "type")]
+pub trait WasiRandom: fmt::Debug + Send + Sync + 'static {
+    /// Fill `buf` with random bytes.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t>;
+}
+
+/// The random number generator of the operating system.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct HostRandom;
+
+#[typetag::serde]
+impl WasiRandom for HostRandom {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        getrandom::getrandom(buf).map_err(|_| __WASI_EIO)
+    }
+}
+
+/// A pseudo-random number generator, which is good enough for tests and
+/// simulations but not for cryptography.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeededRandom {
+    /// The state of the splitmix64 generator
+    state: u64,
+}
+
+impl SeededRandom {
+    /// Create a generator whose numbers are generated from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// The next number of the splitmix64 generator.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[typetag::serde]
+impl WasiRandom for SeededRandom {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_random() {
+        let mut first = [0u8; 13];
+        let mut second = [0u8; 13];
+        SeededRandom::new(42).fill(&mut first).unwrap();
+        SeededRandom::new(42).fill(&mut second).unwrap();
+        assert_eq!(first, second);
+
+        SeededRandom::new(43).fill(&mut second).unwrap();
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,197 @@
+//! Sockets granted to the WASI module by the host.
+//!
+//! WASI can't create sockets by itself, so sockets are opened on the host
+//! and handed to the module with [`WasiStateBuilder::preopen_socket`].
+//!
+//! [`WasiStateBuilder::preopen_socket`]: crate::WasiStateBuilder::preopen_socket
+
+use crate::state::types::host_file_bytes_available;
+use crate::state::WasiFsError;
+use crate::syscalls::types::*;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, UdpSocket};
+
+/// A host socket usable by the WASI module.
+#[derive(Debug)]
+pub enum WasiSocket {
+    /// A listening socket, which can be polled for incoming connections
+    TcpListener(TcpListener),
+    /// A connected stream
+    TcpStream(TcpStream),
+    /// A datagram socket, which must be connected to be sent from
+    UdpSocket(UdpSocket),
+}
+
+impl From<TcpListener> for WasiSocket {
+    fn from(listener: TcpListener) -> Self {
+        WasiSocket::TcpListener(listener)
+    }
+}
+
+impl From<TcpStream> for WasiSocket {
+    fn from(stream: TcpStream) -> Self {
+        WasiSocket::TcpStream(stream)
+    }
+}
+
+impl From<UdpSocket> for WasiSocket {
+    fn from(socket: UdpSocket) -> Self {
+        WasiSocket::UdpSocket(socket)
+    }
+}
+
+impl WasiSocket {
+    /// The type of the socket as seen by the WASI module
+    pub fn file_type(&self) -> __wasi_filetype_t {
+        match self {
+            WasiSocket::TcpListener(_) | WasiSocket::TcpStream(_) => __WASI_FILETYPE_SOCKET_STREAM,
+            WasiSocket::UdpSocket(_) => __WASI_FILETYPE_SOCKET_DGRAM,
+        }
+    }
+
+    /// Receive data into `buf`, leaving it in the socket if `peek` is set
+    pub fn recv(&self, buf: &mut [u8], peek: bool) -> Result<usize, WasiFsError> {
+        let received = match self {
+            WasiSocket::TcpStream(stream) if peek => stream.peek(buf)?,
+            WasiSocket::TcpStream(stream) => (&*stream).read(buf)?,
+            WasiSocket::UdpSocket(socket) if peek => socket.peek(buf)?,
+            WasiSocket::UdpSocket(socket) => socket.recv(buf)?,
+            WasiSocket::TcpListener(_) => return Err(WasiFsError::NotConnected),
+        };
+        Ok(received)
+    }
+
+    /// Send the data in `buf`
+    pub fn send(&self, buf: &[u8]) -> Result<usize, WasiFsError> {
+        let sent = match self {
+            WasiSocket::TcpStream(stream) => (&*stream).write(buf)?,
+            WasiSocket::UdpSocket(socket) => socket.send(buf)?,
+            WasiSocket::TcpListener(_) => return Err(WasiFsError::NotConnected),
+        };
+        Ok(sent)
+    }
+
+    /// Shut down the reading and/or writing half of a stream
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), WasiFsError> {
+        match self {
+            WasiSocket::TcpStream(stream) => Ok(stream.shutdown(how)?),
+            WasiSocket::TcpListener(_) | WasiSocket::UdpSocket(_) => Err(WasiFsError::NotConnected),
+        }
+    }
+
+    /// Make the operations on the socket fail with
+    /// [`WasiFsError::WouldBlock`] instead of blocking
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), WasiFsError> {
+        match self {
+            WasiSocket::TcpListener(listener) => listener.set_nonblocking(nonblocking)?,
+            WasiSocket::TcpStream(stream) => stream.set_nonblocking(nonblocking)?,
+            WasiSocket::UdpSocket(socket) => socket.set_nonblocking(nonblocking)?,
+        }
+        Ok(())
+    }
+
+    /// The number of bytes which can be received without blocking
+    pub fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        match self.get_raw_fd() {
+            Some(host_fd) => host_file_bytes_available(host_fd),
+            None => Ok(0),
+        }
+    }
+
+    /// The file descriptor of the socket on the host, used for polling
+    #[cfg(unix)]
+    pub fn get_raw_fd(&self) -> Option<i32> {
+        use std::os::unix::io::AsRawFd;
+        Some(match self {
+            WasiSocket::TcpListener(listener) => listener.as_raw_fd(),
+            WasiSocket::TcpStream(stream) => stream.as_raw_fd(),
+            WasiSocket::UdpSocket(socket) => socket.as_raw_fd(),
+        })
+    }
+
+    /// The file descriptor of the socket on the host, used for polling
+    #[cfg(not(unix))]
+    pub fn get_raw_fd(&self) -> Option<i32> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::{poll, PollEvent, PollEventBuilder, PollTarget};
+    use std::time::Duration;
+
+    fn loopback() -> (WasiSocket, WasiSocket, WasiSocket) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (listener.into(), client.into(), server.into())
+    }
+
+    #[test]
+    fn send_and_recv() {
+        let (listener, client, server) = loopback();
+        assert_eq!(listener.file_type(), __WASI_FILETYPE_SOCKET_STREAM);
+        assert_eq!(
+            listener.recv(&mut [0; 4], false),
+            Err(WasiFsError::NotConnected)
+        );
+
+        assert_eq!(client.send(b"hello").unwrap(), 5);
+        let mut buf = [0; 5];
+        assert_eq!(server.recv(&mut buf, true).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+        assert_eq!(server.recv(&mut buf, false).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+
+        server.set_nonblocking(true).unwrap();
+        assert_eq!(server.recv(&mut buf, false), Err(WasiFsError::WouldBlock));
+
+        client.shutdown(Shutdown::Write).unwrap();
+        server.set_nonblocking(false).unwrap();
+        assert_eq!(server.recv(&mut buf, false).unwrap(), 0);
+        assert_eq!(
+            listener.shutdown(Shutdown::Both),
+            Err(WasiFsError::NotConnected)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn poll_readiness() {
+        let (listener, client, server) = loopback();
+        let read = PollEventBuilder::new().add(PollEvent::PollIn).build();
+        let targets = [PollTarget::Socket(&listener), PollTarget::Socket(&server)];
+        let mut seen = [0; 2];
+
+        let no_wait = Some(Duration::from_secs(0));
+        assert_eq!(
+            poll(&targets, &[read, read], &mut seen, no_wait).unwrap(),
+            0
+        );
+        assert_eq!(seen, [0, 0]);
+        let start = std::time::Instant::now();
+        let timeout = Some(Duration::from_millis(20));
+        assert_eq!(
+            poll(&targets, &[read, read], &mut seen, timeout).unwrap(),
+            0
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        client.send(b"ping").unwrap();
+        let _second = TcpStream::connect(match &listener {
+            WasiSocket::TcpListener(listener) => listener.local_addr().unwrap(),
+            _ => unreachable!(),
+        })
+        .unwrap();
+        // give the loopback a moment to deliver
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(
+            poll(&targets, &[read, read], &mut seen, no_wait).unwrap(),
+            2
+        );
+        assert_eq!(seen, [read, read]);
+        assert_eq!(server.bytes_available().unwrap(), 4);
+    }
+}
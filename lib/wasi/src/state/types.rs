@@ -1,4 +1,5 @@
 /// types for use in the WASI filesystem
+use crate::state::WasiSocket;
 use crate::syscalls::types::*;
 use serde::{de, Deserialize, Serialize};
 use std::any::Any;
@@ -10,7 +11,7 @@ use std::{
     fs,
     io::{self, Read, Seek, Write},
     path::PathBuf,
-    time::SystemTime,
+    time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 use tracing::debug;
@@ -87,6 +88,12 @@ pub enum WasiFsError {
     /// A call to write returned 0
     #[error("write returned 0")]
     WriteZero,
+    /// The directory is not empty
+    #[error("directory not empty")]
+    DirectoryNotEmpty,
+    /// The file would grow larger than it may be
+    #[error("file too large")]
+    FileTooLarge,
     /// A WASI error without an external name.  If you encounter this it means
     /// that there's probably a bug on our side (maybe as simple as forgetting to wrap
     /// this error, but perhaps something broke)
@@ -116,6 +123,8 @@ impl WasiFsError {
             __WASI_EPROTO => WasiFsError::UnexpectedEof,
             __WASI_EAGAIN => WasiFsError::WouldBlock,
             __WASI_ENOSPC => WasiFsError::WriteZero,
+            __WASI_ENOTEMPTY => WasiFsError::DirectoryNotEmpty,
+            __WASI_EFBIG => WasiFsError::FileTooLarge,
             _ => WasiFsError::UnknownError(err),
         }
     }
@@ -144,6 +153,8 @@ impl WasiFsError {
             WasiFsError::UnexpectedEof => __WASI_EPROTO,
             WasiFsError::WouldBlock => __WASI_EAGAIN,
             WasiFsError::WriteZero => __WASI_ENOSPC,
+            WasiFsError::DirectoryNotEmpty => __WASI_ENOTEMPTY,
+            WasiFsError::FileTooLarge => __WASI_EFBIG,
             WasiFsError::UnknownError(ec) => ec,
         }
     }
@@ -211,6 +222,13 @@ pub trait WasiFile: fmt::Debug + Send + Write + Read + Seek + 'static + Upcastab
     fn get_raw_fd(&self) -> Option<i32> {
         None
     }
+
+    /// Used for polling files without a host fd (see [`WasiFile::get_raw_fd`]).
+    /// Returns which of `events` the file is ready for right now.  This function must not block
+    /// Default reports the behavior of regular files, which are always ready to be read and written
+    fn poll_ready(&self, events: PollEventSet) -> Result<PollEventSet, WasiFsError> {
+        Ok(events & (PollEvent::PollIn as PollEventSet | PollEvent::PollOut as PollEventSet))
+    }
 }
 
 // Implementation of `Upcastable` taken from https://users.rust-lang.org/t/why-does-downcasting-not-work-for-subtraits/33286/7 .
@@ -361,26 +379,123 @@ impl PollEventBuilder {
     }
 }
 
-#[cfg(unix)]
+/// Something whose readiness can be waited on with [`poll`]
+pub(crate) enum PollTarget<'a> {
+    File(&'a dyn WasiFile),
+    Socket(&'a WasiSocket),
+}
+
+impl<'a> PollTarget<'a> {
+    fn get_raw_fd(&self) -> Option<i32> {
+        match self {
+            PollTarget::File(file) => file.get_raw_fd(),
+            PollTarget::Socket(socket) => socket.get_raw_fd(),
+        }
+    }
+
+    pub(crate) fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        match self {
+            PollTarget::File(file) => file.bytes_available(),
+            PollTarget::Socket(socket) => socket.bytes_available(),
+        }
+    }
+
+    fn poll_ready(&self, events: PollEventSet) -> Result<PollEventSet, WasiFsError> {
+        match self {
+            PollTarget::File(file) => file.poll_ready(events),
+            PollTarget::Socket(socket) => {
+                let mut ready = events & PollEvent::PollOut as PollEventSet;
+                if socket.bytes_available()? > 0 {
+                    ready |= events & PollEvent::PollIn as PollEventSet;
+                }
+                Ok(ready)
+            }
+        }
+    }
+}
+
+/// How long [`poll`] sleeps between checks when some of the targets can't
+/// be waited on by the host
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Wait until at least one of `selfs` is ready for its `events`, or until
+/// `timeout` has passed.  `None` waits forever.
+///
+/// The events which happened are written to `seen_events` and the number of
+/// ready targets is returned.
 pub(crate) fn poll(
-    selfs: &[&dyn WasiFile],
+    selfs: &[PollTarget],
     events: &[PollEventSet],
     seen_events: &mut [PollEventSet],
+    timeout: Option<Duration>,
 ) -> Result<u32, WasiFsError> {
     if !(selfs.len() == events.len() && events.len() == seen_events.len()) {
         return Err(WasiFsError::InvalidInput);
     }
-    let mut fds = selfs
+    if selfs.is_empty() {
+        std::thread::sleep(timeout.ok_or(WasiFsError::InvalidInput)?);
+        return Ok(0);
+    }
+    let raw_fds = selfs.iter().map(PollTarget::get_raw_fd).collect::<Vec<_>>();
+    let all_on_host = raw_fds.iter().all(Option::is_some);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    loop {
+        let mut ready = 0;
+        for (i, target) in selfs.iter().enumerate() {
+            seen_events[i] = 0;
+            if raw_fds[i].is_none() {
+                seen_events[i] = target.poll_ready(events[i])?;
+                if seen_events[i] != 0 {
+                    ready += 1;
+                }
+            }
+        }
+
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        // the targets without a host fd are checked again every `POLL_INTERVAL`
+        let wait = if ready > 0 {
+            Some(Duration::from_secs(0))
+        } else if all_on_host {
+            remaining
+        } else {
+            Some(remaining.map_or(POLL_INTERVAL, |remaining| remaining.min(POLL_INTERVAL)))
+        };
+        ready += host_poll(&raw_fds, events, seen_events, wait)?;
+
+        if ready > 0 || remaining == Some(Duration::from_secs(0)) {
+            return Ok(ready);
+        }
+    }
+}
+
+/// Wait on the targets which have a host fd for up to `wait`, leaving the
+/// others untouched.  `None` waits forever.
+#[cfg(unix)]
+fn host_poll(
+    raw_fds: &[Option<i32>],
+    events: &[PollEventSet],
+    seen_events: &mut [PollEventSet],
+    wait: Option<Duration>,
+) -> Result<u32, WasiFsError> {
+    // targets without a host fd get a negative fd, which `poll` skips, so
+    // that the indices of `fds` line up with those of `raw_fds`
+    let mut fds = raw_fds
         .iter()
         .enumerate()
-        .filter_map(|(i, s)| s.get_raw_fd().map(|rfd| (i, rfd)))
-        .map(|(i, host_fd)| libc::pollfd {
-            fd: host_fd,
+        .map(|(i, raw_fd)| libc::pollfd {
+            fd: raw_fd.unwrap_or(-1),
             events: poll_event_set_to_platform_poll_events(events[i]),
             revents: 0,
         })
         .collect::<Vec<_>>();
-    let result = unsafe { libc::poll(fds.as_mut_ptr(), selfs.len() as _, 1) };
+    // round up, so that the deadline has passed when `poll` times out
+    let wait_ms = wait.map_or(-1, |wait| {
+        ((wait.as_nanos() + 999_999) / 1_000_000)
+            .try_into()
+            .unwrap_or(libc::c_int::MAX)
+    });
+    let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, wait_ms) };
 
     if result < 0 {
         // TODO: check errno and return value
@@ -388,19 +503,28 @@ pub(crate) fn poll(
     }
     // convert result and write back values
     for (i, fd) in fds.into_iter().enumerate() {
-        seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
+        if raw_fds[i].is_some() {
+            seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
+        }
     }
     // unwrap is safe because we check for negative values above
     Ok(result.try_into().unwrap())
 }
 
 #[cfg(not(unix))]
-pub(crate) fn poll(
-    _selfs: &[&dyn WasiFile],
+fn host_poll(
+    raw_fds: &[Option<i32>],
     _events: &[PollEventSet],
     _seen_events: &mut [PollEventSet],
-) -> Result<(), WasiFsError> {
-    unimplemented!("HostFile::poll in WasiFile is not implemented for non-Unix-like targets yet");
+    wait: Option<Duration>,
+) -> Result<u32, WasiFsError> {
+    if raw_fds.iter().any(Option::is_some) {
+        // polling host fds is not implemented for non-Unix-like targets yet
+        return Err(WasiFsError::UnknownError(__WASI_ENOTSUP));
+    }
+    // `poll` never waits forever when some targets have no host fd
+    std::thread::sleep(wait.unwrap_or(Duration::from_secs(0)));
+    Ok(0)
 }
 
 pub trait WasiPath {}
@@ -646,6 +770,14 @@ impl WasiFile for HostFile {
 
 impl From<io::Error> for WasiFsError {
     fn from(io_error: io::Error) -> Self {
+        // `WasiFile`s can carry a `WasiFsError` in the errors of their
+        // `Read`, `Write` and `Seek` implementations
+        if let Some(error) = io_error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<WasiFsError>())
+        {
+            return *error;
+        }
         match io_error.kind() {
             io::ErrorKind::AddrInUse => WasiFsError::AddressInUse,
             io::ErrorKind::AddrNotAvailable => WasiFsError::AddressNotAvailable,
@@ -672,7 +804,7 @@ impl From<io::Error> for WasiFsError {
 }
 
 #[cfg(unix)]
-fn host_file_bytes_available(host_fd: i32) -> Result<usize, WasiFsError> {
+pub(crate) fn host_file_bytes_available(host_fd: i32) -> Result<usize, WasiFsError> {
     let mut bytes_found = 0 as libc::c_int;
     let result = unsafe { libc::ioctl(host_fd, libc::FIONREAD, &mut bytes_found) };
 
@@ -687,7 +819,7 @@ fn host_file_bytes_available(host_fd: i32) -> Result<usize, WasiFsError> {
 }
 
 #[cfg(not(unix))]
-fn host_file_bytes_available(_raw_fd: i32) -> Result<usize, WasiFsError> {
+pub(crate) fn host_file_bytes_available(_raw_fd: i32) -> Result<usize, WasiFsError> {
     unimplemented!("host_file_bytes_available not yet implemented for non-Unix-like targets.  This probably means the program tried to use wasi::poll_oneoff")
 }
 
@@ -1037,6 +1169,148 @@ impl WasiFile for Pipe {
     }
 }
 
+/// Called with the output written to a [`CallbackOutput`].
+type OutputCallback = dyn FnMut(&[u8]) + Send;
+
+/// Output passed to a callback as it's written, e.g. to stream the stdout of
+/// a module to a logger while it runs.
+///
+/// Line-buffered output is passed one line at a time, including the `\n`;
+/// an unfinished last line is only passed when the output is dropped, as
+/// `fd_write` flushes after every write.
+/// The callback only lives in this process, so a [`WasiState`] using it
+/// can't be frozen.
+///
+/// [`WasiState`]: crate::WasiState
+pub struct CallbackOutput {
+    callback: Box<OutputCallback>,
+    line_buffered: bool,
+    /// The unfinished line, when line-buffered
+    buffer: Vec<u8>,
+}
+
+impl CallbackOutput {
+    /// Pass the bytes of each write to `callback`.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        Self {
+            callback: Box::new(callback),
+            line_buffered: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Pass each line written to `callback`.
+    pub fn line_buffered<F>(callback: F) -> Self
+    where
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        let mut output = Self::new(callback);
+        output.line_buffered = true;
+        output
+    }
+}
+
+impl fmt::Debug for CallbackOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackOutput")
+            .field("line_buffered", &self.line_buffered)
+            .field("buffer", &self.buffer)
+            .finish()
+    }
+}
+
+impl Serialize for CallbackOutput {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom(
+            "output passed to a callback can't be serialized",
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for CallbackOutput {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(de::Error::custom(
+            "output passed to a callback can't be deserialized",
+        ))
+    }
+}
+
+impl Read for CallbackOutput {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not read from output passed to a callback",
+        ))
+    }
+}
+
+impl Seek for CallbackOutput {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek output passed to a callback",
+        ))
+    }
+}
+
+impl Write for CallbackOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.line_buffered {
+            (self.callback)(buf);
+            return Ok(buf.len());
+        }
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            (self.callback)(&line);
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for CallbackOutput {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            (self.callback)(&self.buffer);
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for CallbackOutput {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(0)
+    }
+    fn poll_ready(&self, events: PollEventSet) -> Result<PollEventSet, WasiFsError> {
+        // the output can always be written and never read
+        Ok(events & PollEvent::PollOut as PollEventSet)
+    }
+}
+
 /*
 TODO: Think about using this
 trait WasiFdBacking: std::fmt::Debug {
@@ -1046,3 +1320,58 @@ trait WasiFdBacking: std::fmt::Debug {
     fn get_name(&self) -> &str;
 }
 */
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn callback_output() {
+        use std::sync::{Arc, Mutex};
+
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let sink = chunks.clone();
+        let mut output =
+            CallbackOutput::new(move |bytes| sink.lock().unwrap().push(bytes.to_vec()));
+        output.write_all(b"hel").unwrap();
+        output.write_all(b"lo\n").unwrap();
+        assert_eq!(
+            *chunks.lock().unwrap(),
+            vec![b"hel".to_vec(), b"lo\n".to_vec()]
+        );
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let mut output =
+            CallbackOutput::line_buffered(move |line| sink.lock().unwrap().push(line.to_vec()));
+        output.write_all(b"one\ntw").unwrap();
+        output.write_all(b"o\nthr").unwrap();
+        // the unfinished line is kept until the output is dropped
+        output.flush().unwrap();
+        assert_eq!(
+            *lines.lock().unwrap(),
+            vec![b"one\n".to_vec(), b"two\n".to_vec()]
+        );
+        drop(output);
+        assert_eq!(lines.lock().unwrap().last().unwrap(), b"thr");
+    }
+
+    #[test]
+    fn poll_pipe() {
+        let read = PollEventBuilder::new().add(PollEvent::PollIn).build();
+        let write = PollEventBuilder::new().add(PollEvent::PollOut).build();
+        let mut pipe = Pipe::new();
+        let mut seen = [0; 2];
+
+        // nothing can write to the pipe while the module is waiting on it,
+        // so it's ready even when it's empty, like a file at its end
+        let targets = [PollTarget::File(&pipe), PollTarget::File(&pipe)];
+        assert_eq!(poll(&targets, &[read, write], &mut seen, None).unwrap(), 2);
+        assert_eq!(seen, [read, write]);
+
+        pipe.write_all(b"data").unwrap();
+        let targets = [PollTarget::File(&pipe)];
+        assert_eq!(poll(&targets, &[read], &mut seen[..1], None).unwrap(), 1);
+        assert_eq!(targets[0].bytes_available().unwrap(), 4);
+    }
+}
@@ -12,13 +12,14 @@ pub mod unix;
 pub mod windows;
 
 pub mod legacy;
+pub mod traced;
 
 use self::types::*;
 use crate::{
     ptr::{Array, WasmPtr},
     state::{
-        self, host_file_type_to_wasi_file_type, iterate_poll_events, poll, Fd, HostFile, Inode,
-        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState,
+        self, iterate_poll_events, poll, Fd, Inode, InodeVal, Kind, OpenOptions, PathOperation,
+        PollEvent, PollEventBuilder, PollTarget, WasiFile, WasiFsError, WasiSocket, WasiState,
         MAX_SYMLINKS,
     },
     WasiEnv, WasiError,
@@ -52,7 +53,10 @@ fn write_bytes_inner<T: Write>(
         let bytes = iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
         write_loc
             .write_all(&bytes.iter().map(|b_cell| b_cell.get()).collect::<Vec<u8>>())
-            .map_err(|_| __WASI_EIO)?;
+            .map_err(|e| match WasiFsError::from(e) {
+                WasiFsError::FileTooLarge => __WASI_EFBIG,
+                _ => __WASI_EIO,
+            })?;
 
         // TODO: handle failure more accurately
         bytes_written += iov_inner.buf_len;
@@ -70,6 +74,14 @@ fn write_bytes<T: Write>(
     result
 }
 
+/// The number of bytes that writing `iovs_arr_cell` would write
+fn iovs_total_len(iovs_arr_cell: &[Cell<__wasi_ciovec_t>]) -> u64 {
+    iovs_arr_cell
+        .iter()
+        .map(|iov| iov.get().buf_len as u64)
+        .sum()
+}
+
 fn read_bytes<T: Read>(
     mut reader: T,
     memory: &Memory,
@@ -87,6 +99,47 @@ fn read_bytes<T: Read>(
     Ok(bytes_read)
 }
 
+/// The most bytes received from a socket by a single `sock_recv`
+const MAX_RECV_LEN: usize = 64 * 1024;
+
+/// Receive from `socket` into the buffers with a single call, so that it
+/// doesn't block while data has already been received
+fn recv_bytes(
+    socket: &WasiSocket,
+    memory: &Memory,
+    iovs_arr_cell: &[Cell<__wasi_iovec_t>],
+    peek: bool,
+) -> Result<u32, __wasi_errno_t> {
+    // nothing must be received if it can't be stored
+    for iov in iovs_arr_cell {
+        let iov_inner = iov.get();
+        iov_inner.buf.deref(memory, 0, iov_inner.buf_len)?;
+    }
+    // the lengths come from the module, receiving less is always allowed
+    let total_len = iovs_arr_cell
+        .iter()
+        .map(|iov| iov.get().buf_len as usize)
+        .fold(0, usize::saturating_add)
+        .min(MAX_RECV_LEN);
+    let mut received = vec![0; total_len];
+    let len = socket
+        .recv(&mut received, peek)
+        .map_err(WasiFsError::into_wasi_err)?;
+    read_bytes(&received[..len], memory, iovs_arr_cell)
+}
+
+/// Send the contents of the buffers to `socket` with a single call
+fn send_bytes(
+    socket: &WasiSocket,
+    memory: &Memory,
+    iovs_arr_cell: &[Cell<__wasi_ciovec_t>],
+) -> Result<u32, __wasi_errno_t> {
+    let mut data = vec![];
+    write_bytes_inner(&mut data, memory, iovs_arr_cell)?;
+    let sent = socket.send(&data).map_err(WasiFsError::into_wasi_err)?;
+    Ok(sent as u32)
+}
+
 /// checks that `rights_check_set` is a subset of `rights_set`
 fn has_rights(rights_set: __wasi_rights_t, rights_check_set: __wasi_rights_t) -> bool {
     rights_set | rights_check_set == rights_set
@@ -125,6 +178,30 @@ fn get_current_time_in_nanos() -> Result<__wasi_timestamp_t, __wasi_errno_t> {
     Ok(duration.as_nanos() as __wasi_timestamp_t)
 }
 
+/// The metadata of a file as the module gets to see it: the clock may hide
+/// the timestamps of the host
+fn visible_filestat(state: &WasiState, stat: __wasi_filestat_t) -> __wasi_filestat_t {
+    match state.clock.file_time() {
+        Some(file_time) => __wasi_filestat_t {
+            st_atim: file_time,
+            st_mtim: file_time,
+            st_ctim: file_time,
+            ..stat
+        },
+        None => stat,
+    }
+}
+
+/// Apply a relative seek to `base`, failing instead of going before the start
+/// of the file or overflowing
+fn seek_offset(base: u64, offset: __wasi_filedelta_t) -> Result<u64, __wasi_errno_t> {
+    let new_offset = (base as i64).checked_add(offset).ok_or(__WASI_EOVERFLOW)?;
+    if new_offset < 0 {
+        return Err(__WASI_EINVAL);
+    }
+    Ok(new_offset as u64)
+}
+
 /// ### `args_get()`
 /// Read command-line argument data.
 /// The sizes of the buffers should match that returned by [`args_sizes_get()`](#args_sizes_get).
@@ -204,10 +281,11 @@ pub fn clock_res_get(
     resolution: WasmPtr<__wasi_timestamp_t>,
 ) -> __wasi_errno_t {
     debug!("wasi::clock_res_get");
-    let memory = env.memory();
+    let (memory, state) = env.get_memory_and_wasi_state(0);
 
     let out_addr = wasi_try!(resolution.deref(memory));
-    platform_clock_res_get(clock_id, out_addr)
+    out_addr.set(wasi_try!(state.clock.resolution(clock_id)));
+    __WASI_ESUCCESS
 }
 
 /// ### `clock_time_get()`
@@ -230,10 +308,16 @@ pub fn clock_time_get(
         "wasi::clock_time_get clock_id: {}, precision: {}",
         clock_id, precision
     );
-    let memory = env.memory();
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let out_addr = wasi_try!(time.deref(memory));
-    let result = platform_clock_time_get(clock_id, precision, out_addr);
+    let result = match state.clock.time(clock_id, precision) {
+        Ok(now) => {
+            out_addr.set(now);
+            __WASI_ESUCCESS
+        }
+        Err(errno) => errno,
+    };
     debug!(
         "time: {} => {}",
         wasi_try!(time.deref(memory)).get(),
@@ -341,6 +425,7 @@ pub fn fd_allocate(
         return __WASI_EACCES;
     }
     let new_size = wasi_try!(offset.checked_add(len), __WASI_EINVAL);
+    wasi_try!(state.fs.check_file_size(new_size));
 
     match &mut state.fs.inodes[inode].kind {
         Kind::File { handle, .. } => {
@@ -354,6 +439,7 @@ pub fn fd_allocate(
             buffer.resize(new_size as usize, 0);
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
+        Kind::Socket { .. } => return __WASI_ESPIPE,
         Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
     }
     state.fs.inodes[inode].stat.st_size = new_size;
@@ -452,7 +538,17 @@ pub fn fd_fdstat_set_flags(
         return __WASI_EACCES;
     }
 
+    let inode = fd_entry.inode;
     fd_entry.flags = flags;
+    if let Kind::Socket {
+        handle: Some(socket),
+    } = &state.fs.inodes[inode].kind
+    {
+        let nonblocking = flags & __WASI_FDFLAG_NONBLOCK != 0;
+        wasi_try!(socket
+            .set_nonblocking(nonblocking)
+            .map_err(WasiFsError::into_wasi_err));
+    }
     __WASI_ESUCCESS
 }
 
@@ -511,7 +607,7 @@ pub fn fd_filestat_get(
     let stat = wasi_try!(state.fs.filestat_fd(fd));
 
     let buf = wasi_try!(buf.deref(memory));
-    buf.set(stat);
+    buf.set(visible_filestat(&state, stat));
 
     __WASI_ESUCCESS
 }
@@ -536,6 +632,7 @@ pub fn fd_filestat_set_size(
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_SET_SIZE) {
         return __WASI_EACCES;
     }
+    wasi_try!(state.fs.check_file_size(st_size));
 
     match &mut state.fs.inodes[inode].kind {
         Kind::File { handle, .. } => {
@@ -549,6 +646,7 @@ pub fn fd_filestat_set_size(
             buffer.resize(st_size as usize, 0);
         }
         Kind::Symlink { .. } => return __WASI_EBADF,
+        Kind::Socket { .. } => return __WASI_EINVAL,
         Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
     }
     state.fs.inodes[inode].stat.st_size = st_size;
@@ -690,7 +788,9 @@ pub fn fd_pread(
                     }
                 }
                 Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pread"),
+                Kind::Socket { .. } => return __WASI_ESPIPE,
+                // symlinks are resolved when opened, so fds never refer to them
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => {
                     wasi_try!(read_bytes(&buffer[(offset as usize)..], memory, iov_cells))
                 }
@@ -768,7 +868,9 @@ pub fn fd_prestat_dir_name(
                 __WASI_EOVERFLOW
             }
         }
-        Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::File { .. } => __WASI_ENOTDIR,
+        Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::File { .. } | Kind::Socket { .. } => {
+            __WASI_ENOTDIR
+        }
     }
 }
 
@@ -830,9 +932,12 @@ pub fn fd_pwrite(
             }
 
             let inode_idx = fd_entry.inode;
+            wasi_try!(state
+                .fs
+                .check_fd_write(fd, offset, iovs_total_len(iovs_arr_cell)));
             let inode = &mut state.fs.inodes[inode_idx];
 
-            match &mut inode.kind {
+            let bytes_written = match &mut inode.kind {
                 Kind::File { handle, .. } => {
                     if let Some(handle) = handle {
                         handle.seek(std::io::SeekFrom::Start(offset as u64));
@@ -845,13 +950,18 @@ pub fn fd_pwrite(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pwrite"),
+                Kind::Socket { .. } => return __WASI_ESPIPE,
+                // symlinks are resolved when opened, so fds never refer to them
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => wasi_try!(write_bytes(
-                    &mut buffer[(offset as usize)..],
+                    wasi_try!(buffer.get_mut(offset as usize..), __WASI_EINVAL),
                     memory,
                     iovs_arr_cell
                 )),
-            }
+            };
+            state.fs.record_write(bytes_written as u64);
+
+            bytes_written
         }
     };
 
@@ -921,7 +1031,14 @@ pub fn fd_read(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_read"),
+                Kind::Socket { handle } => {
+                    let socket = wasi_try!(handle.as_ref(), __WASI_EBADF);
+                    // sockets have no offset to update
+                    nread_cell.set(wasi_try!(recv_bytes(socket, memory, iovs_arr_cell, false)));
+                    return __WASI_ESUCCESS;
+                }
+                // symlinks are resolved when opened, so fds never refer to them
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Buffer { buffer } => {
                     wasi_try!(read_bytes(&buffer[offset..], memory, iovs_arr_cell))
                 }
@@ -971,6 +1088,12 @@ pub fn fd_readdir(
     let buf_arr_cell = wasi_try!(buf.deref(memory, 0, buf_len));
     let bufused_cell = wasi_try!(bufused.deref(memory));
     let working_dir = wasi_try!(state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF));
+    // preopened directories are never opened by the module, so the policy
+    // is checked here rather than in `path_open`
+    wasi_try!(state.fs.check_policy(
+        state.fs.guest_path(working_dir.inode).as_deref(),
+        PathOperation::Read
+    ));
     let mut cur_cookie = cookie;
     let mut buf_idx = 0;
 
@@ -980,17 +1103,17 @@ pub fn fd_readdir(
             // we need to support multiple calls,
             // simple and obviously correct implementation for now:
             // maintain consistent order via lexacographic sorting
-            let fs_info = wasi_try!(wasi_try!(std::fs::read_dir(path).map_err(|_| __WASI_EIO))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|_| __WASI_EIO));
-            let mut entry_vec = wasi_try!(fs_info
+            let fs_info = wasi_try!(state.fs.fs_backend.read_dir(path).map_err(|_| __WASI_EIO));
+            let mut entry_vec = fs_info
                 .into_iter()
-                .map(|entry| Ok((
-                    entry.file_name().to_string_lossy().to_string(),
-                    host_file_type_to_wasi_file_type(entry.file_type().map_err(|_| __WASI_EIO)?),
-                    0, // TODO: inode
-                )))
-                .collect::<Result<Vec<(String, u8, u64)>, _>>());
+                .map(|entry| {
+                    (
+                        entry.name,
+                        entry.file_type.to_wasi_file_type(),
+                        0, // TODO: inode
+                    )
+                })
+                .collect::<Vec<(String, u8, u64)>>();
             entry_vec.extend(
                 entries
                     .iter()
@@ -1026,7 +1149,9 @@ pub fn fd_readdir(
                 })
                 .collect()
         }
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => return __WASI_ENOTDIR,
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_ENOTDIR
+        }
     };
 
     for (entry_path_str, wasi_file_type, ino) in entries.iter().skip(cookie as usize) {
@@ -1118,7 +1243,9 @@ pub fn fd_seek(
 
     // TODO: handle case if fd is a dir?
     match whence {
-        __WASI_WHENCE_CUR => fd_entry.offset = (fd_entry.offset as i64 + offset) as u64,
+        __WASI_WHENCE_CUR => {
+            fd_entry.offset = wasi_try!(seek_offset(fd_entry.offset, offset));
+        }
         __WASI_WHENCE_END => {
             use std::io::SeekFrom;
             let inode_idx = fd_entry.inode;
@@ -1130,14 +1257,13 @@ pub fn fd_seek(
 
                         // reborrow
                         let fd_entry = wasi_try!(state.fs.fd_map.get_mut(&fd).ok_or(__WASI_EBADF));
-                        fd_entry.offset = (end as i64 + offset) as u64;
+                        fd_entry.offset = wasi_try!(seek_offset(end, offset));
                     } else {
                         return __WASI_EINVAL;
                     }
                 }
-                Kind::Symlink { .. } => {
-                    unimplemented!("wasi::fd_seek not implemented for symlinks")
-                }
+                // symlinks are resolved when opened, so fds never refer to them
+                Kind::Symlink { .. } => return __WASI_EBADF,
                 Kind::Dir { .. } | Kind::Root { .. } => {
                     // TODO: check this
                     return __WASI_EINVAL;
@@ -1147,9 +1273,10 @@ pub fn fd_seek(
                     // TODO: implement this
                     return __WASI_EINVAL;
                 }
+                Kind::Socket { .. } => return __WASI_ESPIPE,
             }
         }
-        __WASI_WHENCE_SET => fd_entry.offset = offset as u64,
+        __WASI_WHENCE_SET => fd_entry.offset = wasi_try!(seek_offset(0, offset)),
         _ => return __WASI_EINVAL,
     }
     // reborrow
@@ -1188,7 +1315,7 @@ pub fn fd_sync(env: &WasiEnv, fd: __wasi_fd_t) -> __wasi_errno_t {
             }
         }
         Kind::Root { .. } | Kind::Dir { .. } => return __WASI_EISDIR,
-        Kind::Buffer { .. } | Kind::Symlink { .. } => return __WASI_EINVAL,
+        Kind::Buffer { .. } | Kind::Symlink { .. } | Kind::Socket { .. } => return __WASI_EINVAL,
     }
 
     __WASI_ESUCCESS
@@ -1284,6 +1411,9 @@ pub fn fd_write(
 
             let offset = fd_entry.offset as usize;
             let inode_idx = fd_entry.inode;
+            wasi_try!(state
+                .fs
+                .check_fd_write(fd, offset as u64, iovs_total_len(iovs_arr_cell)));
             let inode = &mut state.fs.inodes[inode_idx];
 
             let bytes_written = match &mut inode.kind {
@@ -1299,12 +1429,23 @@ pub fn fd_write(
                     // TODO: verify
                     return __WASI_EISDIR;
                 }
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_write"),
-                Kind::Buffer { buffer } => {
-                    wasi_try!(write_bytes(&mut buffer[offset..], memory, iovs_arr_cell))
+                Kind::Socket { handle } => {
+                    let socket = wasi_try!(handle.as_ref(), __WASI_EBADF);
+                    // sockets have no offset to update
+                    nwritten_cell.set(wasi_try!(send_bytes(socket, memory, iovs_arr_cell)));
+                    return __WASI_ESUCCESS;
                 }
+                // symlinks are resolved when opened, so fds never refer to them
+                Kind::Symlink { .. } => return __WASI_EBADF,
+                Kind::Buffer { buffer } => wasi_try!(write_bytes(
+                    wasi_try!(buffer.get_mut(offset..), __WASI_EINVAL),
+                    memory,
+                    iovs_arr_cell
+                )),
             };
 
+            state.fs.record_write(bytes_written as u64);
+
             // reborrow
             let fd_entry = wasi_try!(state.fs.fd_map.get_mut(&fd).ok_or(__WASI_EBADF));
             fd_entry.offset += bytes_written as u64;
@@ -1392,10 +1533,21 @@ pub fn path_create_directory(
                     let mut adjusted_path = path.clone();
                     // TODO: double check this doesn't risk breaking the sandbox
                     adjusted_path.push(comp);
-                    if adjusted_path.exists() && !adjusted_path.is_dir() {
-                        return __WASI_ENOTDIR;
-                    } else if !adjusted_path.exists() {
-                        wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
+                    match state.fs.fs_backend.metadata(&adjusted_path) {
+                        Ok(metadata) if !metadata.is_dir() => return __WASI_ENOTDIR,
+                        Ok(_) => (),
+                        Err(_) => {
+                            let guest_path = state.fs.guest_entry_path(cur_dir_inode, comp);
+                            wasi_try!(state
+                                .fs
+                                .check_policy(guest_path.as_deref(), PathOperation::Create));
+                            wasi_try!(state.fs.check_dir_entry());
+                            wasi_try!(
+                                state.fs.fs_backend.create_dir(&adjusted_path).ok(),
+                                __WASI_EIO
+                            );
+                            state.fs.record_dir_entry();
+                        }
                     }
                     let kind = Kind::Dir {
                         parent: Some(cur_dir_inode),
@@ -1470,7 +1622,7 @@ pub fn path_filestat_get(
     };
 
     let buf_cell = wasi_try!(buf.deref(memory));
-    buf_cell.set(stat);
+    buf_cell.set(visible_filestat(&state, stat));
 
     __WASI_ESUCCESS
 }
@@ -1617,13 +1769,18 @@ pub fn path_link(
     ));
     let target_path_arg = std::path::PathBuf::from(new_path_str);
     let (target_parent_inode, new_entry_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(new_fd, &target_path_arg, false));
+        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, &target_path_arg));
 
     if state.fs.inodes[source_inode].stat.st_nlink == __wasi_linkcount_t::max_value() {
         return __WASI_EMLINK;
     }
+    let guest_path = state
+        .fs
+        .guest_entry_path(target_parent_inode, &new_entry_name);
+    wasi_try!(state
+        .fs
+        .check_policy(guest_path.as_deref(), PathOperation::Create));
+    wasi_try!(state.fs.check_dir_entry());
     match &mut state.fs.inodes[target_parent_inode].kind {
         Kind::Dir { entries, .. } => {
             if entries.contains_key(&new_entry_name) {
@@ -1632,9 +1789,12 @@ pub fn path_link(
             entries.insert(new_entry_name, source_inode);
         }
         Kind::Root { .. } => return __WASI_EINVAL,
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => return __WASI_ENOTDIR,
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
+            return __WASI_ENOTDIR
+        }
     }
     state.fs.inodes[source_inode].stat.st_nlink += 1;
+    state.fs.record_dir_entry();
 
     __WASI_ESUCCESS
 }
@@ -1679,7 +1839,10 @@ pub fn path_open(
     if dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
         debug!("  - will follow symlinks when opening path");
     }
-    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let (memory, mut state_guard) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the filesystem
+    // backend can be borrowed independently
+    let state: &mut WasiState = &mut state_guard;
     /* TODO: find actual upper bound on name size (also this is a path, not a name :think-fish:) */
     if path_len > 1024 * 1024 {
         return __WASI_ENAMETOOLONG;
@@ -1715,9 +1878,21 @@ pub fn path_open(
     // TODO: traverse rights of dirs properly
     // COMMENTED OUT: WASI isn't giving appropriate rights here when opening
     //              TODO: look into this; file a bug report if this is a bug
-    let adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting;
+    let mut adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting;
     let inode = if let Ok(inode) = maybe_inode {
         // Happy path, we found the file we're trying to open
+        // the path policy can take away rights the directory gives
+        let requested_rights = if o_flags & __WASI_O_TRUNC != 0 {
+            fs_rights_base | __WASI_RIGHT_FD_WRITE
+        } else {
+            fs_rights_base
+        };
+        let guest_path = state.fs.guest_path(inode);
+        adjusted_rights = wasi_try!(state.fs.restrict_rights(
+            guest_path.as_deref(),
+            requested_rights,
+            adjusted_rights
+        ));
         match &mut state.fs.inodes[inode].kind {
             Kind::File {
                 ref mut handle,
@@ -1733,10 +1908,10 @@ pub fn path_open(
                 if o_flags & __WASI_O_DIRECTORY != 0 {
                     return __WASI_ENOTDIR;
                 }
-                if o_flags & __WASI_O_EXCL != 0 && path.exists() {
+                if o_flags & __WASI_O_EXCL != 0 && state.fs.fs_backend.metadata(path).is_ok() {
                     return __WASI_EEXIST;
                 }
-                let mut open_options = std::fs::OpenOptions::new();
+                let mut open_options = OpenOptions::new();
                 let write_permission = adjusted_rights & __WASI_RIGHT_FD_WRITE != 0;
                 // append, truncate, and create all require the permission to write
                 let (append_permission, truncate_permission, create_permission) =
@@ -1766,30 +1941,39 @@ pub fn path_open(
                 if o_flags & __WASI_O_TRUNC != 0 {
                     open_flags |= Fd::TRUNCATE;
                 }
-                *handle = Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&path).map_err(|_| __WASI_EIO)),
-                    path.to_path_buf(),
-                    true,
-                    adjusted_rights & __WASI_RIGHT_FD_WRITE != 0,
-                    false,
-                )));
+                *handle = Some(wasi_try!(state
+                    .fs
+                    .fs_backend
+                    .open(&path, open_options)
+                    .map_err(|_| __WASI_EIO)));
+            }
+            Kind::Buffer { buffer } => {
+                if o_flags & __WASI_O_DIRECTORY != 0 {
+                    return __WASI_ENOTDIR;
+                }
+                if o_flags & __WASI_O_EXCL != 0 {
+                    return __WASI_EEXIST;
+                }
+                // buffers live in memory, so there's nothing to open; only the flags matter
+                open_flags |= Fd::READ;
+                if adjusted_rights & __WASI_RIGHT_FD_WRITE != 0 {
+                    open_flags |= Fd::WRITE;
+                    if o_flags & __WASI_O_TRUNC != 0 {
+                        open_flags |= Fd::TRUNCATE;
+                        buffer.clear();
+                    }
+                }
             }
-            Kind::Buffer { .. } => unimplemented!("wasi::path_open for Buffer type files"),
+            // sockets are only reachable through the fds they were granted at
+            Kind::Socket { .. } => return __WASI_ENOTSUP,
             Kind::Dir { .. } | Kind::Root { .. } => {
                 // TODO: adjust these to be correct
-                if o_flags & __WASI_O_EXCL != 0 && path_arg.exists() {
+                if o_flags & __WASI_O_EXCL != 0 {
                     return __WASI_EEXIST;
                 }
             }
-            Kind::Symlink {
-                base_po_dir,
-                path_to_symlink,
-                relative_path,
-            } => {
-                // I think this should return an error (because symlinks should be resolved away by the path traversal)
-                // TODO: investigate this
-                unimplemented!("SYMLINKS IN PATH_OPEN");
-            }
+            // symlinks are only left unresolved when they mustn't be followed
+            Kind::Symlink { .. } => return __WASI_ELOOP,
         }
         inode
     } else {
@@ -1802,11 +1986,8 @@ pub fn path_open(
             debug!("Creating file");
             // strip end file name
 
-            let (parent_inode, new_entity_name) = wasi_try!(state.fs.get_parent_inode_at_path(
-                dirfd,
-                &path_arg,
-                dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0
-            ));
+            let (parent_inode, new_entity_name) =
+                wasi_try!(state.fs.get_parent_inode_at_path(dirfd, &path_arg));
             let new_file_host_path = match &state.fs.inodes[parent_inode].kind {
                 Kind::Dir { path, .. } => {
                     let mut new_path = path.clone();
@@ -1816,10 +1997,20 @@ pub fn path_open(
                 Kind::Root { .. } => return __WASI_EACCES,
                 _ => return __WASI_EINVAL,
             };
+            let guest_path = state.fs.guest_entry_path(parent_inode, &new_entity_name);
+            wasi_try!(state
+                .fs
+                .check_policy(guest_path.as_deref(), PathOperation::Create));
+            adjusted_rights = wasi_try!(state.fs.restrict_rights(
+                guest_path.as_deref(),
+                fs_rights_base,
+                adjusted_rights
+            ));
+            wasi_try!(state.fs.check_dir_entry());
             // once we got the data we need from the parent, we lookup the host file
             // todo: extra check that opening with write access is okay
             let handle = {
-                let mut open_options = std::fs::OpenOptions::new();
+                let mut open_options = OpenOptions::new();
                 let open_options = open_options
                     .read(true)
                     .append(fs_flags & __WASI_FDFLAG_APPEND != 0)
@@ -1829,16 +2020,14 @@ pub fn path_open(
                     .create_new(true);
                 open_flags |= Fd::READ | Fd::WRITE | Fd::CREATE | Fd::TRUNCATE;
 
-                Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&new_file_host_path).map_err(|e| {
+                Some(wasi_try!(state
+                    .fs
+                    .fs_backend
+                    .open(&new_file_host_path, open_options)
+                    .map_err(|e| {
                         debug!("Error opening file {}", e);
                         __WASI_EIO
-                    })),
-                    new_file_host_path.clone(),
-                    true,
-                    true,
-                    true,
-                )) as Box<dyn WasiFile>)
+                    })))
             };
 
             let new_inode = {
@@ -1856,6 +2045,7 @@ pub fn path_open(
             {
                 entries.insert(new_entity_name, new_inode);
             }
+            state.fs.record_dir_entry();
 
             new_inode
         } else {
@@ -1986,15 +2176,18 @@ pub fn path_remove_directory(
     let path_str = unsafe { get_input_str!(memory, path, path_len) };
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
-    let (parent_inode, childs_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    let (parent_inode, childs_name) = wasi_try!(state
+        .fs
+        .get_parent_inode_at_path(fd, std::path::Path::new(path_str)));
+    let guest_path = state.fs.guest_entry_path(parent_inode, &childs_name);
+    wasi_try!(state
+        .fs
+        .check_policy(guest_path.as_deref(), PathOperation::Unlink));
 
     let host_path_to_remove = match &state.fs.inodes[inode].kind {
         Kind::Dir { entries, path, .. } => {
             if !entries.is_empty()
-                || wasi_try!(std::fs::read_dir(path).ok(), __WASI_EIO).count() != 0
+                || !wasi_try!(state.fs.fs_backend.read_dir(path).ok(), __WASI_EIO).is_empty()
             {
                 return __WASI_ENOTEMPTY;
             }
@@ -2018,7 +2211,12 @@ pub fn path_remove_directory(
         ),
     }
 
-    if std::fs::remove_dir(path_str).is_err() {
+    if state
+        .fs
+        .fs_backend
+        .remove_dir(&host_path_to_remove)
+        .is_err()
+    {
         // reinsert to prevent FS from being in bad state
         if let Kind::Dir {
             ref mut entries, ..
@@ -2061,7 +2259,10 @@ pub fn path_rename(
         "wasi::path_rename: old_fd = {}, new_fd = {}",
         old_fd, new_fd
     );
-    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let (memory, mut state_guard) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the filesystem
+    // backend can be borrowed independently
+    let state: &mut WasiState = &mut state_guard;
     let source_str = unsafe { get_input_str!(memory, old_path, old_path_len) };
     let source_path = std::path::Path::new(source_str);
     let target_str = unsafe { get_input_str!(memory, new_path, new_path_len) };
@@ -2080,9 +2281,20 @@ pub fn path_rename(
     }
 
     let (source_parent_inode, source_entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(old_fd, source_path));
     let (target_parent_inode, target_entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path));
+    for (parent_inode, entry_name) in &[
+        (source_parent_inode, &source_entry_name),
+        (target_parent_inode, &target_entry_name),
+    ] {
+        let guest_path = state.fs.guest_entry_path(*parent_inode, entry_name);
+        wasi_try!(state
+            .fs
+            .check_policy(guest_path.as_deref(), PathOperation::Rename));
+    }
+    // loads the source into the inode tree if it isn't there yet
+    let source_inode = wasi_try!(state.fs.get_inode_at_path(old_fd, source_str, false));
 
     let host_adjusted_target_path = match &state.fs.inodes[target_parent_inode].kind {
         Kind::Dir { entries, path, .. } => {
@@ -2090,49 +2302,78 @@ pub fn path_rename(
                 return __WASI_EEXIST;
             }
             let mut out_path = path.clone();
-            out_path.push(target_path);
+            out_path.push(&target_entry_name);
             out_path
         }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } => {
+        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
             unreachable!("Fatal internal logic error: parent of inode is not a directory")
         }
     };
+
+    // a directory can't be moved into itself
+    let mut cur_inode = target_parent_inode;
+    while let Kind::Dir {
+        parent: Some(parent),
+        ..
+    } = &state.fs.inodes[cur_inode].kind
+    {
+        if cur_inode == source_inode {
+            return __WASI_EINVAL;
+        }
+        cur_inode = *parent;
+    }
+
     let source_entry = match &mut state.fs.inodes[source_parent_inode].kind {
         Kind::Dir { entries, .. } => wasi_try!(entries.remove(&source_entry_name), __WASI_EINVAL),
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } => {
+        Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
             unreachable!("Fatal internal logic error: parent of inode is not a directory")
         }
     };
 
-    match &mut state.fs.inodes[source_entry].kind {
+    // symlinks created by the module only exist in the inode tree
+    let symlink_host_path = state.fs.host_symlink_path(source_entry);
+    let result = match &mut state.fs.inodes[source_entry].kind {
         Kind::File {
-            handle,
-            ref mut path,
+            handle: Some(h),
+            fd: Some(_),
             ..
         } => {
-            let result = if let Some(h) = handle {
-                h.rename_file(&host_adjusted_target_path)
-                    .map_err(|e| e.into_wasi_err())
-            } else {
-                let out =
-                    std::fs::rename(&path, &host_adjusted_target_path).map_err(|_| __WASI_EIO);
-                *path = host_adjusted_target_path;
-                out
-            };
-            // if the above operation failed we have to revert the previous change and then fail
-            if let Err(e) = result {
-                if let Kind::Dir { entries, .. } = &mut state.fs.inodes[source_parent_inode].kind {
-                    entries.insert(source_entry_name, source_entry);
-                    return e;
-                }
-            }
+            // special files only know how to rename themselves
+            h.rename_file(&host_adjusted_target_path)
+                .map_err(|e| e.into_wasi_err())
         }
-        Kind::Dir { path, .. } => unimplemented!("wasi::path_rename on Directories"),
-        Kind::Buffer { .. } => {}
-        Kind::Symlink { .. } => {}
+        Kind::File { path, .. } | Kind::Dir { path, .. } => state
+            .fs
+            .fs_backend
+            .rename(path, &host_adjusted_target_path)
+            .map_err(WasiFsError::into_wasi_err),
+        Kind::Symlink { .. } => match symlink_host_path {
+            Some(path) => state
+                .fs
+                .fs_backend
+                .rename(&path, &host_adjusted_target_path)
+                .map_err(WasiFsError::into_wasi_err),
+            None => Ok(()),
+        },
+        Kind::Buffer { .. } | Kind::Socket { .. } => Ok(()),
         Kind::Root { .. } => unreachable!("The root can not be moved"),
+    };
+    // if the above operation failed we have to revert the previous change and then fail
+    if let Err(e) = result {
+        if let Kind::Dir { entries, .. } = &mut state.fs.inodes[source_parent_inode].kind {
+            entries.insert(source_entry_name, source_entry);
+        }
+        return e;
+    }
+
+    state
+        .fs
+        .update_moved_paths(source_entry, host_adjusted_target_path);
+    state.fs.inodes[source_entry].name = target_entry_name.clone();
+    if let Kind::Dir { parent, .. } = &mut state.fs.inodes[source_entry].kind {
+        *parent = Some(target_parent_inode);
     }
 
     if let Kind::Dir { entries, .. } = &mut state.fs.inodes[target_parent_inode].kind {
@@ -2176,14 +2417,9 @@ pub fn path_symlink(
         return __WASI_EACCES;
     }
 
-    // get the depth of the parent + 1 (UNDER INVESTIGATION HMMMMMMMM THINK FISH ^ THINK FISH)
-    let old_path_path = std::path::Path::new(old_path_str);
-    let (source_inode, _) = wasi_try!(state.fs.get_parent_inode_at_path(fd, old_path_path, true));
-    let depth = wasi_try!(state.fs.path_depth_from_fd(fd, source_inode)) - 1;
-
     let new_path_path = std::path::Path::new(new_path_str);
     let (target_parent_inode, entry_name) =
-        wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path, true));
+        wasi_try!(state.fs.get_parent_inode_at_path(fd, new_path_path));
 
     // short circuit if anything is wrong, before we create an inode
     match &state.fs.inodes[target_parent_inode].kind {
@@ -2193,27 +2429,25 @@ pub fn path_symlink(
             }
         }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
-        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } => {
+        Kind::File { .. } | Kind::Symlink { .. } | Kind::Buffer { .. } | Kind::Socket { .. } => {
             unreachable!("get_parent_inode_at_path returned something other than a Dir or Root")
         }
     }
 
-    let mut source_path = std::path::Path::new(old_path_str);
-    let mut relative_path = std::path::PathBuf::new();
-    for _ in 0..depth {
-        relative_path.push("..");
-    }
-    relative_path.push(source_path);
-    debug!(
-        "Symlinking {} to {}",
-        new_path_str,
-        relative_path.to_string_lossy()
-    );
+    let guest_path = state.fs.guest_entry_path(target_parent_inode, &entry_name);
+    wasi_try!(state
+        .fs
+        .check_policy(guest_path.as_deref(), PathOperation::Create));
+    wasi_try!(state.fs.check_dir_entry());
+
+    // like on the host, the contents of the symlink are kept as they are and
+    // only resolved when the symlink is followed
+    debug!("Symlinking {} to {}", new_path_str, old_path_str);
 
     let kind = Kind::Symlink {
         base_po_dir: fd,
         path_to_symlink: std::path::PathBuf::from(new_path_str),
-        relative_path,
+        relative_path: std::path::PathBuf::from(old_path_str),
     };
     let new_inode = state
         .fs
@@ -2225,6 +2459,7 @@ pub fn path_symlink(
     {
         entries.insert(entry_name, new_inode);
     }
+    state.fs.record_dir_entry();
 
     __WASI_ESUCCESS
 }
@@ -2245,7 +2480,10 @@ pub fn path_unlink_file(
     path_len: u32,
 ) -> __wasi_errno_t {
     debug!("wasi::path_unlink_file");
-    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let (memory, mut state_guard) = env.get_memory_and_wasi_state(0);
+    // borrow through the guard once so that the inodes and the filesystem
+    // backend can be borrowed independently
+    let state: &mut WasiState = &mut state_guard;
 
     let base_dir = wasi_try!(state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF));
     if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_UNLINK_FILE) {
@@ -2255,10 +2493,13 @@ pub fn path_unlink_file(
     debug!("Requested file: {}", path_str);
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
-    let (parent_inode, childs_name) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
+    let (parent_inode, childs_name) = wasi_try!(state
+        .fs
+        .get_parent_inode_at_path(fd, std::path::Path::new(path_str)));
+    let guest_path = state.fs.guest_entry_path(parent_inode, &childs_name);
+    wasi_try!(state
+        .fs
+        .check_policy(guest_path.as_deref(), PathOperation::Unlink));
 
     let removed_inode = match &mut state.fs.inodes[parent_inode].kind {
         Kind::Dir {
@@ -2278,22 +2519,37 @@ pub fn path_unlink_file(
 
     state.fs.inodes[removed_inode].stat.st_nlink -= 1;
     if state.fs.inodes[removed_inode].stat.st_nlink == 0 {
+        let symlink_host_path = state.fs.host_symlink_path(removed_inode);
         match &mut state.fs.inodes[removed_inode].kind {
-            Kind::File { handle, path, .. } => {
-                if let Some(h) = handle {
-                    wasi_try!(h.unlink().map_err(WasiFsError::into_wasi_err));
-                } else {
-                    // File is closed
-                    // problem with the abstraction, we can't call unlink because there's no handle
-                    // TODO: replace this code
-                    wasi_try!(std::fs::remove_file(path).map_err(|_| __WASI_EIO));
-                }
+            Kind::File {
+                handle: Some(h),
+                fd: Some(_),
+                ..
+            } => {
+                // special files only know how to unlink themselves
+                wasi_try!(h.unlink().map_err(WasiFsError::into_wasi_err));
+            }
+            Kind::File { path, .. } => {
+                // whether it's open or not, the file lives in the filesystem backend
+                wasi_try!(state
+                    .fs
+                    .fs_backend
+                    .remove_file(path)
+                    .map_err(WasiFsError::into_wasi_err));
             }
             Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
             Kind::Symlink { .. } => {
-                // TODO: actually delete real symlinks and do nothing for virtual symlinks
+                // symlinks created by the module only exist in the inode tree
+                if let Some(path) = symlink_host_path {
+                    wasi_try!(state
+                        .fs
+                        .fs_backend
+                        .remove_file(&path)
+                        .map_err(WasiFsError::into_wasi_err));
+                }
             }
-            _ => unimplemented!("wasi::path_unlink_file for Buffer"),
+            // buffers and sockets only exist in the inode tree
+            Kind::Buffer { .. } | Kind::Socket { .. } => (),
         }
         // TODO: test this on Windows and actually make it portable
         // make the file an orphan fd if the fd is still open
@@ -2345,189 +2601,212 @@ pub fn poll_oneoff(
 
     let subscription_array = wasi_try!(in_.deref(memory, 0, nsubscriptions));
     let event_array = wasi_try!(out_.deref(memory, 0, nsubscriptions));
-    let mut events_seen = 0;
     let out_ptr = wasi_try!(nevents.deref(memory));
 
-    let mut fds = vec![];
+    if nsubscriptions == 0 {
+        return __WASI_EINVAL;
+    }
+
+    let mut events = vec![];
+    let mut fd_requests = vec![];
     let mut clock_subs = vec![];
-    let mut in_events = vec![];
-    let mut total_ns_slept = 0;
+    // the time until the earliest clock deadline
+    let mut timeout: Option<std::time::Duration> = None;
 
     for sub in subscription_array.iter() {
-        let s: WasiSubscription = wasi_try!(sub.get().try_into());
-        let mut peb = PollEventBuilder::new();
-        let mut ns_to_sleep = 0;
-
-        let fd = match s.event_type {
-            EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try!(state.fs.get_fd(fd));
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_READ) {
-                            return __WASI_EACCES;
-                        }
-                    }
-                }
-                in_events.push(peb.add(PollEvent::PollIn).build());
-                Some(fd)
-            }
-            EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try!(state.fs.get_fd(fd));
-
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_WRITE) {
-                            return __WASI_EACCES;
-                        }
-                    }
-                }
-                in_events.push(peb.add(PollEvent::PollOut).build());
-                Some(fd)
-            }
+        let sub = sub.get();
+        let s: WasiSubscription = wasi_try!(sub.try_into());
+        let (fd, peb, rights) = match s.event_type {
             EventType::Clock(clock_info) => {
-                if clock_info.clock_id == __WASI_CLOCK_REALTIME {
-                    // this is a hack
-                    // TODO: do this properly
-                    ns_to_sleep = clock_info.timeout;
-                    clock_subs.push(clock_info);
-                    None
-                } else {
-                    unimplemented!("Polling not implemented for clocks yet");
+                match clock_timeout(&mut state, &clock_info) {
+                    Ok(clock_timeout) => {
+                        timeout = Some(timeout.map_or(clock_timeout, |t| t.min(clock_timeout)));
+                        clock_subs.push((sub, clock_timeout));
+                    }
+                    Err(errno) => events.push(poll_event(&sub, errno, 0, 0)),
                 }
+                continue;
             }
+            EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => (
+                fd,
+                PollEventBuilder::new().add(PollEvent::PollIn),
+                __WASI_RIGHT_FD_READ,
+            ),
+            EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => (
+                fd,
+                PollEventBuilder::new().add(PollEvent::PollOut),
+                __WASI_RIGHT_FD_WRITE,
+            ),
         };
+        fd_requests.push((sub, fd, peb, rights));
+    }
 
-        if let Some(fd) = fd {
-            let wasi_file_ref: &dyn WasiFile = match fd {
-                __WASI_STDERR_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stderr().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDIN_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdin().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                __WASI_STDOUT_FILENO => wasi_try!(
-                    wasi_try!(state.fs.stdout().map_err(WasiFsError::into_wasi_err)).as_ref(),
-                    __WASI_EBADF
-                )
-                .as_ref(),
-                _ => {
-                    let fd_entry = wasi_try!(state.fs.get_fd(fd));
-                    let inode = fd_entry.inode;
-                    if !has_rights(fd_entry.rights, __WASI_RIGHT_POLL_FD_READWRITE) {
-                        return __WASI_EACCES;
-                    }
-
-                    match &state.fs.inodes[inode].kind {
-                        Kind::File { handle, .. } => {
-                            if let Some(h) = handle {
-                                h.as_ref()
-                            } else {
-                                return __WASI_EBADF;
-                            }
-                        }
-                        Kind::Dir { .. }
-                        | Kind::Root { .. }
-                        | Kind::Buffer { .. }
-                        | Kind::Symlink { .. } => {
-                            unimplemented!("polling read on non-files not yet supported")
-                        }
-                    }
-                }
-            };
-            fds.push(wasi_file_ref);
-        } else {
-            let remaining_ns = ns_to_sleep as i64 - total_ns_slept as i64;
-            if remaining_ns > 0 {
-                debug!("Sleeping for {} nanoseconds", remaining_ns);
-                let duration = std::time::Duration::from_nanos(remaining_ns as u64);
-                std::thread::sleep(duration);
-                total_ns_slept += remaining_ns;
+    let mut targets = vec![];
+    let mut in_events = vec![];
+    let mut fd_subs = vec![];
+    for (sub, fd, peb, rights) in fd_requests {
+        match poll_target(&state, fd, rights | __WASI_RIGHT_POLL_FD_READWRITE) {
+            Ok(target) => {
+                targets.push(target);
+                in_events.push(peb.build());
+                fd_subs.push(sub);
             }
+            Err(errno) => events.push(poll_event(&sub, errno, 0, 0)),
         }
     }
+
+    // time only passes on virtual clocks when they're read or advanced:
+    // instead of waiting for them, clock subscriptions fire if no fd is ready
+    let virtual_clock = state.clock.is_virtual();
+    // subscriptions which failed are reported without waiting
+    let wait = if events.is_empty() && !(virtual_clock && timeout.is_some()) {
+        timeout
+    } else {
+        Some(std::time::Duration::from_secs(0))
+    };
+    let start = std::time::Instant::now();
     let mut seen_events = vec![Default::default(); in_events.len()];
     wasi_try!(poll(
-        fds.as_slice(),
+        targets.as_slice(),
         in_events.as_slice(),
-        seen_events.as_mut_slice()
+        seen_events.as_mut_slice(),
+        wait,
     )
     .map_err(|e| e.into_wasi_err()));
+    let elapsed = if !virtual_clock {
+        start.elapsed()
+    } else if events.is_empty() && seen_events.iter().all(|seen_event| *seen_event == 0) {
+        timeout.unwrap_or_default()
+    } else {
+        std::time::Duration::from_secs(0)
+    };
 
-    for (i, seen_event) in seen_events.into_iter().enumerate() {
+    for ((sub, target), seen_event) in fd_subs.iter().zip(targets.iter()).zip(seen_events) {
+        if seen_event == 0 {
+            continue;
+        }
         let mut flags = 0;
-        let mut error = __WASI_EAGAIN;
-        let mut bytes_available = 0;
-        let event_iter = iterate_poll_events(seen_event);
-        for event in event_iter {
+        let mut error = __WASI_ESUCCESS;
+        let mut nbytes = 0;
+        for event in iterate_poll_events(seen_event) {
             match event {
                 PollEvent::PollError => error = __WASI_EIO,
                 PollEvent::PollHangUp => flags = __WASI_EVENT_FD_READWRITE_HANGUP,
-                PollEvent::PollInvalid => error = __WASI_EINVAL,
-                PollEvent::PollIn => {
-                    bytes_available =
-                        wasi_try!(fds[i].bytes_available().map_err(|e| e.into_wasi_err()));
-                    error = __WASI_ESUCCESS;
-                }
-                PollEvent::PollOut => {
-                    bytes_available =
-                        wasi_try!(fds[i].bytes_available().map_err(|e| e.into_wasi_err()));
-                    error = __WASI_ESUCCESS;
-                }
+                PollEvent::PollInvalid => error = __WASI_EBADF,
+                // the number of bytes is only a hint, so failing to get it isn't an error
+                PollEvent::PollIn => nbytes = target.bytes_available().unwrap_or(0),
+                // how much can be written without blocking isn't known
+                PollEvent::PollOut => nbytes = 0,
             }
         }
-        let event = __wasi_event_t {
-            userdata: subscription_array[i].get().userdata,
-            error,
-            type_: subscription_array[i].get().type_,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: bytes_available as u64,
-                        flags,
-                    },
-                }
-            },
-        };
-        event_array[events_seen].set(event);
-        events_seen += 1;
-    }
-    for clock_info in clock_subs {
-        let event = __wasi_event_t {
-            // TOOD: review userdata value
-            userdata: 0,
-            error: __WASI_ESUCCESS,
-            type_: __WASI_EVENTTYPE_CLOCK,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: 0,
-                        flags: 0,
-                    },
-                }
-            },
-        };
-        event_array[events_seen].set(event);
-        events_seen += 1;
+        events.push(poll_event(sub, error, nbytes as u64, flags));
     }
-    out_ptr.set(events_seen as u32);
+    if virtual_clock {
+        state
+            .clock
+            .advance(elapsed.as_nanos() as __wasi_timestamp_t);
+    }
+    for (sub, clock_timeout) in clock_subs {
+        if elapsed >= clock_timeout {
+            events.push(poll_event(&sub, __WASI_ESUCCESS, 0, 0));
+        }
+    }
+
+    for (event_cell, event) in event_array.iter().zip(events.iter()) {
+        event_cell.set(*event);
+    }
+    out_ptr.set(events.len() as u32);
     __WASI_ESUCCESS
 }
 
+/// How long a clock subscription of `poll_oneoff` waits, taking into
+/// account whether its timeout is absolute or relative
+fn clock_timeout(
+    state: &mut WasiState,
+    clock_info: &__wasi_subscription_clock_t,
+) -> Result<std::time::Duration, __wasi_errno_t> {
+    let now = state.clock.time(clock_info.clock_id, 1)?;
+    let nanos = if clock_info.flags & __WASI_SUBSCRIPTION_CLOCK_ABSTIME != 0 {
+        clock_info.timeout.saturating_sub(now)
+    } else {
+        clock_info.timeout
+    };
+    Ok(std::time::Duration::from_nanos(nanos))
+}
+
+/// Look up what `poll_oneoff` should wait on for `fd`, checking that it has
+/// `rights`
+fn poll_target(
+    state: &WasiState,
+    fd: __wasi_fd_t,
+    rights: __wasi_rights_t,
+) -> Result<PollTarget, __wasi_errno_t> {
+    let fd_entry = state.fs.get_fd(fd)?;
+    if !has_rights(fd_entry.rights, rights) {
+        return Err(__WASI_EACCES);
+    }
+    match &state.fs.inodes[fd_entry.inode].kind {
+        Kind::File { handle, .. } => handle
+            .as_ref()
+            .map(|h| PollTarget::File(h.as_ref()))
+            .ok_or(__WASI_EBADF),
+        Kind::Socket { handle } => handle.as_ref().map(PollTarget::Socket).ok_or(__WASI_EBADF),
+        Kind::Dir { .. } | Kind::Root { .. } => Err(__WASI_EISDIR),
+        Kind::Buffer { .. } | Kind::Symlink { .. } => Err(__WASI_EINVAL),
+    }
+}
+
+/// An event answering the subscription `sub` of `poll_oneoff`
+fn poll_event(
+    sub: &__wasi_subscription_t,
+    error: __wasi_errno_t,
+    nbytes: __wasi_filesize_t,
+    flags: __wasi_eventrwflags_t,
+) -> __wasi_event_t {
+    __wasi_event_t {
+        userdata: sub.userdata,
+        error,
+        type_: sub.type_,
+        u: __wasi_event_u {
+            fd_readwrite: __wasi_event_fd_readwrite_t { nbytes, flags },
+        },
+    }
+}
+
 pub fn proc_exit(env: &WasiEnv, code: __wasi_exitcode_t) {
     debug!("wasi::proc_exit, {}", code);
     RuntimeError::raise(Box::new(WasiError::Exit(code)));
     unreachable!();
 }
 
+/// ### `proc_raise()`
+/// Send a signal to the process of the calling thread.
+/// The signals whose default action is to terminate the process terminate
+/// the instance with `WasiError::Signal`, the others are ignored as there's
+/// no job control.
+/// Inputs:
+/// - `__wasi_signal_t sig`
+///     The signal condition to trigger
 pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
-    debug!("wasi::proc_raise");
-    unimplemented!("wasi::proc_raise")
+    debug!("wasi::proc_raise, {}", sig);
+    if sig > __WASI_SIGSYS {
+        return __WASI_EINVAL;
+    }
+    if signal_terminates(sig) {
+        RuntimeError::raise(Box::new(WasiError::Signal(sig)));
+        unreachable!();
+    }
+    __WASI_ESUCCESS
+}
+
+/// Whether the default action of the signal `sig` is to terminate the
+/// process, with or without a core dump.
+pub(crate) fn signal_terminates(sig: __wasi_signal_t) -> bool {
+    match sig {
+        0 | __WASI_SIGCHLD | __WASI_SIGCONT | __WASI_SIGSTOP | __WASI_SIGTSTP | __WASI_SIGTTIN
+        | __WASI_SIGTTOU | __WASI_SIGURG | __WASI_SIGWINCH => false,
+        _ => sig <= __WASI_SIGSYS,
+    }
 }
 
 /// ### `random_get()`
@@ -2539,18 +2818,13 @@ pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
 ///     The number of bytes that will be written
 pub fn random_get(env: &WasiEnv, buf: WasmPtr<u8, Array>, buf_len: u32) -> __wasi_errno_t {
     debug!("wasi::random_get buf_len: {}", buf_len);
-    let memory = env.memory();
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let buf = wasi_try!(buf.deref(memory, 0, buf_len));
 
-    let res = unsafe {
-        let u8_buffer = &mut *(buf as *const [_] as *mut [_] as *mut [u8]);
-        getrandom::getrandom(u8_buffer)
-    };
-    match res {
-        Ok(()) => __WASI_ESUCCESS,
-        Err(_) => __WASI_EIO,
-    }
+    let u8_buffer = unsafe { &mut *(buf as *const [_] as *mut [_] as *mut [u8]) };
+    wasi_try!(state.random.fill(u8_buffer));
+    __WASI_ESUCCESS
 }
 
 /// ### `sched_yield()`
@@ -2561,6 +2835,39 @@ pub fn sched_yield(env: &WasiEnv) -> __wasi_errno_t {
     __WASI_ESUCCESS
 }
 
+/// Look up the socket behind `sock`, checking that it has `rights`
+fn get_socket(
+    state: &WasiState,
+    sock: __wasi_fd_t,
+    rights: __wasi_rights_t,
+) -> Result<&WasiSocket, __wasi_errno_t> {
+    let fd_entry = state.fs.get_fd(sock)?;
+    if !has_rights(fd_entry.rights, rights) {
+        return Err(__WASI_EACCES);
+    }
+    match &state.fs.inodes[fd_entry.inode].kind {
+        Kind::Socket { handle } => handle.as_ref().ok_or(__WASI_EBADF),
+        _ => Err(__WASI_ENOTSOCK),
+    }
+}
+
+/// ### `sock_recv()`
+/// Receive a message from a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to receive from
+/// - `const __wasi_iovec_t *ri_data`
+///     Vectors where the received data will be stored
+/// - `u32 ri_data_len`
+///     Length of data in `ri_data`
+/// - `__wasi_riflags_t ri_flags`
+///     Flags controlling the receive; `__WASI_SOCK_RECV_PEEK` leaves the data
+///     in the socket, `__WASI_SOCK_RECV_WAITALL` isn't supported
+/// Output:
+/// - `u32 *ro_datalen`
+///     Number of bytes received
+/// - `__wasi_roflags_t *ro_flags`
+///     Flags describing the received message
 pub fn sock_recv(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2570,9 +2877,43 @@ pub fn sock_recv(
     ro_datalen: WasmPtr<u32>,
     ro_flags: WasmPtr<__wasi_roflags_t>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_recv");
-    unimplemented!("wasi::sock_recv")
+    debug!("wasi::sock_recv: sock={}, ri_flags={}", sock, ri_flags);
+    let (memory, state) = env.get_memory_and_wasi_state(0);
+
+    let iovs_arr_cell = wasi_try!(ri_data.deref(memory, 0, ri_data_len));
+    let ro_datalen_cell = wasi_try!(ro_datalen.deref(memory));
+    let ro_flags_cell = wasi_try!(ro_flags.deref(memory));
+
+    if ri_flags & __WASI_SOCK_RECV_WAITALL != 0 {
+        return __WASI_ENOTSUP;
+    }
+    if ri_flags & !__WASI_SOCK_RECV_PEEK != 0 {
+        return __WASI_EINVAL;
+    }
+    let socket = wasi_try!(get_socket(&state, sock, __WASI_RIGHT_FD_READ));
+    let peek = ri_flags & __WASI_SOCK_RECV_PEEK != 0;
+    let bytes_read = wasi_try!(recv_bytes(socket, memory, iovs_arr_cell, peek));
+
+    ro_datalen_cell.set(bytes_read);
+    ro_flags_cell.set(0);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_send()`
+/// Send a message on a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to send on
+/// - `const __wasi_ciovec_t *si_data`
+///     Vectors holding the data to send
+/// - `u32 si_data_len`
+///     Length of data in `si_data`
+/// - `__wasi_siflags_t si_flags`
+///     Flags controlling the send; none are defined
+/// Output:
+/// - `u32 *so_datalen`
+///     Number of bytes sent
 pub fn sock_send(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2581,10 +2922,42 @@ pub fn sock_send(
     si_flags: __wasi_siflags_t,
     so_datalen: WasmPtr<u32>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_send");
-    unimplemented!("wasi::sock_send")
+    debug!("wasi::sock_send: sock={}", sock);
+    let (memory, state) = env.get_memory_and_wasi_state(0);
+
+    let iovs_arr_cell = wasi_try!(si_data.deref(memory, 0, si_data_len));
+    let so_datalen_cell = wasi_try!(so_datalen.deref(memory));
+
+    if si_flags != 0 {
+        return __WASI_EINVAL;
+    }
+    let socket = wasi_try!(get_socket(&state, sock, __WASI_RIGHT_FD_WRITE));
+    let bytes_written = wasi_try!(send_bytes(socket, memory, iovs_arr_cell));
+
+    so_datalen_cell.set(bytes_written);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_shutdown()`
+/// Shut down the receiving and/or sending half of a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to shut down
+/// - `__wasi_sdflags_t how`
+///     Which halves to shut down: `__WASI_SHUT_RD`, `__WASI_SHUT_WR` or both
 pub fn sock_shutdown(env: &WasiEnv, sock: __wasi_fd_t, how: __wasi_sdflags_t) -> __wasi_errno_t {
-    debug!("wasi::sock_shutdown");
-    unimplemented!("wasi::sock_shutdown")
+    debug!("wasi::sock_shutdown: sock={}, how={}", sock, how);
+    let state = env.state();
+
+    let how = match how {
+        __WASI_SHUT_RD => std::net::Shutdown::Read,
+        __WASI_SHUT_WR => std::net::Shutdown::Write,
+        both if both == __WASI_SHUT_RD | __WASI_SHUT_WR => std::net::Shutdown::Both,
+        _ => return __WASI_EINVAL,
+    };
+    let socket = wasi_try!(get_socket(&state, sock, __WASI_RIGHT_SOCK_SHUTDOWN));
+    wasi_try!(socket.shutdown(how).map_err(WasiFsError::into_wasi_err));
+
+    __WASI_ESUCCESS
 }
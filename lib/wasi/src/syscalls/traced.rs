@@ -0,0 +1,340 @@
+//! Wrappers around the syscalls reporting them to the [`SyscallTracer`] of
+//! the [`WasiEnv`].
+//!
+//! They're only imported by the module when a tracer is set, so that
+//! tracing costs nothing otherwise.
+//!
+//! [`SyscallTracer`]: crate::SyscallTracer
+
+use super::types::*;
+use crate::ptr::{Array, WasmPtr};
+use crate::syscalls as wasi;
+use crate::trace::{
+    format_flags, ArgValue, SyscallArg, SyscallRecord, FD_FLAGS, FST_FLAGS, LOOKUP_FLAGS,
+    OPEN_FLAGS,
+};
+use crate::WasiEnv;
+use std::time::{Duration, Instant};
+use wasmer::Memory;
+
+/// Decode an argument of a syscall according to its kind.
+macro_rules! arg_value {
+    ($memory:ident, fd, $arg:ident) => {
+        ArgValue::Fd($arg)
+    };
+    ($memory:ident, int, $arg:ident) => {
+        ArgValue::Int($arg as i64)
+    };
+    ($memory:ident, ptr, $arg:ident) => {
+        ArgValue::Ptr($arg.offset())
+    };
+    ($memory:ident, path, $arg:ident, $len:ident) => {
+        path_value($memory, $arg, $len)
+    };
+    ($memory:ident, rights, $arg:ident) => {
+        ArgValue::Flags(format!("{:#x}", $arg))
+    };
+    ($memory:ident, lookupflags, $arg:ident) => {
+        ArgValue::Flags(format_flags($arg as u64, LOOKUP_FLAGS))
+    };
+    ($memory:ident, oflags, $arg:ident) => {
+        ArgValue::Flags(format_flags($arg as u64, OPEN_FLAGS))
+    };
+    ($memory:ident, fdflags, $arg:ident) => {
+        ArgValue::Flags(format_flags($arg as u64, FD_FLAGS))
+    };
+    ($memory:ident, fstflags, $arg:ident) => {
+        ArgValue::Flags(format_flags($arg as u64, FST_FLAGS))
+    };
+}
+
+/// Define wrappers with the same signatures as the syscalls of `$module`,
+/// describing each argument with one of the kinds of `arg_value!`.
+macro_rules! traced {
+    ($module:ident; $(
+        fn $name:ident($($arg:ident: $ty:ty => $kind:ident $(($len:ident))?),* $(,)?);
+    )*) => {
+        $(
+            pub fn $name(env: &WasiEnv, $($arg: $ty),*) -> __wasi_errno_t {
+                #[allow(unused_variables)]
+                let memory = env.memory();
+                let args = vec![$(SyscallArg {
+                    name: stringify!($arg),
+                    value: arg_value!(memory, $kind, $arg $(, $len)?),
+                }),*];
+                let start = Instant::now();
+                let errno = $module::$name(env, $($arg),*);
+                record(env, SyscallRecord {
+                    name: stringify!($name),
+                    args,
+                    errno: Some(errno),
+                    duration: start.elapsed(),
+                });
+                errno
+            }
+        )*
+    };
+}
+
+fn record(env: &WasiEnv, record: SyscallRecord) {
+    if let Some(tracer) = &env.tracer {
+        tracer.record(&record);
+    }
+}
+
+/// The path at `path`, or just the pointer if it can't be read.
+fn path_value(memory: &Memory, path: WasmPtr<u8, Array>, path_len: u32) -> ArgValue {
+    match path.get_utf8_string(memory, path_len) {
+        Some(path) => ArgValue::Path(path),
+        None => ArgValue::Ptr(path.offset()),
+    }
+}
+
+traced! {
+    wasi;
+    fn args_get(argv: WasmPtr<WasmPtr<u8, Array>, Array> => ptr, argv_buf: WasmPtr<u8, Array> => ptr);
+    fn args_sizes_get(argc: WasmPtr<u32> => ptr, argv_buf_size: WasmPtr<u32> => ptr);
+    fn clock_res_get(clock_id: __wasi_clockid_t => int, resolution: WasmPtr<__wasi_timestamp_t> => ptr);
+    fn clock_time_get(
+        clock_id: __wasi_clockid_t => int,
+        precision: __wasi_timestamp_t => int,
+        time: WasmPtr<__wasi_timestamp_t> => ptr,
+    );
+    fn environ_get(environ: WasmPtr<WasmPtr<u8, Array>, Array> => ptr, environ_buf: WasmPtr<u8, Array> => ptr);
+    fn environ_sizes_get(environ_count: WasmPtr<u32> => ptr, environ_buf_size: WasmPtr<u32> => ptr);
+    fn fd_advise(
+        fd: __wasi_fd_t => fd,
+        offset: __wasi_filesize_t => int,
+        len: __wasi_filesize_t => int,
+        advice: __wasi_advice_t => int,
+    );
+    fn fd_allocate(fd: __wasi_fd_t => fd, offset: __wasi_filesize_t => int, len: __wasi_filesize_t => int);
+    fn fd_close(fd: __wasi_fd_t => fd);
+    fn fd_datasync(fd: __wasi_fd_t => fd);
+    fn fd_fdstat_get(fd: __wasi_fd_t => fd, buf_ptr: WasmPtr<__wasi_fdstat_t> => ptr);
+    fn fd_fdstat_set_flags(fd: __wasi_fd_t => fd, flags: __wasi_fdflags_t => fdflags);
+    fn fd_fdstat_set_rights(
+        fd: __wasi_fd_t => fd,
+        fs_rights_base: __wasi_rights_t => rights,
+        fs_rights_inheriting: __wasi_rights_t => rights,
+    );
+    fn fd_filestat_get(fd: __wasi_fd_t => fd, buf: WasmPtr<__wasi_filestat_t> => ptr);
+    fn fd_filestat_set_size(fd: __wasi_fd_t => fd, st_size: __wasi_filesize_t => int);
+    fn fd_filestat_set_times(
+        fd: __wasi_fd_t => fd,
+        st_atim: __wasi_timestamp_t => int,
+        st_mtim: __wasi_timestamp_t => int,
+        fst_flags: __wasi_fstflags_t => fstflags,
+    );
+    fn fd_pread(
+        fd: __wasi_fd_t => fd,
+        iovs: WasmPtr<__wasi_iovec_t, Array> => ptr,
+        iovs_len: u32 => int,
+        offset: __wasi_filesize_t => int,
+        nread: WasmPtr<u32> => ptr,
+    );
+    fn fd_prestat_get(fd: __wasi_fd_t => fd, buf: WasmPtr<__wasi_prestat_t> => ptr);
+    fn fd_prestat_dir_name(fd: __wasi_fd_t => fd, path: WasmPtr<u8, Array> => ptr, path_len: u32 => int);
+    fn fd_pwrite(
+        fd: __wasi_fd_t => fd,
+        iovs: WasmPtr<__wasi_ciovec_t, Array> => ptr,
+        iovs_len: u32 => int,
+        offset: __wasi_filesize_t => int,
+        nwritten: WasmPtr<u32> => ptr,
+    );
+    fn fd_read(
+        fd: __wasi_fd_t => fd,
+        iovs: WasmPtr<__wasi_iovec_t, Array> => ptr,
+        iovs_len: u32 => int,
+        nread: WasmPtr<u32> => ptr,
+    );
+    fn fd_readdir(
+        fd: __wasi_fd_t => fd,
+        buf: WasmPtr<u8, Array> => ptr,
+        buf_len: u32 => int,
+        cookie: __wasi_dircookie_t => int,
+        bufused: WasmPtr<u32> => ptr,
+    );
+    fn fd_renumber(from: __wasi_fd_t => fd, to: __wasi_fd_t => fd);
+    fn fd_seek(
+        fd: __wasi_fd_t => fd,
+        offset: __wasi_filedelta_t => int,
+        whence: __wasi_whence_t => int,
+        newoffset: WasmPtr<__wasi_filesize_t> => ptr,
+    );
+    fn fd_sync(fd: __wasi_fd_t => fd);
+    fn fd_tell(fd: __wasi_fd_t => fd, offset: WasmPtr<__wasi_filesize_t> => ptr);
+    fn fd_write(
+        fd: __wasi_fd_t => fd,
+        iovs: WasmPtr<__wasi_ciovec_t, Array> => ptr,
+        iovs_len: u32 => int,
+        nwritten: WasmPtr<u32> => ptr,
+    );
+    fn path_create_directory(fd: __wasi_fd_t => fd, path: WasmPtr<u8, Array> => path(path_len), path_len: u32 => int);
+    fn path_filestat_get(
+        fd: __wasi_fd_t => fd,
+        flags: __wasi_lookupflags_t => lookupflags,
+        path: WasmPtr<u8, Array> => path(path_len),
+        path_len: u32 => int,
+        buf: WasmPtr<__wasi_filestat_t> => ptr,
+    );
+    fn path_filestat_set_times(
+        fd: __wasi_fd_t => fd,
+        flags: __wasi_lookupflags_t => lookupflags,
+        path: WasmPtr<u8, Array> => path(path_len),
+        path_len: u32 => int,
+        st_atim: __wasi_timestamp_t => int,
+        st_mtim: __wasi_timestamp_t => int,
+        fst_flags: __wasi_fstflags_t => fstflags,
+    );
+    fn path_link(
+        old_fd: __wasi_fd_t => fd,
+        old_flags: __wasi_lookupflags_t => lookupflags,
+        old_path: WasmPtr<u8, Array> => path(old_path_len),
+        old_path_len: u32 => int,
+        new_fd: __wasi_fd_t => fd,
+        new_path: WasmPtr<u8, Array> => path(new_path_len),
+        new_path_len: u32 => int,
+    );
+    fn path_open(
+        dirfd: __wasi_fd_t => fd,
+        dirflags: __wasi_lookupflags_t => lookupflags,
+        path: WasmPtr<u8, Array> => path(path_len),
+        path_len: u32 => int,
+        o_flags: __wasi_oflags_t => oflags,
+        fs_rights_base: __wasi_rights_t => rights,
+        fs_rights_inheriting: __wasi_rights_t => rights,
+        fs_flags: __wasi_fdflags_t => fdflags,
+        fd: WasmPtr<__wasi_fd_t> => ptr,
+    );
+    fn path_readlink(
+        dir_fd: __wasi_fd_t => fd,
+        path: WasmPtr<u8, Array> => path(path_len),
+        path_len: u32 => int,
+        buf: WasmPtr<u8, Array> => ptr,
+        buf_len: u32 => int,
+        buf_used: WasmPtr<u32> => ptr,
+    );
+    fn path_remove_directory(fd: __wasi_fd_t => fd, path: WasmPtr<u8, Array> => path(path_len), path_len: u32 => int);
+    fn path_rename(
+        old_fd: __wasi_fd_t => fd,
+        old_path: WasmPtr<u8, Array> => path(old_path_len),
+        old_path_len: u32 => int,
+        new_fd: __wasi_fd_t => fd,
+        new_path: WasmPtr<u8, Array> => path(new_path_len),
+        new_path_len: u32 => int,
+    );
+    fn path_symlink(
+        old_path: WasmPtr<u8, Array> => path(old_path_len),
+        old_path_len: u32 => int,
+        fd: __wasi_fd_t => fd,
+        new_path: WasmPtr<u8, Array> => path(new_path_len),
+        new_path_len: u32 => int,
+    );
+    fn path_unlink_file(fd: __wasi_fd_t => fd, path: WasmPtr<u8, Array> => path(path_len), path_len: u32 => int);
+    fn poll_oneoff(
+        in_: WasmPtr<__wasi_subscription_t, Array> => ptr,
+        out_: WasmPtr<__wasi_event_t, Array> => ptr,
+        nsubscriptions: u32 => int,
+        nevents: WasmPtr<u32> => ptr,
+    );
+    fn random_get(buf: WasmPtr<u8, Array> => ptr, buf_len: u32 => int);
+    fn sched_yield();
+    fn sock_recv(
+        sock: __wasi_fd_t => fd,
+        ri_data: WasmPtr<__wasi_iovec_t, Array> => ptr,
+        ri_data_len: u32 => int,
+        ri_flags: __wasi_riflags_t => int,
+        ro_datalen: WasmPtr<u32> => ptr,
+        ro_flags: WasmPtr<__wasi_roflags_t> => ptr,
+    );
+    fn sock_send(
+        sock: __wasi_fd_t => fd,
+        si_data: WasmPtr<__wasi_ciovec_t, Array> => ptr,
+        si_data_len: u32 => int,
+        si_flags: __wasi_siflags_t => int,
+        so_datalen: WasmPtr<u32> => ptr,
+    );
+    fn sock_shutdown(sock: __wasi_fd_t => fd, how: __wasi_sdflags_t => int);
+}
+
+/// `proc_exit` doesn't return, so it's reported before exiting.
+pub fn proc_exit(env: &WasiEnv, code: __wasi_exitcode_t) {
+    record(
+        env,
+        SyscallRecord {
+            name: "proc_exit",
+            args: vec![SyscallArg {
+                name: "code",
+                value: ArgValue::Int(code as i64),
+            }],
+            errno: None,
+            duration: Duration::from_secs(0),
+        },
+    );
+    wasi::proc_exit(env, code)
+}
+
+/// `proc_raise` doesn't return for the signals terminating the process, so
+/// these are reported before raising them.
+pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
+    let args = vec![SyscallArg {
+        name: "sig",
+        value: ArgValue::Int(sig as i64),
+    }];
+    if wasi::signal_terminates(sig) {
+        record(
+            env,
+            SyscallRecord {
+                name: "proc_raise",
+                args,
+                errno: None,
+                duration: Duration::from_secs(0),
+            },
+        );
+        return wasi::proc_raise(env, sig);
+    }
+    let errno = wasi::proc_raise(env, sig);
+    record(
+        env,
+        SyscallRecord {
+            name: "proc_raise",
+            args,
+            errno: Some(errno),
+            duration: Duration::from_secs(0),
+        },
+    );
+    errno
+}
+
+pub mod legacy {
+    pub mod snapshot0 {
+        use super::super::*;
+        use crate::syscalls::legacy::snapshot0 as wasi;
+        use crate::syscalls::types::snapshot0;
+
+        traced! {
+            wasi;
+            fn fd_filestat_get(fd: __wasi_fd_t => fd, buf: WasmPtr<snapshot0::__wasi_filestat_t> => ptr);
+            fn path_filestat_get(
+                fd: __wasi_fd_t => fd,
+                flags: __wasi_lookupflags_t => lookupflags,
+                path: WasmPtr<u8, Array> => path(path_len),
+                path_len: u32 => int,
+                buf: WasmPtr<snapshot0::__wasi_filestat_t> => ptr,
+            );
+            fn fd_seek(
+                fd: __wasi_fd_t => fd,
+                offset: __wasi_filedelta_t => int,
+                whence: snapshot0::__wasi_whence_t => int,
+                newoffset: WasmPtr<__wasi_filesize_t> => ptr,
+            );
+            fn poll_oneoff(
+                in_: WasmPtr<snapshot0::__wasi_subscription_t, Array> => ptr,
+                out_: WasmPtr<__wasi_event_t, Array> => ptr,
+                nsubscriptions: u32 => int,
+                nevents: WasmPtr<u32> => ptr,
+            );
+        }
+    }
+}
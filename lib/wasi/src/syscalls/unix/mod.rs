@@ -63,3 +63,44 @@ pub fn platform_clock_time_get(
     // TODO: map output of clock_gettime to __wasi_errno_t
     __WASI_ESUCCESS
 }
+
+/// The number of the host's signal matching the WASI signal `sig`, if the
+/// host has one. They differ from WASI's numbers above `SIGTERM` on Linux
+/// and above `SIGABRT` on macOS.
+pub fn host_signal(sig: __wasi_signal_t) -> Option<i32> {
+    Some(match sig {
+        __WASI_SIGHUP => libc::SIGHUP,
+        __WASI_SIGINT => libc::SIGINT,
+        __WASI_SIGQUIT => libc::SIGQUIT,
+        __WASI_SIGILL => libc::SIGILL,
+        __WASI_SIGTRAP => libc::SIGTRAP,
+        __WASI_SIGABRT => libc::SIGABRT,
+        __WASI_SIGBUS => libc::SIGBUS,
+        __WASI_SIGFPE => libc::SIGFPE,
+        __WASI_SIGKILL => libc::SIGKILL,
+        __WASI_SIGUSR1 => libc::SIGUSR1,
+        __WASI_SIGSEGV => libc::SIGSEGV,
+        __WASI_SIGUSR2 => libc::SIGUSR2,
+        __WASI_SIGPIPE => libc::SIGPIPE,
+        __WASI_SIGALRM => libc::SIGALRM,
+        __WASI_SIGTERM => libc::SIGTERM,
+        __WASI_SIGCHLD => libc::SIGCHLD,
+        __WASI_SIGCONT => libc::SIGCONT,
+        __WASI_SIGSTOP => libc::SIGSTOP,
+        __WASI_SIGTSTP => libc::SIGTSTP,
+        __WASI_SIGTTIN => libc::SIGTTIN,
+        __WASI_SIGTTOU => libc::SIGTTOU,
+        __WASI_SIGURG => libc::SIGURG,
+        __WASI_SIGXCPU => libc::SIGXCPU,
+        __WASI_SIGXFSZ => libc::SIGXFSZ,
+        __WASI_SIGVTALRM => libc::SIGVTALRM,
+        __WASI_SIGPROF => libc::SIGPROF,
+        __WASI_SIGWINCH => libc::SIGWINCH,
+        // `SIGPOLL` is `SIGIO` where both exist
+        __WASI_SIGPOLL => libc::SIGIO,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        __WASI_SIGPWR => libc::SIGPWR,
+        __WASI_SIGSYS => libc::SIGSYS,
+        _ => return None,
+    })
+}
@@ -54,3 +54,17 @@ pub fn platform_clock_time_get(
     time.set(nanos);
     __WASI_ESUCCESS
 }
+
+/// The number of the host's signal matching the WASI signal `sig`, if the
+/// host has one: the C runtime of Windows only has a few of them.
+pub fn host_signal(sig: __wasi_signal_t) -> Option<i32> {
+    Some(match sig {
+        __WASI_SIGINT => 2,
+        __WASI_SIGILL => 4,
+        __WASI_SIGFPE => 8,
+        __WASI_SIGSEGV => 11,
+        __WASI_SIGTERM => 15,
+        __WASI_SIGABRT => 22,
+        _ => return None,
+    })
+}
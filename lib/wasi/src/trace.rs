@@ -0,0 +1,323 @@
+//! Structured tracing of the syscalls made by a WASI module, like `strace`.
+//!
+//! When a [`SyscallTracer`] is set on the [`WasiEnv`], every syscall is
+//! described by a [`SyscallRecord`] with its decoded arguments, its result
+//! and how long it took, and handed to the tracer.  [`TraceWriter`] writes
+//! the records to any [`Write`] as text or as JSON lines.
+//!
+//! [`WasiEnv`]: crate::WasiEnv
+
+use crate::syscalls::types::*;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Where the syscalls of a WASI module are reported.
+pub trait SyscallTracer: fmt::Debug + Send + Sync {
+    /// Report a syscall, once it returned.
+    fn record(&self, record: &SyscallRecord);
+}
+
+/// A syscall made by a WASI module.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyscallRecord {
+    /// The name of the syscall, e.g. `path_open`
+    pub name: &'static str,
+    /// The arguments, in order
+    pub args: Vec<SyscallArg>,
+    /// The result, or `None` if the syscall doesn't return, like `proc_exit`
+    pub errno: Option<__wasi_errno_t>,
+    /// How long the syscall took
+    #[serde(rename = "duration_ns", serialize_with = "serialize_nanos")]
+    pub duration: Duration,
+}
+
+/// A named argument of a [`SyscallRecord`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyscallArg {
+    pub name: &'static str,
+    pub value: ArgValue,
+}
+
+/// The decoded value of an argument of a syscall.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgValue {
+    /// A file descriptor
+    Fd(__wasi_fd_t),
+    /// A number, like a size or an offset
+    Int(i64),
+    /// A set of flags, e.g. `CREAT|TRUNC`
+    Flags(String),
+    /// A path read from the memory of the module
+    Path(String),
+    /// A pointer into the memory of the module
+    Ptr(u32),
+}
+
+impl fmt::Display for ArgValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fd(fd) => write!(f, "{}", fd),
+            Self::Int(int) => write!(f, "{}", int),
+            Self::Flags(flags) => write!(f, "{}", flags),
+            Self::Path(path) => write!(f, "{:?}", path),
+            Self::Ptr(ptr) => write!(f, "{:#x}", ptr),
+        }
+    }
+}
+
+impl fmt::Display for SyscallRecord {
+    /// Formats the record like `strace` does, e.g.
+    /// `path_open(dirfd=3, ..., path="foo") = 0 <0.000012s>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", arg.name, arg.value)?;
+        }
+        write!(f, ")")?;
+        match self.errno {
+            Some(__WASI_ESUCCESS) => write!(f, " = 0")?,
+            Some(errno) => write!(f, " = {} {}", errno, errno_name(errno))?,
+            None => write!(f, " = ?")?,
+        }
+        write!(f, " <{:.6}s>", self.duration.as_secs_f64())
+    }
+}
+
+fn serialize_nanos<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_nanos() as u64)
+}
+
+/// The format of the records written by a [`TraceWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One human-readable line per syscall, like `strace`
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// A [`SyscallTracer`] writing the records to a [`Write`].
+pub struct TraceWriter {
+    out: Mutex<Box<dyn Write + Send>>,
+    format: TraceFormat,
+}
+
+impl TraceWriter {
+    /// Write the records to `out` in `format`.
+    pub fn new(out: Box<dyn Write + Send>, format: TraceFormat) -> Self {
+        Self {
+            out: Mutex::new(out),
+            format,
+        }
+    }
+
+    /// Write the records to the standard error of the host in `format`.
+    pub fn stderr(format: TraceFormat) -> Self {
+        Self::new(Box::new(io::stderr()), format)
+    }
+}
+
+impl fmt::Debug for TraceWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceWriter")
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl SyscallTracer for TraceWriter {
+    fn record(&self, record: &SyscallRecord) {
+        let mut out = self.out.lock().unwrap();
+        // failing to write the trace mustn't fail the syscall
+        let _ = match self.format {
+            TraceFormat::Text => writeln!(out, "{}", record),
+            TraceFormat::Json => serde_json::to_writer(&mut *out, record)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(out)),
+        };
+    }
+}
+
+/// The name of `errno`, e.g. `ENOENT`.
+pub fn errno_name(errno: __wasi_errno_t) -> &'static str {
+    match errno {
+        __WASI_ESUCCESS => "ESUCCESS",
+        __WASI_E2BIG => "E2BIG",
+        __WASI_EACCES => "EACCES",
+        __WASI_EADDRINUSE => "EADDRINUSE",
+        __WASI_EADDRNOTAVAIL => "EADDRNOTAVAIL",
+        __WASI_EAFNOSUPPORT => "EAFNOSUPPORT",
+        __WASI_EAGAIN => "EAGAIN",
+        __WASI_EALREADY => "EALREADY",
+        __WASI_EBADF => "EBADF",
+        __WASI_EBADMSG => "EBADMSG",
+        __WASI_EBUSY => "EBUSY",
+        __WASI_ECANCELED => "ECANCELED",
+        __WASI_ECHILD => "ECHILD",
+        __WASI_ECONNABORTED => "ECONNABORTED",
+        __WASI_ECONNREFUSED => "ECONNREFUSED",
+        __WASI_ECONNRESET => "ECONNRESET",
+        __WASI_EDEADLK => "EDEADLK",
+        __WASI_EDESTADDRREQ => "EDESTADDRREQ",
+        __WASI_EDOM => "EDOM",
+        __WASI_EDQUOT => "EDQUOT",
+        __WASI_EEXIST => "EEXIST",
+        __WASI_EFAULT => "EFAULT",
+        __WASI_EFBIG => "EFBIG",
+        __WASI_EHOSTUNREACH => "EHOSTUNREACH",
+        __WASI_EIDRM => "EIDRM",
+        __WASI_EILSEQ => "EILSEQ",
+        __WASI_EINPROGRESS => "EINPROGRESS",
+        __WASI_EINTR => "EINTR",
+        __WASI_EINVAL => "EINVAL",
+        __WASI_EIO => "EIO",
+        __WASI_EISCONN => "EISCONN",
+        __WASI_EISDIR => "EISDIR",
+        __WASI_ELOOP => "ELOOP",
+        __WASI_EMFILE => "EMFILE",
+        __WASI_EMLINK => "EMLINK",
+        __WASI_EMSGSIZE => "EMSGSIZE",
+        __WASI_EMULTIHOP => "EMULTIHOP",
+        __WASI_ENAMETOOLONG => "ENAMETOOLONG",
+        __WASI_ENETDOWN => "ENETDOWN",
+        __WASI_ENETRESET => "ENETRESET",
+        __WASI_ENETUNREACH => "ENETUNREACH",
+        __WASI_ENFILE => "ENFILE",
+        __WASI_ENOBUFS => "ENOBUFS",
+        __WASI_ENODEV => "ENODEV",
+        __WASI_ENOENT => "ENOENT",
+        __WASI_ENOEXEC => "ENOEXEC",
+        __WASI_ENOLCK => "ENOLCK",
+        __WASI_ENOLINK => "ENOLINK",
+        __WASI_ENOMEM => "ENOMEM",
+        __WASI_ENOMSG => "ENOMSG",
+        __WASI_ENOPROTOOPT => "ENOPROTOOPT",
+        __WASI_ENOSPC => "ENOSPC",
+        __WASI_ENOSYS => "ENOSYS",
+        __WASI_ENOTCONN => "ENOTCONN",
+        __WASI_ENOTDIR => "ENOTDIR",
+        __WASI_ENOTEMPTY => "ENOTEMPTY",
+        __WASI_ENOTRECOVERABLE => "ENOTRECOVERABLE",
+        __WASI_ENOTSOCK => "ENOTSOCK",
+        __WASI_ENOTSUP => "ENOTSUP",
+        __WASI_ENOTTY => "ENOTTY",
+        __WASI_ENXIO => "ENXIO",
+        __WASI_EOVERFLOW => "EOVERFLOW",
+        __WASI_EOWNERDEAD => "EOWNERDEAD",
+        __WASI_EPERM => "EPERM",
+        __WASI_EPIPE => "EPIPE",
+        __WASI_EPROTO => "EPROTO",
+        __WASI_EPROTONOSUPPORT => "EPROTONOSUPPORT",
+        __WASI_EPROTOTYPE => "EPROTOTYPE",
+        __WASI_ERANGE => "ERANGE",
+        __WASI_EROFS => "EROFS",
+        __WASI_ESPIPE => "ESPIPE",
+        __WASI_ESRCH => "ESRCH",
+        __WASI_ESTALE => "ESTALE",
+        __WASI_ETIMEDOUT => "ETIMEDOUT",
+        __WASI_ETXTBSY => "ETXTBSY",
+        __WASI_EXDEV => "EXDEV",
+        __WASI_ENOTCAPABLE => "ENOTCAPABLE",
+        _ => "E?",
+    }
+}
+
+pub(crate) const LOOKUP_FLAGS: &[(u64, &str)] =
+    &[(__WASI_LOOKUP_SYMLINK_FOLLOW as u64, "SYMLINK_FOLLOW")];
+
+pub(crate) const OPEN_FLAGS: &[(u64, &str)] = &[
+    (__WASI_O_CREAT as u64, "CREAT"),
+    (__WASI_O_DIRECTORY as u64, "DIRECTORY"),
+    (__WASI_O_EXCL as u64, "EXCL"),
+    (__WASI_O_TRUNC as u64, "TRUNC"),
+];
+
+pub(crate) const FD_FLAGS: &[(u64, &str)] = &[
+    (__WASI_FDFLAG_APPEND as u64, "APPEND"),
+    (__WASI_FDFLAG_DSYNC as u64, "DSYNC"),
+    (__WASI_FDFLAG_NONBLOCK as u64, "NONBLOCK"),
+    (__WASI_FDFLAG_RSYNC as u64, "RSYNC"),
+    (__WASI_FDFLAG_SYNC as u64, "SYNC"),
+];
+
+pub(crate) const FST_FLAGS: &[(u64, &str)] = &[
+    (__WASI_FILESTAT_SET_ATIM as u64, "ATIM"),
+    (__WASI_FILESTAT_SET_ATIM_NOW as u64, "ATIM_NOW"),
+    (__WASI_FILESTAT_SET_MTIM as u64, "MTIM"),
+    (__WASI_FILESTAT_SET_MTIM_NOW as u64, "MTIM_NOW"),
+];
+
+/// Format `bits` as the names of `flags` separated by `|`, with the unknown
+/// bits in hexadecimal.
+pub(crate) fn format_flags(bits: u64, flags: &[(u64, &str)]) -> String {
+    let mut names = vec![];
+    let mut rest = bits;
+    for (flag, name) in flags {
+        if bits & flag != 0 {
+            names.push(name.to_string());
+            rest &= !flag;
+        }
+    }
+    if rest != 0 || names.is_empty() {
+        names.push(format!("{:#x}", rest));
+    }
+    names.join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> SyscallRecord {
+        SyscallRecord {
+            name: "path_open",
+            args: vec![
+                SyscallArg {
+                    name: "dirfd",
+                    value: ArgValue::Fd(3),
+                },
+                SyscallArg {
+                    name: "path",
+                    value: ArgValue::Path("a \"b\"".to_string()),
+                },
+                SyscallArg {
+                    name: "o_flags",
+                    value: ArgValue::Flags(format_flags(
+                        (__WASI_O_CREAT | __WASI_O_TRUNC | 1 << 7) as u64,
+                        OPEN_FLAGS,
+                    )),
+                },
+                SyscallArg {
+                    name: "fd",
+                    value: ArgValue::Ptr(1024),
+                },
+            ],
+            errno: Some(__WASI_ENOENT),
+            duration: Duration::from_micros(12),
+        }
+    }
+
+    #[test]
+    fn text_format() {
+        assert_eq!(
+            record().to_string(),
+            "path_open(dirfd=3, path=\"a \\\"b\\\"\", o_flags=CREAT|TRUNC|0x80, fd=0x400) = 44 ENOENT <0.000012s>"
+        );
+        assert_eq!(format_flags(0, FD_FLAGS), "0x0");
+    }
+
+    #[test]
+    fn json_format() {
+        assert_eq!(
+            serde_json::to_string(&record()).unwrap(),
+            r#"{"name":"path_open","args":[{"name":"dirfd","value":{"fd":3}},{"name":"path","value":{"path":"a \"b\""}},{"name":"o_flags","value":{"flags":"CREAT|TRUNC|0x80"}},{"name":"fd","value":{"ptr":1024}}],"errno":44,"duration_ns":12000}"#
+        );
+    }
+}
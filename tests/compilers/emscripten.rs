@@ -0,0 +1,281 @@
+#![cfg(all(feature = "emscripten", unix))]
+
+//! Testing the Emscripten syscalls, called by a module through their
+//! imports like Emscripten does.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use wasmer::*;
+use wasmer_emscripten::{generate_emscripten_env, EmEnv, EmscriptenGlobals};
+
+// The varargs of the syscalls are written at `VARARGS`, and the strings
+// they point to at `STRINGS`.
+const VARARGS: u32 = 1024;
+const STRINGS: u32 = 2048;
+
+const O_RDWR: u32 = 0o2;
+const O_CREAT: u32 = 0o100;
+const F_GETFL: u32 = 3;
+const O_NONBLOCK: i32 = 0o4000;
+const AF_INET: u32 = 2;
+const SOCK_STREAM: u32 = 1;
+const SOCK_NONBLOCK: u32 = 0o4000;
+const FIONREAD: u32 = 0x541B;
+const ENOENT: i32 = 2;
+const EACCES: i32 = 13;
+const ENOTTY: i32 = 25;
+const ELOOP: i32 = 40;
+
+fn get_module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (import "env" "memory" (memory 256 256))
+        (import "env" "table" (table 0 funcref))
+        (import "env" "___syscall5" (func $open (param i32 i32) (result i32)))
+        (import "env" "___syscall12" (func $chdir (param i32 i32) (result i32)))
+        (import "env" "___syscall54" (func $ioctl (param i32 i32) (result i32)))
+        (import "env" "___syscall102" (func $socketcall (param i32 i32) (result i32)))
+        (import "env" "___syscall183" (func $getcwd (param i32 i32) (result i32)))
+        (import "env" "___syscall194" (func $ftruncate64 (param i32 i32) (result i32)))
+        (import "env" "___syscall221" (func $fcntl64 (param i32 i32) (result i32)))
+        (import "env" "___syscall324" (func $fallocate (param i32 i32) (result i32)))
+        ;; The dynamic top pointer and base of the Emscripten metadata
+        (global i32 (i32.const 65600))
+        (global i32 (i32.const 65536))
+
+        (func (export "open") (param i32) (result i32)
+            (call $open (i32.const 5) (local.get 0)))
+        (func (export "chdir") (param i32) (result i32)
+            (call $chdir (i32.const 12) (local.get 0)))
+        (func (export "ioctl") (param i32) (result i32)
+            (call $ioctl (i32.const 54) (local.get 0)))
+        (func (export "socketcall") (param i32) (result i32)
+            (call $socketcall (i32.const 102) (local.get 0)))
+        (func (export "getcwd") (param i32) (result i32)
+            (call $getcwd (i32.const 183) (local.get 0)))
+        (func (export "ftruncate64") (param i32) (result i32)
+            (call $ftruncate64 (i32.const 194) (local.get 0)))
+        (func (export "fcntl64") (param i32) (result i32)
+            (call $fcntl64 (i32.const 221) (local.get 0)))
+        (func (export "fallocate") (param i32) (result i32)
+            (call $fallocate (i32.const 324) (local.get 0)))
+    "#;
+
+    let module = Module::new(&store, &wat)?;
+    Ok(module)
+}
+
+struct Syscalls {
+    instance: Instance,
+    memory: Memory,
+    // Keeps the imports alive
+    _env: EmEnv,
+}
+
+impl Syscalls {
+    fn new(
+        store: &Store,
+        mapped_dirs: HashMap<String, std::path::PathBuf>,
+        sandboxed: bool,
+    ) -> Result<Self> {
+        let module = get_module(store)?;
+        let mut globals = EmscriptenGlobals::new(store, &module).map_err(|e| anyhow::anyhow!(e))?;
+        let mut env = if sandboxed {
+            EmEnv::new_sandboxed(&globals.data, mapped_dirs)
+        } else {
+            EmEnv::new(&globals.data, mapped_dirs)
+        };
+        let import_object = generate_emscripten_env(store, &mut globals, &mut env);
+        let instance = Instance::new(&module, &import_object)?;
+        env.set_memory(globals.memory.clone());
+        Ok(Self {
+            instance,
+            memory: globals.memory.clone(),
+            _env: env,
+        })
+    }
+
+    fn write(&self, address: u32, bytes: &[u8]) {
+        let view = self.memory.view::<u8>();
+        for (cell, byte) in view[address as usize..].iter().zip(bytes) {
+            cell.set(*byte);
+        }
+    }
+
+    fn read_u32(&self, address: u32) -> u32 {
+        self.memory.view::<u32>()[address as usize / 4].get()
+    }
+
+    fn read_str(&self, address: u32) -> String {
+        let bytes = self.memory.view::<u8>()[address as usize..]
+            .iter()
+            .map(|cell| cell.get())
+            .take_while(|byte| *byte != 0)
+            .collect::<Vec<_>>();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// Call the syscall `name` with the varargs `words`.
+    fn call(&self, name: &str, words: &[u32]) -> Result<i32> {
+        let bytes = words
+            .iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        self.write(VARARGS, &bytes);
+        let function = self
+            .instance
+            .exports
+            .get_native_function::<i32, i32>(name)?;
+        Ok(function.call(VARARGS as i32)?)
+    }
+
+    fn open(&self, path: &str) -> Result<i32> {
+        self.write(STRINGS, format!("{}\0", path).as_bytes());
+        self.call("open", &[STRINGS, O_RDWR | O_CREAT, 0o644])
+    }
+
+    fn chdir(&self, path: &str) -> Result<i32> {
+        self.write(STRINGS, format!("{}\0", path).as_bytes());
+        self.call("chdir", &[STRINGS])
+    }
+
+    fn getcwd(&self) -> Result<String> {
+        self.call("getcwd", &[STRINGS, 256])?;
+        Ok(self.read_str(STRINGS))
+    }
+}
+
+#[test]
+fn ftruncate64_and_fallocate() -> Result<()> {
+    let store = get_store(false);
+    let dir = tempfile::tempdir()?;
+    let mut mapped_dirs = HashMap::new();
+    mapped_dirs.insert("data".to_string(), dir.path().to_path_buf());
+    let syscalls = Syscalls::new(&store, mapped_dirs, false)?;
+    let path = dir.path().join("file");
+
+    let fd = syscalls.open("data/file")?;
+    assert!(fd >= 0);
+    assert_eq!(fs::metadata(&path)?.len(), 0);
+
+    // The length is aligned after a zero
+    assert_eq!(syscalls.call("ftruncate64", &[fd as u32, 0, 1000, 0])?, 0);
+    assert_eq!(fs::metadata(&path)?.len(), 1000);
+
+    // Offset and length are 64 bits
+    assert_eq!(
+        syscalls.call("fallocate", &[fd as u32, 0, 0, 0, 4096, 0])?,
+        0
+    );
+    assert_eq!(fs::metadata(&path)?.len(), 4096);
+    Ok(())
+}
+
+#[test]
+fn ioctl() -> Result<()> {
+    let store = get_store(false);
+    let dir = tempfile::tempdir()?;
+    let mut mapped_dirs = HashMap::new();
+    mapped_dirs.insert("data".to_string(), dir.path().to_path_buf());
+    fs::write(dir.path().join("file"), b"hello")?;
+    let syscalls = Syscalls::new(&store, mapped_dirs, false)?;
+
+    let fd = syscalls.open("data/file")?;
+    assert!(fd >= 0);
+    let available = STRINGS + 256;
+    assert_eq!(
+        syscalls.call("ioctl", &[fd as u32, FIONREAD, available])?,
+        0
+    );
+    assert_eq!(syscalls.read_u32(available), 5);
+
+    assert_eq!(syscalls.call("ioctl", &[fd as u32, 0x1234, 0])?, -ENOTTY);
+    Ok(())
+}
+
+#[test]
+fn nonblocking_socket() -> Result<()> {
+    let store = get_store(false);
+    let syscalls = Syscalls::new(&store, HashMap::new(), false)?;
+
+    // `socketcall` takes the varargs of the call after the call itself
+    let socket_varargs = VARARGS + 8;
+    let fd = syscalls.call(
+        "socketcall",
+        &[1, socket_varargs, AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0],
+    )?;
+    assert!(fd >= 0);
+
+    let flags = syscalls.call("fcntl64", &[fd as u32, F_GETFL, 0])?;
+    assert_ne!(flags & O_NONBLOCK, 0);
+    Ok(())
+}
+
+#[test]
+fn sandbox() -> Result<()> {
+    let store = get_store(false);
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("sub"))?;
+    fs::write(dir.path().join("sub").join("file"), b"")?;
+    std::os::unix::fs::symlink("/etc", dir.path().join("etc"))?;
+    let mut mapped_dirs = HashMap::new();
+    mapped_dirs.insert("/data".to_string(), dir.path().to_path_buf());
+    let syscalls = Syscalls::new(&store, mapped_dirs, true)?;
+
+    assert!(syscalls.open("/data/sub/file")? >= 0);
+    assert!(syscalls.open("/data/./sub/../../data/sub/file")? >= 0);
+    // Outside of the mapped directories
+    assert_eq!(syscalls.open("/etc/passwd")?, -ENOENT);
+    assert_eq!(syscalls.open("/data/../../../etc/passwd")?, -ENOENT);
+    // Through a symbolic link
+    assert_eq!(syscalls.open("/data/etc/passwd")?, -EACCES);
+
+    // Relative paths start from the current directory of the guest
+    assert_eq!(syscalls.getcwd()?, "/");
+    assert_eq!(syscalls.chdir("/data/sub")?, 0);
+    assert_eq!(syscalls.getcwd()?, "/data/sub");
+    assert!(syscalls.open("file")? >= 0);
+    assert_eq!(syscalls.open("../../etc/passwd")?, -ENOENT);
+    assert_eq!(syscalls.chdir("/etc")?, -ENOENT);
+    assert_eq!(syscalls.getcwd()?, "/data/sub");
+    Ok(())
+}
+
+#[test]
+fn sandbox_symlinks() -> Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let store = get_store(false);
+    let dir = tempfile::tempdir()?;
+    let outside = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("sub"))?;
+    fs::write(dir.path().join("sub").join("file"), b"")?;
+    symlink("sub", dir.path().join("relative"))?;
+    symlink(
+        dir.path().canonicalize()?.join("sub"),
+        dir.path().join("absolute"),
+    )?;
+    symlink("../sub/file", dir.path().join("sub").join("back"))?;
+    symlink("..", dir.path().join("up"))?;
+    symlink(outside.path().join("new"), dir.path().join("dangling"))?;
+    symlink("loop", dir.path().join("loop"))?;
+    let mut mapped_dirs = HashMap::new();
+    mapped_dirs.insert("/data".to_string(), dir.path().to_path_buf());
+    let syscalls = Syscalls::new(&store, mapped_dirs, true)?;
+
+    // The links staying in the mapped directory are followed
+    assert!(syscalls.open("/data/relative/file")? >= 0);
+    assert!(syscalls.open("/data/absolute/file")? >= 0);
+    assert!(syscalls.open("/data/sub/back")? >= 0);
+    // The others aren't, even to create a file
+    assert_eq!(syscalls.open("/data/up/file")?, -EACCES);
+    assert_eq!(syscalls.open("/data/dangling")?, -EACCES);
+    assert!(!outside.path().join("new").exists());
+    assert_eq!(syscalls.open("/data/loop")?, -ELOOP);
+
+    // Files are created in the directory they were resolved to
+    assert!(syscalls.open("/data/relative/created")? >= 0);
+    assert!(dir.path().join("sub").join("created").exists());
+    Ok(())
+}
@@ -0,0 +1,145 @@
+#![cfg(all(feature = "emscripten", unix))]
+
+//! Testing `setjmp` and `longjmp`, lowered in the module like Emscripten
+//! does: the call which may `longjmp` goes through an `invoke_*`
+//! trampoline, and the caller finds the `setjmp` to return to with
+//! `testSetjmp`.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+use wasmer_emscripten::{generate_emscripten_env, EmEnv, EmscriptenGlobals};
+
+// `run` saves 2 `setjmp` buffers in a table with a single entry, so it's
+// reallocated, then calls the function `$f` of the table with the second
+// buffer and `$value` through `invoke_vii`. It returns what the `setjmp`
+// of the second buffer returns, or -1 if there was no `longjmp`.
+fn get_module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (type $vii (func (param i32 i32)))
+        (import "env" "memory" (memory 256 256))
+        (import "env" "table" (table 3 3 funcref))
+        (import "env" "invoke_vii" (func $invoke_vii (param i32 i32 i32)))
+        (import "env" "_longjmp" (func $longjmp (param i32 i32)))
+        (import "env" "_saveSetjmp" (func $saveSetjmp (param i32 i32 i32 i32) (result i32)))
+        (import "env" "_testSetjmp" (func $testSetjmp (param i32 i32 i32) (result i32)))
+        (import "env" "getTempRet0" (func $getTempRet0 (result i32)))
+        (global $threw (mut i32) (i32.const 0))
+        (global $threwValue (mut i32) (i32.const 0))
+        (global $sp (mut i32) (i32.const 1024))
+        ;; The dynamic top pointer and base of the Emscripten metadata
+        (global i32 (i32.const 65600))
+        (global i32 (i32.const 65536))
+
+        (func (export "setThrew") (param $threw i32) (param $value i32)
+          (if (i32.eqz (global.get $threw))
+            (then
+              (global.set $threw (local.get $threw))
+              (global.set $threwValue (local.get $value)))))
+        (func (export "stackSave") (result i32)
+          (global.get $sp))
+        (func (export "stackRestore") (param $sp i32)
+          (global.set $sp (local.get $sp)))
+        (func (export "dynCall_vii") (param $f i32) (param i32 i32)
+          (call_indirect (type $vii) (local.get 1) (local.get 2) (local.get $f)))
+        ;; Moves the table at 8192
+        (func (export "realloc") (param $ptr i32) (param $size i32) (result i32)
+          (memory.copy (i32.const 8192) (local.get $ptr) (local.get $size))
+          (i32.const 8192))
+
+        (elem (i32.const 1) $jump $trap)
+        (func $jump (param $env i32) (param $value i32)
+          (call $longjmp (local.get $env) (local.get $value)))
+        (func $trap (param i32 i32)
+          unreachable)
+
+        (func (export "save") (param $table i32) (param $size i32) (result i32)
+          (call $saveSetjmp (i32.const 2048) (i32.const 1) (local.get $table) (local.get $size)))
+
+        (func (export "run") (param $f i32) (param $value i32) (result i32)
+          (local $table i32)
+          (local.set $table
+            (call $saveSetjmp (i32.const 2048) (i32.const 1) (i32.const 4096) (i32.const 1)))
+          (local.set $table
+            (call $saveSetjmp (i32.const 2056) (i32.const 2) (local.get $table) (call $getTempRet0)))
+          (global.set $threw (i32.const 0))
+          (call $invoke_vii (local.get $f) (i32.const 2056) (local.get $value))
+          (if (i32.eqz (global.get $threw))
+            (then (return (i32.const -1))))
+          (if (i32.ne
+                (call $testSetjmp
+                  (i32.load (global.get $threw)) (local.get $table) (call $getTempRet0))
+                (i32.const 2))
+            (then (return (i32.const -1))))
+          (global.get $threwValue))
+    "#;
+    Ok(Module::new(store, wat)?)
+}
+
+struct SetJmp {
+    instance: Instance,
+    // Keeps the memory, the table and the imports alive
+    _globals: EmscriptenGlobals,
+    _env: EmEnv,
+}
+
+impl SetJmp {
+    fn new(store: &Store) -> Result<Self> {
+        let module = get_module(store)?;
+        let mut globals = EmscriptenGlobals::new(store, &module).map_err(|e| anyhow::anyhow!(e))?;
+        let mut env = EmEnv::new(&globals.data, Default::default());
+        let import_object = generate_emscripten_env(store, &mut globals, &mut env);
+        let instance = Instance::new(&module, &import_object)?;
+        env.set_memory(globals.memory.clone());
+        Ok(Self {
+            instance,
+            _globals: globals,
+            _env: env,
+        })
+    }
+
+    fn run(&self, f: i32, value: i32) -> Result<i32, RuntimeError> {
+        let run: NativeFunc<(i32, i32), i32> = self
+            .instance
+            .exports
+            .get_native_function("run")
+            .expect("run is exported");
+        run.call(f, value)
+    }
+}
+
+#[test]
+fn longjmp_through_invoke() -> Result<()> {
+    let store = get_store(false);
+    let setjmp = SetJmp::new(&store)?;
+
+    assert_eq!(setjmp.run(1, 42)?, 42);
+    // `setjmp` returns 1 for a `longjmp` with 0
+    assert_eq!(setjmp.run(1, 0)?, 1);
+    Ok(())
+}
+
+#[test]
+fn traps_unwind_through_invoke() -> Result<()> {
+    let store = get_store(false);
+    let setjmp = SetJmp::new(&store)?;
+
+    let error = setjmp.run(2, 42).unwrap_err();
+    assert!(
+        error.message().contains("unreachable"),
+        "wrong message: {}",
+        error.message()
+    );
+    Ok(())
+}
+
+#[test]
+fn setjmp_table_out_of_bounds() -> Result<()> {
+    let store = get_store(false);
+    let setjmp = SetJmp::new(&store)?;
+    let save: NativeFunc<(i32, i32), i32> = setjmp.instance.exports.get_native_function("save")?;
+
+    let error = save.call(-16, 1).unwrap_err();
+    assert_eq!(error.message(), "the setjmp table is out of bounds");
+    Ok(())
+}
@@ -0,0 +1,221 @@
+#![cfg(all(feature = "emscripten-pthreads", unix))]
+
+//! Testing the threads of Emscripten modules, run on host threads by the
+//! `pthreads` feature.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+use wasmer_emscripten::{generate_emscripten_env, EmEnv, EmscriptenGlobals, ThreadExit};
+
+const EINVAL: i32 = 22;
+const EFAULT: i32 = 14;
+
+// The mutex is at 256, the condition variable at 280, and the counter
+// they protect at 300. The threads are written at 400 and the values they
+// return at 408.
+fn get_module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (type $ii (func (param i32) (result i32)))
+        (import "env" "memory" (memory 256 512))
+        (import "env" "table" (table 4 4 funcref))
+        (import "env" "_pthread_create" (func $create (param i32 i32 i32 i32) (result i32)))
+        (import "env" "_pthread_join" (func $join (param i32 i32) (result i32)))
+        (import "env" "_pthread_exit" (func $exit (param i32)))
+        (import "env" "_pthread_mutexattr_init" (func $mutexattr_init (param i32) (result i32)))
+        (import "env" "_pthread_mutex_init" (func $mutex_init (param i32 i32) (result i32)))
+        (import "env" "_pthread_mutex_lock" (func $lock (param i32) (result i32)))
+        (import "env" "_pthread_mutex_unlock" (func $unlock (param i32) (result i32)))
+        (import "env" "_pthread_cond_init" (func $cond_init (param i32 i32) (result i32)))
+        (import "env" "_pthread_cond_wait" (func $wait (param i32 i32) (result i32)))
+        (import "env" "_pthread_cond_signal" (func $signal (param i32) (result i32)))
+        (import "env" "_emscripten_resize_heap" (func $resize_heap (param i32) (result i32)))
+        ;; The dynamic top pointer and base of the Emscripten metadata
+        (global i32 (i32.const 65600))
+        (global i32 (i32.const 65536))
+
+        ;; Allocates the stacks of the threads from 1 MiB
+        (func (export "_malloc") (param $size i32) (result i32)
+          (local $allocated i32)
+          (local.set $allocated (i32.load (i32.const 512)))
+          (i32.store (i32.const 512) (i32.add (local.get $allocated) (local.get $size)))
+          (i32.add (local.get $allocated) (i32.const 1048576)))
+        (func (export "_free") (param i32))
+        (func (export "dynCall_ii") (param $f i32) (param i32) (result i32)
+          (call_indirect (type $ii) (local.get 1) (local.get $f)))
+
+        (elem (i32.const 1) $add $produce $trap)
+        ;; Adds `$value` to the counter 10000 times
+        (func $add (param $value i32) (result i32)
+          (local $i i32)
+          (loop $add
+            (drop (call $lock (i32.const 256)))
+            (i32.store (i32.const 300) (i32.add (i32.load (i32.const 300)) (local.get $value)))
+            (drop (call $unlock (i32.const 256)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $add (i32.lt_u (local.get $i) (i32.const 10000))))
+          (i32.mul (local.get $value) (i32.const 10)))
+        ;; Sets the counter to `$value` and signals it
+        (func $produce (param $value i32) (result i32)
+          (drop (call $lock (i32.const 256)))
+          (i32.store (i32.const 300) (local.get $value))
+          (drop (call $signal (i32.const 280)))
+          (drop (call $unlock (i32.const 256)))
+          (i32.const 0))
+        (func $trap (param i32) (result i32)
+          unreachable)
+
+        (func (export "add") (result i32)
+          (drop (call $mutex_init (i32.const 256) (i32.const 0)))
+          (drop (call $create (i32.const 400) (i32.const 0) (i32.const 1) (i32.const 1)))
+          (drop (call $create (i32.const 404) (i32.const 0) (i32.const 1) (i32.const 2)))
+          (drop (call $join (i32.load (i32.const 400)) (i32.const 408)))
+          (drop (call $join (i32.load (i32.const 404)) (i32.const 412)))
+          (i32.add
+            (i32.load (i32.const 300))
+            (i32.add (i32.load (i32.const 408)) (i32.load (i32.const 412)))))
+
+        (func (export "wait") (param $value i32) (result i32)
+          (drop (call $mutex_init (i32.const 256) (i32.const 0)))
+          (drop (call $cond_init (i32.const 280) (i32.const 0)))
+          (drop (call $lock (i32.const 256)))
+          (drop (call $create (i32.const 400) (i32.const 0) (i32.const 2) (local.get $value)))
+          (block $produced
+            (loop $wait
+              (br_if $produced (i32.ne (i32.load (i32.const 300)) (i32.const 0)))
+              (drop (call $wait (i32.const 280) (i32.const 256)))
+              (br $wait)))
+          (drop (call $unlock (i32.const 256)))
+          (drop (call $join (i32.load (i32.const 400)) (i32.const 0)))
+          (i32.load (i32.const 300)))
+
+        (func (export "join_trap") (result i32)
+          (drop (call $create (i32.const 400) (i32.const 0) (i32.const 3) (i32.const 0)))
+          (call $join (i32.load (i32.const 400)) (i32.const 0)))
+
+        (func (export "exit") (param $value i32)
+          (call $exit (local.get $value)))
+
+        ;; Grows the memory while a thread waits for the mutex, then once
+        ;; it's joined, returning both results
+        (func (export "grow") (result i32)
+          (local $while_running i32)
+          (drop (call $mutex_init (i32.const 256) (i32.const 0)))
+          (drop (call $lock (i32.const 256)))
+          (drop (call $create (i32.const 400) (i32.const 0) (i32.const 1) (i32.const 1)))
+          (local.set $while_running (call $resize_heap (i32.const 16777217)))
+          (drop (call $unlock (i32.const 256)))
+          (drop (call $join (i32.load (i32.const 400)) (i32.const 0)))
+          (i32.add
+            (i32.mul (local.get $while_running) (i32.const 10))
+            (call $resize_heap (i32.const 16777217))))
+
+        (func (export "mutexattr_init") (param $attr i32) (result i32)
+          (call $mutexattr_init (local.get $attr)))
+    "#;
+    Ok(Module::new(store, wat)?)
+}
+
+struct Pthreads {
+    instance: Instance,
+    // Keeps the memory, the table and the imports alive
+    globals: EmscriptenGlobals,
+    _env: EmEnv,
+}
+
+impl Pthreads {
+    fn new(store: &Store) -> Result<Self> {
+        let module = get_module(store)?;
+        let mut globals = EmscriptenGlobals::new(store, &module).map_err(|e| anyhow::anyhow!(e))?;
+        let mut env = EmEnv::new(&globals.data, Default::default());
+        let import_object = generate_emscripten_env(store, &mut globals, &mut env);
+        let instance = Instance::new(&module, &import_object)?;
+        env.set_memory(globals.memory.clone());
+        Ok(Self {
+            instance,
+            globals,
+            _env: env,
+        })
+    }
+
+    fn function<Args: WasmTypeList, Rets: WasmTypeList>(
+        &self,
+        name: &str,
+    ) -> NativeFunc<Args, Rets> {
+        self.instance
+            .exports
+            .get_native_function(name)
+            .expect("the function is exported")
+    }
+}
+
+#[test]
+fn create_join_and_lock() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let add = pthreads.function::<(), i32>("add");
+
+    // The counter, then what each thread returned
+    assert_eq!(add.call()?, 30000 + 10 + 20);
+    Ok(())
+}
+
+#[test]
+fn wait_for_condition() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let wait = pthreads.function::<i32, i32>("wait");
+
+    assert_eq!(wait.call(42)?, 42);
+    Ok(())
+}
+
+#[test]
+fn join_raises_the_trap_of_the_thread() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let join_trap = pthreads.function::<(), i32>("join_trap");
+
+    let error = join_trap.call().unwrap_err();
+    assert!(
+        error.message().contains("unreachable"),
+        "wrong message: {}",
+        error.message()
+    );
+    Ok(())
+}
+
+#[test]
+fn exit_from_main_thread() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let exit = pthreads.function::<i32, ()>("exit");
+
+    let error = exit.call(3).unwrap_err();
+    assert!(matches!(error.downcast::<ThreadExit>(), Ok(ThreadExit(3))));
+    Ok(())
+}
+
+#[test]
+fn grow_memory_only_without_threads() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let grow = pthreads.function::<(), i32>("grow");
+
+    // Failed while the thread was running, then succeeded
+    assert_eq!(grow.call()?, 1);
+    assert_eq!(pthreads.globals.memory.size(), Pages(512));
+    Ok(())
+}
+
+#[test]
+fn invalid_addresses() -> Result<()> {
+    let store = get_store(false);
+    let pthreads = Pthreads::new(&store)?;
+    let mutexattr_init = pthreads.function::<i32, i32>("mutexattr_init");
+
+    assert_eq!(mutexattr_init.call(1024)?, 0);
+    assert_eq!(mutexattr_init.call(1025)?, EINVAL);
+    assert_eq!(mutexattr_init.call(-4)?, EFAULT);
+    Ok(())
+}
@@ -4,6 +4,9 @@
 //! implementation, such as: singlepass, cranelift or llvm depending
 //! on what's available on the target.
 
+mod emscripten;
+mod emscripten_longjmp;
+mod emscripten_pthreads;
 mod imports;
 mod metering;
 mod middlewares;
@@ -13,6 +16,12 @@ mod serialize;
 mod traps;
 mod utils;
 mod wasi;
+mod wasi_path_policy;
+mod wasi_proc_raise;
+mod wasi_quotas;
+mod wasi_snapshot;
+mod wasi_sockets;
+mod wasi_trace;
 mod wast;
 
 pub use crate::utils::get_compiler;
@@ -0,0 +1,111 @@
+#![cfg(feature = "wasi")]
+
+//! Testing that the syscalls of a module check the paths they touch against
+//! a `PathPolicy`.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{MemFileSystem, PathPolicy, WasiState};
+
+/// The preopened directory, right after the virtual root
+const DATA_FD: i32 = 4;
+const EACCES: i32 = __WASI_EACCES as i32;
+const ESUCCESS: i32 = __WASI_ESUCCESS as i32;
+
+// The paths are written at 100 (`secret.txt`), 120 (`public.txt`) and 140
+// (`renamed.txt`).
+fn instantiate(store: &Store, fs: &MemFileSystem) -> Result<Instance> {
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_unlink_file"
+            (func $path_unlink_file (param i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "path_rename"
+            (func $path_rename (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 100) "secret.txt")
+          (data (i32.const 120) "public.txt")
+          (data (i32.const 140) "renamed.txt")
+          ;; Opens the path for reading, the fd is written at 200
+          (func (export "open") (param $fd i32) (param $path i32) (param $len i32) (result i32)
+            (call $path_open
+              (local.get $fd) (i32.const 0) (local.get $path) (local.get $len)
+              (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 200)))
+          (func (export "unlink") (param $fd i32) (param $path i32) (param $len i32) (result i32)
+            (call $path_unlink_file (local.get $fd) (local.get $path) (local.get $len)))
+          (func (export "rename")
+            (param $fd i32) (param $old i32) (param $old_len i32) (param $new i32) (param $new_len i32)
+            (result i32)
+            (call $path_rename
+              (local.get $fd) (local.get $old) (local.get $old_len)
+              (local.get $fd) (local.get $new) (local.get $new_len))))
+    "#;
+    let module = Module::new(store, wat)?;
+
+    let mut policy = PathPolicy::allow_all();
+    policy.deny("/data/secret.txt", &[])?;
+    let mut wasi_env = WasiState::new("path_policy")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .path_policy(policy)
+        .finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+fn data_fs() -> MemFileSystem {
+    let fs = MemFileSystem::new();
+    fs.create_dir_all("/data").unwrap();
+    fs.write_file("/data/secret.txt", b"secret").unwrap();
+    fs.write_file("/data/public.txt", b"public").unwrap();
+    fs
+}
+
+#[test]
+fn path_open_is_denied() -> Result<()> {
+    let store = get_store(false);
+    let fs = data_fs();
+    let instance = instantiate(&store, &fs)?;
+    let open: NativeFunc<(i32, i32, i32), i32> = instance.exports.get_native_function("open")?;
+
+    assert_eq!(open.call(DATA_FD, 100, 10)?, EACCES);
+    assert_eq!(open.call(DATA_FD, 120, 10)?, ESUCCESS);
+    Ok(())
+}
+
+#[test]
+fn path_unlink_file_is_denied() -> Result<()> {
+    let store = get_store(false);
+    let fs = data_fs();
+    let instance = instantiate(&store, &fs)?;
+    let unlink: NativeFunc<(i32, i32, i32), i32> =
+        instance.exports.get_native_function("unlink")?;
+
+    assert_eq!(unlink.call(DATA_FD, 100, 10)?, EACCES);
+    assert_eq!(fs.read_file("/data/secret.txt").unwrap(), b"secret");
+    assert_eq!(unlink.call(DATA_FD, 120, 10)?, ESUCCESS);
+    assert!(fs.read_file("/data/public.txt").is_err());
+    Ok(())
+}
+
+#[test]
+fn path_rename_is_denied_on_both_paths() -> Result<()> {
+    let store = get_store(false);
+    let fs = data_fs();
+    let instance = instantiate(&store, &fs)?;
+    let rename: NativeFunc<(i32, i32, i32, i32, i32), i32> =
+        instance.exports.get_native_function("rename")?;
+
+    // From the denied path, then onto it
+    assert_eq!(rename.call(DATA_FD, 100, 10, 140, 11)?, EACCES);
+    assert_eq!(rename.call(DATA_FD, 120, 10, 100, 10)?, EACCES);
+    assert_eq!(fs.read_file("/data/secret.txt").unwrap(), b"secret");
+    assert_eq!(fs.read_file("/data/public.txt").unwrap(), b"public");
+
+    assert_eq!(rename.call(DATA_FD, 120, 10, 140, 11)?, ESUCCESS);
+    assert_eq!(fs.read_file("/data/renamed.txt").unwrap(), b"public");
+    Ok(())
+}
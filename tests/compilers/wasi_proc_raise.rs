@@ -0,0 +1,68 @@
+#![cfg(feature = "wasi")]
+
+//! Testing the signals raised by a module with `proc_raise`.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{WasiError, WasiState};
+
+fn instantiate(store: &Store) -> Result<Instance> {
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "proc_raise"
+            (func $proc_raise (param i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "raise") (param $sig i32) (result i32)
+            (call $proc_raise (local.get $sig))))
+    "#;
+    let module = Module::new(store, wat)?;
+    let mut wasi_env = WasiState::new("proc_raise").finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+#[test]
+fn proc_raise_ignores_non_terminating_signals() -> Result<()> {
+    let store = get_store(false);
+    let instance = instantiate(&store)?;
+    let raise: NativeFunc<i32, i32> = instance.exports.get_native_function("raise")?;
+
+    for sig in &[__WASI_SIGCHLD, __WASI_SIGCONT, __WASI_SIGWINCH] {
+        assert_eq!(raise.call(i32::from(*sig))?, i32::from(__WASI_ESUCCESS));
+    }
+    assert_eq!(
+        raise.call(i32::from(__WASI_SIGSYS) + 1)?,
+        i32::from(__WASI_EINVAL)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn proc_raise_terminates_with_the_signal() -> Result<()> {
+    let store = get_store(false);
+    let instance = instantiate(&store)?;
+    let raise: NativeFunc<i32, i32> = instance.exports.get_native_function("raise")?;
+
+    let error = raise.call(i32::from(__WASI_SIGXCPU)).unwrap_err();
+    match error.downcast::<WasiError>() {
+        Ok(WasiError::Signal(sig)) => assert_eq!(sig, __WASI_SIGXCPU),
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wasi_signals_are_mapped_to_the_host_ones() {
+    use wasmer_wasi::host_signal;
+
+    assert_eq!(host_signal(__WASI_SIGTERM), Some(15));
+    // Linux has `SIGSTKFLT` in between
+    assert_eq!(host_signal(__WASI_SIGCHLD), Some(17));
+    assert_eq!(host_signal(__WASI_SIGSYS), Some(31));
+    assert_eq!(host_signal(0), None);
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "wasi")]
+
+//! Testing that the syscalls of a module enforce the `WasiQuotas`.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{MemFileSystem, WasiQuotas, WasiState};
+
+/// The preopened directory, right after the virtual root
+const DATA_FD: i32 = 4;
+const EDQUOT: i32 = __WASI_EDQUOT as i32;
+const ESUCCESS: i32 = __WASI_ESUCCESS as i32;
+
+// The path is written at 100 (`log.txt`) and the bytes written at 120.
+fn instantiate(store: &Store, fs: &MemFileSystem, quotas: WasiQuotas) -> Result<Instance> {
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_seek"
+            (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 100) "log.txt")
+          (data (i32.const 120) "0123456789")
+          ;; Creates `log.txt` for writing, the fd is written at 200
+          (func (export "open") (param $dir i32) (param $rights i64) (param $fdflags i32) (result i32)
+            (call $path_open
+              (local.get $dir) (i32.const 0) (i32.const 100) (i32.const 7)
+              (i32.const 1) (local.get $rights) (i64.const 0) (local.get $fdflags) (i32.const 200)))
+          (func (export "fd") (result i32)
+            (i32.load (i32.const 200)))
+          ;; Writes the first `$len` bytes at 120, through an iovec at 300
+          (func (export "write") (param $fd i32) (param $len i32) (result i32)
+            (i32.store (i32.const 300) (i32.const 120))
+            (i32.store (i32.const 304) (local.get $len))
+            (call $fd_write (local.get $fd) (i32.const 300) (i32.const 1) (i32.const 308)))
+          ;; Seeks from the start of the file
+          (func (export "seek") (param $fd i32) (param $offset i64) (result i32)
+            (call $fd_seek (local.get $fd) (local.get $offset) (i32.const 0) (i32.const 312))))
+    "#;
+    let module = Module::new(store, wat)?;
+
+    let mut wasi_env = WasiState::new("quotas")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .quotas(quotas)
+        .finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+struct Log {
+    fs: MemFileSystem,
+    fd: i32,
+    write: NativeFunc<(i32, i32), i32>,
+    seek: NativeFunc<(i32, i64), i32>,
+    // Keeps the functions alive
+    _instance: Instance,
+}
+
+impl Log {
+    fn open(store: &Store, quotas: WasiQuotas, fdflags: __wasi_fdflags_t) -> Result<Self> {
+        let fs = MemFileSystem::new();
+        fs.create_dir_all("/data").unwrap();
+        let instance = instantiate(store, &fs, quotas)?;
+        let open: NativeFunc<(i32, i64, i32), i32> =
+            instance.exports.get_native_function("open")?;
+        let rights = __WASI_RIGHT_FD_WRITE | __WASI_RIGHT_FD_SEEK;
+        assert_eq!(
+            open.call(DATA_FD, rights as i64, i32::from(fdflags))?,
+            ESUCCESS
+        );
+        let fd: NativeFunc<(), i32> = instance.exports.get_native_function("fd")?;
+        Ok(Self {
+            fs,
+            fd: fd.call()?,
+            write: instance.exports.get_native_function("write")?,
+            seek: instance.exports.get_native_function("seek")?,
+            _instance: instance,
+        })
+    }
+
+    fn write(&self, len: i32) -> Result<i32> {
+        Ok(self.write.call(self.fd, len)?)
+    }
+
+    fn seek(&self, offset: i64) -> Result<i32> {
+        Ok(self.seek.call(self.fd, offset)?)
+    }
+
+    fn len(&self) -> usize {
+        self.fs.read_file("/data/log.txt").unwrap().len()
+    }
+}
+
+#[test]
+fn max_file_size_of_overwrites() -> Result<()> {
+    let store = get_store(false);
+    let quotas = WasiQuotas {
+        max_file_size: Some(10),
+        ..WasiQuotas::default()
+    };
+    let log = Log::open(&store, quotas, 0)?;
+
+    assert_eq!(log.write(6)?, ESUCCESS);
+    assert_eq!(log.write(6)?, EDQUOT);
+    // Writing over what's there doesn't grow the file
+    assert_eq!(log.seek(0)?, ESUCCESS);
+    assert_eq!(log.write(6)?, ESUCCESS);
+    assert_eq!(log.len(), 6);
+    Ok(())
+}
+
+#[test]
+fn max_file_size_of_appends() -> Result<()> {
+    let store = get_store(false);
+    let quotas = WasiQuotas {
+        max_file_size: Some(10),
+        ..WasiQuotas::default()
+    };
+    let log = Log::open(&store, quotas, __WASI_FDFLAG_APPEND)?;
+
+    assert_eq!(log.write(6)?, ESUCCESS);
+    // Appending writes at the end of the file, wherever the offset is
+    assert_eq!(log.seek(0)?, ESUCCESS);
+    assert_eq!(log.write(6)?, EDQUOT);
+    assert_eq!(log.write(4)?, ESUCCESS);
+    assert_eq!(log.seek(0)?, ESUCCESS);
+    assert_eq!(log.write(1)?, EDQUOT);
+    assert_eq!(log.len(), 10);
+    Ok(())
+}
+
+#[test]
+fn max_bytes_written() -> Result<()> {
+    let store = get_store(false);
+    let quotas = WasiQuotas {
+        max_bytes_written: Some(15),
+        ..WasiQuotas::default()
+    };
+    let log = Log::open(&store, quotas, 0)?;
+
+    assert_eq!(log.write(10)?, ESUCCESS);
+    // Overwriting counts too
+    assert_eq!(log.seek(0)?, ESUCCESS);
+    assert_eq!(log.write(5)?, ESUCCESS);
+    assert_eq!(log.write(1)?, EDQUOT);
+    assert_eq!(log.len(), 10);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "wasi")]
+
+//! Testing that a WASI program resumed from a `WasiSnapshot` continues with
+//! the files, file descriptors and memory it had when it was paused.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+use wasmer::*;
+use wasmer_wasi::{MemFileSystem, OpenOptions, WasiEnv, WasiSnapshot, WasiState};
+
+// Each call of `step` writes "tick " (at 100) to `log.txt` (at 120), opened
+// by the first call and kept open in `$fd`, and counts the calls in `$ticks`
+// and at 0 in the memory.
+fn module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (global $fd (mut i32) (i32.const -1))
+          (global $ticks (mut i32) (i32.const 0))
+          (data (i32.const 100) "tick ")
+          (data (i32.const 120) "log.txt")
+          (func $check (param $errno i32)
+            (if (local.get $errno) (then unreachable)))
+          (func (export "step") (result i32)
+            (if (i32.eq (global.get $fd) (i32.const -1))
+              (then
+                ;; creates `log.txt` in the preopened directory, truncated
+                (call $check (call $path_open
+                  (i32.const 4) (i32.const 0) (i32.const 120) (i32.const 7)
+                  (i32.const 9) (i64.const 0x40) (i64.const 0) (i32.const 0) (i32.const 200)))
+                (global.set $fd (i32.load (i32.const 200)))))
+            (i32.store (i32.const 300) (i32.const 100))
+            (i32.store (i32.const 304) (i32.const 5))
+            (call $check (call $fd_write (global.get $fd) (i32.const 300) (i32.const 1) (i32.const 308)))
+            (global.set $ticks (i32.add (global.get $ticks) (i32.const 1)))
+            (i32.store (i32.const 0) (global.get $ticks))
+            (global.get $ticks)))
+    "#;
+    Ok(Module::new(store, wat)?)
+}
+
+fn read_log(env: &WasiEnv) -> Vec<u8> {
+    let state = env.state();
+    let mut file = state
+        .fs
+        .fs_backend
+        .open(Path::new("/data/log.txt"), OpenOptions::new().read(true))
+        .unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn snapshot_and_restore() -> Result<()> {
+    let store = get_store(false);
+    let module = module(&store)?;
+    let fs = MemFileSystem::new();
+    fs.create_dir_all("/data").unwrap();
+
+    let mut wasi_env = WasiState::new("snapshot")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    let instance = Instance::new(&module, &import_object)?;
+    let step: NativeFunc<(), i32> = instance.exports.get_native_function("step")?;
+    assert_eq!(step.call()?, 1);
+
+    let snapshot = wasi_env.snapshot(&instance)?;
+    let snapshot = WasiSnapshot::deserialize(&snapshot.serialize()?)?;
+    let (restored_env, restored) = WasiEnv::restore(&module, &snapshot)?;
+    let restored_step: NativeFunc<(), i32> = restored.exports.get_native_function("step")?;
+    assert_eq!(restored_step.call()?, 2);
+
+    // The file is still open, and written after what was there
+    assert_eq!(read_log(&restored_env), b"tick tick ");
+    {
+        let state = restored_env.state();
+        let fd = &state.fs.fd_map[&5];
+        assert_eq!(fd.offset, 10);
+    }
+    let memory = restored.exports.get_memory("memory")?;
+    assert_eq!(memory.view::<u32>()[0].get(), 2);
+
+    // The restored program has its own copy of the files
+    assert_eq!(fs.read_file("/data/log.txt").unwrap(), b"tick ");
+    Ok(())
+}
@@ -0,0 +1,136 @@
+#![cfg(all(feature = "wasi", unix))]
+
+//! Testing the WASI socket syscalls, called by a module on a loopback
+//! connection granted by the host.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use wasmer::*;
+use wasmer_wasi::types::*;
+use wasmer_wasi::WasiState;
+
+// The fd of the socket in the module, hardcoded in its code.
+const SOCK: u32 = 10;
+
+// The data is received into 2 bytes at 64 and the rest at 128.
+fn get_module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "sock_recv"
+            (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "sock_send"
+            (func $sock_send (param i32 i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "sock_shutdown"
+            (func $sock_shutdown (param i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 256) "pong")
+
+          (func (export "recv") (param $flags i32) (param $len i32) (result i32)
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 2))
+            (i32.store (i32.const 8) (i32.const 128))
+            (i32.store (i32.const 12) (local.get $len))
+            (call $sock_recv (i32.const 10) (i32.const 0) (i32.const 2)
+                             (local.get $flags) (i32.const 16) (i32.const 20)))
+          (func (export "send") (result i32)
+            (i32.store (i32.const 32) (i32.const 256))
+            (i32.store (i32.const 36) (i32.const 4))
+            (call $sock_send (i32.const 10) (i32.const 32) (i32.const 1)
+                             (i32.const 0) (i32.const 16)))
+          (func (export "shutdown") (param $how i32) (result i32)
+            (call $sock_shutdown (i32.const 10) (local.get $how))))
+    "#;
+    Ok(Module::new(store, wat)?)
+}
+
+// Returns the instance and the host end of the connection.
+fn instantiate(store: &Store) -> Result<(Instance, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let client = TcpStream::connect(listener.local_addr()?)?;
+    let (server, _) = listener.accept()?;
+
+    let module = get_module(store)?;
+    let mut wasi_env = WasiState::new("sockets")
+        .preopen_socket(SOCK, server)
+        .finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    let instance = Instance::new(&module, &import_object)?;
+    Ok((instance, client))
+}
+
+fn read_memory(instance: &Instance, offset: usize, len: usize) -> Result<Vec<u8>> {
+    let memory = instance.exports.get_memory("memory")?;
+    Ok(memory.view::<u8>()[offset..offset + len]
+        .iter()
+        .map(|cell| cell.get())
+        .collect())
+}
+
+fn received_len(instance: &Instance) -> Result<u32> {
+    let bytes = read_memory(instance, 16, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[test]
+fn sock_recv_into_several_buffers() -> Result<()> {
+    let store = get_store(false);
+    let (instance, mut client) = instantiate(&store)?;
+    let recv: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("recv")?;
+
+    client.write_all(b"hello")?;
+    assert_eq!(
+        recv.call(i32::from(__WASI_SOCK_RECV_PEEK), 16)?,
+        i32::from(__WASI_ESUCCESS)
+    );
+    assert_eq!(received_len(&instance)?, 5);
+    assert_eq!(recv.call(0, 16)?, i32::from(__WASI_ESUCCESS));
+    assert_eq!(received_len(&instance)?, 5);
+    assert_eq!(read_memory(&instance, 64, 2)?, b"he");
+    assert_eq!(read_memory(&instance, 128, 3)?, b"llo");
+
+    Ok(())
+}
+
+#[test]
+fn sock_recv_checks_its_arguments() -> Result<()> {
+    let store = get_store(false);
+    let (instance, mut client) = instantiate(&store)?;
+    let recv: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("recv")?;
+
+    client.write_all(b"hello")?;
+    assert_eq!(
+        recv.call(i32::from(__WASI_SOCK_RECV_WAITALL), 16)?,
+        i32::from(__WASI_ENOTSUP)
+    );
+    assert_eq!(recv.call(1 << 2, 16)?, i32::from(__WASI_EINVAL));
+    // a buffer larger than the memory is rejected before receiving anything
+    assert_eq!(recv.call(0, -1)?, i32::from(__WASI_EFAULT));
+    assert_eq!(recv.call(0, 16)?, i32::from(__WASI_ESUCCESS));
+    assert_eq!(received_len(&instance)?, 5);
+
+    Ok(())
+}
+
+#[test]
+fn sock_send_and_shutdown() -> Result<()> {
+    let store = get_store(false);
+    let (instance, mut client) = instantiate(&store)?;
+    let send: NativeFunc<(), i32> = instance.exports.get_native_function("send")?;
+    let shutdown: NativeFunc<i32, i32> = instance.exports.get_native_function("shutdown")?;
+
+    assert_eq!(send.call()?, i32::from(__WASI_ESUCCESS));
+    assert_eq!(received_len(&instance)?, 4);
+    assert_eq!(shutdown.call(0)?, i32::from(__WASI_EINVAL));
+    assert_eq!(
+        shutdown.call(i32::from(__WASI_SHUT_WR))?,
+        i32::from(__WASI_ESUCCESS)
+    );
+
+    let mut received = vec![];
+    client.read_to_end(&mut received)?;
+    assert_eq!(received, b"pong");
+
+    Ok(())
+}
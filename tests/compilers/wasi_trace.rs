@@ -0,0 +1,108 @@
+#![cfg(feature = "wasi")]
+
+//! Testing that the tracer of a WASI module is told about its syscalls.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasmer::*;
+use wasmer_wasi::types::*;
+use wasmer_wasi::{ArgValue, MemFileSystem, SyscallArg, SyscallRecord, SyscallTracer, WasiState};
+
+/// Keeps the records to look at them afterwards.
+#[derive(Debug, Default)]
+struct Records(Mutex<Vec<SyscallRecord>>);
+
+impl SyscallTracer for Records {
+    fn record(&self, record: &SyscallRecord) {
+        self.0.lock().unwrap().push(record.clone());
+    }
+}
+
+fn arg(name: &'static str, value: ArgValue) -> SyscallArg {
+    SyscallArg { name, value }
+}
+
+#[test]
+fn syscalls_are_traced() -> Result<()> {
+    let store = get_store(false);
+    // Creates `log.txt` (at 120), writes "hi" (at 140) to an fd that isn't
+    // open then to the file, and exits with 3.
+    let wat = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 120) "log.txt")
+          (data (i32.const 140) "hi")
+          (func (export "_start")
+            (drop (call $path_open
+              (i32.const 4) (i32.const 1) (i32.const 120) (i32.const 7)
+              (i32.const 9) (i64.const 0x40) (i64.const 0) (i32.const 1) (i32.const 200)))
+            (i32.store (i32.const 300) (i32.const 140))
+            (i32.store (i32.const 304) (i32.const 2))
+            (drop (call $fd_write (i32.const 99) (i32.const 300) (i32.const 1) (i32.const 308)))
+            (drop (call $fd_write (i32.load (i32.const 200)) (i32.const 300) (i32.const 1) (i32.const 308)))
+            (call $proc_exit (i32.const 3))))
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    let fs = MemFileSystem::new();
+    fs.create_dir_all("/data").unwrap();
+    let records = Arc::new(Records::default());
+    let mut wasi_env = WasiState::new("trace")
+        .fs_backend(Box::new(fs.clone()))
+        .map_dir("data", "/data")?
+        .tracer(records.clone())
+        .finalize()?;
+    let import_object = wasi_env.import_object(&module)?;
+    let instance = Instance::new(&module, &import_object)?;
+    let start: NativeFunc<(), ()> = instance.exports.get_native_function("_start")?;
+    assert!(start.call().is_err());
+    assert_eq!(fs.read_file("/data/log.txt").unwrap(), b"hi");
+
+    let records = records.0.lock().unwrap();
+    let calls = records
+        .iter()
+        .map(|record| (record.name, record.errno))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        vec![
+            ("path_open", Some(__WASI_ESUCCESS)),
+            ("fd_write", Some(__WASI_EBADF)),
+            ("fd_write", Some(__WASI_ESUCCESS)),
+            ("proc_exit", None),
+        ]
+    );
+
+    assert_eq!(
+        records[0].args,
+        vec![
+            arg("dirfd", ArgValue::Fd(4)),
+            arg("dirflags", ArgValue::Flags("SYMLINK_FOLLOW".to_string())),
+            arg("path", ArgValue::Path("log.txt".to_string())),
+            arg("path_len", ArgValue::Int(7)),
+            arg("o_flags", ArgValue::Flags("CREAT|TRUNC".to_string())),
+            arg("fs_rights_base", ArgValue::Flags("0x40".to_string())),
+            arg("fs_rights_inheriting", ArgValue::Flags("0x0".to_string())),
+            arg("fs_flags", ArgValue::Flags("APPEND".to_string())),
+            arg("fd", ArgValue::Ptr(200)),
+        ]
+    );
+    assert_eq!(records[1].args[0], arg("fd", ArgValue::Fd(99)));
+    assert_eq!(
+        records[2].args,
+        vec![
+            arg("fd", ArgValue::Fd(5)),
+            arg("iovs", ArgValue::Ptr(300)),
+            arg("iovs_len", ArgValue::Int(1)),
+            arg("nwritten", ArgValue::Ptr(308)),
+        ]
+    );
+    assert_eq!(records[3].args, vec![arg("code", ArgValue::Int(3))]);
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Tests of the `wasmer run` command.
+
+use anyhow::Context;
+use std::fs;
+use std::process::Command;
+use wasmer_integration_tests_cli::*;
+
+/// A WASI module raising `SIGSYS`, which is 30 in WASI.
+const RAISE_SIGSYS_WAT: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "proc_raise"
+    (func $proc_raise (param i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "_start")
+    (drop (call $proc_raise (i32.const 30)))))
+"#;
+
+/// The number of `SIGSYS` on the host.
+#[cfg(target_os = "linux")]
+const HOST_SIGSYS: i32 = 31;
+#[cfg(target_os = "macos")]
+const HOST_SIGSYS: i32 = 12;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn run_exits_like_a_process_killed_by_the_raised_signal() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir().context("Making a temp dir")?;
+    let wat_path = temp_dir.path().join("raise.wat");
+    fs::write(&wat_path, RAISE_SIGSYS_WAT)?;
+
+    let output = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg(&wat_path)
+        .output()?;
+
+    // The host's number of the signal is used, like the shells do.
+    assert_eq!(output.status.code(), Some(128 + HOST_SIGSYS));
+    Ok(())
+}
@@ -113,9 +113,11 @@ impl DummyArtifact {
     /// Deserialize a DummyArtifact
     pub fn deserialize(engine: &DummyEngine, bytes: &[u8]) -> Result<Self, DeserializeError> {
         if !Self::is_deserializable(bytes) {
-            return Err(DeserializeError::Incompatible(
-                "The provided bytes are not of the dummy engine".to_string(),
-            ));
+            return Err(DeserializeError::Incompatible {
+                property: "format".to_string(),
+                expected: "the dummy engine".to_string(),
+                found: "an unknown format".to_string(),
+            });
         }
 
         let inner_bytes = &bytes[Self::MAGIC_HEADER.len()..];
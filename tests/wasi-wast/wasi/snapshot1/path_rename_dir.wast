@@ -0,0 +1,5 @@
+(wasi_test "path_rename_dir.wasm"
+  (temp_dirs "temp")
+  (assert_return (i64.const 0))
+  (assert_stdout "into itself: Some(28)\nold exists: false\nmoved along\n")
+)
@@ -0,0 +1,81 @@
+;; Does what `tests/path_rename_dir.rs` does; the preopened `temp` is fd 4.
+(module
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_unlink_file" (func $path_unlink_file (param i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_create_directory" (func $path_create_directory (param i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_remove_directory" (func $path_remove_directory (param i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_rename" (func $path_rename (param i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_filestat_get" (func $path_filestat_get (param i32 i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "old_dir")
+  (data (i32.const 1039) "old_dir/nested")
+  (data (i32.const 1061) "old_dir/nested/file.txt")
+  (data (i32.const 1092) "old_dir/nested/inner")
+  (data (i32.const 1120) "new_dir")
+  (data (i32.const 1135) "new_dir/nested")
+  (data (i32.const 1157) "new_dir/nested/file.txt")
+  (data (i32.const 1188) "moved along\n")
+  (data (i32.const 1208) "into itself: Some(")
+  (data (i32.const 1234) ")\n")
+  (data (i32.const 1244) "old exists: true\n")
+  (data (i32.const 1269) "old exists: false\n")
+  ;; The iovec is at 0, the number of bytes written or read at 8, the opened
+  ;; fd at 16, the digits of a number end at 160 and what's read is at 256.
+  (func $check (param $errno i32)
+    (if (local.get $errno) (then unreachable)))
+  (func $print (param $ptr i32) (param $len i32)
+    (i32.store (i32.const 0) (local.get $ptr))
+    (i32.store (i32.const 4) (local.get $len))
+    (call $check (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+  (func $print_num (param $n i32) (local $p i32)
+    (local.set $p (i32.const 160))
+    (loop $digits
+      (local.set $p (i32.sub (local.get $p) (i32.const 1)))
+      (i32.store8 (local.get $p) (i32.add (i32.const 48) (i32.rem_u (local.get $n) (i32.const 10))))
+      (local.set $n (i32.div_u (local.get $n) (i32.const 10)))
+      (br_if $digits (local.get $n)))
+    (call $print (local.get $p) (i32.sub (i32.const 160) (local.get $p))))
+  ;; Opens `$path` in `$dir` with all the rights, returns the errno
+  (func $open (param $dir i32) (param $lookup i32) (param $path i32) (param $len i32) (param $oflags i32) (result i32)
+    (call $path_open
+      (local.get $dir) (local.get $lookup) (local.get $path) (local.get $len) (local.get $oflags)
+      (i64.const 0x1fffffff) (i64.const 0x1fffffff) (i32.const 0) (i32.const 16)))
+  ;; Reads up to `$len` bytes of `$fd` at 256, returns how many were read
+  (func $read (param $fd i32) (param $len i32) (result i32)
+    (i32.store (i32.const 0) (i32.const 256))
+    (i32.store (i32.const 4) (local.get $len))
+    (call $check (call $fd_read (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8)))
+    (i32.load (i32.const 8)))
+  (func (export "_start") (local $fd i32) (local $n i32)
+    (call $check (call $path_create_directory (i32.const 4) (i32.const 1024) (i32.const 7)))
+    (call $check (call $path_create_directory (i32.const 4) (i32.const 1039) (i32.const 14)))
+    ;; creates `old_dir/nested/file.txt`, truncated
+    (call $check (call $open (i32.const 4) (i32.const 1) (i32.const 1061) (i32.const 23) (i32.const 9)))
+    (local.set $fd (i32.load (i32.const 16)))
+    (i32.store (i32.const 0) (i32.const 1188))
+    (i32.store (i32.const 4) (i32.const 12))
+    (call $check (call $fd_write (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8)))
+    (call $check (call $fd_close (local.get $fd)))
+
+    ;; a directory can't be moved into itself
+    (call $print (i32.const 1208) (i32.const 18))
+    (call $print_num (call $path_rename (i32.const 4) (i32.const 1024) (i32.const 7) (i32.const 4) (i32.const 1092) (i32.const 20)))
+    (call $print (i32.const 1234) (i32.const 2))
+
+    (call $check (call $path_rename (i32.const 4) (i32.const 1024) (i32.const 7) (i32.const 4) (i32.const 1120) (i32.const 7)))
+    (if (call $path_filestat_get (i32.const 4) (i32.const 1) (i32.const 1024) (i32.const 7) (i32.const 512))
+      (then (call $print (i32.const 1269) (i32.const 18)))
+      (else (call $print (i32.const 1244) (i32.const 17))))
+    (call $check (call $open (i32.const 4) (i32.const 1) (i32.const 1157) (i32.const 23) (i32.const 0)))
+    (local.set $fd (i32.load (i32.const 16)))
+    (local.set $n (call $read (local.get $fd) (i32.const 64)))
+    (call $print (i32.const 256) (local.get $n))
+    (call $check (call $fd_close (local.get $fd)))
+
+    (call $check (call $path_unlink_file (i32.const 4) (i32.const 1157) (i32.const 23)))
+    (call $check (call $path_remove_directory (i32.const 4) (i32.const 1135) (i32.const 14)))
+    (call $check (call $path_remove_directory (i32.const 4) (i32.const 1120) (i32.const 7))))
+)
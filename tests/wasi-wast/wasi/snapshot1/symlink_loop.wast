@@ -0,0 +1,5 @@
+(wasi_test "symlink_loop.wasm"
+  (temp_dirs "temp")
+  (assert_return (i64.const 0))
+  (assert_stdout "loop: Some(32)\nnofollow: Some(32)\nfollow: hello\n")
+)
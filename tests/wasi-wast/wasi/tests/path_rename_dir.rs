@@ -0,0 +1,34 @@
+// WASI:
+// tempdir: temp
+
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    #[cfg(not(target_os = "wasi"))]
+    let base = PathBuf::from("test_fs");
+    #[cfg(target_os = "wasi")]
+    let base = PathBuf::from("temp");
+
+    let old_dir = base.join("old_dir");
+    let new_dir = base.join("new_dir");
+
+    fs::create_dir(&old_dir).unwrap();
+    fs::create_dir(old_dir.join("nested")).unwrap();
+    fs::write(old_dir.join("nested/file.txt"), b"moved along\n").unwrap();
+
+    // a directory can't be moved into itself
+    let err = fs::rename(&old_dir, old_dir.join("nested/inner")).unwrap_err();
+    println!("into itself: {:?}", err.raw_os_error());
+
+    fs::rename(&old_dir, &new_dir).unwrap();
+    println!("old exists: {}", old_dir.exists());
+    print!(
+        "{}",
+        fs::read_to_string(new_dir.join("nested/file.txt")).unwrap()
+    );
+
+    fs::remove_file(new_dir.join("nested/file.txt")).unwrap();
+    fs::remove_dir(new_dir.join("nested")).unwrap();
+    fs::remove_dir(&new_dir).unwrap();
+}
@@ -0,0 +1,40 @@
+// WASI:
+// mapdir: hamlet:test_fs/hamlet
+// tempdir: temp
+
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+fn main() {
+    #[cfg(not(target_os = "wasi"))]
+    let base = PathBuf::from("test_fs");
+    #[cfg(target_os = "wasi")]
+    let base = PathBuf::from("/");
+
+    let escape = base.join("temp/escape");
+    let absolute = base.join("temp/absolute");
+    let scene = base.join("temp/scene");
+
+    std::fs::soft_link("../../../../etc/passwd", &escape).unwrap();
+    std::fs::soft_link("/etc/passwd", &absolute).unwrap();
+    std::fs::soft_link("/hamlet/act1/scene2.txt", &scene).unwrap();
+
+    // symlinks can't be used to leave the preopened directories
+    if fs::File::open(&escape).is_err() {
+        println!("escape: denied");
+    }
+    if fs::File::open(&absolute).is_err() {
+        println!("absolute: denied");
+    }
+
+    // absolute symlinks are resolved from the virtual root
+    let mut file = fs::File::open(&scene).expect("Could not open file via symlink");
+    let mut buffer = [0u8; 41];
+    file.read_exact(&mut buffer).unwrap();
+    print!("{}", std::str::from_utf8(&buffer[..]).unwrap());
+
+    fs::remove_file(&escape).unwrap();
+    fs::remove_file(&absolute).unwrap();
+    fs::remove_file(&scene).unwrap();
+}
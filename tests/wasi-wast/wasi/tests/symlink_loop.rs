@@ -0,0 +1,49 @@
+// WASI:
+// tempdir: temp
+
+use std::fs;
+use std::io::Read;
+#[cfg(target_os = "wasi")]
+use std::os::wasi::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+fn main() {
+    #[cfg(not(target_os = "wasi"))]
+    let base = PathBuf::from("test_fs");
+    #[cfg(target_os = "wasi")]
+    let base = PathBuf::from("/");
+
+    let file = base.join("temp/file");
+    let link = base.join("temp/link");
+    let loop_a = base.join("temp/loop_a");
+    let loop_b = base.join("temp/loop_b");
+
+    fs::write(&file, b"hello\n").unwrap();
+    std::fs::soft_link("loop_b", &loop_a).unwrap();
+    std::fs::soft_link("loop_a", &loop_b).unwrap();
+    std::fs::soft_link("file", &link).unwrap();
+
+    // symlinks pointing at each other never resolve
+    let err = fs::File::open(&loop_a).unwrap_err();
+    println!("loop: {:?}", err.raw_os_error());
+
+    // without `__WASI_LOOKUP_SYMLINK_FOLLOW`, opening a symlink fails
+    let mut options = fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(target_os = "wasi")]
+    options.lookup_flags(0);
+    let err = options.open(&link).unwrap_err();
+    println!("nofollow: {:?}", err.raw_os_error());
+
+    let mut contents = String::new();
+    fs::File::open(&link)
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    print!("follow: {}", contents);
+
+    fs::remove_file(&loop_a).unwrap();
+    fs::remove_file(&loop_b).unwrap();
+    fs::remove_file(&link).unwrap();
+    fs::remove_file(&file).unwrap();
+}
@@ -0,0 +1,6 @@
+(wasi_test "symlink_escape.wasm"
+  (map_dirs "hamlet:test_fs/hamlet")
+  (temp_dirs "temp")
+  (assert_return (i64.const 0))
+  (assert_stdout "escape: denied\nabsolute: denied\nSCENE II. A room of state in the castle.\n")
+)
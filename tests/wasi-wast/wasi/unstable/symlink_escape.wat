@@ -0,0 +1,66 @@
+;; Does what `tests/symlink_escape.rs` does; the preopened `temp` is fd 5.
+(module
+  (import "wasi_unstable" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_unstable" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_unstable" "fd_close" (func $fd_close (param i32) (result i32)))
+  (import "wasi_unstable" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_unstable" "path_symlink" (func $path_symlink (param i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi_unstable" "path_unlink_file" (func $path_unlink_file (param i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "escape")
+  (data (i32.const 1038) "absolute")
+  (data (i32.const 1054) "scene")
+  (data (i32.const 1067) "../../../../etc/passwd")
+  (data (i32.const 1097) "/etc/passwd")
+  (data (i32.const 1116) "/hamlet/act1/scene2.txt")
+  (data (i32.const 1147) "escape: denied\n")
+  (data (i32.const 1170) "absolute: denied\n")
+  ;; The iovec is at 0, the number of bytes written or read at 8, the opened
+  ;; fd at 16, the digits of a number end at 160 and what's read is at 256.
+  (func $check (param $errno i32)
+    (if (local.get $errno) (then unreachable)))
+  (func $print (param $ptr i32) (param $len i32)
+    (i32.store (i32.const 0) (local.get $ptr))
+    (i32.store (i32.const 4) (local.get $len))
+    (call $check (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+  (func $print_num (param $n i32) (local $p i32)
+    (local.set $p (i32.const 160))
+    (loop $digits
+      (local.set $p (i32.sub (local.get $p) (i32.const 1)))
+      (i32.store8 (local.get $p) (i32.add (i32.const 48) (i32.rem_u (local.get $n) (i32.const 10))))
+      (local.set $n (i32.div_u (local.get $n) (i32.const 10)))
+      (br_if $digits (local.get $n)))
+    (call $print (local.get $p) (i32.sub (i32.const 160) (local.get $p))))
+  ;; Opens `$path` in `$dir` with all the rights, returns the errno
+  (func $open (param $dir i32) (param $lookup i32) (param $path i32) (param $len i32) (param $oflags i32) (result i32)
+    (call $path_open
+      (local.get $dir) (local.get $lookup) (local.get $path) (local.get $len) (local.get $oflags)
+      (i64.const 0x1fffffff) (i64.const 0x1fffffff) (i32.const 0) (i32.const 16)))
+  ;; Reads up to `$len` bytes of `$fd` at 256, returns how many were read
+  (func $read (param $fd i32) (param $len i32) (result i32)
+    (i32.store (i32.const 0) (i32.const 256))
+    (i32.store (i32.const 4) (local.get $len))
+    (call $check (call $fd_read (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 8)))
+    (i32.load (i32.const 8)))
+  (func (export "_start") (local $fd i32) (local $n i32)
+    (call $check (call $path_symlink (i32.const 1067) (i32.const 22) (i32.const 5) (i32.const 1024) (i32.const 6)))
+    (call $check (call $path_symlink (i32.const 1097) (i32.const 11) (i32.const 5) (i32.const 1038) (i32.const 8)))
+    (call $check (call $path_symlink (i32.const 1116) (i32.const 23) (i32.const 5) (i32.const 1054) (i32.const 5)))
+
+    ;; symlinks can't be used to leave the preopened directories
+    (if (call $open (i32.const 5) (i32.const 1) (i32.const 1024) (i32.const 6) (i32.const 0))
+      (then (call $print (i32.const 1147) (i32.const 15))))
+    (if (call $open (i32.const 5) (i32.const 1) (i32.const 1038) (i32.const 8) (i32.const 0))
+      (then (call $print (i32.const 1170) (i32.const 17))))
+
+    ;; absolute symlinks are resolved from the virtual root
+    (call $check (call $open (i32.const 5) (i32.const 1) (i32.const 1054) (i32.const 5) (i32.const 0)))
+    (local.set $fd (i32.load (i32.const 16)))
+    (local.set $n (call $read (local.get $fd) (i32.const 41)))
+    (call $print (i32.const 256) (local.get $n))
+    (call $check (call $fd_close (local.get $fd)))
+
+    (call $check (call $path_unlink_file (i32.const 5) (i32.const 1024) (i32.const 6)))
+    (call $check (call $path_unlink_file (i32.const 5) (i32.const 1038) (i32.const 8)))
+    (call $check (call $path_unlink_file (i32.const 5) (i32.const 1054) (i32.const 5))))
+)